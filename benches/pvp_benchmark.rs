@@ -1,14 +1,51 @@
-use chess::game::modes;
+use chess_cli::alpha_beta_searcher::transposition_table::DEFAULT_HASH_MB;
+use chess_cli::game::computer_vs_computer::computer_vs_computer;
+use chess_cli::game::event::OutputFormat;
+use chess_cli::game::game_options::GameOptions;
+use chess_cli::game::renderer::DisplayConfig;
 
 use criterion::{criterion_group, criterion_main, Criterion};
 
+fn options() -> GameOptions {
+    GameOptions {
+        depth: 0,
+        hash_mb: DEFAULT_HASH_MB,
+        root_jitter: None,
+        weights: None,
+        verify_mates: false,
+        watchdog: None,
+        #[cfg(feature = "opening-explorer")]
+        opening_explorer: false,
+        display: DisplayConfig::default(),
+        output: OutputFormat::Text,
+    }
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("computer vs computer (depth 3)", |b| {
-        b.iter(|| modes::computer_vs_computer(25, 0, 3))
+        b.iter(|| {
+            computer_vs_computer(
+                25,
+                0,
+                &GameOptions {
+                    depth: 3,
+                    ..options()
+                },
+            )
+        })
     });
 
     c.bench_function("computer vs computer (depth 4)", |b| {
-        b.iter(|| modes::computer_vs_computer(10, 0, 4))
+        b.iter(|| {
+            computer_vs_computer(
+                10,
+                0,
+                &GameOptions {
+                    depth: 4,
+                    ..options()
+                },
+            )
+        })
     });
 }
 
@@ -7,7 +7,7 @@ use std::{
     },
 };
 
-use crate::bitboard::square::from_rank_file;
+use crate::bitboard::square::{from_rank_file, to_algebraic};
 
 /// Represents a chess board as a 64-bit integer. In practice, there will be
 /// one bitboard for each player's piece type (e.g. white pawns, black knights).
@@ -66,6 +66,43 @@ impl Bitboard {
         self.0 &= !(1 << lsb);
         Bitboard(1 << lsb)
     }
+
+    /// Each set square as its own bitboard, from A1 to H8.
+    pub fn squares(&self) -> Vec<Bitboard> {
+        let mut remaining = *self;
+        let mut squares = Vec::with_capacity(self.count_ones() as usize);
+        while !remaining.is_empty() {
+            squares.push(remaining.pop_lsb());
+        }
+        squares
+    }
+
+    /// The names of the set squares, e.g. `["e4", "d5"]`, from A1 to H8.
+    pub fn square_names(&self) -> Vec<&'static str> {
+        self.squares().into_iter().map(to_algebraic).collect()
+    }
+
+    /// Draws the bitboard as a board seen from white's side, with the ranks and
+    /// files labeled and the set squares marked `X`. Handy for debugging mask
+    /// arithmetic, e.g. `println!("{}", (attacks & !occupied).render())`.
+    pub fn render(&self) -> String {
+        let mut result = String::new();
+        for rank in (0..8).rev() {
+            result.push_str(&format!("{} ", rank + 1));
+            for file in 0..8 {
+                let cell = match self.overlaps(from_rank_file(rank, file)) {
+                    true => 'X',
+                    false => '.',
+                };
+                result.push(cell);
+                result.push(' ');
+            }
+            result.pop();
+            result.push('\n');
+        }
+        result.push_str("  a b c d e f g h\n");
+        result
+    }
 }
 
 /// These macros efficiently implement bitwise operations for the Bitboard struct.
@@ -171,3 +208,33 @@ impl Display for Bitboard {
         write!(f, "{}", result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::square::*;
+
+    #[test]
+    fn test_squares() {
+        let bitboard = E4 | A1 | H8;
+        assert_eq!(bitboard.squares(), vec![A1, E4, H8]);
+        assert_eq!(bitboard.square_names(), vec!["a1", "e4", "h8"]);
+        assert!(Bitboard::EMPTY.squares().is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let expected = "\
+8 . . . . . . . X
+7 . . . . . . . .
+6 . . . . . . . .
+5 . . . . . . . .
+4 . . . . X . . .
+3 . . . . . . . .
+2 . . . . . . . .
+1 X . . . . . . .
+  a b c d e f g h
+";
+        assert_eq!((E4 | A1 | H8).render(), expected);
+    }
+}
@@ -1,5 +1,5 @@
-use chess::board::color::Color;
-use chess::{board::Board, move_generator::MoveGenerator};
+use chess_core::board::color::Color;
+use chess_core::{board::Board, move_generator::MoveGenerator};
 
 use criterion::{criterion_group, criterion_main, Criterion};
 
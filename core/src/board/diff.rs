@@ -0,0 +1,91 @@
+use std::fmt;
+
+use common::bitboard::bitboard::Bitboard;
+use common::bitboard::square::{to_algebraic, ORDERED_SQUARES};
+
+use super::color::Color;
+use super::piece::Piece;
+use super::Board;
+
+/// A square whose contents differ between two boards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SquareChange {
+    pub square: Bitboard,
+    pub before: Option<(Piece, Color)>,
+    pub after: Option<(Piece, Color)>,
+}
+
+impl Board {
+    /// Returns the squares whose contents differ from `other`, file by file from A1 to H8.
+    /// `before` is this board's content, and `after` is `other`'s.
+    pub fn diff(&self, other: &Board) -> Vec<SquareChange> {
+        let occupied = self.occupied() | other.occupied();
+
+        ORDERED_SQUARES
+            .iter()
+            .filter(|&&square| occupied.overlaps(square))
+            .filter_map(|&square| {
+                let before = self.get(square);
+                let after = other.get(square);
+                (before != after).then_some(SquareChange {
+                    square,
+                    before,
+                    after,
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for SquareChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |content: Option<(Piece, Color)>| match content {
+            Some((piece, color)) => piece.to_char(color),
+            None => '.',
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            to_algebraic(self.square),
+            describe(self.before),
+            describe(self.after)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::chess_move::ChessMove;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::std_move;
+    use common::bitboard::square::*;
+
+    #[test]
+    fn test_diff() {
+        let before = Board::starting_position();
+        assert!(before.diff(&before).is_empty());
+
+        let mut after = before.clone();
+        std_move!(E2, E4).apply(&mut after).unwrap();
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes,
+            vec![
+                SquareChange {
+                    square: E2,
+                    before: Some((Piece::Pawn, Color::White)),
+                    after: None,
+                },
+                SquareChange {
+                    square: E4,
+                    before: None,
+                    after: Some((Piece::Pawn, Color::White)),
+                },
+            ]
+        );
+        assert_eq!(changes[0].to_string(), "e2: P -> .");
+        assert_eq!(changes[1].to_string(), "e4: . -> P");
+    }
+}
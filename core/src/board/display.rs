@@ -1,5 +1,3 @@
-use common::bitboard::square::from_rank_file;
-
 use super::color::Color;
 use super::piece::Piece;
 use super::Board;
@@ -9,20 +7,16 @@ const EMPTY_CELL: char = '.';
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut rows: Vec<String> = vec![];
-
-        for rank in (0..8).rev() {
-            let mut cells: Vec<String> = vec![];
-            for file in 0..8 {
-                let sq = from_rank_file(rank, file);
-                let cell = match self.get(sq) {
-                    Some((piece, color)) => get_piece_char(piece, color),
-                    None => EMPTY_CELL,
-                };
-                cells.push(cell.to_string());
-            }
-            rows.push(cells.join("").to_string());
+        let mut cells = [EMPTY_CELL; 64];
+        for (square, piece, color) in self.iter() {
+            cells[square.trailing_zeros() as usize] = get_piece_char(piece, color);
         }
+
+        let rows: Vec<String> = cells
+            .chunks(8)
+            .rev()
+            .map(|rank| rank.iter().collect())
+            .collect();
         write!(f, "{}", rows.join("\n"))
     }
 }
@@ -0,0 +1,316 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use super::fen::FenError;
+use super::Board;
+
+/// Operations whose operands are strings, which are always written quoted.
+const STRING_OPCODES: [&str; 11] = [
+    "id", "c0", "c1", "c2", "c3", "c4", "c5", "c6", "c7", "c8", "c9",
+];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EpdError {
+    #[error("expected at least 4 space-separated fields, found {count}")]
+    WrongFieldCount { count: usize },
+    #[error("invalid position: {error}")]
+    InvalidPosition { error: FenError },
+    #[error("unterminated string in operation {opcode:?}")]
+    UnterminatedString { opcode: String },
+    #[error("invalid operand for {opcode:?}: {operand:?}")]
+    InvalidOperand { opcode: String, operand: String },
+}
+
+/// One of an EPD record's operations: an opcode, e.g. `bm`, and its operands,
+/// e.g. the best moves in algebraic notation. String operands are kept without
+/// their quotes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+/// A position in Extended Position Description: the first four fields of a
+/// FEN, followed by operations that describe it, as test suites like WAC and
+/// Bratko-Kopec are written, e.g.
+/// `2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";`.
+/// See: https://www.chessprogramming.org/Extended_Position_Description
+#[derive(Clone)]
+pub struct Epd {
+    pub board: Board,
+    /// In the order they were written.
+    pub operations: Vec<EpdOperation>,
+}
+
+impl Epd {
+    pub fn new(board: Board) -> Self {
+        Self {
+            board,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Parses an EPD record. The halfmove and fullmove clocks are read from the
+    /// `hmvc` and `fmvn` operations, and default to 0 and 1 without them. A
+    /// full FEN is accepted in place of the four fields, as long as no
+    /// operations follow it. Castling rights that the king and rook aren't in
+    /// place for are dropped, as many suites carry them.
+    pub fn parse(epd: &str) -> Result<Self, EpdError> {
+        let epd = epd.trim();
+        let mut fields = epd.splitn(5, char::is_whitespace);
+        let position: Vec<&str> = fields.by_ref().take(4).collect();
+        if position.len() < 4 {
+            return Err(EpdError::WrongFieldCount {
+                count: position.len(),
+            });
+        }
+        let rest = fields.next().unwrap_or("").trim();
+
+        // Two numbers and nothing else are a FEN's clocks.
+        let is_fen_clocks = {
+            let clocks: Vec<&str> = rest.split_whitespace().collect();
+            clocks.len() == 2 && clocks.iter().all(|clock| clock.parse::<u16>().is_ok())
+        };
+        let (clocks, operations) = if is_fen_clocks {
+            (rest.to_string(), Vec::new())
+        } else {
+            let operations = parse_operations(rest)?;
+            let clock = |opcode: &str, default: &str| -> Result<String, EpdError> {
+                match operations
+                    .iter()
+                    .find(|operation| operation.opcode == opcode)
+                {
+                    Some(operation) => match operation.operands.as_slice() {
+                        [clock] => Ok(clock.clone()),
+                        operands => Err(EpdError::InvalidOperand {
+                            opcode: opcode.to_string(),
+                            operand: operands.join(" "),
+                        }),
+                    },
+                    None => Ok(default.to_string()),
+                }
+            };
+            let clocks = format!("{} {}", clock("hmvc", "0")?, clock("fmvn", "1")?);
+            (clocks, operations)
+        };
+
+        let board = Board::from_fen_lenient(&format!("{} {}", position.join(" "), clocks))
+            .map_err(|error| EpdError::InvalidPosition { error })?;
+        Ok(Self { board, operations })
+    }
+
+    /// The first operation with `opcode`, if there is one.
+    pub fn operation(&self, opcode: &str) -> Option<&EpdOperation> {
+        self.operations
+            .iter()
+            .find(|operation| operation.opcode == opcode)
+    }
+
+    /// The operands of the first operation with `opcode`, or none if there's no
+    /// such operation.
+    pub fn operands(&self, opcode: &str) -> &[String] {
+        self.operation(opcode)
+            .map_or(&[], |operation| operation.operands.as_slice())
+    }
+
+    /// Replaces the first operation with `opcode`, or adds one at the end.
+    pub fn set_operation(&mut self, opcode: &str, operands: Vec<String>) {
+        match self
+            .operations
+            .iter_mut()
+            .find(|operation| operation.opcode == opcode)
+        {
+            Some(operation) => operation.operands = operands,
+            None => self.operations.push(EpdOperation {
+                opcode: opcode.to_string(),
+                operands,
+            }),
+        }
+    }
+
+    /// The best moves (`bm`), in algebraic notation.
+    pub fn best_moves(&self) -> &[String] {
+        self.operands("bm")
+    }
+
+    /// The moves to avoid (`am`), in algebraic notation.
+    pub fn avoid_moves(&self) -> &[String] {
+        self.operands("am")
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operands("id").first().map(String::as_str)
+    }
+
+    /// The centipawn evaluation (`ce`), from the perspective of the side to
+    /// move.
+    pub fn centipawn_evaluation(&self) -> Option<i16> {
+        self.operands("ce").first()?.parse().ok()
+    }
+}
+
+/// Splits the operations that follow the position at their semicolons, and
+/// each into its opcode and operands. The last operation's semicolon may be
+/// left off.
+fn parse_operations(operations: &str) -> Result<Vec<EpdOperation>, EpdError> {
+    let mut parsed = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = operations.chars().peekable();
+    let finish = |tokens: &mut Vec<String>, parsed: &mut Vec<EpdOperation>| {
+        if !tokens.is_empty() {
+            let opcode = tokens.remove(0);
+            parsed.push(EpdOperation {
+                opcode,
+                operands: std::mem::take(tokens),
+            });
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => finish(&mut tokens, &mut parsed),
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => string.push(c),
+                        None => {
+                            return Err(EpdError::UnterminatedString {
+                                opcode: tokens.first().cloned().unwrap_or_default(),
+                            })
+                        }
+                    }
+                }
+                tokens.push(string);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' || c == '"' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    finish(&mut tokens, &mut parsed);
+    Ok(parsed)
+}
+
+impl fmt::Display for EpdOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.opcode)?;
+        let is_string = STRING_OPCODES.contains(&self.opcode.as_str());
+        for operand in &self.operands {
+            let needs_quotes = operand.is_empty()
+                || operand.contains(|c: char| c.is_whitespace() || c == ';' || c == '"');
+            if is_string || needs_quotes {
+                write!(f, " \"{}\"", operand)?;
+            } else {
+                write!(f, " {}", operand)?;
+            }
+        }
+        write!(f, ";")
+    }
+}
+
+/// Writes the record with its position's four fields, and its operations in
+/// order, each ending in a semicolon. The clocks are only written as the
+/// `hmvc` and `fmvn` operations, if the record has them.
+impl fmt::Display for Epd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fen = self.board.to_fen();
+        let position: Vec<&str> = fen.split_whitespace().take(4).collect();
+        write!(f, "{}", position.join(" "))?;
+        for operation in &self.operations {
+            write!(f, " {}", operation)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_epd() {
+        let epd = Epd::parse(
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";",
+        )
+        .unwrap();
+        assert_eq!(
+            epd.board.to_fen(),
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1"
+        );
+        assert_eq!(epd.best_moves(), ["Qg6"]);
+        assert!(epd.avoid_moves().is_empty());
+        assert_eq!(epd.id(), Some("WAC.001"));
+        assert_eq!(epd.centipawn_evaluation(), None);
+
+        let epd = Epd::parse(
+            "r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - am Nxc6 Bxc5; \
+             ce -15; hmvc 3; fmvn 7; c0 \"a comment; with a semicolon\"; noop",
+        )
+        .unwrap();
+        assert_eq!(epd.board.halfmove_clock(), 3);
+        assert_eq!(epd.board.fullmove_clock(), 7);
+        assert_eq!(epd.avoid_moves(), ["Nxc6", "Bxc5"]);
+        assert_eq!(epd.centipawn_evaluation(), Some(-15));
+        assert_eq!(epd.operands("c0"), ["a comment; with a semicolon"]);
+        assert!(epd.operands("noop").is_empty());
+        assert!(epd.operation("noop").is_some());
+
+        // A full FEN reads as a record without operations.
+        let epd = Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w - - 12 40").unwrap();
+        assert_eq!(epd.board.halfmove_clock(), 12);
+        assert!(epd.operations.is_empty());
+    }
+
+    #[test]
+    fn test_parse_epd_errors() {
+        assert_eq!(
+            Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w -").err(),
+            Some(EpdError::WrongFieldCount { count: 3 })
+        );
+        assert!(matches!(
+            Epd::parse("4k3/8/8/8/8/8/4P3/4K3 x - - bm e4;"),
+            Err(EpdError::InvalidPosition { .. })
+        ));
+        assert_eq!(
+            Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w - - id \"kpk;").err(),
+            Some(EpdError::UnterminatedString {
+                opcode: "id".to_string()
+            })
+        );
+        assert!(matches!(
+            Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w - - hmvc 1 2;"),
+            Err(EpdError::InvalidOperand { .. })
+        ));
+    }
+
+    #[test]
+    fn test_write_epd() {
+        let record = "4k3/8/8/8/8/8/4P3/4K3 w - - bm e4 e3; id \"kpk\"; c0 \"\"; pv e4 Kd7;";
+        let mut epd = Epd::parse(record).unwrap();
+        assert_eq!(epd.to_string(), record);
+
+        epd.set_operation("bm", vec!["e4".to_string()]);
+        epd.set_operation("ce", vec!["250".to_string()]);
+        assert_eq!(
+            epd.to_string(),
+            "4k3/8/8/8/8/8/4P3/4K3 w - - bm e4; id \"kpk\"; c0 \"\"; pv e4 Kd7; ce 250;"
+        );
+
+        let epd = Epd::new(Board::starting_position());
+        assert_eq!(
+            epd.to_string(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"
+        );
+    }
+}
@@ -0,0 +1,452 @@
+use common::bitboard::bitboard::Bitboard;
+use common::bitboard::square::{
+    from_rank_file, square_string_to_bitboard, to_algebraic, A1, A8, E1, E8, H1, H8,
+};
+use thiserror::Error;
+
+use super::castle_rights_bitmask::{
+    ALL_CASTLE_RIGHTS, BLACK_KINGSIDE_RIGHTS, BLACK_QUEENSIDE_RIGHTS, WHITE_KINGSIDE_RIGHTS,
+    WHITE_QUEENSIDE_RIGHTS,
+};
+use super::color::Color;
+use super::piece::Piece;
+use super::Board;
+
+pub const STARTING_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Castle rights in the order they are written in FEN.
+pub(super) const CASTLE_RIGHTS_CHARS: [(char, u8); 4] = [
+    ('K', WHITE_KINGSIDE_RIGHTS),
+    ('Q', WHITE_QUEENSIDE_RIGHTS),
+    ('k', BLACK_KINGSIDE_RIGHTS),
+    ('q', BLACK_QUEENSIDE_RIGHTS),
+];
+
+/// The same castle rights written as the castling rook's file, as Shredder-FEN
+/// and X-FEN do. Those formats exist for Chess960; here the rooks always start
+/// on the a- and h-files, so these are the only files a right can name.
+const CASTLE_RIGHTS_FILE_CHARS: [(char, u8); 4] = [
+    ('H', WHITE_KINGSIDE_RIGHTS),
+    ('A', WHITE_QUEENSIDE_RIGHTS),
+    ('h', BLACK_KINGSIDE_RIGHTS),
+    ('a', BLACK_QUEENSIDE_RIGHTS),
+];
+
+/// Where the king and rook have to be for each castle right to be possible,
+/// in the same order as `CASTLE_RIGHTS_CHARS`.
+pub(super) const CASTLE_RIGHTS_SQUARES: [(Color, Bitboard, Bitboard); 4] = [
+    (Color::White, E1, H1),
+    (Color::White, E1, A1),
+    (Color::Black, E8, H8),
+    (Color::Black, E8, A8),
+];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FenError {
+    #[error("expected 6 space-separated fields, found {count}")]
+    WrongFieldCount { count: usize },
+    #[error("invalid piece placement: {placement:?}")]
+    InvalidPiecePlacement { placement: String },
+    #[error("invalid active color: {color:?}")]
+    InvalidActiveColor { color: String },
+    #[error("invalid castling rights: {rights:?}")]
+    InvalidCastlingRights { rights: String },
+    #[error(
+        "castling right {right:?} needs a {color} king on {king} and a {color} rook on {rook}"
+    )]
+    ImpossibleCastlingRight {
+        right: char,
+        color: Color,
+        king: String,
+        rook: String,
+    },
+    #[error(
+        "castling right {right:?} is for a rook that didn't start in a corner, as in Chess960"
+    )]
+    UnsupportedCastlingFile { right: char },
+    #[error("invalid en passant target: {target:?}")]
+    InvalidEnPassantTarget { target: String },
+    #[error("invalid move clock: {clock:?}")]
+    InvalidClock { clock: String },
+}
+
+impl Board {
+    /// Parses a position in Forsyth-Edwards Notation. The halfmove and fullmove
+    /// clocks may be omitted, in which case they default to 0 and 1. Castling
+    /// rights that the king and rook aren't in place for are rejected. They may
+    /// also be written as Shredder-FEN and X-FEN write them, by the rook's file
+    /// (e.g. `HAha`), which is read as the usual `KQkq`.
+    ///
+    /// Only the standard starting position's rooks can be named that way, on the
+    /// a- and h-files: there is no Chess960 castling, so a right naming files b
+    /// through g is rejected with `FenError::UnsupportedCastlingFile`. Nor is the
+    /// notation kept: `to_fen` writes the same rights as `KQkq`, so the position
+    /// round-trips, but the text of a FEN written by file doesn't.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        Self::parse_fen(fen, false)
+    }
+
+    /// Like `from_fen`, but drops castling rights that the king and rook aren't
+    /// in place for, rather than rejecting them.
+    pub fn from_fen_lenient(fen: &str) -> Result<Self, FenError> {
+        Self::parse_fen(fen, true)
+    }
+
+    fn parse_fen(fen: &str, lenient: bool) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let fields: [&str; 6] = match fields.len() {
+            4 => [fields[0], fields[1], fields[2], fields[3], "0", "1"],
+            6 => [
+                fields[0], fields[1], fields[2], fields[3], fields[4], fields[5],
+            ],
+            count => return Err(FenError::WrongFieldCount { count }),
+        };
+        let [placement, color, castling, en_passant, halfmove, fullmove] = fields;
+
+        let mut board = Board::new();
+
+        let invalid_placement = || FenError::InvalidPiecePlacement {
+            placement: placement.to_string(),
+        };
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(invalid_placement());
+        }
+        for (i, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - i as u8;
+            let mut file = 0;
+            for c in rank_str.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    file += skip as u8;
+                    continue;
+                }
+                let (piece, color) = Piece::from_char(c).ok_or_else(invalid_placement)?;
+                if file > 7 {
+                    return Err(invalid_placement());
+                }
+                board
+                    .put(from_rank_file(rank, file), piece, color)
+                    .map_err(|_| invalid_placement())?;
+                file += 1;
+            }
+            if file != 8 {
+                return Err(invalid_placement());
+            }
+        }
+
+        match color {
+            "w" => board.set_turn(Color::White),
+            "b" => board.set_turn(Color::Black),
+            _ => {
+                return Err(FenError::InvalidActiveColor {
+                    color: color.to_string(),
+                })
+            }
+        };
+
+        let invalid_castling = || FenError::InvalidCastlingRights {
+            rights: castling.to_string(),
+        };
+        let mut castle_rights = 0;
+        if castling != "-" {
+            for c in castling.chars() {
+                let (_, rights) = match CASTLE_RIGHTS_CHARS
+                    .iter()
+                    .chain(CASTLE_RIGHTS_FILE_CHARS.iter())
+                    .find(|(rights_char, _)| *rights_char == c)
+                {
+                    Some(found) => found,
+                    None if matches!(c, 'B'..='G' | 'b'..='g') => {
+                        return Err(FenError::UnsupportedCastlingFile { right: c })
+                    }
+                    None => return Err(invalid_castling()),
+                };
+                castle_rights |= rights;
+            }
+        }
+        // A right the position can't support would produce an illegal castle
+        // move later, deep in a search.
+        for (&(right, rights), &(color, king, rook)) in
+            CASTLE_RIGHTS_CHARS.iter().zip(CASTLE_RIGHTS_SQUARES.iter())
+        {
+            let in_place = board.get(king) == Some((Piece::King, color))
+                && board.get(rook) == Some((Piece::Rook, color));
+            if castle_rights & rights == 0 || in_place {
+                continue;
+            }
+            if !lenient {
+                return Err(FenError::ImpossibleCastlingRight {
+                    right,
+                    color,
+                    king: to_algebraic(king).to_lowercase(),
+                    rook: to_algebraic(rook).to_lowercase(),
+                });
+            }
+            castle_rights &= !rights;
+        }
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS & !castle_rights);
+
+        if en_passant != "-" {
+            let valid = en_passant.len() == 2
+                && matches!(en_passant.as_bytes()[0], b'a'..=b'h')
+                && matches!(en_passant.as_bytes()[1], b'3' | b'6');
+            if !valid {
+                return Err(FenError::InvalidEnPassantTarget {
+                    target: en_passant.to_string(),
+                });
+            }
+            board.push_en_passant_target(square_string_to_bitboard(en_passant));
+        }
+
+        let parse_clock = |clock: &str| {
+            clock.parse::<u16>().map_err(|_| FenError::InvalidClock {
+                clock: clock.to_string(),
+            })
+        };
+        board.push_halfmove_clock(parse_clock(halfmove)?);
+        board.set_fullmove_clock(parse_clock(fullmove)?);
+
+        Ok(board)
+    }
+
+    /// Writes the position in Forsyth-Edwards Notation. Castling rights are
+    /// always written as `KQkq`, even for a position read from a FEN that named
+    /// them by the rook's file.
+    pub fn to_fen(&self) -> String {
+        let mut squares = [None; 64];
+        for (square, piece, color) in self.iter() {
+            squares[square.trailing_zeros() as usize] = Some((piece, color));
+        }
+
+        let mut ranks = Vec::with_capacity(8);
+        for rank in squares.chunks(8).rev() {
+            let mut rank_str = String::new();
+            let mut empty = 0;
+            for &square in rank {
+                match square {
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            rank_str.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        rank_str.push(piece.to_char(color));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                rank_str.push_str(&empty.to_string());
+            }
+            ranks.push(rank_str);
+        }
+
+        let color = match self.turn() {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let castle_rights = self.peek_castle_rights();
+        let mut castling: String = CASTLE_RIGHTS_CHARS
+            .iter()
+            .filter(|(_, rights)| castle_rights & rights != 0)
+            .map(|(rights_char, _)| rights_char)
+            .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant_target = self.peek_en_passant_target();
+        let en_passant = if en_passant_target == Bitboard::EMPTY {
+            "-"
+        } else {
+            to_algebraic(en_passant_target)
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            ranks.join("/"),
+            color,
+            castling,
+            en_passant,
+            self.halfmove_clock(),
+            self.fullmove_clock()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::capture::Capture;
+    use crate::chess_move::chess_move::ChessMove;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::en_passant::EnPassantChessMove;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::{en_passant_move, std_move};
+    use common::bitboard::square::*;
+
+    #[test]
+    fn test_starting_position_fen() {
+        let board = Board::from_fen(STARTING_POSITION_FEN).unwrap();
+        assert_eq!(
+            board.current_position_hash(),
+            Board::starting_position().current_position_hash()
+        );
+        assert_eq!(board.to_fen(), STARTING_POSITION_FEN);
+        assert_eq!(Board::starting_position().to_fen(), STARTING_POSITION_FEN);
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        let fen = "r3k2r/pp3ppp/2n5/3pP3/8/8/PPP2PPP/R3K2R b Kq d6 3 17";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.turn(), Color::Black);
+        assert_eq!(
+            board.peek_castle_rights(),
+            WHITE_KINGSIDE_RIGHTS | BLACK_QUEENSIDE_RIGHTS
+        );
+        assert_eq!(board.peek_en_passant_target(), D6);
+        assert_eq!(board.halfmove_clock(), 3);
+        assert_eq!(board.fullmove_clock(), 17);
+        assert_eq!(board.get(C6), Some((Piece::Knight, Color::Black)));
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_fen_after_moves() {
+        // The fullmove number only goes up once black has moved.
+        let moves = [
+            (
+                std_move!(E2, E4),
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            ),
+            (
+                std_move!(D7, D5),
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            ),
+            (
+                std_move!(E4, E5),
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+            ),
+            (
+                std_move!(F7, F5),
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            ),
+            (
+                en_passant_move!(E5, F6),
+                "rnbqkbnr/ppp1p1pp/5P2/3p4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3",
+            ),
+            (
+                std_move!(G8, F6, Capture(Piece::Pawn)),
+                "rnbqkb1r/ppp1p1pp/5n2/3p4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 4",
+            ),
+        ];
+        let mut board = Board::starting_position();
+        for (chess_move, fen) in moves.iter() {
+            chess_move.apply(&mut board).unwrap();
+            board.toggle_turn();
+            assert_eq!(board.to_fen(), *fen);
+        }
+        for (chess_move, _) in moves.iter().rev() {
+            board.toggle_turn();
+            chess_move.undo(&mut board).unwrap();
+        }
+        assert_eq!(board.to_fen(), STARTING_POSITION_FEN);
+    }
+
+    #[test]
+    fn test_fen_with_long_game_clocks() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 b - - 299 412";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.halfmove_clock(), 299);
+        assert_eq!(board.fullmove_clock(), 412);
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_fen_without_clocks() {
+        let board = Board::from_fen("8/8/8/8/8/8/8/K6k w - -").unwrap();
+        assert_eq!(board.to_fen(), "8/8/8/8/8/8/8/K6k w - - 0 1");
+    }
+
+    #[test]
+    fn test_fen_with_castling_rights_by_file() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        for rights in ["HAha", "KAhq", "AHah"] {
+            let by_file = fen.replace("KQkq", rights);
+            assert!(Board::from_fen(&by_file).unwrap() == board, "{}", rights);
+        }
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b Ha - 0 1").unwrap();
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 0 1");
+        assert!(Board::from_fen(&board.to_fen()).unwrap() == board);
+
+        assert_eq!(
+            Board::from_fen("1r2k3/8/8/8/8/8/8/4K3 w b - 0 1").err(),
+            Some(FenError::UnsupportedCastlingFile { right: 'b' })
+        );
+        assert_eq!(
+            Board::from_fen("4k3/8/8/8/8/8/8/4K3 w H - 0 1").err(),
+            Some(FenError::ImpossibleCastlingRight {
+                right: 'K',
+                color: Color::White,
+                king: "e1".to_string(),
+                rook: "h1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_lenient_fen_drops_impossible_castling_rights() {
+        // The black queenside rook has moved, and the white king has castled.
+        let fen = "1r2k2r/8/8/8/8/8/8/R4RK1 w KQkq - 0 1";
+        assert_eq!(
+            Board::from_fen(fen).err().map(|error| error.to_string()),
+            Some("castling right 'K' needs a white king on e1 and a white rook on h1".to_string())
+        );
+        let board = Board::from_fen_lenient(fen).unwrap();
+        assert_eq!(board.peek_castle_rights(), BLACK_KINGSIDE_RIGHTS);
+        assert_eq!(board.to_fen(), "1r2k2r/8/8/8/8/8/8/R4RK1 w k - 0 1");
+    }
+
+    #[test]
+    fn test_invalid_fen() {
+        assert_eq!(
+            Board::from_fen("8/8/8 w - - 0 1").err(),
+            Some(FenError::InvalidPiecePlacement {
+                placement: "8/8/8".to_string()
+            })
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k x - - 0 1").err(),
+            Some(FenError::InvalidActiveColor {
+                color: "x".to_string()
+            })
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k w KX - 0 1").err(),
+            Some(FenError::InvalidCastlingRights {
+                rights: "KX".to_string()
+            })
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k w K - 0 1").err(),
+            Some(FenError::ImpossibleCastlingRight {
+                right: 'K',
+                color: Color::White,
+                king: "e1".to_string(),
+                rook: "h1".to_string()
+            })
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k w - e4 0 1").err(),
+            Some(FenError::InvalidEnPassantTarget {
+                target: "e4".to_string()
+            })
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k w - -  0").err(),
+            Some(FenError::WrongFieldCount { count: 5 })
+        );
+    }
+}
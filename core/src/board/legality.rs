@@ -0,0 +1,238 @@
+use common::bitboard::bitboard::Bitboard;
+use common::bitboard::square::*;
+
+use crate::chess_move::chess_move::ChessMove;
+use crate::move_generator::targets::{pawn_attack_targets, Targets};
+
+use super::castle_rights_bitmask::{
+    BLACK_KINGSIDE_RIGHTS, BLACK_QUEENSIDE_RIGHTS, WHITE_KINGSIDE_RIGHTS, WHITE_QUEENSIDE_RIGHTS,
+};
+use super::color::Color;
+use super::piece::Piece;
+use super::Board;
+
+impl Board {
+    /// Whether the side to move can play `chess_move`: its piece is on the
+    /// from-square, it can reach the to-square, it captures what's there, and
+    /// it doesn't leave its own king in check. Only this one move is looked
+    /// at, so it's a cheap way to check a move from outside the engine.
+    pub fn is_legal(&mut self, chess_move: &ChessMove) -> bool {
+        if !self.is_reachable(chess_move) {
+            return false;
+        }
+
+        // As the move generator does, play the move and see whether the king
+        // is left in check.
+        if chess_move.apply(self).is_err() {
+            return false;
+        }
+        let leaves_king_in_check = self.is_in_check();
+        chess_move
+            .undo(self)
+            .expect("a move that was applied can be undone");
+
+        !leaves_king_in_check
+    }
+
+    fn is_reachable(&self, chess_move: &ChessMove) -> bool {
+        let color = self.turn;
+        let from_square = chess_move.from_square();
+        let to_square = chess_move.to_square();
+        if from_square.count_ones() != 1 || to_square.count_ones() != 1 {
+            return false;
+        }
+
+        let piece = match self.pieces(color).get(from_square) {
+            Some(piece) => piece,
+            None => return false,
+        };
+        let captured = self.pieces(color.opposite()).get(to_square);
+        let promotion_rank = match color {
+            Color::White => Bitboard::RANK_8,
+            Color::Black => Bitboard::RANK_1,
+        };
+
+        match chess_move {
+            ChessMove::Standard(m) => {
+                if m.captures().map(|capture| capture.0) != captured {
+                    return false;
+                }
+                match piece {
+                    Piece::Pawn => {
+                        !to_square.overlaps(promotion_rank)
+                            && self.pawn_can_reach(from_square, to_square)
+                    }
+                    _ => Targets::shared()
+                        .piece_targets(self, color, from_square, piece)
+                        .overlaps(to_square),
+                }
+            }
+            ChessMove::PawnPromotion(m) => {
+                piece == Piece::Pawn
+                    && m.captures().map(|capture| capture.0) == captured
+                    && to_square.overlaps(promotion_rank)
+                    && matches!(
+                        m.promote_to_piece(),
+                        Piece::Knight | Piece::Bishop | Piece::Rook | Piece::Queen
+                    )
+                    && self.pawn_can_reach(from_square, to_square)
+            }
+            ChessMove::EnPassant(_) => {
+                let captured_square = match color {
+                    Color::White => to_square >> 8,
+                    Color::Black => to_square << 8,
+                };
+                piece == Piece::Pawn
+                    && to_square == self.peek_en_passant_target()
+                    && pawn_attack_targets(from_square, color).overlaps(to_square)
+                    && self.pieces(color.opposite()).get(captured_square) == Some(Piece::Pawn)
+            }
+            ChessMove::Castle(_) => piece == Piece::King && self.can_castle_to(to_square),
+        }
+    }
+
+    /// Whether the side to move's pawn on `from_square` can push or capture to
+    /// `to_square`.
+    fn pawn_can_reach(&self, from_square: Bitboard, to_square: Bitboard) -> bool {
+        let color = self.turn;
+        let occupied = self.occupied();
+
+        if pawn_attack_targets(from_square, color).overlaps(to_square) {
+            return self.pieces(color.opposite()).is_occupied(to_square);
+        }
+
+        let (single_move, starting_rank) = match color {
+            Color::White => (from_square << 8, Bitboard::RANK_2),
+            Color::Black => (from_square >> 8, Bitboard::RANK_7),
+        };
+        if single_move.overlaps(occupied) {
+            return false;
+        }
+        if to_square == single_move {
+            return true;
+        }
+
+        let double_move = match color {
+            Color::White => single_move << 8,
+            Color::Black => single_move >> 8,
+        };
+        from_square.overlaps(starting_rank)
+            && to_square == double_move
+            && !double_move.overlaps(occupied)
+    }
+
+    /// Whether the side to move's king can castle to `to_square`, short of
+    /// landing in check: it has the right, the squares between it and its
+    /// rook are empty, and it isn't in or passing through check.
+    fn can_castle_to(&self, to_square: Bitboard) -> bool {
+        let color = self.turn;
+        let castle_rights = self.peek_castle_rights();
+        let (rights, transit_square, empty_squares) = match (color, to_square) {
+            (Color::White, G1) => (WHITE_KINGSIDE_RIGHTS, F1, F1 | G1),
+            (Color::White, C1) => (WHITE_QUEENSIDE_RIGHTS, D1, B1 | C1 | D1),
+            (Color::Black, G8) => (BLACK_KINGSIDE_RIGHTS, F8, F8 | G8),
+            (Color::Black, C8) => (BLACK_QUEENSIDE_RIGHTS, D8, B8 | C8 | D8),
+            _ => return false,
+        };
+
+        castle_rights & rights > 0
+            && !empty_squares.overlaps(self.occupied())
+            && !self.is_in_check()
+            && Targets::shared()
+                .attackers_to(self, transit_square, color.opposite())
+                .is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::capture::Capture;
+    use crate::chess_move::castle::CastleChessMove;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::en_passant::EnPassantChessMove;
+    use crate::chess_move::pawn_promotion::PawnPromotionChessMove;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::move_generator::MoveGenerator;
+    use crate::{castle_kingside, castle_queenside, en_passant_move, promotion, std_move};
+
+    #[test]
+    fn test_is_legal_agrees_with_the_move_generator() {
+        let mut move_generator = MoveGenerator::new();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let turn = board.turn();
+            let legal_moves = move_generator.generate_moves(&mut board, turn);
+            for chess_move in legal_moves.iter() {
+                assert!(board.is_legal(chess_move), "{} in {}", chess_move, fen);
+            }
+
+            // Every move of any piece to any square, legal or not.
+            let occupied = board.occupied();
+            for from_square in ORDERED_SQUARES {
+                if !occupied.overlaps(from_square) {
+                    continue;
+                }
+                for to_square in ORDERED_SQUARES {
+                    let captures = board.get(to_square).map(|(piece, _)| Capture(piece));
+                    let chess_move = ChessMove::Standard(StandardChessMove::new(
+                        from_square,
+                        to_square,
+                        captures,
+                    ));
+                    assert_eq!(
+                        board.is_legal(&chess_move),
+                        legal_moves.contains(&chess_move),
+                        "{} in {}",
+                        chess_move,
+                        fen
+                    );
+                }
+            }
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_is_legal_rejects_mismatched_moves() {
+        let mut board = Board::from_fen("r3k2r/8/8/3pP3/8/8/1p6/R3K2R w KQkq d6 0 1").unwrap();
+
+        assert!(board.is_legal(&en_passant_move!(E5, D6)));
+        assert!(board.is_legal(&castle_kingside!(Color::White)));
+        assert!(board.is_legal(&std_move!(E5, E6)));
+        // Castling queenside would put the king in check from the pawn.
+        assert!(!board.is_legal(&castle_queenside!(Color::White)));
+
+        // The wrong side's move.
+        assert!(!board.is_legal(&castle_kingside!(Color::Black)));
+        assert!(!board.is_legal(&promotion!(B2, B1, None, Piece::Queen)));
+        // A capture of a piece that isn't there, and a quiet move onto one.
+        assert!(!board.is_legal(&std_move!(A1, A8)));
+        assert!(!board.is_legal(&std_move!(A1, A7, Capture(Piece::Rook))));
+        // A pawn can't be taken en passant off a square other than the target.
+        assert!(!board.is_legal(&en_passant_move!(E5, F6)));
+
+        board.toggle_turn();
+        // A pawn reaching the last rank has to promote, to a piece it can be.
+        assert!(!board.is_legal(&std_move!(B2, B1)));
+        assert!(!board.is_legal(&promotion!(B2, B1, None, Piece::King)));
+        assert!(board.is_legal(&promotion!(B2, B1, None, Piece::Knight)));
+        assert!(board.is_legal(&promotion!(
+            B2,
+            A1,
+            Some(Capture(Piece::Rook)),
+            Piece::Queen
+        )));
+        // Black can't castle queenside through the rook's attack on d8...
+        board.remove(D5).unwrap();
+        board.put(D1, Piece::Rook, Color::White).unwrap();
+        assert!(!board.is_legal(&castle_queenside!(Color::Black)));
+        // ...but can castle kingside.
+        assert!(board.is_legal(&castle_kingside!(Color::Black)));
+    }
+}
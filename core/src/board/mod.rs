@@ -0,0 +1,823 @@
+pub mod castle_rights_bitmask;
+pub mod color;
+pub mod diff;
+pub mod epd;
+pub mod error;
+pub mod fen;
+pub mod packed;
+pub mod piece;
+pub mod piece_square_values;
+pub mod polyglot;
+pub mod zobrist_version;
+
+mod display;
+mod legality;
+mod move_info;
+mod piece_set;
+mod position_info;
+
+use color::Color;
+use common::bitboard::bitboard::Bitboard;
+use error::BoardError;
+use piece::Piece;
+use piece_set::PieceSet;
+use piece_square_values::{PieceSquareScore, PieceSquareValues};
+use rustc_hash::FxHashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+#[cfg(feature = "nnue")]
+use crate::nnue::{Accumulator, Network};
+
+use crate::chess_position;
+use crate::move_generator::targets::Targets;
+
+use self::{
+    castle_rights_bitmask::CastleRightsBitmask, move_info::MoveInfo, position_info::PositionInfo,
+};
+
+/// Represents the state of a chess board. The top level struct holds piece position
+/// info, whereas the lower level `move_info` and `position_info` structs hold state
+/// related to en passant targets, castling rights, and zobrist hashing.
+#[derive(Clone)]
+pub struct Board {
+    white: PieceSet,
+    black: PieceSet,
+    turn: Color,
+    move_info: MoveInfo,
+    position_info: PositionInfo,
+    /// The network's hidden layer for the position, when the board is
+    /// evaluated with NNUE.
+    #[cfg(feature = "nnue")]
+    nnue: Option<Box<Accumulator>>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self {
+            white: PieceSet::new(),
+            black: PieceSet::new(),
+            turn: Color::White,
+            move_info: MoveInfo::new(),
+            position_info: PositionInfo::new(),
+            #[cfg(feature = "nnue")]
+            nnue: None,
+        }
+    }
+}
+
+/// Boards are equal when their positions are the same by the repetition rule:
+/// the same pieces on the same squares, the same side to move, and the same
+/// castle rights and en passant target. An en passant target no pawn can
+/// capture on counts as no target, so a double pawn push that can't be taken
+/// en passant reaches the same position as two single pushes. The clocks and
+/// the moves that led to the position don't matter.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.white == other.white
+            && self.black == other.black
+            && self.turn == other.turn
+            && self.peek_castle_rights() == other.peek_castle_rights()
+            && self.peek_capturable_en_passant_target() == other.peek_capturable_en_passant_target()
+    }
+}
+
+impl Eq for Board {}
+
+/// The zobrist hash covers the same things that equality compares.
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.current_position_hash());
+    }
+}
+
+impl Board {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn pieces(&self, color: Color) -> &PieceSet {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    pub fn starting_position() -> Self {
+        chess_position! {
+            rnbqkbnr
+            pppppppp
+            ........
+            ........
+            ........
+            ........
+            PPPPPPPP
+            RNBQKBNR
+        }
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.white.occupied() | self.black.occupied()
+    }
+
+    pub fn is_occupied(&self, square: Bitboard) -> bool {
+        !(self.occupied() & square).is_empty()
+    }
+
+    pub fn get(&self, square: Bitboard) -> Option<(Piece, Color)> {
+        let color = if self.white.is_occupied(square) {
+            Color::White
+        } else if self.black.is_occupied(square) {
+            Color::Black
+        } else {
+            return None;
+        };
+
+        let maybe_piece = match color {
+            Color::White => self.white.get(square),
+            Color::Black => self.black.get(square),
+        };
+
+        maybe_piece.map(|piece| (piece, color))
+    }
+
+    /// Every piece on the board, with its square and color, from A1 to H8.
+    pub fn iter(&self) -> impl Iterator<Item = (Bitboard, Piece, Color)> + '_ {
+        let mut occupied = self.occupied();
+        std::iter::from_fn(move || {
+            if occupied.is_empty() {
+                return None;
+            }
+            let square = occupied.pop_lsb();
+            let (piece, color) = self.get(square)?;
+            Some((square, piece, color))
+        })
+    }
+
+    #[deprecated(note = "renamed to `iter`")]
+    pub fn iter_pieces(&self) -> impl Iterator<Item = (Bitboard, Piece, Color)> + '_ {
+        self.iter()
+    }
+
+    pub fn put(&mut self, square: Bitboard, piece: Piece, color: Color) -> Result<(), BoardError> {
+        if self.is_occupied(square) {
+            return Err(BoardError::SquareOccupiedBoardPutError);
+        }
+
+        let result = match color {
+            Color::White => self.white.put(square, piece),
+            Color::Black => self.black.put(square, piece),
+        };
+
+        if result.is_ok() {
+            self.position_info
+                .update_zobrist_hash_toggle_piece(square, piece, color);
+            self.position_info
+                .update_piece_square_scores_put(square, piece, color);
+            #[cfg(feature = "nnue")]
+            if let Some(mut nnue) = self.nnue.take() {
+                nnue.on_put(self, square, piece, color);
+                self.nnue = Some(nnue);
+            }
+        }
+
+        result
+    }
+
+    pub fn remove(&mut self, square: Bitboard) -> Option<(Piece, Color)> {
+        let (piece, color) = self.get(square)?;
+        match color {
+            Color::White => self.white.remove(square),
+            Color::Black => self.black.remove(square),
+        }?;
+        self.position_info
+            .update_zobrist_hash_toggle_piece(square, piece, color);
+        self.position_info
+            .update_piece_square_scores_remove(square, piece, color);
+        #[cfg(feature = "nnue")]
+        if let Some(nnue) = &mut self.nnue {
+            nnue.on_remove(square, piece, color);
+        }
+        Some((piece, color))
+    }
+
+    /// Keeps running totals of each player's pieces' `values` as pieces are put
+    /// and removed, or stops keeping them given `None`.
+    pub fn set_piece_square_values(&mut self, values: Option<Arc<dyn PieceSquareValues>>) {
+        self.position_info.set_piece_square_values(values);
+        for (square, piece, color) in self.iter().collect::<Vec<_>>() {
+            self.position_info
+                .update_piece_square_scores_put(square, piece, color);
+        }
+    }
+
+    pub fn piece_square_values(&self) -> Option<&Arc<dyn PieceSquareValues>> {
+        self.position_info.piece_square_values()
+    }
+
+    /// The running totals of the player's pieces' values, if the board keeps
+    /// them with `values`, and `None` if it keeps none or keeps them with
+    /// other values.
+    #[inline(always)]
+    pub fn piece_square_score<V: ?Sized>(
+        &self,
+        color: Color,
+        values: &Arc<V>,
+    ) -> Option<PieceSquareScore> {
+        self.position_info.piece_square_score(color, values)
+    }
+
+    /// Evaluates the board with `network`, keeping its accumulator up to date
+    /// as pieces move, or stops evaluating it with a network given `None`.
+    #[cfg(feature = "nnue")]
+    pub fn set_nnue(&mut self, network: Option<Arc<Network>>) {
+        self.nnue = network.map(|network| Box::new(Accumulator::new(network, self)));
+    }
+
+    #[cfg(feature = "nnue")]
+    pub fn nnue(&self) -> Option<&Accumulator> {
+        self.nnue.as_deref()
+    }
+
+    /// The network's score for the position, from white's perspective, or
+    /// `None` if the board isn't evaluated with NNUE.
+    #[cfg(feature = "nnue")]
+    pub fn nnue_score(&self) -> Option<i16> {
+        let score = self.nnue.as_ref()?.evaluate(self.turn)?;
+        Some(match self.turn {
+            Color::White => score,
+            Color::Black => -score,
+        })
+    }
+
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    /// The squares of the pieces checking the side to move's king.
+    pub fn checkers(&self) -> Bitboard {
+        let king = self.pieces(self.turn).locate(Piece::King);
+        if king.is_empty() {
+            return Bitboard::EMPTY;
+        }
+        Targets::shared().attackers_to(self, king, self.turn.opposite())
+    }
+
+    pub fn is_in_check(&self) -> bool {
+        !self.checkers().is_empty()
+    }
+
+    /// The same position with the colors swapped: the board flipped top to
+    /// bottom, each piece changed to the other color, and the other side to
+    /// move, with the castle rights and en passant target to match. Only the
+    /// current position is kept, not the moves that led to it.
+    pub fn mirrored(&self) -> Self {
+        let flip = |square: Bitboard| Bitboard(square.0.swap_bytes());
+        let mut mirrored = Board::new();
+        for (square, piece, color) in self.iter() {
+            mirrored
+                .put(flip(square), piece, color.opposite())
+                .expect("each flipped square is empty");
+        }
+        mirrored.set_turn(self.turn.opposite());
+        // Each white right becomes the matching black right, and vice versa.
+        let rights = self.peek_castle_rights();
+        let mirrored_rights = ((rights & 0b1010) >> 1) | ((rights & 0b0101) << 1);
+        mirrored.lose_castle_rights(castle_rights_bitmask::ALL_CASTLE_RIGHTS & !mirrored_rights);
+        let en_passant_target = self.peek_en_passant_target();
+        if !en_passant_target.is_empty() {
+            mirrored.push_en_passant_target(flip(en_passant_target));
+        }
+        mirrored.push_halfmove_clock(self.halfmove_clock());
+        mirrored.set_fullmove_clock(self.fullmove_clock());
+        mirrored.set_piece_square_values(self.piece_square_values().cloned());
+        #[cfg(feature = "nnue")]
+        mirrored.set_nnue(self.nnue.as_ref().map(|nnue| nnue.network().clone()));
+        mirrored
+    }
+
+    pub fn toggle_turn(&mut self) -> Color {
+        self.turn = self.turn.opposite();
+        self.position_info.update_zobrist_hash_toggle_turn();
+        self.turn
+    }
+
+    pub fn set_turn(&mut self, turn: Color) -> Color {
+        if turn != self.turn {
+            self.toggle_turn();
+        }
+        turn
+    }
+
+    /// Passes the turn without moving, for null-move pruning and for seeing
+    /// what the opponent would do if it were their move. Like a quiet move, it
+    /// advances both clocks, clears the en passant target, and keeps the castle
+    /// rights, but unlike a move, it also flips the turn, and the position isn't
+    /// counted for repetitions. Undo it with `undo_null_move`.
+    pub fn apply_null_move(&mut self) -> Color {
+        self.increment_halfmove_clock();
+        self.increment_fullmove_clock(self.turn);
+        self.push_en_passant_target(Bitboard::EMPTY);
+        self.preserve_castle_rights();
+        self.toggle_turn()
+    }
+
+    pub fn undo_null_move(&mut self) -> Color {
+        self.pop_castle_rights();
+        self.pop_en_passant_target();
+        self.decrement_fullmove_clock(self.turn.opposite());
+        self.pop_halfmove_clock();
+        self.toggle_turn()
+    }
+
+    pub fn push_en_passant_target(&mut self, target_square: Bitboard) -> Bitboard {
+        // Whether a pawn can capture on the target is decided once, here:
+        // only a double pawn push sets a target, and it doesn't move any of
+        // the pawns that could capture on it.
+        let capturable_target_square = if self.can_capture_en_passant_on(target_square) {
+            target_square
+        } else {
+            Bitboard::EMPTY
+        };
+        // The hash holds only the current target, so the previous one is
+        // toggled out as the new one is toggled in.
+        self.position_info
+            .update_zobrist_hash_toggle_en_passant_target(self.peek_capturable_en_passant_target());
+        self.position_info
+            .update_zobrist_hash_toggle_en_passant_target(capturable_target_square);
+        self.move_info
+            .push_en_passant_target(target_square, capturable_target_square)
+    }
+
+    pub fn peek_en_passant_target(&self) -> Bitboard {
+        self.move_info.peek_en_passant_target()
+    }
+
+    /// The en passant target if a pawn can capture on it, or an empty
+    /// bitboard. Equality and the zobrist hash only see this target.
+    pub fn peek_capturable_en_passant_target(&self) -> Bitboard {
+        self.move_info.peek_capturable_en_passant_target()
+    }
+
+    pub fn pop_en_passant_target(&mut self) -> Bitboard {
+        let (target_square, capturable_target_square) = self.move_info.pop_en_passant_target();
+        self.position_info
+            .update_zobrist_hash_toggle_en_passant_target(capturable_target_square);
+        self.position_info
+            .update_zobrist_hash_toggle_en_passant_target(self.peek_capturable_en_passant_target());
+        target_square
+    }
+
+    /// Whether a pawn stands beside the pawn that just passed over `target`,
+    /// ready to capture it en passant. The pin of that pawn isn't checked.
+    fn can_capture_en_passant_on(&self, target: Bitboard) -> bool {
+        let (capturer, attackers) = if target.overlaps(Bitboard::RANK_3) {
+            let attackers =
+                ((target << 7) & !Bitboard::H_FILE) | ((target << 9) & !Bitboard::A_FILE);
+            (Color::Black, attackers)
+        } else {
+            let attackers =
+                ((target >> 7) & !Bitboard::A_FILE) | ((target >> 9) & !Bitboard::H_FILE);
+            (Color::White, attackers)
+        };
+        attackers.overlaps(self.pieces(capturer).locate(Piece::Pawn))
+    }
+
+    pub fn preserve_castle_rights(&mut self) -> CastleRightsBitmask {
+        // zobrist does not change
+        self.move_info.preserve_castle_rights()
+    }
+
+    pub fn peek_castle_rights(&self) -> u8 {
+        self.move_info.peek_castle_rights()
+    }
+
+    pub fn lose_castle_rights(&mut self, lost_rights: CastleRightsBitmask) -> CastleRightsBitmask {
+        let (old_rights, new_rights) = self.move_info.lose_castle_rights(lost_rights);
+        self.position_info
+            .update_zobrist_hash_toggle_castling_rights(old_rights);
+        self.position_info
+            .update_zobrist_hash_toggle_castling_rights(new_rights);
+        new_rights
+    }
+
+    pub fn pop_castle_rights(&mut self) -> CastleRightsBitmask {
+        let (old_rights, new_rights) = self.move_info.pop_castle_rights();
+        self.position_info
+            .update_zobrist_hash_toggle_castling_rights(old_rights);
+        self.position_info
+            .update_zobrist_hash_toggle_castling_rights(new_rights);
+        new_rights
+    }
+
+    /// Counts a move by `color`. As in FEN, the clock numbers full moves, so
+    /// it only advances once black has moved.
+    pub fn increment_fullmove_clock(&mut self, color: Color) -> u16 {
+        match color {
+            Color::White => self.move_info.fullmove_clock(),
+            Color::Black => self.move_info.increment_fullmove_clock(),
+        }
+    }
+
+    /// Takes back a move by `color`, undoing `increment_fullmove_clock`.
+    pub fn decrement_fullmove_clock(&mut self, color: Color) -> u16 {
+        match color {
+            Color::White => self.move_info.fullmove_clock(),
+            Color::Black => self.move_info.decrement_fullmove_clock(),
+        }
+    }
+
+    pub fn set_fullmove_clock(&mut self, clock: u16) -> u16 {
+        self.move_info.set_fullmove_clock(clock)
+    }
+
+    pub fn fullmove_clock(&self) -> u16 {
+        self.move_info.fullmove_clock()
+    }
+
+    pub fn push_halfmove_clock(&mut self, clock: u16) -> u16 {
+        self.move_info.push_halfmove_clock(clock)
+    }
+
+    pub fn increment_halfmove_clock(&mut self) -> u16 {
+        self.move_info.increment_halfmove_clock()
+    }
+
+    pub fn reset_halfmove_clock(&mut self) -> u16 {
+        self.move_info.reset_halfmove_clock()
+    }
+
+    pub fn halfmove_clock(&self) -> u16 {
+        self.move_info.halfmove_clock()
+    }
+
+    pub fn pop_halfmove_clock(&mut self) -> u16 {
+        self.move_info.pop_halfmove_clock()
+    }
+
+    /// For debugging: the halfmove clock before each move applied to the
+    /// board, oldest first, ending with the current clock.
+    pub fn halfmove_clock_stack(&self) -> &[u16] {
+        self.move_info.halfmove_clock_stack()
+    }
+
+    /// For debugging: the castle rights before each move applied to the
+    /// board, oldest first, ending with the current rights.
+    pub fn castle_rights_stack(&self) -> &[CastleRightsBitmask] {
+        self.move_info.castle_rights_stack()
+    }
+
+    // PositionInfo delegation
+
+    pub fn count_current_position(&mut self) -> u8 {
+        self.position_info.count_current_position()
+    }
+
+    pub fn uncount_current_position(&mut self) -> u8 {
+        self.position_info.uncount_current_position()
+    }
+
+    pub fn max_seen_position_count(&self) -> u8 {
+        self.position_info.max_seen_position_count()
+    }
+
+    /// For debugging: how many times each counted position has been seen, by
+    /// hash.
+    pub fn position_counts(&self) -> &FxHashMap<u64, u8> {
+        self.position_info.position_counts()
+    }
+
+    pub fn current_position_hash(&self) -> u64 {
+        self.position_info.current_position_hash()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::{castle_kingside, std_move};
+
+    use super::*;
+    use crate::chess_move::capture::Capture;
+    use crate::chess_move::castle::CastleChessMove;
+    use crate::chess_move::chess_move::ChessMove;
+    use crate::chess_move::standard::StandardChessMove;
+    use common::bitboard::square::*;
+    use rustc_hash::FxHashSet;
+
+    #[test]
+    fn test_iter() {
+        let board = Board::starting_position();
+        let pieces: Vec<_> = board.iter().collect();
+        assert_eq!(pieces.len(), 32);
+        assert_eq!(pieces[0], (A1, Piece::Rook, Color::White));
+        assert_eq!(pieces[4], (E1, Piece::King, Color::White));
+        assert_eq!(pieces[31], (H8, Piece::Rook, Color::Black));
+        assert!(pieces
+            .iter()
+            .all(|&(square, piece, color)| board.get(square) == Some((piece, color))));
+
+        assert_eq!(Board::new().iter().count(), 0);
+
+        let white: Vec<_> = board.pieces(Color::White).iter().collect();
+        assert_eq!(white.len(), 16);
+        assert_eq!(white[0], (A1, Piece::Rook));
+        let rooks: Vec<_> = board
+            .pieces(Color::Black)
+            .iter_pieces(Piece::Rook)
+            .collect();
+        assert_eq!(rooks, [A8, H8]);
+        assert_eq!(
+            board.pieces(Color::White).iter_pieces(Piece::Pawn).count(),
+            8
+        );
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let board =
+            Board::from_fen("r3k2r/pp1n1ppp/8/2pP4/8/8/PPP2PPP/R3K1NR w KQk c6 0 12").unwrap();
+        let mirrored = board.mirrored();
+        assert_eq!(
+            mirrored.to_fen(),
+            "r3k1nr/ppp2ppp/8/8/2Pp4/8/PP1N1PPP/R3K2R b Kkq c3 0 12"
+        );
+        assert!(mirrored.mirrored() == board);
+    }
+
+    #[test]
+    fn test_checkers() {
+        let board = Board::starting_position();
+        assert_eq!(board.checkers(), Bitboard::EMPTY);
+        assert!(!board.is_in_check());
+
+        let board = Board::from_fen("4k3/8/8/8/8/3n4/8/r3K3 w - - 0 1").unwrap();
+        assert_eq!(board.checkers(), A1 | D3);
+        assert!(board.is_in_check());
+
+        let board = Board::from_fen("4k3/8/8/1B6/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(board.checkers(), B5);
+        // Only the side to move's king counts.
+        let board = Board::from_fen("4k3/8/8/1B6/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!board.is_in_check());
+    }
+
+    #[test]
+    fn test_position_equality() {
+        let mut board = Board::starting_position();
+        let mut transposed = Board::starting_position();
+        for (board, moves) in [
+            (
+                &mut board,
+                [std_move!(G1, F3), std_move!(G8, F6), std_move!(F3, G1)],
+            ),
+            (
+                &mut transposed,
+                [std_move!(B1, C3), std_move!(G8, F6), std_move!(C3, B1)],
+            ),
+        ] {
+            for chess_move in moves.iter() {
+                chess_move.apply(board).unwrap();
+                board.toggle_turn();
+            }
+        }
+
+        // The same position, reached by different moves, or with different
+        // clocks.
+        assert!(board == transposed);
+        let fen = "rnbqkb1r/pppppppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 7 30";
+        assert!(board == Board::from_fen(fen).unwrap());
+        let positions: FxHashSet<Board> = vec![board.clone(), transposed].into_iter().collect();
+        assert_eq!(positions.len(), 1);
+
+        // The same pieces, but a different side to move...
+        let mut other_turn = board.clone();
+        other_turn.toggle_turn();
+        assert!(board != other_turn);
+        // ...or castle rights.
+        let mut without_castling = board.clone();
+        without_castling.lose_castle_rights(castle_rights_bitmask::WHITE_KINGSIDE_RIGHTS);
+        assert!(board != without_castling);
+    }
+
+    #[test]
+    fn test_null_move() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 3 2";
+        let mut board = Board::from_fen(fen).unwrap();
+        let hash = board.current_position_hash();
+        let position_counts = board.position_counts().clone();
+
+        assert_eq!(board.apply_null_move(), Color::Black);
+        let passed = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 4 2";
+        assert_eq!(board.to_fen(), passed);
+        assert_eq!(
+            board.current_position_hash(),
+            Board::from_fen(passed).unwrap().current_position_hash()
+        );
+        assert_eq!(board.position_counts(), &position_counts);
+
+        assert_eq!(board.undo_null_move(), Color::White);
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.current_position_hash(), hash);
+    }
+
+    /// Values pieces by their kind, and their placement by the square's index.
+    struct SquareIndexValues;
+
+    impl PieceSquareValues for SquareIndexValues {
+        fn material(&self, piece: Piece) -> i16 {
+            100 * (piece as i16 + 1)
+        }
+
+        fn bonuses(&self, _: Piece, color: Color, square: Bitboard) -> (i16, i16) {
+            let index = square.trailing_zeros() as i16;
+            match color {
+                Color::White => (index, -index),
+                Color::Black => (63 - index, index - 63),
+            }
+        }
+    }
+
+    #[test]
+    fn test_piece_square_scores_follow_the_pieces() {
+        let values: Arc<dyn PieceSquareValues> = Arc::new(SquareIndexValues);
+        let mut board = Board::starting_position();
+        assert_eq!(board.piece_square_score(Color::White, &values), None);
+
+        board.set_piece_square_values(Some(values.clone()));
+        let starting_score = board.piece_square_score(Color::White, &values).unwrap();
+        assert_eq!(starting_score.material, 100 * (8 + 4 + 6 + 8 + 5 + 6));
+        assert_eq!(
+            board.piece_square_score(Color::Black, &values),
+            Some(starting_score)
+        );
+        // Equal values that the board doesn't keep its totals with don't count.
+        let other: Arc<dyn PieceSquareValues> = Arc::new(SquareIndexValues);
+        assert_eq!(board.piece_square_score(Color::White, &other), None);
+
+        let moves = [
+            std_move!(E2, E4),
+            std_move!(D7, D5),
+            std_move!(E4, D5, Capture(Piece::Pawn)),
+        ];
+        for chess_move in &moves {
+            chess_move.apply(&mut board).unwrap();
+            board.toggle_turn();
+        }
+        let mut fresh = Board::from_fen(&board.to_fen()).unwrap();
+        fresh.set_piece_square_values(Some(values.clone()));
+        for color in [Color::White, Color::Black] {
+            assert_eq!(
+                board.piece_square_score(color, &values),
+                fresh.piece_square_score(color, &values)
+            );
+        }
+
+        for chess_move in moves.iter().rev() {
+            board.toggle_turn();
+            chess_move.undo(&mut board).unwrap();
+        }
+        assert_eq!(
+            board.piece_square_score(Color::White, &values),
+            Some(starting_score)
+        );
+    }
+
+    #[test]
+    fn test_zobrist_hash_drops_en_passant_target_after_next_move() {
+        let mut board = Board::starting_position();
+        std_move!(E2, E4).apply(&mut board).unwrap();
+        let hash_with_target = board.current_position_hash();
+        std_move!(G8, F6).apply(&mut board).unwrap();
+        let fen = board.to_fen();
+        assert_eq!(
+            board.current_position_hash(),
+            Board::from_fen(&fen).unwrap().current_position_hash()
+        );
+
+        std_move!(G8, F6).undo(&mut board).unwrap();
+        assert_eq!(board.current_position_hash(), hash_with_target);
+    }
+
+    #[test]
+    fn test_uncapturable_en_passant_target_transposes_with_single_pushes() {
+        // 1. Nf3 a6 2. Ng1 a5 3. e4, where no black pawn can take on e3.
+        let mut double_push = Board::starting_position();
+        for chess_move in [
+            std_move!(G1, F3),
+            std_move!(A7, A6),
+            std_move!(F3, G1),
+            std_move!(A6, A5),
+            std_move!(E2, E4),
+        ] {
+            chess_move.apply(&mut double_push).unwrap();
+            double_push.toggle_turn();
+        }
+        // 1. e3 a5 2. e4
+        let mut single_pushes = Board::starting_position();
+        for chess_move in [std_move!(E2, E3), std_move!(A7, A5), std_move!(E3, E4)] {
+            chess_move.apply(&mut single_pushes).unwrap();
+            single_pushes.toggle_turn();
+        }
+
+        assert_eq!(double_push.peek_en_passant_target(), E3);
+        assert!(single_pushes.peek_en_passant_target().is_empty());
+        assert!(double_push == single_pushes);
+        assert_eq!(
+            double_push.current_position_hash(),
+            single_pushes.current_position_hash()
+        );
+
+        // A target a pawn can capture on still tells the positions apart.
+        let capturable = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let uncapturable = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        assert!(capturable != uncapturable);
+        assert_ne!(
+            capturable.current_position_hash(),
+            uncapturable.current_position_hash()
+        );
+    }
+
+    #[test]
+    fn test_zobrist_hashing_is_equal_for_transpositions() {
+        let mut board1 = Board::starting_position();
+        let mut board2 = Board::starting_position();
+        let initial_hash_1 = board1.current_position_hash();
+        let initial_hash_2 = board2.current_position_hash();
+        assert_eq!(initial_hash_1, initial_hash_2);
+
+        let board1_moves = [
+            std_move!(E2, E4),
+            std_move!(E7, E5),
+            std_move!(G1, F3),
+            std_move!(B8, C6),
+            std_move!(F1, C4),
+            std_move!(G8, F6),
+            castle_kingside!(Color::White),
+        ];
+
+        let board2_moves = [
+            std_move!(G1, F3),
+            std_move!(B8, C6),
+            std_move!(E2, E4),
+            std_move!(E7, E5),
+            std_move!(F1, C4),
+            std_move!(G8, F6),
+            castle_kingside!(Color::White),
+        ];
+
+        let mut board1_hashes = vec![initial_hash_1];
+        let mut board2_hashes = vec![initial_hash_2];
+
+        for (move1, move2) in board1_moves.iter().zip(board2_moves.iter()) {
+            move1.apply(&mut board1).unwrap();
+            move2.apply(&mut board2).unwrap();
+            board1_hashes.push(board1.current_position_hash());
+            board2_hashes.push(board2.current_position_hash());
+        }
+        assert_eq!(
+            board1.current_position_hash(),
+            board2.current_position_hash()
+        );
+
+        // undo the moves and see that we get back to the same position
+        board1_hashes.pop();
+        board2_hashes.pop();
+        for (move1, move2) in board1_moves.iter().rev().zip(board2_moves.iter().rev()) {
+            println!("undoing moves {} and {}", move1, move2);
+            move1.undo(&mut board1).unwrap();
+            move2.undo(&mut board2).unwrap();
+            println!(
+                "hashes: {} and {}",
+                board1.current_position_hash(),
+                board2.current_position_hash()
+            );
+            // compare to the last hash in the vec
+            assert_eq!(
+                board1.current_position_hash(),
+                board1_hashes.pop().unwrap(),
+                "hash 1 should be equal after undoing moves"
+            );
+            assert_eq!(
+                board2.current_position_hash(),
+                board2_hashes.pop().unwrap(),
+                "hash 2 should be equal after undoing moves"
+            );
+        }
+        assert_eq!(
+            board1.current_position_hash(),
+            board2.current_position_hash(),
+            "hashes should be equal after undoing moves"
+        );
+        assert_eq!(
+            initial_hash_1,
+            board1.current_position_hash(),
+            "hashes should be equal to the initial hash"
+        );
+        assert_eq!(
+            initial_hash_2,
+            board2.current_position_hash(),
+            "hashes should be equal to the initial hash"
+        );
+    }
+}
@@ -6,16 +6,18 @@ use super::castle_rights_bitmask::{CastleRightsBitmask, ALL_CASTLE_RIGHTS};
 /// including en passant targets, castle rights, and position clocks.
 #[derive(Clone)]
 pub struct MoveInfo {
-    en_passant_target_stack: Vec<Bitboard>,
+    /// Each en passant target, with the target again if a pawn can capture
+    /// there, or an empty bitboard if none can.
+    en_passant_target_stack: Vec<(Bitboard, Bitboard)>,
     castle_rights_stack: Vec<CastleRightsBitmask>,
-    halfmove_clock_stack: Vec<u8>,
-    fullmove_clock: u8,
+    halfmove_clock_stack: Vec<u16>,
+    fullmove_clock: u16,
 }
 
 impl Default for MoveInfo {
     fn default() -> Self {
         Self {
-            en_passant_target_stack: vec![Bitboard::EMPTY],
+            en_passant_target_stack: vec![(Bitboard::EMPTY, Bitboard::EMPTY)],
             castle_rights_stack: vec![ALL_CASTLE_RIGHTS],
             halfmove_clock_stack: vec![0],
             fullmove_clock: 1,
@@ -30,16 +32,28 @@ impl MoveInfo {
 
     // En passant state management
 
-    pub fn push_en_passant_target(&mut self, target_square: Bitboard) -> Bitboard {
-        self.en_passant_target_stack.push(target_square);
+    pub fn push_en_passant_target(
+        &mut self,
+        target_square: Bitboard,
+        capturable_target_square: Bitboard,
+    ) -> Bitboard {
+        self.en_passant_target_stack
+            .push((target_square, capturable_target_square));
         target_square
     }
 
     pub fn peek_en_passant_target(&self) -> Bitboard {
-        *self.en_passant_target_stack.last().unwrap()
+        self.en_passant_target_stack.last().unwrap().0
+    }
+
+    /// The en passant target if a pawn can capture there, or an empty bitboard.
+    pub fn peek_capturable_en_passant_target(&self) -> Bitboard {
+        self.en_passant_target_stack.last().unwrap().1
     }
 
-    pub fn pop_en_passant_target(&mut self) -> Bitboard {
+    /// Returns the en passant target, and the target if a pawn could capture
+    /// there.
+    pub fn pop_en_passant_target(&mut self) -> (Bitboard, Bitboard) {
         self.en_passant_target_stack.pop().unwrap()
     }
 
@@ -81,47 +95,59 @@ impl MoveInfo {
 
     // Position clock state management
 
-    pub fn increment_fullmove_clock(&mut self) -> u8 {
+    pub fn increment_fullmove_clock(&mut self) -> u16 {
         self.fullmove_clock += 1;
         self.fullmove_clock
     }
 
-    pub fn decrement_fullmove_clock(&mut self) -> u8 {
+    pub fn decrement_fullmove_clock(&mut self) -> u16 {
         self.fullmove_clock -= 1;
         self.fullmove_clock
     }
 
-    pub fn set_fullmove_clock(&mut self, clock: u8) -> u8 {
+    pub fn set_fullmove_clock(&mut self, clock: u16) -> u16 {
         self.fullmove_clock = clock;
         clock
     }
 
-    pub fn fullmove_clock(&self) -> u8 {
+    pub fn fullmove_clock(&self) -> u16 {
         self.fullmove_clock
     }
 
-    pub fn push_halfmove_clock(&mut self, clock: u8) -> u8 {
+    pub fn push_halfmove_clock(&mut self, clock: u16) -> u16 {
         self.halfmove_clock_stack.push(clock);
         clock
     }
 
-    pub fn increment_halfmove_clock(&mut self) -> u8 {
+    pub fn increment_halfmove_clock(&mut self) -> u16 {
         let old_clock = self.halfmove_clock_stack.last().unwrap();
-        let new_clock = old_clock + 1;
+        let new_clock = old_clock.saturating_add(1);
         self.halfmove_clock_stack.push(new_clock);
         new_clock
     }
 
-    pub fn reset_halfmove_clock(&mut self) -> u8 {
+    pub fn reset_halfmove_clock(&mut self) -> u16 {
         self.halfmove_clock_stack.push(0);
         0
     }
 
-    pub fn halfmove_clock(&self) -> u8 {
+    pub fn halfmove_clock(&self) -> u16 {
         *self.halfmove_clock_stack.last().unwrap()
     }
 
-    pub fn pop_halfmove_clock(&mut self) -> u8 {
+    pub fn pop_halfmove_clock(&mut self) -> u16 {
         self.halfmove_clock_stack.pop().unwrap()
     }
+
+    /// The halfmove clock before each move applied to the board, oldest first,
+    /// ending with the current clock.
+    pub fn halfmove_clock_stack(&self) -> &[u16] {
+        &self.halfmove_clock_stack
+    }
+
+    /// The castle rights before each move applied to the board, oldest first,
+    /// ending with the current rights.
+    pub fn castle_rights_stack(&self) -> &[CastleRightsBitmask] {
+        &self.castle_rights_stack
+    }
 }
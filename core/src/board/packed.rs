@@ -0,0 +1,205 @@
+use common::bitboard::bitboard::Bitboard;
+use common::bitboard::square::to_algebraic;
+use thiserror::Error;
+
+use super::castle_rights_bitmask::ALL_CASTLE_RIGHTS;
+use super::color::Color;
+use super::fen::{CASTLE_RIGHTS_CHARS, CASTLE_RIGHTS_SQUARES};
+use super::piece::Piece;
+use super::Board;
+
+/// The size of a packed position, in bytes:
+///
+/// - 8: the occupied squares, as a little-endian bitboard
+/// - 16: a 4-bit code for the piece on each occupied square, from A1 to H8,
+///   low nibble first: its color (1 for white) above its piece index
+/// - 1: the side to move (1 for white) in the low bit, and the castle rights
+///   bitmask above it
+/// - 1: the en passant target's square index, or `NO_EN_PASSANT_TARGET`
+/// - 2 and 2: the halfmove and fullmove clocks, little-endian
+pub const PACKED_POSITION_LEN: usize = 30;
+
+const PIECE_CODES_OFFSET: usize = 8;
+const STATE_OFFSET: usize = 24;
+const EN_PASSANT_OFFSET: usize = 25;
+const HALFMOVE_CLOCK_OFFSET: usize = 26;
+const FULLMOVE_CLOCK_OFFSET: usize = 28;
+const NO_EN_PASSANT_TARGET: u8 = 0xFF;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PackedPositionError {
+    #[error("expected 30 bytes, found {len}")]
+    WrongLength { len: usize },
+    #[error("{count} squares are occupied, but there's only room for 32 pieces")]
+    TooManyPieces { count: u32 },
+    #[error("invalid piece code {code} on {square}")]
+    InvalidPiece { code: u8, square: String },
+    #[error("invalid castling rights: {rights:#06b}")]
+    InvalidCastlingRights { rights: u8 },
+    #[error("invalid en passant target: {index}")]
+    InvalidEnPassantTarget { index: u8 },
+}
+
+impl Board {
+    /// Packs the position into a fixed number of bytes, for storing or sending
+    /// many of them; see `PACKED_POSITION_LEN` for the layout. Like FEN, only
+    /// the current position is kept, not the moves that led to it.
+    pub fn to_packed(&self) -> [u8; PACKED_POSITION_LEN] {
+        let mut bytes = [0; PACKED_POSITION_LEN];
+        bytes[..PIECE_CODES_OFFSET].copy_from_slice(&self.occupied().0.to_le_bytes());
+
+        for (i, (_, piece, color)) in self.iter().enumerate() {
+            let code = (color as u8) << 3 | piece as u8;
+            bytes[PIECE_CODES_OFFSET + i / 2] |= code << (4 * (i % 2));
+        }
+
+        bytes[STATE_OFFSET] = self.turn as u8 | self.peek_castle_rights() << 1;
+        let en_passant_target = self.peek_en_passant_target();
+        bytes[EN_PASSANT_OFFSET] = match en_passant_target.is_empty() {
+            true => NO_EN_PASSANT_TARGET,
+            false => en_passant_target.trailing_zeros() as u8,
+        };
+        bytes[HALFMOVE_CLOCK_OFFSET..FULLMOVE_CLOCK_OFFSET]
+            .copy_from_slice(&self.halfmove_clock().to_le_bytes());
+        bytes[FULLMOVE_CLOCK_OFFSET..].copy_from_slice(&self.fullmove_clock().to_le_bytes());
+
+        bytes
+    }
+
+    /// Unpacks a position written by `to_packed`, checking it as `from_fen`
+    /// does.
+    pub fn from_packed(bytes: &[u8]) -> Result<Self, PackedPositionError> {
+        if bytes.len() != PACKED_POSITION_LEN {
+            return Err(PackedPositionError::WrongLength { len: bytes.len() });
+        }
+
+        let mut occupied = [0; 8];
+        occupied.copy_from_slice(&bytes[..PIECE_CODES_OFFSET]);
+        let mut occupied = Bitboard(u64::from_le_bytes(occupied));
+        let count = occupied.count_ones();
+        if count > 32 {
+            return Err(PackedPositionError::TooManyPieces { count });
+        }
+
+        let mut board = Board::new();
+        let mut i = 0;
+        while !occupied.is_empty() {
+            let square = occupied.pop_lsb();
+            let code = bytes[PIECE_CODES_OFFSET + i / 2] >> (4 * (i % 2)) & 0b1111;
+            let piece_index = (code & 0b111) as usize;
+            if piece_index > Piece::King as usize {
+                return Err(PackedPositionError::InvalidPiece {
+                    code,
+                    square: to_algebraic(square).to_lowercase(),
+                });
+            }
+            let color = match code >> 3 {
+                0 => Color::Black,
+                _ => Color::White,
+            };
+            board
+                .put(square, Piece::from_usize(piece_index), color)
+                .expect("each occupied square is put once");
+            i += 1;
+        }
+
+        let state = bytes[STATE_OFFSET];
+        board.set_turn(match state & 1 {
+            0 => Color::Black,
+            _ => Color::White,
+        });
+        let castle_rights = state >> 1;
+        let in_place = |&(color, king, rook): &(Color, Bitboard, Bitboard)| {
+            board.get(king) == Some((Piece::King, color))
+                && board.get(rook) == Some((Piece::Rook, color))
+        };
+        let possible = CASTLE_RIGHTS_CHARS
+            .iter()
+            .zip(CASTLE_RIGHTS_SQUARES.iter())
+            .all(|(&(_, rights), squares)| castle_rights & rights == 0 || in_place(squares));
+        if castle_rights & !ALL_CASTLE_RIGHTS != 0 || !possible {
+            return Err(PackedPositionError::InvalidCastlingRights {
+                rights: castle_rights,
+            });
+        }
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS & !castle_rights);
+
+        match bytes[EN_PASSANT_OFFSET] {
+            NO_EN_PASSANT_TARGET => {}
+            index if index < 64 && matches!(index / 8, 2 | 5) => {
+                board.push_en_passant_target(Bitboard(1 << index));
+            }
+            index => return Err(PackedPositionError::InvalidEnPassantTarget { index }),
+        }
+
+        let clock = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        board.push_halfmove_clock(clock(HALFMOVE_CLOCK_OFFSET));
+        board.set_fullmove_clock(clock(FULLMOVE_CLOCK_OFFSET));
+
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w Kq e6 3 12",
+            "8/8/8/8/8/8/8/4K2k b - - 99 300",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let unpacked = Board::from_packed(&board.to_packed()).unwrap();
+            assert_eq!(unpacked.to_fen(), fen);
+            assert_eq!(
+                unpacked.current_position_hash(),
+                board.current_position_hash()
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_packed_position() {
+        let packed = Board::starting_position().to_packed();
+
+        assert_eq!(
+            Board::from_packed(&packed[1..]).err(),
+            Some(PackedPositionError::WrongLength { len: 29 })
+        );
+
+        let mut bytes = packed;
+        bytes[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            Board::from_packed(&bytes).err(),
+            Some(PackedPositionError::TooManyPieces { count: 64 })
+        );
+
+        // Piece index 7, on a1.
+        let mut bytes = packed;
+        bytes[8] |= 0b0111;
+        assert!(matches!(
+            Board::from_packed(&bytes),
+            Err(PackedPositionError::InvalidPiece { code: 15, .. })
+        ));
+
+        // Castle rights for black, who has no rooks.
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w - - 0 1").unwrap();
+        let mut bytes = board.to_packed();
+        bytes[24] |= ALL_CASTLE_RIGHTS << 1;
+        assert!(matches!(
+            Board::from_packed(&bytes),
+            Err(PackedPositionError::InvalidCastlingRights { .. })
+        ));
+
+        let mut bytes = packed;
+        bytes[25] = 28;
+        assert_eq!(
+            Board::from_packed(&bytes).err(),
+            Some(PackedPositionError::InvalidEnPassantTarget { index: 28 })
+        );
+    }
+}
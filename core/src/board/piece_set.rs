@@ -41,6 +41,29 @@ impl PieceSet {
         None
     }
 
+    /// Each of the set's pieces, with its square, from A1 to H8.
+    pub fn iter(&self) -> impl Iterator<Item = (Bitboard, Piece)> + '_ {
+        let mut occupied = self.occupied;
+        std::iter::from_fn(move || {
+            if occupied.is_empty() {
+                return None;
+            }
+            let square = occupied.pop_lsb();
+            Some((square, self.get(square)?))
+        })
+    }
+
+    /// The squares of each of the set's `piece`s, from A1 to H8.
+    pub fn iter_pieces(&self, piece: Piece) -> impl Iterator<Item = Bitboard> {
+        let mut squares = self.locate(piece);
+        std::iter::from_fn(move || {
+            if squares.is_empty() {
+                return None;
+            }
+            Some(squares.pop_lsb())
+        })
+    }
+
     pub fn occupied(&self) -> Bitboard {
         self.occupied
     }
@@ -61,15 +84,11 @@ impl PieceSet {
     }
 
     pub fn remove(&mut self, square: Bitboard) -> Option<Piece> {
-        let removed = self.get(square);
-        let removed_piece = match removed {
-            Some(piece) => piece,
-            None => return None,
-        };
+        let removed_piece = self.get(square)?;
 
         self.bitboards[removed_piece as usize] ^= square;
         self.occupied ^= square;
 
-        removed
+        Some(removed_piece)
     }
 }
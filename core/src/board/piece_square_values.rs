@@ -0,0 +1,52 @@
+use common::bitboard::bitboard::Bitboard;
+
+use super::{color::Color, piece::Piece};
+
+/// What an evaluation makes of each piece on each square: its material value,
+/// and its placement bonuses. The board keeps running totals of these for
+/// each player as pieces are put and removed (see
+/// `Board::set_piece_square_values`), so that the evaluation doesn't have to
+/// add them up square by square for every position it scores.
+pub trait PieceSquareValues: Send + Sync {
+    fn material(&self, piece: Piece) -> i16;
+
+    /// The piece's placement bonuses on `square`, in the middlegame and in the
+    /// endgame.
+    fn bonuses(&self, piece: Piece, color: Color, square: Bitboard) -> (i16, i16);
+}
+
+/// One player's running totals of the values of their pieces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PieceSquareScore {
+    pub material: i16,
+    pub middlegame_bonuses: i16,
+    pub endgame_bonuses: i16,
+}
+
+impl PieceSquareScore {
+    pub fn add(
+        &mut self,
+        values: &dyn PieceSquareValues,
+        piece: Piece,
+        color: Color,
+        square: Bitboard,
+    ) {
+        let (middlegame, endgame) = values.bonuses(piece, color, square);
+        self.material += values.material(piece);
+        self.middlegame_bonuses += middlegame;
+        self.endgame_bonuses += endgame;
+    }
+
+    pub fn subtract(
+        &mut self,
+        values: &dyn PieceSquareValues,
+        piece: Piece,
+        color: Color,
+        square: Bitboard,
+    ) {
+        let (middlegame, endgame) = values.bonuses(piece, color, square);
+        self.material -= values.material(piece);
+        self.middlegame_bonuses -= middlegame;
+        self.endgame_bonuses -= endgame;
+    }
+}
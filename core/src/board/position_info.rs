@@ -1,26 +1,39 @@
+use std::sync::Arc;
+
 use common::bitboard::bitboard::Bitboard;
 use rustc_hash::FxHashMap;
 
+use super::piece_square_values::{PieceSquareScore, PieceSquareValues};
 use super::{color::Color, piece::Piece};
 
 include!(concat!(env!("OUT_DIR"), "/zobrist_table.rs"));
 
 /// Stores information about state changes related to the current (and previous) positions.
 /// Holds the logic for incrementally updating the hash of the current position using
-/// Zobrist hashing: https://www.chessprogramming.org/Zobrist_Hashing
+/// Zobrist hashing: https://www.chessprogramming.org/Zobrist_Hashing, and the running
+/// totals of the pieces' values, when the board is given values to keep them with.
 #[derive(Clone)]
 pub struct PositionInfo {
     position_count: FxHashMap<u64, u8>,
+    /// The hash of each counted position, so that a position is uncounted by
+    /// the hash it was counted by, whichever side is to move by then.
+    counted_position_hashes: Vec<u64>,
     max_seen_position_count_stack: Vec<u8>,
     current_position_hash: u64,
+    piece_square_values: Option<Arc<dyn PieceSquareValues>>,
+    /// By `Color`.
+    piece_square_scores: [PieceSquareScore; 2],
 }
 
 impl Default for PositionInfo {
     fn default() -> Self {
         Self {
             position_count: FxHashMap::default(),
+            counted_position_hashes: Vec::new(),
             max_seen_position_count_stack: vec![1],
             current_position_hash: 0,
+            piece_square_values: None,
+            piece_square_scores: [PieceSquareScore::default(); 2],
         }
     }
 }
@@ -31,27 +44,38 @@ impl PositionInfo {
     }
 
     pub fn count_current_position(&mut self) -> u8 {
-        self.position_count
-            .entry(self.current_position_hash)
-            .and_modify(|count| *count += 1)
-            .or_insert(1);
-        let count = *self
+        let count = self
             .position_count
-            .get(&self.current_position_hash)
-            .unwrap();
+            .entry(self.current_position_hash)
+            .or_insert(0);
+        *count += 1;
+        let count = *count;
+        self.counted_position_hashes
+            .push(self.current_position_hash);
         self.max_seen_position_count_stack.push(count);
         count
     }
 
+    /// Uncounts the last counted position, returning how many times it's
+    /// still counted.
     pub fn uncount_current_position(&mut self) -> u8 {
-        self.position_count
-            .entry(self.current_position_hash)
-            .and_modify(|count| *count -= 1);
+        let hash = match self.counted_position_hashes.pop() {
+            Some(hash) => hash,
+            None => return 0,
+        };
         self.max_seen_position_count_stack.pop();
-        *self
-            .position_count
-            .get(&self.current_position_hash)
-            .unwrap()
+        let count = self.position_count.get_mut(&hash).unwrap();
+        *count -= 1;
+        let count = *count;
+        if count == 0 {
+            self.position_count.remove(&hash);
+        }
+        count
+    }
+
+    /// How many times each counted position has been seen, by hash.
+    pub fn position_counts(&self) -> &FxHashMap<u64, u8> {
+        &self.position_count
     }
 
     pub fn max_seen_position_count(&self) -> u8 {
@@ -81,12 +105,64 @@ impl PositionInfo {
         self.current_position_hash ^= ZOBRIST_CASTLING_RIGHTS_TABLE[castling_rights as usize];
     }
 
+    pub fn update_zobrist_hash_toggle_turn(&mut self) {
+        self.current_position_hash ^= ZOBRIST_BLACK_TO_MOVE;
+    }
+
     pub fn current_position_hash(&self) -> u64 {
         self.current_position_hash
     }
+
+    /// Keeps the totals with `values` from now on, starting them from zero.
+    pub fn set_piece_square_values(&mut self, values: Option<Arc<dyn PieceSquareValues>>) {
+        self.piece_square_values = values;
+        self.piece_square_scores = [PieceSquareScore::default(); 2];
+    }
+
+    pub fn piece_square_values(&self) -> Option<&Arc<dyn PieceSquareValues>> {
+        self.piece_square_values.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn update_piece_square_scores_put(&mut self, square: Bitboard, piece: Piece, color: Color) {
+        if let Some(values) = &self.piece_square_values {
+            self.piece_square_scores[color as usize].add(values.as_ref(), piece, color, square);
+        }
+    }
+
+    #[inline(always)]
+    pub fn update_piece_square_scores_remove(
+        &mut self,
+        square: Bitboard,
+        piece: Piece,
+        color: Color,
+    ) {
+        if let Some(values) = &self.piece_square_values {
+            self.piece_square_scores[color as usize].subtract(
+                values.as_ref(),
+                piece,
+                color,
+                square,
+            );
+        }
+    }
+
+    /// The player's totals, if they're kept with `values`: the very same
+    /// values, not just equal ones, so that checking is a pointer comparison.
+    #[inline(always)]
+    pub fn piece_square_score<V: ?Sized>(
+        &self,
+        color: Color,
+        values: &Arc<V>,
+    ) -> Option<PieceSquareScore> {
+        let kept = self.piece_square_values.as_ref()?;
+        (Arc::as_ptr(kept) as *const () == Arc::as_ptr(values) as *const ())
+            .then(|| self.piece_square_scores[color as usize])
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_range_loop)]
 mod tests {
     use common::bitboard::square::ORDERED_SQUARES;
 
@@ -152,7 +228,6 @@ mod tests {
                 random_piece,
                 Color::White,
             );
-            hash ^= ZOBRIST_PIECES_TABLE[random_piece as usize][i][Color::White as usize];
         }
         assert_eq!(position_info.current_position_hash(), 0);
     }
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use super::position_info::ZOBRIST_TABLE_VERSION;
+
+/// Wraps data keyed by zobrist hashes (cached search results, position
+/// statistics, and the like) for saving to disk. The tables are generated at
+/// build time, so hashes saved by another build would silently mean different
+/// positions here. Stamping the data with the tables' version lets a build
+/// refuse it instead.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ZobristStamped<T> {
+    zobrist_version: u64,
+    data: T,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ZobristVersionError {
+    #[error("saved with zobrist tables {found:016x}, but this build uses {expected:016x}")]
+    Mismatch { found: u64, expected: u64 },
+}
+
+impl<T> ZobristStamped<T> {
+    /// Stamps `data` with this build's tables.
+    pub fn new(data: T) -> Self {
+        Self {
+            zobrist_version: ZOBRIST_TABLE_VERSION,
+            data,
+        }
+    }
+
+    pub fn zobrist_version(&self) -> u64 {
+        self.zobrist_version
+    }
+
+    /// The data, if it was stamped with this build's tables.
+    pub fn into_data(self) -> Result<T, ZobristVersionError> {
+        if self.zobrist_version != ZOBRIST_TABLE_VERSION {
+            return Err(ZobristVersionError::Mismatch {
+                found: self.zobrist_version,
+                expected: ZOBRIST_TABLE_VERSION,
+            });
+        }
+        Ok(self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn test_zobrist_stamped_round_trip() {
+        let hash = Board::starting_position().current_position_hash();
+        let json = serde_json::to_string(&ZobristStamped::new(vec![hash])).unwrap();
+        let stamped: ZobristStamped<Vec<u64>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(stamped.zobrist_version(), ZOBRIST_TABLE_VERSION);
+        assert_eq!(stamped.into_data(), Ok(vec![hash]));
+
+        let other_build = ZobristStamped {
+            zobrist_version: ZOBRIST_TABLE_VERSION ^ 1,
+            data: vec![hash],
+        };
+        assert_eq!(
+            other_build.into_data(),
+            Err(ZobristVersionError::Mismatch {
+                found: ZOBRIST_TABLE_VERSION ^ 1,
+                expected: ZOBRIST_TABLE_VERSION,
+            })
+        );
+    }
+}
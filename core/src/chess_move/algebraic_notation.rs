@@ -1,7 +1,8 @@
 use common::bitboard::{
     bitboard::Bitboard,
-    square::{to_algebraic, C1, C8, E1, E8, G1, G8},
+    square::{square_string_to_bitboard, to_algebraic, C1, C8, E1, E8, G1, G8},
 };
+use regex::Regex;
 
 use crate::{
     board::{color::Color, piece::Piece, Board},
@@ -19,6 +20,9 @@ const PROMOTION_CHAR: &str = "=";
 
 const EMPTY_STRING: &str = "";
 
+/// The most legal moves suggested in place of an illegal one.
+const MAX_SUGGESTIONS: usize = 6;
+
 /// For a given board state, this function lists all candidate moves with their algebraic notation.
 /// By enumerating the entire list of moves and their notations, we can avoid
 /// needing functions like `to_algebraic_notation` and `from_algebraic_notation`,
@@ -185,6 +189,110 @@ fn get_promotion_chars(chess_move: &ChessMove) -> String {
     }
 }
 
+/// Looks up the algebraic notation of a move among the candidate moves that were
+/// enumerated before it was made.
+pub fn algebraic_notation_for(
+    chess_move: &ChessMove,
+    enumerated_candidate_moves: &[(ChessMove, String)],
+) -> String {
+    enumerated_candidate_moves
+        .iter()
+        .find(|(move_, _)| move_ == chess_move)
+        .map(|(_, notation)| notation.clone())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Normalizes SAN so that notation from other tools can be compared with the
+/// notation generated by this engine: castling with zeros, check markers, and
+/// en passant suffixes are all accepted.
+pub fn normalize_san(san: &str) -> String {
+    san.trim()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .trim_end_matches("e.p.")
+        .trim()
+        .replace('0', "O")
+}
+
+/// Finds the candidate move whose algebraic notation matches `san`.
+pub fn find_move_by_san(
+    san: &str,
+    enumerated_candidate_moves: &[(ChessMove, String)],
+) -> Option<ChessMove> {
+    let san = normalize_san(san);
+    enumerated_candidate_moves
+        .iter()
+        .find(|(_, notation)| normalize_san(notation) == san)
+        .map(|(chess_move, _)| chess_move.clone())
+}
+
+/// Suggests the legal moves nearest to `input`, a move that matched none of the
+/// candidates. If `input` only differs from a candidate by its check marker or
+/// castling zeros, that candidate is the only suggestion. Otherwise, moves by the
+/// same piece to the same square (which catches ambiguous moves like "Nd2") come
+/// first, followed by other moves to the same square, then other moves by the
+/// same piece.
+pub fn suggest_moves(
+    input: &str,
+    enumerated_candidate_moves: &[(ChessMove, String)],
+) -> Vec<String> {
+    let normalized = normalize_san(input);
+    if let Some((_, san)) = enumerated_candidate_moves
+        .iter()
+        .find(|(_, san)| normalize_san(san) == normalized)
+    {
+        return vec![san.clone()];
+    }
+
+    let square_re = Regex::new("[a-h][1-8]").unwrap();
+    let squares: Vec<&str> = square_re
+        .find_iter(&normalized)
+        .map(|m| m.as_str())
+        .collect();
+    let is_coordinate = Regex::new("^[a-h][1-8][a-h][1-8]$")
+        .unwrap()
+        .is_match(&normalized);
+
+    let destination = squares
+        .last()
+        .map(|square| square_string_to_bitboard(square));
+    // For coordinate input, the piece is whichever one stands on the from square.
+    let from_square = is_coordinate.then(|| square_string_to_bitboard(squares[0]));
+    let piece = (!is_coordinate && !normalized.is_empty()).then(|| san_piece(&normalized));
+
+    let same_destination = |chess_move: &ChessMove| Some(chess_move.to_square()) == destination;
+    let same_piece = |chess_move: &ChessMove, san: &str| match from_square {
+        Some(from_square) => chess_move.from_square() == from_square,
+        None => Some(san_piece(san)) == piece,
+    };
+
+    let mut ranked: Vec<(u8, &String)> = enumerated_candidate_moves
+        .iter()
+        .filter_map(|(chess_move, san)| {
+            match (same_destination(chess_move), same_piece(chess_move, san)) {
+                (true, true) => Some((0, san)),
+                (true, false) => Some((1, san)),
+                (false, true) => Some((2, san)),
+                (false, false) => None,
+            }
+        })
+        .collect();
+    ranked.sort_by_key(|(rank, _)| *rank);
+
+    let mut suggestions: Vec<String> = ranked.into_iter().map(|(_, san)| san.clone()).collect();
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// The piece letter a move in algebraic notation is made with, using "P" for
+/// pawn moves and "K" for castling.
+fn san_piece(san: &str) -> char {
+    match san.chars().next() {
+        Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => c,
+        Some('O') => 'K',
+        _ => 'P',
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use common::bitboard::square::*;
@@ -633,4 +741,46 @@ mod tests {
             "Qh7#"
         );
     }
+
+    fn starting_candidates() -> Vec<(ChessMove, String)> {
+        enumerate_candidate_moves_with_algebraic_notation(
+            &mut Board::starting_position(),
+            Color::White,
+            &mut MoveGenerator::new(),
+        )
+    }
+
+    #[test]
+    fn test_find_move_by_san() {
+        let candidates = starting_candidates();
+        let chess_move = find_move_by_san("Nf3!", &candidates).unwrap();
+        assert_eq!(chess_move.to_uci(), "g1f3");
+        assert!(find_move_by_san("Nf4", &candidates).is_none());
+        assert_eq!(normalize_san("0-0-0+"), "O-O-O");
+    }
+
+    #[test]
+    fn test_suggest_moves() {
+        let candidates = starting_candidates();
+
+        // Only the check marker is wrong.
+        assert_eq!(suggest_moves("Nf3+", &candidates), vec!["Nf3"]);
+
+        // No piece can reach e5, so suggest the other pawn moves.
+        let suggestions = suggest_moves("e5", &candidates);
+        assert_eq!(suggestions.len(), MAX_SUGGESTIONS);
+        assert!(suggestions.iter().all(|san| san_piece(san) == 'P'));
+
+        // No knight can reach d4 yet, so suggest the pawn move there, then the
+        // knight moves.
+        let mut suggestions = suggest_moves("Nd4", &candidates);
+        assert_eq!(suggestions.remove(0), "d4");
+        suggestions.sort();
+        assert_eq!(suggestions, vec!["Na3", "Nc3", "Nf3", "Nh3"]);
+
+        // The e2 pawn can't move three squares, so suggest the moves it can make.
+        let mut suggestions = suggest_moves("e2e5", &candidates);
+        suggestions.sort();
+        assert_eq!(suggestions, vec!["e3", "e4"]);
+    }
 }
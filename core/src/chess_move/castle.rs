@@ -132,7 +132,7 @@ impl CastleChessMove {
         };
 
         board.increment_halfmove_clock();
-        board.increment_fullmove_clock();
+        board.increment_fullmove_clock(color);
         board.push_en_passant_target(Bitboard::EMPTY);
         board.lose_castle_rights(lost_castle_rights);
 
@@ -197,7 +197,7 @@ impl CastleChessMove {
         board.put(rook_from, Piece::Rook, color).unwrap();
 
         // Revert the board state.
-        board.decrement_fullmove_clock();
+        board.decrement_fullmove_clock(color);
         board.pop_halfmove_clock();
         board.pop_en_passant_target();
         board.pop_castle_rights();
@@ -73,11 +73,15 @@ impl ChessMove {
             ChessMove::EnPassant(m) => m.apply(board),
             ChessMove::Castle(m) => m.apply(board),
         };
+        if result.is_ok() {
+            board.count_current_position();
+        }
 
         map_ok(result)
     }
 
     pub fn undo(&self, board: &mut Board) -> Result<(), BoardError> {
+        board.uncount_current_position();
         let result = match self {
             ChessMove::Standard(m) => m.undo(board),
             ChessMove::PawnPromotion(m) => m.undo(board),
@@ -180,3 +184,53 @@ macro_rules! checkmate_move {
         $chess_move.set_effect(ChessMoveEffect::Checkmate).clone()
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::move_generator::MoveGenerator;
+
+    #[test]
+    fn test_halfmove_clock_round_trips_through_random_games() {
+        let mut move_generator = MoveGenerator::new();
+        let mut rng = StdRng::seed_from_u64(2024);
+        for _ in 0..20 {
+            let mut board = Board::starting_position();
+            let initial_stack = board.halfmove_clock_stack().to_vec();
+            let initial_fen = board.to_fen();
+            let mut played = Vec::new();
+            for _ in 0..200 {
+                let turn = board.turn();
+                let moves = move_generator.generate_moves(&mut board, turn);
+                if moves.is_empty() {
+                    break;
+                }
+                let chess_move = moves[rng.gen_range(0..moves.len())].clone();
+                let is_pawn_move =
+                    matches!(board.get(chess_move.from_square()), Some((Piece::Pawn, _)));
+                let expected_clock = if is_pawn_move || chess_move.captures().is_some() {
+                    0
+                } else {
+                    board.halfmove_clock().saturating_add(1)
+                };
+                chess_move.apply(&mut board).unwrap();
+                board.toggle_turn();
+                assert_eq!(board.halfmove_clock(), expected_clock, "{}", chess_move);
+                played.push(chess_move);
+                assert_eq!(
+                    board.halfmove_clock_stack().len(),
+                    initial_stack.len() + played.len()
+                );
+            }
+            for chess_move in played.iter().rev() {
+                board.toggle_turn();
+                chess_move.undo(&mut board).unwrap();
+            }
+            assert_eq!(board.halfmove_clock_stack(), initial_stack.as_slice());
+            assert_eq!(board.to_fen(), initial_fen);
+        }
+    }
+}
@@ -77,7 +77,7 @@ impl EnPassantChessMove {
         }
 
         board.reset_halfmove_clock();
-        board.increment_fullmove_clock();
+        board.increment_fullmove_clock(color);
         board.push_en_passant_target(Bitboard::EMPTY);
         board.preserve_castle_rights();
         board.put(*to_square, piece_to_move, color)?;
@@ -116,7 +116,7 @@ impl EnPassantChessMove {
 
         // Revert the board state.
         board.pop_halfmove_clock();
-        board.decrement_fullmove_clock();
+        board.decrement_fullmove_clock(piece_color);
         board.pop_en_passant_target();
         board.pop_castle_rights();
         board.put(captures_square, Piece::Pawn, piece_color.opposite())?;
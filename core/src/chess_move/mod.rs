@@ -1,6 +1,7 @@
 pub mod algebraic_notation;
 pub mod capture;
 pub mod castle;
+#[allow(clippy::module_inception)]
 pub mod chess_move;
 pub mod chess_move_effect;
 pub mod en_passant;
@@ -211,6 +211,29 @@ mod tests {
         assert_eq!(Some((Piece::Rook, Color::Black)), board.get(B8));
     }
 
+    #[test]
+    fn test_pawn_promotion_resets_halfmove_clock() {
+        let mut board = chess_position! {
+            ........
+            P.......
+            ........
+            ........
+            ........
+            ........
+            ........
+            ........
+        };
+        board.push_halfmove_clock(7);
+
+        let promotion = PawnPromotionChessMove::new(A7, A8, None, Piece::Queen);
+        promotion.apply(&mut board).unwrap();
+        assert_eq!(board.halfmove_clock(), 0);
+
+        promotion.undo(&mut board).unwrap();
+        assert_eq!(board.halfmove_clock(), 7);
+        assert_eq!(board.halfmove_clock_stack(), &[0, 7]);
+    }
+
     #[test]
     fn test_zobrist_hashing_reversible_for_pawn_promotion() {
         let mut board = chess_position! {
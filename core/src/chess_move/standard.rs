@@ -97,13 +97,14 @@ impl StandardChessMove {
                 *from_square,
             ) | get_lost_castle_rights_if_rook_taken(captured_piece_and_color, *to_square);
 
-        if captured_piece_and_color.is_some() {
+        // Pawn moves, including promotions, reset the clock as captures do.
+        if piece_to_move == Piece::Pawn || captured_piece_and_color.is_some() {
             board.reset_halfmove_clock();
         } else {
             board.increment_halfmove_clock();
         }
 
-        board.increment_fullmove_clock();
+        board.increment_fullmove_clock(color_of_piece_to_move);
         board.push_en_passant_target(en_passant_target);
         board.lose_castle_rights(lost_castle_rights);
         board
@@ -137,7 +138,7 @@ impl StandardChessMove {
 
         // Revert the board state.
         board.pop_halfmove_clock();
-        board.decrement_fullmove_clock();
+        board.decrement_fullmove_clock(color_of_piece_to_move_back);
         board.pop_en_passant_target();
         board.pop_castle_rights();
         board
@@ -0,0 +1,5 @@
+pub mod board;
+pub mod chess_move;
+pub mod move_generator;
+#[cfg(feature = "nnue")]
+pub mod nnue;
@@ -0,0 +1,166 @@
+use smallvec::smallvec;
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+use crate::chess_move::pawn_promotion::PawnPromotionChessMove;
+use common::bitboard::bitboard::Bitboard;
+
+use super::targets::Targets;
+use super::{
+    expand_piece_targets, generate_castle_moves, generate_en_passant_moves, remove_invalid_moves,
+    ChessMoveList, PAWN_PROMOTIONS,
+};
+
+/// The legal moves in a position, generated lazily, one piece at a time, for
+/// callers that only look at the moves once, e.g. to list them for a GUI. A
+/// piece's moves fit in a `ChessMoveList` without spilling, so no list of
+/// moves is ever allocated, and unlike `MoveGenerator::generate_moves`,
+/// nothing is cached. The moves come in no particular order, and their
+/// effects aren't set.
+pub struct LegalMoves<'a> {
+    board: &'a mut Board,
+    targets: &'a mut Targets,
+    color: Color,
+    /// The squares of the pieces whose moves haven't been generated yet.
+    pieces: Bitboard,
+    /// The legal moves of the last piece, yet to be returned.
+    moves: ChessMoveList,
+}
+
+impl<'a> LegalMoves<'a> {
+    pub(super) fn new(board: &'a mut Board, targets: &'a mut Targets) -> Self {
+        let color = board.turn();
+        let pieces = board.pieces(color).occupied();
+        Self {
+            board,
+            targets,
+            color,
+            pieces,
+            moves: ChessMoveList::new(),
+        }
+    }
+
+    fn generate_piece_moves(&mut self, square: Bitboard) {
+        let (board, color) = (&*self.board, self.color);
+        let piece = match board.pieces(color).get(square) {
+            Some(piece) => piece,
+            None => return,
+        };
+        match piece {
+            Piece::Pawn => generate_pawn_moves(&mut self.moves, board, color, square),
+            _ => {
+                let targets = self.targets.piece_targets(board, color, square, piece);
+                expand_piece_targets(&mut self.moves, board, color, smallvec![(square, targets)]);
+                if piece == Piece::King {
+                    generate_castle_moves(&mut self.moves, board, color, self.targets);
+                }
+            }
+        }
+        remove_invalid_moves(&mut self.moves, self.board, color, self.targets);
+    }
+}
+
+impl Iterator for LegalMoves<'_> {
+    type Item = ChessMove;
+
+    fn next(&mut self) -> Option<ChessMove> {
+        loop {
+            if let Some(chess_move) = self.moves.pop() {
+                return Some(chess_move);
+            }
+            if self.pieces.is_empty() {
+                return None;
+            }
+            let square = self.pieces.pop_lsb();
+            self.generate_piece_moves(square);
+        }
+    }
+}
+
+/// The pawn's pushes, captures, promotions and en passant captures, as
+/// `generate_pawn_moves` generates them for every pawn.
+fn generate_pawn_moves(moves: &mut ChessMoveList, board: &Board, color: Color, pawn: Bitboard) {
+    let occupied = board.occupied();
+    let (single_move, double_move_rank, promotion_rank) = match color {
+        Color::White => (pawn << 8, Bitboard::RANK_4, Bitboard::RANK_8),
+        Color::Black => (pawn >> 8, Bitboard::RANK_5, Bitboard::RANK_1),
+    };
+    let mut targets = single_move & !occupied;
+    if !targets.is_empty() {
+        let double_move = match color {
+            Color::White => single_move << 8,
+            Color::Black => single_move >> 8,
+        };
+        targets |= double_move & double_move_rank & !occupied;
+    }
+    let attacks = match color {
+        Color::White => ((pawn << 9) & !Bitboard::A_FILE) | ((pawn << 7) & !Bitboard::H_FILE),
+        Color::Black => ((pawn >> 7) & !Bitboard::A_FILE) | ((pawn >> 9) & !Bitboard::H_FILE),
+    };
+    targets |= attacks & board.pieces(color.opposite()).occupied();
+
+    if targets.overlaps(promotion_rank) {
+        let mut pawn_moves = ChessMoveList::new();
+        expand_piece_targets(&mut pawn_moves, board, color, smallvec![(pawn, targets)]);
+        for pawn_move in pawn_moves {
+            for &promotion in &PAWN_PROMOTIONS {
+                moves.push(ChessMove::PawnPromotion(PawnPromotionChessMove::new(
+                    pawn,
+                    pawn_move.to_square(),
+                    pawn_move.captures(),
+                    promotion,
+                )));
+            }
+        }
+        return;
+    }
+    expand_piece_targets(moves, board, color, smallvec![(pawn, targets)]);
+
+    if attacks.overlaps(board.peek_en_passant_target()) {
+        generate_en_passant_moves(moves, board, color);
+        moves.retain(|chess_move| {
+            !matches!(chess_move, ChessMove::EnPassant(_)) || chess_move.from_square() == pawn
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::move_generator::MoveGenerator;
+
+    use super::*;
+
+    #[test]
+    fn test_legal_moves_match_generated_moves() {
+        let mut move_generator = MoveGenerator::new();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            // en passant on either side of the pushed pawn
+            "4k3/8/8/2PpP3/8/8/8/4K3 w - d6 0 1",
+            // in check, with a pinned piece
+            "4k3/8/8/8/1b6/8/3N4/r3K3 w - - 0 1",
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let turn = board.turn();
+            let mut expected: Vec<String> = move_generator
+                .generate_moves(&mut board, turn)
+                .iter()
+                .map(ChessMove::to_uci)
+                .collect();
+            expected.sort();
+
+            let mut legal_moves: Vec<String> = move_generator
+                .legal_moves(&mut board)
+                .map(|chess_move| chess_move.to_uci())
+                .collect();
+            legal_moves.sort();
+            assert_eq!(legal_moves, expected, "{}", fen);
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+}
@@ -1,5 +1,7 @@
+pub mod legal_moves;
 mod magic_table;
-mod targets;
+pub mod perft;
+pub(crate) mod targets;
 
 use std::num::NonZeroUsize;
 
@@ -16,13 +18,15 @@ use crate::chess_move::chess_move_effect::ChessMoveEffect;
 use crate::chess_move::en_passant::EnPassantChessMove;
 use crate::chess_move::pawn_promotion::PawnPromotionChessMove;
 use crate::chess_move::standard::StandardChessMove;
-use crate::evaluate::{player_is_in_check, player_is_in_checkmate};
 use common::bitboard::bitboard::Bitboard;
 use common::bitboard::square::*;
+use legal_moves::LegalMoves;
 use lru::LruCache;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use smallvec::{smallvec, SmallVec};
 use targets::Targets;
+use tracing::trace_span;
 
 use self::targets::{generate_pawn_attack_targets, generate_pawn_move_targets, PieceTargetList};
 
@@ -83,6 +87,7 @@ impl MoveGenerator {
     }
 
     pub fn generate_moves(&mut self, board: &mut Board, player: Color) -> ChessMoveList {
+        let _span = trace_span!("movegen").entered();
         let key = (board.current_position_hash(), player as u8);
         if let Some(moves) = self.cache.get(&key) {
             self.hit_count += 1;
@@ -94,12 +99,19 @@ impl MoveGenerator {
         moves
     }
 
+    /// The legal moves for the side to move, generated lazily rather than
+    /// all at once (see `LegalMoves`).
+    pub fn legal_moves<'a>(&'a mut self, board: &'a mut Board) -> LegalMoves<'a> {
+        LegalMoves::new(board, &mut self.targets)
+    }
+
     fn lazily_update_chess_move_effect_for_checks_and_checkmates(
         &mut self,
         moves: &mut ChessMoveList,
         board: &mut Board,
         player: Color,
     ) {
+        let _span = trace_span!("move_effects").entered();
         let opponent = player.opposite();
         for chess_move in moves.iter_mut() {
             self.lazily_calculate_chess_move_effect(chess_move, board, opponent);
@@ -138,7 +150,11 @@ impl MoveGenerator {
         let next_player = player.opposite();
 
         // `par_iter` is a rayon primitive that allows for parallel iteration over a collection.
-        let inner_counts = candidates.par_iter().map(|chess_move| {
+        #[cfg(feature = "parallel")]
+        let candidate_iter = candidates.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let candidate_iter = candidates.iter();
+        let inner_counts = candidate_iter.map(|chess_move| {
             let mut local_board = board.clone();
             let mut local_move_generator = MoveGenerator::new();
 
@@ -156,6 +172,16 @@ impl MoveGenerator {
         initial_count + inner_counts.sum::<usize>()
     }
 
+    /// Removes the moves that would leave the side to move's king in check,
+    /// with the same check that move generation uses, so that moves built
+    /// elsewhere (from a book, a GUI, or a test) can be validated in bulk. The
+    /// moves must otherwise fit the board, as generated moves do: each moves
+    /// one of the side to move's pieces, and captures what's on its target.
+    pub fn filter_legal(&mut self, board: &mut Board, moves: &mut ChessMoveList) {
+        let color = board.turn();
+        remove_invalid_moves(moves, board, color, &mut self.targets);
+    }
+
     pub fn get_attack_targets(&mut self, board: &Board, player: Color) -> Bitboard {
         let board_hash = board.current_position_hash();
 
@@ -170,6 +196,22 @@ impl MoveGenerator {
 
         attack_targets
     }
+
+    /// The squares attacked by each of `player`'s pieces, by the piece's square.
+    pub fn get_piece_attack_targets(&self, board: &Board, player: Color) -> PieceTargetList {
+        self.targets.generate_piece_attack_targets(board, player)
+    }
+
+    /// The squares of `player`'s pieces that attack `square`, including any
+    /// that defend a piece of their own on it.
+    pub fn attackers_to(&self, board: &Board, square: Bitboard, player: Color) -> Bitboard {
+        self.targets.attackers_to(board, square, player)
+    }
+
+    /// The king's square and the squares around it.
+    pub fn get_king_zone(&self, king: Bitboard) -> Bitboard {
+        self.targets.king_zone(king)
+    }
 }
 
 fn count_positions_inner(
@@ -434,9 +476,33 @@ fn remove_invalid_moves(
     candidates.append(&mut valid_moves);
 }
 
+#[inline(always)]
+pub fn player_is_in_check(
+    board: &Board,
+    move_generator: &mut MoveGenerator,
+    player: Color,
+) -> bool {
+    let king = board.pieces(player).locate(Piece::King);
+    let attacked_squares = move_generator.get_attack_targets(board, player.opposite());
+
+    king.overlaps(attacked_squares)
+}
+
+#[inline(always)]
+pub fn player_is_in_checkmate(
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    player: Color,
+) -> bool {
+    let candidates = move_generator.generate_moves(board, player);
+    let check = player_is_in_check(board, move_generator, player);
+    check && candidates.is_empty()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::board::castle_rights_bitmask::ALL_CASTLE_RIGHTS;
     use crate::chess_move::chess_move::ChessMove;
     use crate::{
         castle_kingside, castle_queenside, chess_position, en_passant_move, promotion, std_move,
@@ -944,6 +1010,37 @@ mod tests {
         assert_eq!(expected_white_moves, white_moves);
     }
 
+    #[test]
+    fn test_filter_legal() {
+        let mut board = chess_position! {
+            ....r...
+            ........
+            ........
+            ........
+            ........
+            ........
+            r...N...
+            ....K...
+        };
+        board.set_turn(Color::White);
+        println!("Testing board:\n{}", board);
+
+        let mut moves: ChessMoveList = smallvec![
+            // The knight is pinned, so none of its moves are legal...
+            std_move!(E2, C3),
+            std_move!(E2, G1),
+            // ...nor is stepping onto the rank the other rook attacks.
+            std_move!(E1, D2),
+            std_move!(E1, D1),
+            std_move!(E1, F1),
+        ];
+        MoveGenerator::new().filter_legal(&mut board, &mut moves);
+        assert_eq!(
+            moves,
+            smallvec![std_move!(E1, D1), std_move!(E1, F1)] as ChessMoveList
+        );
+    }
+
     /// The lower level `move_generator` functions generate chess moves before their
     /// effect (check, checkmate, etc.) is calculated. At this stage, the effect
     /// is set to `NotYetCalculated`. This macro sets the effect to `None` for
@@ -953,4 +1050,64 @@ mod tests {
             chess_move.set_effect(ChessMoveEffect::None);
         }
     }
+
+    #[test]
+    fn test_player_is_in_check() {
+        let mut move_generator = MoveGenerator::new();
+        let mut board = chess_position! {
+            .......k
+            .....ppp
+            ........
+            ...b....
+            ........
+            ........
+            .Q......
+            K......q
+        };
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        board.set_turn(Color::White);
+
+        println!("Testing board:\n{}", board);
+
+        assert!(player_is_in_check(
+            &board,
+            &mut move_generator,
+            Color::White
+        ));
+        assert!(!player_is_in_check(
+            &board,
+            &mut move_generator,
+            Color::Black
+        ));
+    }
+
+    #[test]
+    fn test_player_is_in_checkmate() {
+        let mut move_generator = MoveGenerator::new();
+        let mut board = chess_position! {
+            .......k
+            ........
+            ........
+            ........
+            ........
+            ........
+            PPP.....
+            .K.....r
+        };
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        board.set_turn(Color::White);
+
+        println!("Testing board:\n{}", board);
+
+        assert!(player_is_in_checkmate(
+            &mut board,
+            &mut move_generator,
+            Color::White
+        ));
+        assert!(!player_is_in_checkmate(
+            &mut board,
+            &mut move_generator,
+            Color::Black
+        ));
+    }
 }
@@ -0,0 +1,182 @@
+use std::ops::AddAssign;
+
+use rustc_hash::FxHashMap;
+
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+use crate::chess_move::chess_move_effect::ChessMoveEffect;
+
+use super::MoveGenerator;
+
+/// The positions reachable in exactly `depth` plies, with the moves that reached
+/// them broken out by kind, as in the reference tables on the Chess Programming
+/// Wiki: captures include en passant captures, and checks include checkmates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerftResult {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub checkmates: u64,
+}
+
+impl AddAssign for PerftResult {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+impl PerftResult {
+    fn leaf(chess_move: &ChessMove) -> Self {
+        let effect = chess_move.effect();
+        Self {
+            nodes: 1,
+            captures: chess_move.captures().is_some() as u64,
+            en_passants: matches!(chess_move, ChessMove::EnPassant(_)) as u64,
+            castles: matches!(chess_move, ChessMove::Castle(_)) as u64,
+            promotions: matches!(chess_move, ChessMove::PawnPromotion(_)) as u64,
+            checks: matches!(effect, ChessMoveEffect::Check | ChessMoveEffect::Checkmate) as u64,
+            checkmates: (effect == ChessMoveEffect::Checkmate) as u64,
+        }
+    }
+}
+
+/// Walks every line of `depth` plies from `board`, with the side to move
+/// moving first, and counts where they end. Comparing the counts with known
+/// values for the same position validates the move generator. `board` is left
+/// as it was found.
+pub fn perft(board: &mut Board, depth: u8) -> PerftResult {
+    let mut move_generator = MoveGenerator::new();
+    perft_inner(board, depth, &mut move_generator)
+}
+
+fn perft_inner(board: &mut Board, depth: u8, move_generator: &mut MoveGenerator) -> PerftResult {
+    let mut result = PerftResult::default();
+    if depth == 0 {
+        result.nodes = 1;
+        return result;
+    }
+
+    let player = board.turn();
+    if depth == 1 {
+        // Only the last ply's moves are broken out, so only they need their
+        // effects worked out.
+        let moves =
+            move_generator.generate_moves_and_lazily_update_chess_move_effects(board, player);
+        for chess_move in moves.iter() {
+            result += PerftResult::leaf(chess_move);
+        }
+        return result;
+    }
+
+    let moves = move_generator.generate_moves(board, player);
+    for chess_move in moves.iter() {
+        chess_move.apply(board).unwrap();
+        board.toggle_turn();
+        result += perft_inner(board, depth - 1, move_generator);
+        board.toggle_turn();
+        chess_move.undo(board).unwrap();
+    }
+    result
+}
+
+/// How well the zobrist hash tells apart the positions reachable from a board.
+/// Positions are compared by their FEN, without the move clocks, which the hash
+/// doesn't cover, and without an en passant target no pawn can capture on,
+/// which it doesn't cover either.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HashAudit {
+    /// The distinct positions reached.
+    pub positions: u64,
+    /// Positions whose hash was already taken by a different position. A
+    /// transposition table can't tell these apart.
+    pub collisions: u64,
+    /// Positions reached with a different hash than they were reached with
+    /// before, meaning the hash depends on the moves that led to them.
+    pub inconsistent_hashes: u64,
+}
+
+impl HashAudit {
+    /// The share of positions that collided with another.
+    pub fn collision_rate(&self) -> f64 {
+        match self.positions {
+            0 => 0.0,
+            positions => self.collisions as f64 / positions as f64,
+        }
+    }
+}
+
+/// Walks every line of up to `depth` plies from `board`, as `perft` does, and
+/// checks each position reached against the others for hash collisions.
+/// `board` is left as it was found. The positions are all kept in memory, so
+/// deep walks take a lot of it.
+pub fn audit_position_hashes(board: &mut Board, depth: u8) -> HashAudit {
+    let mut move_generator = MoveGenerator::new();
+    let mut auditor = HashAuditor::default();
+    auditor.walk(board, depth, &mut move_generator);
+    auditor.audit
+}
+
+#[derive(Default)]
+struct HashAuditor {
+    audit: HashAudit,
+    position_by_hash: FxHashMap<u64, String>,
+    hash_by_position: FxHashMap<String, u64>,
+}
+
+impl HashAuditor {
+    fn walk(&mut self, board: &mut Board, depth: u8, move_generator: &mut MoveGenerator) {
+        self.check(board);
+        if depth == 0 {
+            return;
+        }
+        let moves = move_generator.generate_moves(board, board.turn());
+        for chess_move in moves.iter() {
+            chess_move.apply(board).unwrap();
+            board.toggle_turn();
+            self.walk(board, depth - 1, move_generator);
+            board.toggle_turn();
+            chess_move.undo(board).unwrap();
+        }
+    }
+
+    fn check(&mut self, board: &Board) {
+        let hash = board.current_position_hash();
+        let fen = board.to_fen();
+        // The last two fields are the move clocks.
+        let mut position = fen.rsplitn(3, ' ').last().unwrap().to_string();
+        if board.peek_capturable_en_passant_target().is_empty() {
+            let without_target = position.rsplitn(2, ' ').last().unwrap();
+            position = format!("{} -", without_target);
+        }
+        match self.hash_by_position.get(&position) {
+            Some(&previous_hash) => {
+                if previous_hash != hash {
+                    self.audit.inconsistent_hashes += 1;
+                }
+                return;
+            }
+            None => {
+                self.hash_by_position.insert(position.clone(), hash);
+            }
+        }
+        self.audit.positions += 1;
+        match self.position_by_hash.get(&hash) {
+            Some(previous_position) if *previous_position != position => {
+                self.audit.collisions += 1;
+            }
+            Some(_) => (),
+            None => {
+                self.position_by_hash.insert(hash, position);
+            }
+        }
+    }
+}
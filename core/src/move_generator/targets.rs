@@ -5,6 +5,7 @@ use common::bitboard::bitboard::Bitboard;
 use common::bitboard::square::ORDERED_SQUARES;
 use rustc_hash::FxHashMap;
 use smallvec::{smallvec, SmallVec};
+use std::sync::OnceLock;
 
 use super::magic_table::MagicTable;
 
@@ -40,10 +41,27 @@ impl Default for Targets {
 }
 
 impl Targets {
+    /// One set of tables for callers that only look targets up, such as the
+    /// board's check queries, which have no move generator of their own.
+    pub fn shared() -> &'static Self {
+        static TARGETS: OnceLock<Targets> = OnceLock::new();
+        TARGETS.get_or_init(Targets::default)
+    }
+
     pub fn generate_attack_targets(&mut self, board: &Board, color: Color) -> Bitboard {
-        let mut piece_targets: PieceTargetList = smallvec![];
         let mut attack_targets = Bitboard::EMPTY;
 
+        for (_piece, targets) in self.generate_piece_attack_targets(board, color) {
+            attack_targets |= targets;
+        }
+
+        attack_targets
+    }
+
+    /// The squares attacked by each of `color`'s pieces, by the piece's square.
+    pub fn generate_piece_attack_targets(&self, board: &Board, color: Color) -> PieceTargetList {
+        let mut piece_targets: PieceTargetList = smallvec![];
+
         generate_pawn_attack_targets(&mut piece_targets, board, color);
         self.generate_sliding_targets(&mut piece_targets, board, color);
         self.generate_targets_from_precomputed_tables(
@@ -59,11 +77,33 @@ impl Targets {
             Piece::King,
         );
 
-        for (_piece, targets) in piece_targets {
-            attack_targets |= targets;
-        }
+        piece_targets
+    }
 
-        attack_targets
+    /// The king's square and the squares around it.
+    pub fn king_zone(&self, king: Bitboard) -> Bitboard {
+        king | self.get_precomputed_targets(king, Piece::King)
+    }
+
+    /// The squares of `color`'s pieces that attack `square`, whatever stands
+    /// on it. Each piece's targets are looked up from the square itself: a
+    /// knight on `square` attacks the same squares that attack it as a knight,
+    /// and so on.
+    pub fn attackers_to(&self, board: &Board, square: Bitboard, color: Color) -> Bitboard {
+        let pieces = board.pieces(color);
+        let occupied = board.occupied();
+        let queens = pieces.locate(Piece::Queen);
+
+        let pawns = pawn_attack_targets(square, color.opposite()) & pieces.locate(Piece::Pawn);
+        let knights =
+            self.get_precomputed_targets(square, Piece::Knight) & pieces.locate(Piece::Knight);
+        let kings = self.get_precomputed_targets(square, Piece::King) & pieces.locate(Piece::King);
+        let diagonals = self.magic_table.get_bishop_targets(square, occupied)
+            & (pieces.locate(Piece::Bishop) | queens);
+        let orthogonals = self.magic_table.get_rook_targets(square, occupied)
+            & (pieces.locate(Piece::Rook) | queens);
+
+        pawns | knights | kings | diagonals | orthogonals
     }
 
     pub fn generate_targets_from_precomputed_tables(
@@ -98,13 +138,7 @@ impl Targets {
     ) {
         let occupied = board.occupied();
 
-        for x in 0..64 {
-            let square = Bitboard(1 << x);
-            let piece = match board.pieces(color).get(square) {
-                Some(p) => p,
-                None => continue,
-            };
-
+        for (square, piece) in board.pieces(color).iter() {
             let targets_including_own_pieces = match piece {
                 Piece::Rook => self.magic_table.get_rook_targets(square, occupied),
                 Piece::Bishop => self.magic_table.get_bishop_targets(square, occupied),
@@ -120,6 +154,29 @@ impl Targets {
         }
     }
 
+    /// The squares a knight, bishop, rook, queen or king on `square` can move
+    /// to, short of castling: those it attacks, other than its own pieces'.
+    pub fn piece_targets(
+        &self,
+        board: &Board,
+        color: Color,
+        square: Bitboard,
+        piece: Piece,
+    ) -> Bitboard {
+        let occupied = board.occupied();
+        let targets = match piece {
+            Piece::Knight | Piece::King => self.get_precomputed_targets(square, piece),
+            Piece::Rook => self.magic_table.get_rook_targets(square, occupied),
+            Piece::Bishop => self.magic_table.get_bishop_targets(square, occupied),
+            Piece::Queen => {
+                self.magic_table.get_rook_targets(square, occupied)
+                    | self.magic_table.get_bishop_targets(square, occupied)
+            }
+            Piece::Pawn => panic!("invalid piece type for piece targets: {}", piece),
+        };
+        targets & !board.pieces(color).occupied()
+    }
+
     pub fn get_cached_attack(&self, color: Color, board_hash: u64) -> Option<Bitboard> {
         self.attacks_cache.get(&(color as u8, board_hash)).copied()
     }
@@ -165,11 +222,7 @@ pub fn generate_pawn_move_targets(board: &Board, color: Color) -> PieceTargetLis
     };
     let move_targets = (single_move_targets | double_move_targets) & !occupied;
 
-    for x in 0..64 {
-        let pawn = Bitboard(1 << x);
-        if !pawns.overlaps(pawn) {
-            continue;
-        }
+    for pawn in board.pieces(color).iter_pieces(Piece::Pawn) {
         let mut targets = Bitboard::EMPTY;
 
         let single_move = match color {
@@ -208,27 +261,24 @@ pub fn generate_pawn_attack_targets(
     board: &Board,
     color: Color,
 ) {
-    let pawns = board.pieces(color).locate(Piece::Pawn);
-
-    for x in 0..64 {
-        let pawn = Bitboard(1 << x);
-        if !pawns.overlaps(pawn) {
-            continue;
-        }
+    for pawn in board.pieces(color).iter_pieces(Piece::Pawn) {
+        piece_targets.push((pawn, pawn_attack_targets(pawn, color)));
+    }
+}
 
-        let attack_west = match color {
-            Color::White => (pawn << 9) & !Bitboard::A_FILE,
-            Color::Black => (pawn >> 7) & !Bitboard::A_FILE,
-        };
+/// The squares a `color` pawn on `pawn` attacks.
+pub fn pawn_attack_targets(pawn: Bitboard, color: Color) -> Bitboard {
+    let attack_west = match color {
+        Color::White => (pawn << 9) & !Bitboard::A_FILE,
+        Color::Black => (pawn >> 7) & !Bitboard::A_FILE,
+    };
 
-        let attack_east = match color {
-            Color::White => (pawn << 7) & !Bitboard::H_FILE,
-            Color::Black => (pawn >> 9) & !Bitboard::H_FILE,
-        };
+    let attack_east = match color {
+        Color::White => (pawn << 7) & !Bitboard::H_FILE,
+        Color::Black => (pawn >> 9) & !Bitboard::H_FILE,
+    };
 
-        let targets = attack_east | attack_west;
-        piece_targets.push((pawn, targets));
-    }
+    attack_east | attack_west
 }
 
 pub fn generate_knight_targets_table() -> [Bitboard; 64] {
@@ -293,6 +343,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_attackers_to() {
+        let targets = Targets::new();
+        let board = chess_position! {
+            ...r..k.
+            ........
+            ..n.....
+            ...p....
+            ..P.B...
+            ........
+            ...Q....
+            ...R..K.
+        };
+
+        // The rook behind the queen doesn't attack through it.
+        assert_eq!(targets.attackers_to(&board, D5, Color::White), C4 | D2 | E4);
+        assert_eq!(
+            targets.attackers_to(&board, D2, Color::Black),
+            Bitboard::EMPTY
+        );
+        // A piece's defenders count as attacking its square.
+        assert_eq!(targets.attackers_to(&board, D5, Color::Black), D8);
+        assert_eq!(targets.attackers_to(&board, E4, Color::Black), D5);
+        assert_eq!(targets.attackers_to(&board, D2, Color::White), D1);
+        assert_eq!(targets.attackers_to(&board, F2, Color::White), D2 | G1);
+    }
+
+    #[test]
+    fn test_attackers_to_matches_attack_targets() {
+        let targets = Targets::new();
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+
+        for color in [Color::White, Color::Black] {
+            let piece_targets = targets.generate_piece_attack_targets(&board, color);
+            for square in ORDERED_SQUARES {
+                if board.pieces(color).occupied().overlaps(square) {
+                    continue;
+                }
+                let expected = piece_targets
+                    .iter()
+                    .filter(|(_, targets)| targets.overlaps(square))
+                    .fold(Bitboard::EMPTY, |attackers, (piece, _)| attackers | *piece);
+                assert_eq!(targets.attackers_to(&board, square, color), expected);
+            }
+        }
+    }
+
     #[test]
     fn test_generate_attack_targets_1() {
         let mut targets = Targets::new();
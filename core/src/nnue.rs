@@ -0,0 +1,304 @@
+use std::sync::Arc;
+
+use common::bitboard::bitboard::Bitboard;
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+
+/// HalfKP inputs, for each perspective: the square of that side's king, times
+/// each of the other 10 pieces (the perspective's own pawn through queen, then
+/// the opponent's) on each square.
+pub const INPUTS: usize = 64 * 10 * 64;
+/// The quantization of the hidden layer's activations and the output weights,
+/// and the scale from the network's output to centipawns.
+const QA: i32 = 255;
+const QB: i32 = 64;
+const SCALE: i32 = 400;
+
+/// A small efficiently updatable neural network: HalfKP inputs for each side,
+/// one hidden layer that both sides share, clipped, and a single output. The
+/// engine reads and writes networks in its file format.
+#[derive(Debug, PartialEq)]
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+impl Network {
+    /// A network with `hidden` neurons: `INPUTS` × `hidden` feature weights,
+    /// input by input, `hidden` feature biases, and 2 × `hidden` output
+    /// weights, for the side to move's half, then the other's.
+    ///
+    /// Panics if the weights and biases don't match `hidden`.
+    pub fn new(
+        hidden: usize,
+        feature_weights: Vec<i16>,
+        feature_biases: Vec<i16>,
+        output_weights: Vec<i16>,
+        output_bias: i32,
+    ) -> Self {
+        assert_eq!(feature_weights.len(), INPUTS * hidden);
+        assert_eq!(feature_biases.len(), hidden);
+        assert_eq!(output_weights.len(), 2 * hidden);
+        Self {
+            hidden,
+            feature_weights,
+            feature_biases,
+            output_weights,
+            output_bias,
+        }
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.hidden
+    }
+
+    pub fn feature_weights(&self) -> &[i16] {
+        &self.feature_weights
+    }
+
+    pub fn feature_biases(&self) -> &[i16] {
+        &self.feature_biases
+    }
+
+    pub fn output_weights(&self) -> &[i16] {
+        &self.output_weights
+    }
+
+    pub fn output_bias(&self) -> i32 {
+        self.output_bias
+    }
+
+    fn weights(&self, input: usize) -> &[i16] {
+        &self.feature_weights[input * self.hidden..(input + 1) * self.hidden]
+    }
+}
+
+/// The input for a piece on `square`, seen from `perspective` with its king on
+/// `king`. Black sees the board flipped, so that both sides share the weights.
+fn input(
+    perspective: Color,
+    king: Bitboard,
+    square: Bitboard,
+    piece: Piece,
+    color: Color,
+) -> usize {
+    let orient = |square: Bitboard| match perspective {
+        Color::White => square.trailing_zeros() as usize,
+        Color::Black => square.trailing_zeros() as usize ^ 56,
+    };
+    let side = if color == perspective { 0 } else { 5 };
+    orient(king) * 640 + (side + piece as usize) * 64 + orient(square)
+}
+
+/// The hidden layer's values for a position, from each side's perspective,
+/// kept up to date as pieces are put on and removed from the board so that
+/// each move costs a handful of additions rather than a full pass over the
+/// network. Moving a king changes every input on its side, so its side is
+/// recomputed from the board once the king is put back.
+#[derive(Clone)]
+pub struct Accumulator {
+    network: Arc<Network>,
+    /// By `Color`.
+    values: [Vec<i16>; 2],
+    /// Each side's king, or `None` while it's off the board mid-move, when its
+    /// side's values are stale.
+    kings: [Option<Bitboard>; 2],
+}
+
+impl Accumulator {
+    pub fn new(network: Arc<Network>, board: &Board) -> Self {
+        let mut accumulator = Self {
+            values: [
+                network.feature_biases.clone(),
+                network.feature_biases.clone(),
+            ],
+            network,
+            kings: [None, None],
+        };
+        accumulator.refresh(board, Color::White);
+        accumulator.refresh(board, Color::Black);
+        accumulator
+    }
+
+    pub fn network(&self) -> &Arc<Network> {
+        &self.network
+    }
+
+    fn refresh(&mut self, board: &Board, perspective: Color) {
+        let side = perspective as usize;
+        self.values[side].copy_from_slice(&self.network.feature_biases);
+        let king = board.pieces(perspective).locate(Piece::King);
+        self.kings[side] = (king.count_ones() == 1).then_some(king);
+        if self.kings[side].is_none() {
+            return;
+        }
+        for (square, piece, color) in board.iter() {
+            if piece != Piece::King {
+                self.update(perspective, king, square, piece, color, 1);
+            }
+        }
+    }
+
+    fn update(
+        &mut self,
+        perspective: Color,
+        king: Bitboard,
+        square: Bitboard,
+        piece: Piece,
+        color: Color,
+        sign: i16,
+    ) {
+        let weights = self
+            .network
+            .weights(input(perspective, king, square, piece, color));
+        for (value, &weight) in self.values[perspective as usize].iter_mut().zip(weights) {
+            *value = value.wrapping_add(sign.wrapping_mul(weight));
+        }
+    }
+
+    /// Called by `Board::put` once the piece is on the board.
+    pub(crate) fn on_put(&mut self, board: &Board, square: Bitboard, piece: Piece, color: Color) {
+        if piece == Piece::King {
+            self.refresh(board, color);
+            return;
+        }
+        self.toggle(square, piece, color, 1);
+    }
+
+    /// Called by `Board::remove` once the piece is off the board.
+    pub(crate) fn on_remove(&mut self, square: Bitboard, piece: Piece, color: Color) {
+        if piece == Piece::King {
+            self.kings[color as usize] = None;
+            return;
+        }
+        self.toggle(square, piece, color, -1);
+    }
+
+    fn toggle(&mut self, square: Bitboard, piece: Piece, color: Color, sign: i16) {
+        for perspective in [Color::White, Color::Black] {
+            if let Some(king) = self.kings[perspective as usize] {
+                self.update(perspective, king, square, piece, color, sign);
+            }
+        }
+    }
+
+    /// The network's score for the position, in centipawns from `turn`'s
+    /// perspective, or `None` if a side has no king.
+    pub fn evaluate(&self, turn: Color) -> Option<i16> {
+        if self.kings.iter().any(Option::is_none) {
+            return None;
+        }
+        let hidden = self.network.hidden;
+        let (ours, theirs) = self.network.output_weights.split_at(hidden);
+        let activation = |values: &[i16], weights: &[i16]| {
+            values
+                .iter()
+                .zip(weights)
+                .map(|(&value, &weight)| (value as i32).clamp(0, QA) * weight as i32)
+                .sum::<i32>()
+        };
+        let output = self.network.output_bias
+            + activation(&self.values[turn as usize], ours)
+            + activation(&self.values[turn.opposite() as usize], theirs);
+        let score = output as i64 * SCALE as i64 / (QA * QB) as i64;
+        Some(score.clamp(i16::MIN as i64 / 2, i16::MAX as i64 / 2) as i16)
+    }
+
+    #[cfg(test)]
+    fn values(&self) -> &[Vec<i16>; 2] {
+        &self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::algebraic_notation::{
+        enumerate_candidate_moves_with_algebraic_notation, find_move_by_san,
+    };
+    use crate::move_generator::MoveGenerator;
+
+    /// A network with arbitrary but repeatable weights.
+    fn test_network() -> Network {
+        let hidden = 4;
+        let arbitrary = |count: usize, seed: usize| {
+            (0..count)
+                .map(|i| ((i * 7919 + seed) % 61) as i16 - 30)
+                .collect::<Vec<_>>()
+        };
+        Network {
+            hidden,
+            feature_weights: arbitrary(INPUTS * hidden, 1),
+            feature_biases: arbitrary(hidden, 2),
+            output_weights: arbitrary(2 * hidden, 3),
+            output_bias: 100,
+        }
+    }
+
+    #[test]
+    fn test_incremental_updates_match_a_refresh() {
+        let network = Arc::new(test_network());
+        // Captures and castling on both sides, then king moves, en passant, and
+        // a promotion.
+        for line in [
+            vec![
+                "e4", "d5", "exd5", "Qxd5", "Nc3", "Qa5", "d4", "Nf6", "Nf3", "Bf5", "Bc4", "e6",
+                "O-O", "Na6", "Bd2", "Bb4", "a3", "O-O-O",
+            ],
+            vec![
+                "e4", "d5", "e5", "f5", "exf6", "Nc6", "fxg7", "Kf7", "gxh8=Q", "Qd6", "Qxh7+",
+            ],
+        ] {
+            let mut board = Board::starting_position();
+            let mut move_generator = MoveGenerator::new();
+            board.set_nnue(Some(network.clone()));
+            let mut moves = Vec::new();
+            for san in line {
+                let turn = board.turn();
+                let candidates = enumerate_candidate_moves_with_algebraic_notation(
+                    &mut board,
+                    turn,
+                    &mut move_generator,
+                );
+                let chess_move = find_move_by_san(san, &candidates).unwrap();
+                chess_move.apply(&mut board).unwrap();
+                board.toggle_turn();
+                moves.push(chess_move);
+
+                let fresh = Accumulator::new(network.clone(), &board);
+                assert_eq!(board.nnue().unwrap().values(), fresh.values(), "{}", san);
+                let score = fresh.evaluate(board.turn()).unwrap();
+                let white_score = match board.turn() {
+                    Color::White => score,
+                    Color::Black => -score,
+                };
+                assert_eq!(board.nnue_score(), Some(white_score));
+            }
+
+            for chess_move in moves.iter().rev() {
+                board.toggle_turn();
+                chess_move.undo(&mut board).unwrap();
+            }
+            let fresh = Accumulator::new(network.clone(), &Board::starting_position());
+            assert_eq!(board.nnue().unwrap().values(), fresh.values());
+        }
+    }
+
+    #[test]
+    fn test_perspectives_are_symmetric() {
+        let network = Arc::new(test_network());
+        // The same position with the colors swapped scores the same for the
+        // side to move.
+        let white = Board::from_fen("4k3/8/8/3p4/8/2N5/8/4K3 w - - 0 1").unwrap();
+        let black = Board::from_fen("4k3/8/2n5/8/3P4/8/8/4K3 b - - 0 1").unwrap();
+        let white_score = Accumulator::new(network.clone(), &white).evaluate(Color::White);
+        let black_score = Accumulator::new(network, &black).evaluate(Color::Black);
+        assert!(white_score.is_some());
+        assert_eq!(white_score, black_score);
+    }
+}
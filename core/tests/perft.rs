@@ -0,0 +1,135 @@
+use chess_core::board::Board;
+use chess_core::move_generator::perft::{audit_position_hashes, perft, PerftResult};
+
+/// Reference values from the Chess Programming Wiki's perft results. Each row is
+/// a depth, with nodes, captures, en passants, castles, promotions, checks, and
+/// checkmates.
+const STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const STARTING_POSITION_RESULTS: [[u64; 7]; 3] = [
+    [20, 0, 0, 0, 0, 0, 0],
+    [400, 0, 0, 0, 0, 0, 0],
+    [8902, 34, 0, 0, 0, 12, 0],
+];
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const KIWIPETE_RESULTS: [[u64; 7]; 2] = [[48, 8, 0, 2, 0, 0, 0], [2039, 351, 1, 91, 0, 3, 0]];
+
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_3_RESULTS: [[u64; 7]; 4] = [
+    [14, 1, 0, 0, 0, 2, 0],
+    [191, 14, 0, 0, 0, 10, 0],
+    [2812, 209, 2, 0, 0, 267, 0],
+    [43238, 3348, 123, 0, 0, 1680, 17],
+];
+
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_4_RESULTS: [[u64; 7]; 3] = [
+    [6, 0, 0, 0, 0, 0, 0],
+    [264, 87, 0, 6, 48, 10, 0],
+    [9467, 1021, 4, 0, 120, 38, 22],
+];
+
+/// Only node counts are published for this one.
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_5_NODES: [u64; 2] = [44, 1486];
+
+fn assert_perft_results(fen: &str, results: &[[u64; 7]]) {
+    for (depth, expected) in (1..).zip(results) {
+        let mut board = Board::from_fen(fen).unwrap();
+        let [nodes, captures, en_passants, castles, promotions, checks, checkmates] = *expected;
+        let expected = PerftResult {
+            nodes,
+            captures,
+            en_passants,
+            castles,
+            promotions,
+            checks,
+            checkmates,
+        };
+        assert_eq!(
+            perft(&mut board, depth),
+            expected,
+            "{} at depth {}",
+            fen,
+            depth
+        );
+        assert_eq!(board.to_fen(), Board::from_fen(fen).unwrap().to_fen());
+    }
+}
+
+fn assert_perft_nodes(fen: &str, nodes: &[u64]) {
+    for (depth, &expected) in (1..).zip(nodes) {
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(
+            perft(&mut board, depth).nodes,
+            expected,
+            "{} at depth {}",
+            fen,
+            depth
+        );
+    }
+}
+
+#[test]
+fn test_perft_starting_position() {
+    assert_perft_results(STARTING_POSITION, &STARTING_POSITION_RESULTS);
+}
+
+#[test]
+fn test_perft_kiwipete() {
+    assert_perft_results(KIWIPETE, &KIWIPETE_RESULTS);
+}
+
+#[test]
+fn test_perft_position_3() {
+    assert_perft_results(POSITION_3, &POSITION_3_RESULTS);
+}
+
+#[test]
+fn test_perft_position_4() {
+    assert_perft_results(POSITION_4, &POSITION_4_RESULTS);
+}
+
+#[test]
+fn test_perft_position_5() {
+    assert_perft_nodes(POSITION_5, &POSITION_5_NODES);
+}
+
+#[test]
+fn test_no_hash_collisions_from_starting_position() {
+    let mut board = Board::from_fen(STARTING_POSITION).unwrap();
+    let audit = audit_position_hashes(&mut board, 3);
+    // 1 + 20 + 400 + 5362 distinct positions after 0 to 3 plies.
+    assert_eq!(audit.positions, 5783);
+    assert_eq!(audit.collisions, 0);
+    assert_eq!(audit.inconsistent_hashes, 0);
+}
+
+/// Audits the hash over every position within a few plies of each reference
+/// position. Slow, and takes over a gigabyte of memory, so it's only run on request:
+/// `cargo test --release --test perft -- --ignored`.
+#[test]
+#[ignore]
+fn test_hash_collision_rate() {
+    for (fen, depth) in [
+        (STARTING_POSITION, 5),
+        (KIWIPETE, 4),
+        (POSITION_3, 6),
+        (POSITION_4, 4),
+        (POSITION_5, 4),
+    ] {
+        let mut board = Board::from_fen(fen).unwrap();
+        let audit = audit_position_hashes(&mut board, depth);
+        println!(
+            "{} at depth {}: {} positions, {} collisions ({:.6}%), {} inconsistent hashes",
+            fen,
+            depth,
+            audit.positions,
+            audit.collisions,
+            audit.collision_rate() * 100.0,
+            audit.inconsistent_hashes
+        );
+        assert_eq!(audit.collisions, 0, "{}", fen);
+        assert_eq!(audit.inconsistent_hashes, 0, "{}", fen);
+    }
+}
@@ -1,11 +1,11 @@
-use chess::alpha_beta_searcher::{alpha_beta_search, SearchContext};
-use chess::board::castle_rights_bitmask::ALL_CASTLE_RIGHTS;
-use chess::board::color::Color;
-use chess::board::piece::Piece;
-use chess::board::Board;
-use chess::chess_position;
-use chess::evaluate::{self, GameEnding};
-use chess::move_generator::MoveGenerator;
+use chess_engine::alpha_beta_searcher::{alpha_beta_search, SearchContext};
+use chess_engine::board::castle_rights_bitmask::ALL_CASTLE_RIGHTS;
+use chess_engine::board::color::Color;
+use chess_engine::board::piece::Piece;
+use chess_engine::board::Board;
+use chess_engine::chess_position;
+use chess_engine::evaluate::{self, GameEnding};
+use chess_engine::move_generator::MoveGenerator;
 
 use common::bitboard::bitboard::Bitboard;
 use criterion::{criterion_group, criterion_main, Criterion};
@@ -0,0 +1,31 @@
+/// Centipawns of contempt for every 100 rating points the engine is rated above
+/// its opponent.
+const CONTEMPT_PER_100_POINTS: i32 = 10;
+/// The most contempt either way. Past this, the engine would give up real
+/// advantages to dodge a draw, or accept real disadvantages to reach one.
+pub const MAX_CONTEMPT: i16 = 50;
+
+/// How many centipawns below even the engine should value a draw, given its
+/// rating and its opponent's. Against a weaker opponent, a draw is a poor
+/// result, so the engine pays a little to avoid one and keeps pressing for a
+/// win. Against a stronger one, contempt is negative, and the engine steers
+/// toward solid, drawish positions instead.
+pub fn contempt_for_ratings(engine_rating: u32, opponent_rating: u32) -> i16 {
+    let difference = engine_rating as i32 - opponent_rating as i32;
+    let contempt = difference.saturating_mul(CONTEMPT_PER_100_POINTS) / 100;
+    contempt.clamp(-(MAX_CONTEMPT as i32), MAX_CONTEMPT as i32) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contempt_for_ratings() {
+        assert_eq!(contempt_for_ratings(2000, 2000), 0);
+        assert_eq!(contempt_for_ratings(2000, 1800), 20);
+        assert_eq!(contempt_for_ratings(1800, 2000), -20);
+        assert_eq!(contempt_for_ratings(2500, 1000), MAX_CONTEMPT);
+        assert_eq!(contempt_for_ratings(1000, 2500), -MAX_CONTEMPT);
+    }
+}
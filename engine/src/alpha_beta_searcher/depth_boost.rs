@@ -0,0 +1,67 @@
+use crate::board::Board;
+
+/// Searches deeper as pieces come off the board, where there are fewer moves
+/// to search at each node and depth matters more, e.g. to see a pawn through
+/// to promotion. A position with `max_pieces` pieces or fewer, kings and pawns
+/// included, is searched one ply deeper, and one more ply for each further
+/// `pieces_per_ply` pieces that are gone, up to `max_plies`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthBoost {
+    pub max_pieces: u32,
+    pub pieces_per_ply: u32,
+    pub max_plies: u8,
+}
+
+impl Default for DepthBoost {
+    fn default() -> Self {
+        Self {
+            max_pieces: 10,
+            pieces_per_ply: 4,
+            max_plies: 2,
+        }
+    }
+}
+
+impl DepthBoost {
+    /// How many plies deeper than its nominal depth to search the position.
+    pub fn extra_plies(&self, board: &Board) -> u8 {
+        let pieces = board.occupied().count_ones();
+        if pieces > self.max_pieces {
+            return 0;
+        }
+        let plies = 1 + (self.max_pieces - pieces) / self.pieces_per_ply.max(1);
+        plies.min(self.max_plies as u32) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_plies() {
+        let boost = DepthBoost::default();
+        for (fen, expected) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0,
+            ),
+            ("4k3/ppp2pp1/8/8/8/8/PPP2PP1/4K3 w - - 0 1", 0),
+            ("4k3/pp3pp1/8/8/8/8/PP3PP1/4K3 w - - 0 1", 1),
+            ("4k3/p7/8/8/8/8/PP3PP1/4K3 w - - 0 1", 1),
+            ("4k3/p7/8/8/8/8/PP3P2/4K3 w - - 0 1", 2),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", 2),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(boost.extra_plies(&board), expected, "{}", fen);
+        }
+
+        let steep = DepthBoost {
+            max_pieces: 6,
+            pieces_per_ply: 1,
+            max_plies: 3,
+        };
+        let board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(steep.extra_plies(&board), 3);
+    }
+}
@@ -0,0 +1,102 @@
+use common::bitboard::bitboard::Bitboard;
+
+use crate::board::piece::Piece;
+use crate::board::Board;
+use crate::move_generator::ChessMoveList;
+
+use super::prioritize_chess_moves::is_quiet;
+
+/// How much each quiet move, by the piece moved and the square it moved to, has
+/// caused beta cutoffs. Cutoffs near the root prune the most, so they count for
+/// more. Unlike killer moves, which only remember the last couple of cutoffs at
+/// each ply, the table orders every quiet move.
+#[derive(Clone, Debug)]
+pub struct HistoryTable {
+    scores: [[u32; 64]; 6],
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        Self {
+            scores: [[0; 64]; 6],
+        }
+    }
+}
+
+impl HistoryTable {
+    /// Records a cutoff by a quiet move of `piece` to `to_square`, found with
+    /// `depth` plies remaining.
+    pub fn record(&mut self, piece: Piece, to_square: Bitboard, depth: u8) {
+        let score = &mut self.scores[piece as usize][to_square.trailing_zeros() as usize];
+        *score = score.saturating_add(depth as u32 * depth as u32);
+    }
+
+    pub fn score(&self, piece: Piece, to_square: Bitboard) -> u32 {
+        self.scores[piece as usize][to_square.trailing_zeros() as usize]
+    }
+
+    /// Halves every score, so that cutoffs from earlier searches count for less
+    /// than those from the current one.
+    pub fn age(&mut self) {
+        for score in self.scores.iter_mut().flatten() {
+            *score /= 2;
+        }
+    }
+
+    /// Forgets every cutoff, for a new game.
+    pub fn clear(&mut self) {
+        for score in self.scores.iter_mut().flatten() {
+            *score = 0;
+        }
+    }
+
+    /// Sorts the quiet moves in `moves`, highest history score first. `moves`
+    /// should already be sorted with `sort_chess_moves`, which puts the quiet
+    /// moves last; their order is kept where scores tie.
+    pub fn sort_quiet_moves(&self, moves: &mut ChessMoveList, board: &Board) {
+        let first_quiet = match moves.iter().position(is_quiet) {
+            Some(index) => index,
+            None => return,
+        };
+        moves[first_quiet..].sort_by_key(|chess_move| {
+            let score = match board.get(chess_move.from_square()) {
+                Some((piece, _)) => self.score(piece, chess_move.to_square()),
+                None => 0,
+            };
+            std::cmp::Reverse(score)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::chess_move::ChessMove;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::std_move;
+    use common::bitboard::square::*;
+
+    #[test]
+    fn test_sort_quiet_moves_by_history() {
+        let board = Board::starting_position();
+        let mut history = HistoryTable::default();
+        history.record(Piece::Knight, F3, 2);
+        history.record(Piece::Pawn, D4, 3);
+        history.record(Piece::Pawn, D4, 1);
+        assert_eq!(history.score(Piece::Pawn, D4), 10);
+        assert_eq!(history.score(Piece::Pawn, E4), 0);
+
+        let mut moves = ChessMoveList::new();
+        moves.push(std_move!(E2, E4));
+        moves.push(std_move!(G1, F3));
+        moves.push(std_move!(D2, D4));
+        moves.push(std_move!(A2, A3));
+        history.sort_quiet_moves(&mut moves, &board);
+        let from_squares: Vec<_> = moves.iter().map(|m| m.from_square()).collect();
+        assert_eq!(from_squares, vec![D2, G1, E2, A2]);
+
+        history.age();
+        assert_eq!(history.score(Piece::Pawn, D4), 5);
+    }
+}
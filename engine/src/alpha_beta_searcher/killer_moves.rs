@@ -0,0 +1,112 @@
+use crate::chess_move::chess_move::ChessMove;
+use crate::move_generator::ChessMoveList;
+
+use super::prioritize_chess_moves::is_quiet;
+
+/// Quiet moves that recently caused a beta cutoff, kept per ply from the root.
+/// A move that refutes one line often refutes its siblings too, so trying these
+/// right after captures produces cutoffs sooner than the static move ordering.
+#[derive(Clone, Debug, Default)]
+pub struct KillerMoves {
+    slots: Vec<[Option<ChessMove>; KILLER_MOVES_PER_PLY]>,
+}
+
+const KILLER_MOVES_PER_PLY: usize = 2;
+
+impl KillerMoves {
+    /// Records a quiet move that caused a cutoff at `ply`. The newest killer
+    /// takes the first slot, and the oldest is forgotten.
+    pub fn record(&mut self, ply: usize, chess_move: &ChessMove) {
+        if self.slots.len() <= ply {
+            self.slots.resize(ply + 1, Default::default());
+        }
+        let slots = &mut self.slots[ply];
+        if slots[0]
+            .as_ref()
+            .is_some_and(|killer| same_move(killer, chess_move))
+        {
+            return;
+        }
+        slots.rotate_right(1);
+        slots[0] = Some(chess_move.clone());
+    }
+
+    /// Forgets every killer, for a new game.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+
+    pub fn get(&self, ply: usize) -> impl Iterator<Item = &ChessMove> {
+        self.slots.get(ply).into_iter().flatten().flatten()
+    }
+
+    /// Moves this ply's killers that are among `moves` ahead of the other quiet
+    /// moves, right after the checks, captures, and promotions, newest killer
+    /// first. `moves` should already be sorted with `sort_chess_moves`.
+    pub fn prioritize(&self, ply: usize, moves: &mut ChessMoveList) {
+        let first_quiet = match moves.iter().position(is_quiet) {
+            Some(index) => index,
+            None => return,
+        };
+        let killers: Vec<&ChessMove> = self.get(ply).collect();
+        for killer in killers.into_iter().rev() {
+            let found = moves[first_quiet..]
+                .iter()
+                .position(|chess_move| same_move(chess_move, killer));
+            if let Some(offset) = found {
+                let chess_move = moves.remove(first_quiet + offset);
+                moves.insert(first_quiet, chess_move);
+            }
+        }
+    }
+}
+
+/// Killers are matched by their squares, since the same move can have a
+/// different effect in a sibling position.
+fn same_move(a: &ChessMove, b: &ChessMove) -> bool {
+    a.from_square() == b.from_square() && a.to_square() == b.to_square()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::piece::Piece;
+    use crate::chess_move::capture::Capture;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::{check_move, std_move};
+    use common::bitboard::square::*;
+
+    #[test]
+    fn test_record_keeps_two_newest_killers() {
+        let mut killers = KillerMoves::default();
+        killers.record(3, &std_move!(A2, A3));
+        killers.record(3, &std_move!(B2, B3));
+        killers.record(3, &std_move!(B2, B3));
+        killers.record(3, &std_move!(C2, C3));
+
+        let expected = [std_move!(C2, C3), std_move!(B2, B3)];
+        assert!(killers.get(3).eq(expected.iter()));
+        assert_eq!(killers.get(0).count(), 0);
+        assert_eq!(killers.get(10).count(), 0);
+    }
+
+    #[test]
+    fn test_prioritize_puts_killers_after_captures() {
+        let mut killers = KillerMoves::default();
+        killers.record(1, &std_move!(H2, H3));
+        killers.record(1, &std_move!(G2, G3));
+
+        let mut moves = ChessMoveList::new();
+        moves.push(check_move!(std_move!(D1, D7)));
+        moves.push(std_move!(E4, D5, Capture(Piece::Pawn)));
+        moves.push(std_move!(A2, A3));
+        moves.push(std_move!(H2, H3));
+        moves.push(std_move!(B2, B3));
+        moves.push(std_move!(G2, G3));
+        killers.prioritize(1, &mut moves);
+
+        let squares: Vec<_> = moves.iter().map(|m| m.from_square()).collect();
+        assert_eq!(squares, vec![D1, E4, G2, H2, A2, B2]);
+    }
+}
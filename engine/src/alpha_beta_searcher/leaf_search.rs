@@ -0,0 +1,239 @@
+use std::cmp::{max, min};
+
+use crate::board::Board;
+use crate::evaluate;
+use crate::move_generator::MoveGenerator;
+
+use super::node_stats::NodeType;
+use super::prioritize_chess_moves::sort_chess_moves;
+use super::{
+    check_cache, record_cutoff, record_node_type, record_selective_depth, set_cache, SearchContext,
+    SearchError,
+};
+
+/// Nodes this close to the leaves are searched by `leaf_search`.
+pub const LEAF_SEARCH_DEPTH: u8 = 2;
+
+/// `alpha_beta_minimax` specialized for the last `DEPTH` plies, which are where
+/// nearly all of a search's nodes are. The depth is a const parameter, so each
+/// ply gets its own copy of the function with the depth checks folded away.
+/// Compared to the general search, it:
+///
+/// - scores the leaves in place, rather than recursing into them, and doesn't
+///   cache their scores, since a static evaluation is cheaper than a cache
+///   lookup;
+/// - counts the nodes it searches once, rather than taking the shared counter's
+///   lock at every leaf;
+/// - doesn't work out which moves give check or checkmate before ordering them
+///   one ply from the leaves. That takes a move generation for every move, and
+///   there's too little left to search for the better ordering to pay for it.
+///
+/// Scores are the same as the general search's.
+pub(super) fn leaf_search<const DEPTH: u8>(
+    context: &mut SearchContext,
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    alpha: i16,
+    beta: i16,
+    maximizing_player: bool,
+) -> Result<i16, SearchError> {
+    if context.should_stop_at_node() {
+        return Err(SearchError::Stopped);
+    }
+
+    let search_node = (board.current_position_hash(), alpha, beta, DEPTH);
+    if let Some(score) = check_cache(context, search_node) {
+        return Ok(score);
+    }
+
+    let mut nodes = 1;
+    let current_turn = board.turn();
+    let ply = context.iteration_depth - DEPTH;
+    let mut candidates = if DEPTH == 1 {
+        move_generator.generate_moves(board, current_turn)
+    } else {
+        move_generator.generate_moves_and_lazily_update_chess_move_effects(board, current_turn)
+    };
+    sort_chess_moves(&mut candidates, board);
+    context
+        .history
+        .read()
+        .unwrap()
+        .sort_quiet_moves(&mut candidates, board);
+    context
+        .killer_moves
+        .read()
+        .unwrap()
+        .prioritize(ply as usize, &mut candidates);
+
+    let value = if candidates.is_empty() {
+        evaluate::score_in_window(
+            board,
+            move_generator,
+            current_turn,
+            ply,
+            &context.evaluation_terms,
+            alpha,
+            beta,
+            Some(&context.eval_cache),
+        )
+    } else {
+        let (original_alpha, original_beta) = (alpha, beta);
+        let (mut alpha, mut beta) = (alpha, beta);
+        let mut cutoff = false;
+        let mut value = if maximizing_player {
+            i16::MIN
+        } else {
+            i16::MAX
+        };
+        for (move_index, chess_move) in candidates.iter().enumerate() {
+            chess_move.apply(board).unwrap();
+            board.toggle_turn();
+            let score = match DEPTH {
+                1 => {
+                    nodes += 1;
+                    evaluate::score_in_window(
+                        board,
+                        move_generator,
+                        board.turn(),
+                        ply + 1,
+                        &context.evaluation_terms,
+                        alpha,
+                        beta,
+                        Some(&context.eval_cache),
+                    )
+                }
+                _ => leaf_search::<1>(
+                    context,
+                    board,
+                    move_generator,
+                    alpha,
+                    beta,
+                    !maximizing_player,
+                )?,
+            };
+            chess_move.undo(board).unwrap();
+            board.toggle_turn();
+
+            if maximizing_player {
+                value = max(value, score);
+                alpha = max(alpha, value);
+            } else {
+                value = min(value, score);
+                beta = min(beta, value);
+            }
+            if beta <= alpha {
+                record_cutoff(context, board, ply as usize, DEPTH, move_index, chess_move);
+                cutoff = true;
+                break;
+            }
+        }
+        record_node_type(
+            context,
+            NodeType::classify(value, original_alpha, original_beta, cutoff),
+        );
+        value
+    };
+
+    if candidates.is_empty() {
+        record_selective_depth(context, ply);
+    } else if DEPTH == 1 {
+        // The leaves are scored in place, rather than searched.
+        record_selective_depth(context, ply + 1);
+    }
+    *context.searched_position_count.write().unwrap() += nodes;
+    context.root_move_nodes += nodes;
+    set_cache(context, search_node, value);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::castle_rights_bitmask::ALL_CASTLE_RIGHTS;
+    use crate::board::color::Color;
+    use crate::board::piece::Piece;
+    use crate::chess_position;
+    use common::bitboard::bitboard::Bitboard;
+
+    /// A plain minimax, with no pruning, caching or move ordering.
+    fn minimax(
+        board: &mut Board,
+        move_generator: &mut MoveGenerator,
+        depth: u8,
+        ply: u8,
+        maximizing_player: bool,
+    ) -> i16 {
+        let current_turn = board.turn();
+        let moves = move_generator.generate_moves(board, current_turn);
+        if depth == 0 || moves.is_empty() {
+            return evaluate::score(board, move_generator, current_turn, ply);
+        }
+        let scores = moves.iter().map(|chess_move| {
+            chess_move.apply(board).unwrap();
+            board.toggle_turn();
+            let score = minimax(
+                board,
+                move_generator,
+                depth - 1,
+                ply + 1,
+                !maximizing_player,
+            );
+            chess_move.undo(board).unwrap();
+            board.toggle_turn();
+            score
+        });
+        let scores: Vec<i16> = scores.collect();
+        if maximizing_player {
+            *scores.iter().max().unwrap()
+        } else {
+            *scores.iter().min().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_leaf_search_matches_minimax() {
+        let mut board = chess_position! {
+            ...rk...
+            ppp..ppp
+            ..n.....
+            ....p...
+            ..B.P.q.
+            .....N..
+            PPP..PPP
+            R..QK..R
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        let mut move_generator = MoveGenerator::new();
+
+        for depth in 1..=LEAF_SEARCH_DEPTH {
+            let mut context = SearchContext::new(depth);
+            let score = match depth {
+                1 => leaf_search::<1>(
+                    &mut context,
+                    &mut board,
+                    &mut move_generator,
+                    i16::MIN,
+                    i16::MAX,
+                    true,
+                ),
+                _ => leaf_search::<2>(
+                    &mut context,
+                    &mut board,
+                    &mut move_generator,
+                    i16::MIN,
+                    i16::MAX,
+                    true,
+                ),
+            };
+            assert_eq!(
+                score.unwrap(),
+                minimax(&mut board, &mut move_generator, depth, 0, true),
+                "depth {}",
+                depth
+            );
+            assert!(context.searched_position_count() > 1);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::{self, checkmate_distance, GameEnding};
+use crate::move_generator::MoveGenerator;
+
+use super::{alpha_beta_search, SearchContext};
+
+/// Confirms that `first_move` forces the checkmate its `score` claims, by playing
+/// the line out: each reply is found by a fresh search, with a transposition
+/// table of its own, that looks only as far ahead as the rest of the mate. That
+/// is far cheaper than the search that found the mate, and a result cached
+/// under the wrong position can't carry over into it. The claim stands if every
+/// search still sees the mate, no further away, and the line ends in checkmate.
+pub fn verify_mate(
+    board: &Board,
+    first_move: &ChessMove,
+    score: i16,
+    move_generator: &mut MoveGenerator,
+) -> bool {
+    let mut remaining = match checkmate_distance(score) {
+        Some(distance) => distance,
+        None => return false,
+    };
+    let white_wins = score > 0;
+    let mut board = board.clone();
+    let mut next_move = first_move.clone();
+    loop {
+        if next_move.apply(&mut board).is_err() {
+            return false;
+        }
+        board.toggle_turn();
+        remaining = remaining.saturating_sub(1);
+
+        let turn = board.turn();
+        if let Some(ending) = evaluate::game_ending(&mut board, move_generator, turn) {
+            // The side to move is the one checkmated.
+            return matches!(ending, GameEnding::Checkmate) && (turn == Color::Black) == white_wins;
+        }
+        if remaining == 0 {
+            return false;
+        }
+
+        let mut context = SearchContext::new(remaining);
+        next_move = match alpha_beta_search(&mut context, &mut board, move_generator) {
+            Ok(chess_move) => chess_move,
+            Err(_) => return false,
+        };
+        let still_mates = context.last_score().is_some_and(|score| {
+            (score > 0) == white_wins
+                && checkmate_distance(score).is_some_and(|distance| distance <= remaining)
+        });
+        if !still_mates {
+            return false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate::MATE;
+    use crate::move_generator::MoveGenerator;
+
+    #[test]
+    fn test_verify_mate() {
+        // Back rank mate: Rd8#.
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1").unwrap();
+        let mut move_generator = MoveGenerator::new();
+        let mut context = SearchContext::new(2);
+        let best_move =
+            alpha_beta_search(&mut context, &mut board.clone(), &mut move_generator).unwrap();
+        let score = context.last_score().unwrap();
+        assert_eq!(evaluate::mate_in(score), Some(1));
+        assert!(verify_mate(&board, &best_move, score, &mut move_generator));
+
+        // Claiming mate for the wrong side, or mate with a quiet move, fails.
+        assert!(!verify_mate(
+            &board,
+            &best_move,
+            -(MATE - 1),
+            &mut move_generator
+        ));
+        let quiet_move = move_generator
+            .generate_moves(&mut board.clone(), Color::White)
+            .into_iter()
+            .find(|chess_move| chess_move.to_uci() == "g1f1")
+            .unwrap();
+        assert!(!verify_mate(
+            &board,
+            &quiet_move,
+            MATE - 1,
+            &mut move_generator
+        ));
+    }
+}
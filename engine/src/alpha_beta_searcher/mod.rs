@@ -0,0 +1,1354 @@
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::eval_cache::EvalCache;
+use crate::evaluate::{self, EvaluationTerms};
+use crate::move_generator::MoveGenerator;
+use log::{debug, error, warn};
+use thiserror::Error;
+use tracing::trace_span;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::cmp::{max, min};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use self::depth_boost::DepthBoost;
+use self::history::HistoryTable;
+use self::killer_moves::KillerMoves;
+use self::leaf_search::{leaf_search, LEAF_SEARCH_DEPTH};
+use self::mate_verification::verify_mate;
+use self::move_stability::MoveStability;
+use self::node_stats::{NodeStats, NodeType};
+use self::prioritize_chess_moves::{is_quiet, sort_by_previous_scores, sort_chess_moves};
+use self::root_jitter::RootJitter;
+use self::search_control::SearchControl;
+use self::time_manager::TimeManager;
+use self::transposition_table::TranspositionTable;
+use self::watchdog::Watchdog;
+
+type SearchNode = (u64, i16, i16, u8); // position_hash, alpha, beta, remaining depth
+
+/// Each iteration of iterative deepening usually takes several times as long as
+/// the one before it, so an iteration isn't started unless at least this many
+/// times the previous iteration's duration remains in the budget.
+const NEXT_ITERATION_TIME_FACTOR: u32 = 3;
+/// Reading the clock at every node would slow the search down, so each node
+/// only reads it once this many more nodes have been searched under its root
+/// move.
+const CLOCK_CHECK_INTERVAL: usize = 1024;
+
+pub mod contempt;
+pub mod depth_boost;
+mod history;
+mod killer_moves;
+mod leaf_search;
+pub mod mate_verification;
+mod move_stability;
+pub mod node_stats;
+mod prioritize_chess_moves;
+pub mod root_jitter;
+pub mod search_control;
+pub mod time_manager;
+pub mod transposition_table;
+pub mod watchdog;
+
+/// Represents the state and control of a search for the best move in a chess position.
+/// The search is implemented using alpha-beta minimax search, and uses `rayon`
+/// to parallelize the search across multiple threads. Access to the search context is thread-safe.
+#[derive(Clone)]
+pub struct SearchContext {
+    search_depth: u8,
+    /// The depth of the iteration in progress, from which each node's ply is derived.
+    iteration_depth: u8,
+    transposition_table: Arc<RwLock<TranspositionTable>>,
+    searched_position_count: Arc<RwLock<usize>>,
+    cache_hit_count: Arc<RwLock<usize>>,
+    termination_count: Arc<RwLock<usize>>,
+    node_stats: Arc<RwLock<NodeStats>>,
+    /// The deepest ply the iteration in progress has reached.
+    selective_depth: Arc<RwLock<u8>>,
+    last_score: Option<i16>,
+    root_scores: Vec<(i16, ChessMove)>,
+    time_manager: TimeManager,
+    root_jitter: Option<RootJitter>,
+    depth_boost: Option<DepthBoost>,
+    node_limit: Option<u64>,
+    watchdog: Option<Watchdog>,
+    /// Whether the watchdog has aborted the search in progress.
+    watchdog_tripped: Arc<RwLock<bool>>,
+    /// The nodes searched under the root move that this copy of the context is
+    /// searching, for the watchdog's diagnostics.
+    root_move_nodes: usize,
+    /// `root_move_nodes` when this copy of the context last read the clock.
+    clock_checked_at: usize,
+    verify_mates: bool,
+    /// Whether the last search's mate, if it found one, held up when verified.
+    mate_verified: Option<bool>,
+    depth_stats: Vec<DepthStats>,
+    evaluation_terms: EvaluationTerms,
+    /// Static evaluations, scored with `evaluation_terms`.
+    eval_cache: Arc<EvalCache>,
+    /// How many centipawns below even the engine values a draw (see
+    /// `contempt_for_ratings`).
+    contempt: i16,
+    killer_moves: Arc<RwLock<KillerMoves>>,
+    history: Arc<RwLock<HistoryTable>>,
+    control: SearchControl,
+}
+
+/// Statistics for one completed search depth.
+#[derive(Clone, Copy, Debug)]
+pub struct DepthStats {
+    pub depth: u8,
+    pub nodes: usize,
+    pub duration: Duration,
+    pub score: i16,
+    pub node_types: NodeStats,
+    /// The deepest ply the iteration reached. Lines that end in checkmate,
+    /// stalemate, or a cached result stop short of the depth, and nothing
+    /// searches past it.
+    pub selective_depth: u8,
+    /// How many times as many nodes the iteration searched as the one before
+    /// it, or `None` for the first iteration.
+    pub branching_factor: Option<f64>,
+    /// How many centipawns the best move scored ahead of the second best, or
+    /// `None` when there's only one legal move.
+    pub margin: Option<i16>,
+}
+
+#[derive(Error, Debug)]
+pub enum SearchError {
+    #[error("no available moves")]
+    NoAvailableMoves,
+    #[error("depth must be at least 1")]
+    DepthTooLow,
+    #[error("the search was stopped before it completed an iteration")]
+    Stopped,
+}
+
+impl SearchContext {
+    /// A search that deepens one ply at a time, up to `depth`, for as long as
+    /// its time budget allows.
+    pub fn with_time_limit(depth: u8, time_limit: Duration) -> Self {
+        let mut context = Self::new(depth);
+        context.time_manager.set_budget(Some(time_limit));
+        context
+    }
+
+    /// Stops the search once it has visited `node_limit` nodes, keeping the best
+    /// move from its last completed iteration, as a stopped search does. The
+    /// search deepens one ply at a time, as it does under a time budget. With the
+    /// `parallel` feature, threads share the count, so exactly where the search
+    /// stops can vary from run to run.
+    pub fn with_node_limit(mut self, node_limit: u64) -> Self {
+        self.node_limit = Some(node_limit);
+        self
+    }
+
+    /// Caps the transposition table at `megabytes` of memory (see
+    /// `TranspositionTable::new`).
+    pub fn with_hash_size(mut self, megabytes: usize) -> Self {
+        self.set_hash_size(megabytes);
+        self
+    }
+
+    pub fn new(depth: u8) -> Self {
+        Self {
+            search_depth: depth,
+            iteration_depth: depth,
+            transposition_table: Arc::new(RwLock::new(TranspositionTable::default())),
+            searched_position_count: Arc::new(RwLock::new(0)),
+            cache_hit_count: Arc::new(RwLock::new(0)),
+            termination_count: Arc::new(RwLock::new(0)),
+            node_stats: Arc::new(RwLock::new(NodeStats::default())),
+            selective_depth: Arc::new(RwLock::new(0)),
+            last_score: None,
+            root_scores: Vec::new(),
+            time_manager: TimeManager::new(),
+            root_jitter: None,
+            depth_boost: None,
+            node_limit: None,
+            watchdog: None,
+            watchdog_tripped: Arc::new(RwLock::new(false)),
+            root_move_nodes: 0,
+            clock_checked_at: 0,
+            verify_mates: false,
+            mate_verified: None,
+            depth_stats: Vec::new(),
+            evaluation_terms: EvaluationTerms::default(),
+            eval_cache: Arc::new(EvalCache::default()),
+            contempt: 0,
+            killer_moves: Arc::new(RwLock::new(KillerMoves::default())),
+            history: Arc::new(RwLock::new(HistoryTable::default())),
+            control: SearchControl::new(),
+        }
+    }
+
+    pub fn reset_stats(&mut self) {
+        *self.searched_position_count.write().unwrap() = 0;
+        *self.cache_hit_count.write().unwrap() = 0;
+        *self.termination_count.write().unwrap() = 0;
+        *self.node_stats.write().unwrap() = NodeStats::default();
+        self.depth_stats.clear();
+        self.mate_verified = None;
+        *self.watchdog_tripped.write().unwrap() = false;
+    }
+
+    /// Forgets everything learned from earlier searches: the cached results
+    /// and evaluations, killer moves, and history, along with the stats and scores of the last
+    /// search. The tables are cleared rather than reallocated, so this is
+    /// cheap to do between games.
+    pub fn new_game(&mut self) {
+        self.transposition_table.write().unwrap().reset();
+        self.eval_cache.clear();
+        self.killer_moves.write().unwrap().clear();
+        self.history.write().unwrap().clear();
+        self.reset_stats();
+        self.last_score = None;
+        self.root_scores.clear();
+    }
+
+    pub fn searched_position_count(&self) -> usize {
+        *self.searched_position_count.read().unwrap()
+    }
+
+    pub fn cache_hit_count(&self) -> usize {
+        *self.cache_hit_count.read().unwrap()
+    }
+
+    pub fn termination_count(&self) -> usize {
+        *self.termination_count.read().unwrap()
+    }
+
+    /// How many PV, cut, and all nodes the most recent search visited.
+    pub fn node_stats(&self) -> NodeStats {
+        *self.node_stats.read().unwrap()
+    }
+
+    /// How full the transposition table is, as a percentage.
+    pub fn hash_fill_percentage(&self) -> f64 {
+        self.transposition_table.read().unwrap().fill_percentage()
+    }
+
+    pub fn search_depth(&self) -> u8 {
+        self.search_depth
+    }
+
+    pub fn set_search_depth(&mut self, search_depth: u8) {
+        self.search_depth = search_depth;
+    }
+
+    pub fn last_score(&self) -> Option<i16> {
+        self.last_score
+    }
+
+    /// The score of every root move in the last completed iteration, from
+    /// white's perspective, best move last.
+    pub fn root_scores(&self) -> &[(i16, ChessMove)] {
+        &self.root_scores
+    }
+
+    /// Nodes, time, and score for each depth completed by the most recent search.
+    pub fn depth_stats(&self) -> &[DepthStats] {
+        &self.depth_stats
+    }
+
+    pub fn time_manager(&self) -> &TimeManager {
+        &self.time_manager
+    }
+
+    pub fn time_manager_mut(&mut self) -> &mut TimeManager {
+        &mut self.time_manager
+    }
+
+    /// Enables (or disables, with `None`) random selection among near-best root
+    /// moves. Off by default, so that searches are deterministic.
+    pub fn set_root_jitter(&mut self, root_jitter: Option<RootJitter>) {
+        self.root_jitter = root_jitter;
+    }
+
+    /// Searches positions with few pieces left more deeply than the search
+    /// depth (or, with `None`, to exactly the search depth). Off by default.
+    pub fn set_depth_boost(&mut self, depth_boost: Option<DepthBoost>) {
+        self.depth_boost = depth_boost;
+    }
+
+    /// Enables (or disables) playing out each checkmate the search claims, with
+    /// `verify_mate`, before it's reported. Off by default, since it's a check
+    /// on the search itself.
+    pub fn set_verify_mates(&mut self, verify_mates: bool) {
+        self.verify_mates = verify_mates;
+    }
+
+    /// `Some(false)` when the last search claimed a checkmate that didn't hold
+    /// up, and `None` when it claimed none or mates aren't being verified.
+    pub fn mate_verified(&self) -> Option<bool> {
+        self.mate_verified
+    }
+
+    /// The control for stopping this context's searches, or ending their ponder,
+    /// from another thread.
+    pub fn control(&self) -> &SearchControl {
+        &self.control
+    }
+
+    /// Aborts searches that run far past their budget (or, with `None`, lets
+    /// them run), rather than waiting on them indefinitely.
+    pub fn set_watchdog(&mut self, watchdog: Option<Watchdog>) {
+        self.watchdog = watchdog;
+    }
+
+    /// Whether the search has been stopped through its control, has used up
+    /// its node limit or its time budget, or has been aborted by its watchdog.
+    fn should_stop(&self) -> bool {
+        self.is_stopped_or_over_node_limit() || self.is_out_of_time() || self.check_watchdog()
+    }
+
+    /// `should_stop`, for a node inside the search, which only reads the clock
+    /// every `CLOCK_CHECK_INTERVAL` nodes.
+    fn should_stop_at_node(&mut self) -> bool {
+        if self.is_stopped_or_over_node_limit() {
+            return true;
+        }
+        if self.root_move_nodes < self.clock_checked_at + CLOCK_CHECK_INTERVAL {
+            return false;
+        }
+        self.clock_checked_at = self.root_move_nodes;
+        self.is_out_of_time() || self.check_watchdog()
+    }
+
+    fn is_stopped_or_over_node_limit(&self) -> bool {
+        self.control.is_stopped()
+            || self
+                .node_limit
+                .is_some_and(|limit| self.searched_position_count() as u64 >= limit)
+    }
+
+    /// Whether the time budget is spent, counting from a ponderhit that
+    /// arrived during the current iteration, which `apply_ponderhit` only
+    /// records once the iteration is over. The first iteration always
+    /// completes, so that there's a move to fall back on.
+    fn is_out_of_time(&self) -> bool {
+        if self.depth_stats.is_empty() {
+            return false;
+        }
+        if !self.time_manager.is_pondering() {
+            return self.time_manager.is_out_of_time();
+        }
+        match self.control.ponderhit_at() {
+            Some(ponderhit_at) => {
+                let mut time_manager = self.time_manager;
+                time_manager.ponderhit_at(ponderhit_at);
+                time_manager.is_out_of_time()
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the watchdog has aborted the search, tripping it if the search
+    /// has now run too long.
+    fn check_watchdog(&self) -> bool {
+        let watchdog = match self.watchdog {
+            Some(watchdog) => watchdog,
+            None => return false,
+        };
+        if *self.watchdog_tripped.read().unwrap() {
+            return true;
+        }
+        let expired = watchdog
+            .time_limit(self.time_manager.allotted())
+            .is_some_and(|time_limit| self.time_manager.elapsed() > time_limit);
+        if expired {
+            *self.watchdog_tripped.write().unwrap() = true;
+        }
+        expired
+    }
+
+    /// Gives the context a control of its own, and returns it. Clones of a
+    /// context share its control, so a clone that searches separately, or a
+    /// context whose control was stopped, needs a new one before searching.
+    pub fn reset_control(&mut self) -> SearchControl {
+        self.control = SearchControl::new();
+        self.control.clone()
+    }
+
+    /// Replaces the transposition table with an empty one of at most
+    /// `megabytes`.
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        *self.transposition_table.write().unwrap() = TranspositionTable::new(megabytes);
+    }
+
+    /// How much memory the transposition table takes once allocated, in bytes.
+    pub fn hash_size_in_bytes(&self) -> usize {
+        self.transposition_table.read().unwrap().size_in_bytes()
+    }
+
+    pub fn evaluation_terms(&self) -> &EvaluationTerms {
+        &self.evaluation_terms
+    }
+
+    /// Has the engine value a draw `contempt` centipawns below even, for the
+    /// side to move when a search starts, which is the side it's playing. Zero
+    /// by default, so that draws are even.
+    pub fn set_contempt(&mut self, contempt: i16) {
+        self.contempt = contempt;
+    }
+
+    /// Changes which evaluation terms the search scores positions with. Cached
+    /// results and evaluations are cleared, since they were scored with the
+    /// previous terms.
+    pub fn set_evaluation_terms(&mut self, evaluation_terms: EvaluationTerms) {
+        self.evaluation_terms = evaluation_terms;
+        self.transposition_table.write().unwrap().clear();
+        self.eval_cache.clear();
+    }
+}
+
+/// Searches for the best move. With a time budget, the search deepens one ply at
+/// a time (iterative deepening) up to the context's search depth, and returns the
+/// best move from the last iteration that completed in time. The budget shrinks
+/// when the best move is stable and well ahead, and grows when it keeps changing
+/// (see `MoveStability`). Otherwise, it
+/// searches straight to the search depth. Stopping the search through its
+/// control, or reaching its node limit, also returns the best move from the
+/// last completed iteration.
+pub fn alpha_beta_search(
+    context: &mut SearchContext,
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+) -> Result<ChessMove, SearchError> {
+    context.reset_stats();
+    context.history.write().unwrap().age();
+    context.transposition_table.write().unwrap().new_search();
+    set_draw_score(context, board.turn());
+    context.time_manager.start();
+    debug!("alpha-beta search depth: {}", context.search_depth());
+
+    if context.search_depth() < 1 {
+        return Err(SearchError::DepthTooLow);
+    }
+
+    let extra_plies = context
+        .depth_boost
+        .map_or(0, |depth_boost| depth_boost.extra_plies(board));
+    if extra_plies > 0 {
+        debug!(
+            "searching {} plies deeper with few pieces left",
+            extra_plies
+        );
+    }
+    let max_depth = context.search_depth().saturating_add(extra_plies);
+    let first_depth = match (context.time_manager.budget(), context.node_limit) {
+        (None, None) => max_depth,
+        _ => 1,
+    };
+
+    let mut best_move = None;
+    let mut stability = MoveStability::default();
+    for depth in first_depth..=max_depth {
+        apply_ponderhit(context);
+        if context.should_stop() {
+            debug!("search stopped before depth {}", depth);
+            break;
+        }
+        if let (Some(remaining), Some(last_iteration)) = (
+            context.time_manager.remaining(),
+            context.depth_stats.last().map(|stats| stats.duration),
+        ) {
+            if remaining < last_iteration * NEXT_ITERATION_TIME_FACTOR {
+                debug!("stopping iterative deepening before depth {}", depth);
+                break;
+            }
+        }
+        match search_root(context, board, move_generator, depth) {
+            Ok(chess_move) => {
+                // Easy moves give back some of their budget, and hard moves
+                // borrow extra.
+                let margin = context.depth_stats.last().and_then(|stats| stats.margin);
+                stability.update(&chess_move, margin);
+                context
+                    .time_manager
+                    .set_budget_scale(stability.budget_scale());
+                best_move = Some(chess_move);
+            }
+            Err(SearchError::Stopped) => break,
+            Err(error) => return Err(error),
+        }
+    }
+    apply_ponderhit(context);
+
+    if let (true, Some(chess_move), Some(score)) =
+        (context.verify_mates, &best_move, context.last_score)
+    {
+        if evaluate::is_checkmate_score(score) {
+            let verified = verify_mate(board, chess_move, score, move_generator);
+            if !verified {
+                warn!(
+                    "the search's {} didn't hold up",
+                    evaluate::format_score(score)
+                );
+            }
+            context.mate_verified = Some(verified);
+        }
+    }
+
+    match best_move {
+        Some(chess_move) => Ok(chess_move),
+        None if context.should_stop() => Err(SearchError::Stopped),
+        None => Err(SearchError::NoAvailableMoves),
+    }
+}
+
+/// Scores draws with the context's contempt, against `engine`, the side the
+/// search is for.
+fn set_draw_score(context: &mut SearchContext, engine: Color) {
+    let draw_score = match engine {
+        Color::White => -context.contempt,
+        Color::Black => context.contempt,
+    };
+    if context.evaluation_terms.draw_score != draw_score {
+        context.evaluation_terms.draw_score = draw_score;
+        // Cached results were scored with the old draw score.
+        context.transposition_table.write().unwrap().clear();
+    }
+}
+
+/// Logs what a search aborted by its watchdog was searching, so that the
+/// position can be reproduced: the FEN, the iteration's depth, and how many
+/// nodes each root move had taken, most first.
+fn log_watchdog_diagnostics(
+    context: &SearchContext,
+    board: &Board,
+    depth: u8,
+    root_move_nodes: &[(&ChessMove, usize)],
+) {
+    let mut root_move_nodes = root_move_nodes.to_vec();
+    root_move_nodes.sort_by(|(_, a), (_, b)| b.cmp(a));
+    let root_move_nodes: Vec<String> = root_move_nodes
+        .iter()
+        .map(|(chess_move, nodes)| format!("{} {}", chess_move.to_uci(), nodes))
+        .collect();
+    error!(
+        "watchdog aborted the search after {:?}, during depth {}: fen {}, {} nodes, by root move: {}",
+        context.time_manager.elapsed(),
+        depth,
+        board.to_fen(),
+        context.searched_position_count(),
+        root_move_nodes.join(", ")
+    );
+}
+
+/// Once the predicted move has been played, the time since then is charged
+/// against the budget.
+fn apply_ponderhit(context: &mut SearchContext) {
+    if !context.time_manager.is_pondering() {
+        return;
+    }
+    if let Some(ponderhit_at) = context.control.ponderhit_at() {
+        context.time_manager.ponderhit_at(ponderhit_at);
+    }
+}
+
+/// Searches every root move to the given depth, and returns the best one. If the
+/// search is stopped before any iteration has completed, the best of the root
+/// moves scored so far is returned, since it beats having no move at all.
+fn search_root(
+    context: &mut SearchContext,
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    depth: u8,
+) -> Result<ChessMove, SearchError> {
+    let _span = trace_span!("search").entered();
+    let iteration_started_at = context.time_manager.elapsed();
+    let nodes_before = context.searched_position_count();
+    let node_stats_before = context.node_stats();
+    context.iteration_depth = depth;
+    *context.selective_depth.write().unwrap() = 0;
+
+    let current_player = board.turn();
+    let current_player_is_maximizing = current_player.maximize_score();
+    let mut candidates =
+        move_generator.generate_moves_and_lazily_update_chess_move_effects(board, current_player);
+    sort_chess_moves(&mut candidates, board);
+    // After the first iteration, the moves that scored best in the last one are
+    // the likeliest to be best in this one too, so they're searched first.
+    if !context.depth_stats.is_empty() {
+        sort_by_previous_scores(&mut candidates, &context.root_scores);
+    }
+
+    // First, score each of the candidates. Note: `par_iter` is a rayon
+    // primitive that allows for parallel iteration over a collection. Without
+    // the `parallel` feature, the candidates are scored one at a time.
+    #[cfg(feature = "parallel")]
+    let candidate_iter = candidates.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let candidate_iter = candidates.iter();
+    let scored_moves = candidate_iter.map(|chess_move| {
+        let _span = trace_span!("root_move").entered();
+        // Skip setting up the search of the remaining moves once stopped.
+        if context.should_stop() {
+            return (chess_move, 0, Err(SearchError::Stopped));
+        }
+        let mut local_board = board.clone();
+        let mut local_move_generator = MoveGenerator::new();
+        let mut local_context = context.clone();
+        local_context.root_move_nodes = 0;
+        local_context.clock_checked_at = 0;
+
+        chess_move.apply(&mut local_board).unwrap();
+        local_board.toggle_turn();
+
+        let result = alpha_beta_minimax(
+            &mut local_context,
+            &mut local_board,
+            &mut local_move_generator,
+            depth - 1,
+            i16::MIN,
+            i16::MAX,
+            // The current iteration is for `current_player_is_maximizing == true`,
+            // so the next layer of alpha-beta should do the opposite.
+            !current_player_is_maximizing,
+        );
+        let score = match result {
+            Ok(score) => score,
+            Err(error) => return (chess_move, local_context.root_move_nodes, Err(error)),
+        };
+
+        chess_move.undo(&mut local_board).unwrap();
+        local_board.toggle_turn();
+
+        (
+            chess_move,
+            local_context.root_move_nodes,
+            Ok((score, chess_move.clone())),
+        )
+    });
+
+    let mut stopped = false;
+    let mut completed_moves = Vec::new();
+    let mut root_move_nodes = Vec::new();
+    for (chess_move, nodes, result) in scored_moves.collect::<Vec<_>>() {
+        root_move_nodes.push((chess_move, nodes));
+        match result {
+            Ok(scored_move) => completed_moves.push(scored_move),
+            Err(SearchError::Stopped) => stopped = true,
+            Err(error) => return Err(error),
+        }
+    }
+    if *context.watchdog_tripped.read().unwrap() {
+        log_watchdog_diagnostics(context, board, depth, &root_move_nodes);
+    }
+    let completed_iterations = context.depth_stats.len();
+    if stopped && (completed_iterations > 0 || completed_moves.is_empty()) {
+        return Err(SearchError::Stopped);
+    }
+
+    // Sort the best move to the end so we can pop it off.
+    let mut scored_moves = completed_moves;
+    scored_moves.sort_by(|(a, _), (b, _)| b.cmp(a));
+    debug!(
+        "Alpha-beta search results before sorting: {:?}",
+        scored_moves
+    );
+    if current_player_is_maximizing {
+        scored_moves.reverse();
+    }
+    debug!(
+        "Alpha-beta search results after sorting: {:?}",
+        scored_moves
+    );
+
+    if scored_moves.is_empty() {
+        return Err(SearchError::NoAvailableMoves);
+    }
+
+    let best_index = match context.root_jitter.as_mut() {
+        Some(root_jitter) => root_jitter.choose(&scored_moves),
+        None => scored_moves.len() - 1,
+    };
+    let margin = match scored_moves.len() {
+        0 | 1 => None,
+        len => Some((scored_moves[len - 1].0 - scored_moves[len - 2].0).abs()),
+    };
+    if !stopped {
+        context.root_scores = scored_moves.clone();
+    }
+    let (score, result) = scored_moves.swap_remove(best_index);
+    context.last_score = Some(score);
+    if stopped {
+        debug!(
+            "search stopped during depth {}; using a partial result",
+            depth
+        );
+        return Ok(result);
+    }
+    let nodes = context.searched_position_count() - nodes_before;
+    let branching_factor = context
+        .depth_stats
+        .last()
+        .filter(|previous| previous.nodes > 0)
+        .map(|previous| nodes as f64 / previous.nodes as f64);
+    context.depth_stats.push(DepthStats {
+        depth,
+        nodes,
+        duration: context.time_manager.elapsed() - iteration_started_at,
+        score,
+        node_types: context.node_stats() - node_stats_before,
+        selective_depth: *context.selective_depth.read().unwrap(),
+        branching_factor,
+        margin,
+    });
+    debug!(
+        "Alpha-beta search returning best move: {:?} (score: {})",
+        result, score
+    );
+    Ok(result)
+}
+
+fn alpha_beta_minimax(
+    context: &mut SearchContext,
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    depth: u8,
+    alpha: i16,
+    beta: i16,
+    maximizing_player: bool,
+) -> Result<i16, SearchError> {
+    // A stopped search unwinds without caching anything, since none of the
+    // scores on the way back up are complete. The boards it leaves behind are
+    // the per-thread copies made in `search_root`, so they're discarded anyway.
+    if context.should_stop_at_node() {
+        return Err(SearchError::Stopped);
+    }
+    match depth {
+        1 => {
+            return leaf_search::<1>(
+                context,
+                board,
+                move_generator,
+                alpha,
+                beta,
+                maximizing_player,
+            )
+        }
+        LEAF_SEARCH_DEPTH => {
+            return leaf_search::<LEAF_SEARCH_DEPTH>(
+                context,
+                board,
+                move_generator,
+                alpha,
+                beta,
+                maximizing_player,
+            )
+        }
+        _ => (),
+    }
+
+    let search_node = (board.current_position_hash(), alpha, beta, depth);
+    if let Some(score) = check_cache(context, search_node) {
+        return Ok(score);
+    }
+
+    {
+        let mut count = context.searched_position_count.write().unwrap();
+        *count += 1;
+    }
+    context.root_move_nodes += 1;
+
+    let current_turn = board.turn();
+    let ply = context.iteration_depth - depth;
+    if depth == 0 {
+        record_selective_depth(context, ply);
+        let score = evaluate::score_in_window(
+            board,
+            move_generator,
+            current_turn,
+            ply,
+            &context.evaluation_terms,
+            alpha,
+            beta,
+            Some(&context.eval_cache),
+        );
+        set_cache(context, search_node, score);
+        return Ok(score);
+    }
+
+    let mut candidates =
+        move_generator.generate_moves_and_lazily_update_chess_move_effects(board, current_turn);
+    sort_chess_moves(&mut candidates, board);
+    context
+        .history
+        .read()
+        .unwrap()
+        .sort_quiet_moves(&mut candidates, board);
+    context
+        .killer_moves
+        .read()
+        .unwrap()
+        .prioritize(ply as usize, &mut candidates);
+
+    if candidates.is_empty() {
+        record_selective_depth(context, ply);
+        let score = evaluate::score_in_window(
+            board,
+            move_generator,
+            current_turn,
+            ply,
+            &context.evaluation_terms,
+            alpha,
+            beta,
+            Some(&context.eval_cache),
+        );
+        set_cache(context, search_node, score);
+        return Ok(score);
+    }
+
+    let (original_alpha, original_beta) = (alpha, beta);
+    let mut cutoff = false;
+    let value = if maximizing_player {
+        let mut value = i16::MIN;
+        let mut alpha = alpha;
+        for (move_index, chess_move) in candidates.iter().enumerate() {
+            chess_move.apply(board).unwrap();
+            board.toggle_turn();
+            value = max(
+                value,
+                alpha_beta_minimax(
+                    context,
+                    board,
+                    move_generator,
+                    depth - 1,
+                    alpha,
+                    beta,
+                    false,
+                )?,
+            );
+            chess_move.undo(board).unwrap();
+            board.toggle_turn();
+
+            alpha = max(alpha, value);
+            if beta <= alpha {
+                record_cutoff(context, board, ply as usize, depth, move_index, chess_move);
+                cutoff = true;
+                break;
+            }
+        }
+        value
+    } else {
+        let mut value = i16::MAX;
+        let mut beta = beta;
+        for (move_index, chess_move) in candidates.iter().enumerate() {
+            chess_move.apply(board).unwrap();
+            board.toggle_turn();
+            value = min(
+                value,
+                alpha_beta_minimax(context, board, move_generator, depth - 1, alpha, beta, true)?,
+            );
+            chess_move.undo(board).unwrap();
+            board.toggle_turn();
+
+            beta = min(beta, value);
+            if beta <= alpha {
+                record_cutoff(context, board, ply as usize, depth, move_index, chess_move);
+                cutoff = true;
+                break;
+            }
+        }
+        value
+    };
+
+    record_node_type(
+        context,
+        NodeType::classify(value, original_alpha, original_beta, cutoff),
+    );
+    set_cache(context, search_node, value);
+    Ok(value)
+}
+
+fn record_node_type(context: &mut SearchContext, node_type: NodeType) {
+    context.node_stats.write().unwrap().record(node_type);
+}
+
+fn record_selective_depth(context: &mut SearchContext, ply: u8) {
+    let mut selective_depth = context.selective_depth.write().unwrap();
+    *selective_depth = max(*selective_depth, ply);
+}
+
+/// Counts a beta cutoff by the `move_index`th move searched, and remembers a
+/// quiet move that caused one, so that it's tried earlier in sibling and later
+/// positions.
+fn record_cutoff(
+    context: &mut SearchContext,
+    board: &Board,
+    ply: usize,
+    depth: u8,
+    move_index: usize,
+    chess_move: &ChessMove,
+) {
+    context
+        .node_stats
+        .write()
+        .unwrap()
+        .record_cutoff(move_index);
+    if !is_quiet(chess_move) {
+        return;
+    }
+    context
+        .killer_moves
+        .write()
+        .unwrap()
+        .record(ply, chess_move);
+    if let Some((piece, _)) = board.get(chess_move.from_square()) {
+        context
+            .history
+            .write()
+            .unwrap()
+            .record(piece, chess_move.to_square(), depth);
+    }
+}
+
+/// Checkmate scores count plies from the root, but the same position can be
+/// reached at a different ply, in a later iteration or a later search. So they're
+/// cached counting plies from the cached position instead.
+fn score_to_cache(score: i16, ply: u8) -> i16 {
+    if !evaluate::is_checkmate_score(score) {
+        score
+    } else if score > 0 {
+        score + ply as i16
+    } else {
+        score - ply as i16
+    }
+}
+
+fn score_from_cache(score: i16, ply: u8) -> i16 {
+    if !evaluate::is_checkmate_score(score) {
+        score
+    } else if score > 0 {
+        score - ply as i16
+    } else {
+        score + ply as i16
+    }
+}
+
+fn set_cache(context: &mut SearchContext, search_node: SearchNode, score: i16) {
+    let ply = context.iteration_depth - search_node.3;
+    let mut table = context.transposition_table.write().unwrap();
+    table.store(search_node, score_to_cache(score, ply));
+}
+
+fn check_cache(context: &mut SearchContext, search_node: SearchNode) -> Option<i16> {
+    let _span = trace_span!("tt_probe").entered();
+    let table = context.transposition_table.read().unwrap();
+    match table.probe(search_node) {
+        Some(prev_best_score) => {
+            let mut count = context.cache_hit_count.write().unwrap();
+            *count += 1;
+            let ply = context.iteration_depth - search_node.3;
+            Some(score_from_cache(prev_best_score, ply))
+        }
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::castle_rights_bitmask::ALL_CASTLE_RIGHTS;
+    use crate::board::color::Color;
+    use crate::board::piece::Piece;
+    use crate::chess_move::capture::Capture;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::{check_move, checkmate_move, chess_position, std_move};
+    use common::bitboard::bitboard::Bitboard;
+    use common::bitboard::square::*;
+
+    #[test]
+    fn test_find_mate_in_1_white() {
+        let mut search_context = SearchContext::new(4);
+        let mut move_generator = MoveGenerator::new();
+
+        let mut board = chess_position! {
+            .Q......
+            ........
+            ........
+            ........
+            ........
+            ........
+            k.K.....
+            ........
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        println!("Testing board:\n{}", board);
+
+        let chess_move =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        let valid_checkmates = [
+            checkmate_move!(std_move!(B8, B2)),
+            checkmate_move!(std_move!(B8, A8)),
+            checkmate_move!(std_move!(B8, A7)),
+        ];
+        assert!(
+            valid_checkmates.contains(&chess_move),
+            "{} does not lead to checkmate",
+            chess_move
+        );
+
+        let depth_stats = search_context.depth_stats();
+        assert_eq!(depth_stats.len(), 1);
+        assert_eq!(depth_stats[0].depth, 4);
+        assert_eq!(
+            depth_stats[0].nodes,
+            search_context.searched_position_count()
+        );
+        assert_eq!(Some(depth_stats[0].score), search_context.last_score());
+    }
+
+    #[test]
+    fn test_iterative_deepening() {
+        let mut board = chess_position! {
+            .Q......
+            ........
+            ........
+            ........
+            ........
+            ........
+            k.K.....
+            ........
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        let mut move_generator = MoveGenerator::new();
+
+        let mut search_context = SearchContext::with_time_limit(3, Duration::from_secs(600));
+        alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        let depths: Vec<u8> = search_context
+            .depth_stats()
+            .iter()
+            .map(|stats| stats.depth)
+            .collect();
+        assert_eq!(depths, vec![1, 2, 3]);
+        let total_nodes: usize = search_context
+            .depth_stats()
+            .iter()
+            .map(|stats| stats.nodes)
+            .sum();
+        assert_eq!(total_nodes, search_context.searched_position_count());
+        let node_types =
+            search_context
+                .depth_stats()
+                .iter()
+                .fold(NodeStats::default(), |total, stats| NodeStats {
+                    pv: total.pv + stats.node_types.pv,
+                    cut: total.cut + stats.node_types.cut,
+                    all: total.all + stats.node_types.all,
+                    first_move_cutoffs: total.first_move_cutoffs
+                        + stats.node_types.first_move_cutoffs,
+                });
+        assert_eq!(node_types, search_context.node_stats());
+        assert!(node_types.cut > 0 && node_types.pv > 0);
+        assert!(node_types.first_move_cutoffs <= node_types.cut);
+        for stats in search_context.depth_stats() {
+            assert!(stats.selective_depth <= stats.depth);
+        }
+        assert_eq!(search_context.depth_stats()[0].branching_factor, None);
+        assert!(search_context.depth_stats()[1].branching_factor.unwrap() > 0.0);
+        assert!(search_context.hash_fill_percentage() > 0.0);
+
+        // The first iteration always completes, even if the budget is already spent.
+        let mut search_context = SearchContext::with_time_limit(3, Duration::ZERO);
+        let chess_move =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        assert_eq!(search_context.depth_stats().len(), 1);
+        assert!(chess_move.to_uci().starts_with("b8"));
+    }
+
+    #[test]
+    fn test_find_mate_in_1_black() {
+        let mut search_context = SearchContext::new(4);
+        let mut move_generator = MoveGenerator::new();
+        let mut board = chess_position! {
+            .q......
+            ........
+            ........
+            ........
+            ........
+            ........
+            K.k.....
+            ........
+        };
+        board.set_turn(Color::Black);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+
+        println!("Testing board:\n{}", board);
+
+        let chess_move =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+
+        let valid_checkmates = [
+            checkmate_move!(std_move!(B8, B2)),
+            checkmate_move!(std_move!(B8, A8)),
+            checkmate_move!(std_move!(B8, A7)),
+        ];
+        assert!(
+            valid_checkmates.contains(&chess_move),
+            "{} does not lead to checkmate",
+            chess_move
+        );
+        assert_eq!(search_context.last_score(), Some(-evaluate::MATE + 1));
+    }
+
+    #[test]
+    fn test_find_back_rank_mate_in_2_white() {
+        let mut search_context = SearchContext::new(4);
+        let mut move_generator = MoveGenerator::new();
+
+        let mut board = chess_position! {
+            .k.....r
+            ppp.....
+            ........
+            ........
+            ........
+            ........
+            ...Q....
+            K..R....
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+
+        println!("Testing board:\n{}", board);
+
+        let expected_moves = [
+            check_move!(std_move!(D2, D8)),
+            std_move!(H8, D8, Capture(Piece::Queen)),
+            checkmate_move!(std_move!(D1, D8, Capture(Piece::Rook))),
+        ];
+        let mut expected_move_iter = expected_moves.iter();
+
+        let move1 =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        move1.apply(&mut board).unwrap();
+        board.toggle_turn();
+        assert_eq!(expected_move_iter.next().unwrap(), &move1);
+        // The mate is 3 plies away, and each search after it counts from its
+        // own root, even though the earlier searches' results are cached.
+        assert_eq!(search_context.last_score(), Some(evaluate::MATE - 3));
+        println!("Testing board:\n{}", board);
+
+        let move2 =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        move2.apply(&mut board).unwrap();
+        board.toggle_turn();
+        assert_eq!(expected_move_iter.next().unwrap(), &move2);
+        assert_eq!(search_context.last_score(), Some(evaluate::MATE - 2));
+        println!("Testing board:\n{}", board);
+
+        let move3 =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        move3.apply(&mut board).unwrap();
+        board.toggle_turn();
+        assert_eq!(expected_move_iter.next().unwrap(), &move3);
+        assert_eq!(search_context.last_score(), Some(evaluate::MATE - 1));
+        println!("Testing board:\n{}", board);
+    }
+
+    #[test]
+    fn test_find_back_rank_mate_in_2_black() {
+        let mut search_context = SearchContext::new(4);
+        let mut move_generator = MoveGenerator::new();
+
+        let mut board = chess_position! {
+            ....r..k
+            ....q...
+            ........
+            ........
+            ........
+            ........
+            .....PPP
+            R.....K.
+        };
+        board.set_turn(Color::Black);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+
+        println!("Testing board:\n{}", board);
+
+        let expected_moves = [
+            check_move!(std_move!(E7, E1)),
+            std_move!(A1, E1, Capture(Piece::Queen)),
+            checkmate_move!(std_move!(E8, E1, Capture(Piece::Rook))),
+        ];
+        let mut expected_move_iter = expected_moves.iter();
+
+        let move1 =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        move1.apply(&mut board).unwrap();
+        board.toggle_turn();
+        assert_eq!(
+            expected_move_iter.next().unwrap(),
+            &move1,
+            "failed to find first move of mate in 2"
+        );
+        println!("Testing board:\n{}", board);
+
+        let move2 =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        move2.apply(&mut board).unwrap();
+        board.toggle_turn();
+        assert_eq!(
+            expected_move_iter.next().unwrap(),
+            &move2,
+            "failed to find second move of mate in 2"
+        );
+        println!("Testing board:\n{}", board);
+
+        let move3 =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        move3.apply(&mut board).unwrap();
+        board.toggle_turn();
+        assert_eq!(
+            expected_move_iter.next().unwrap(),
+            &move3,
+            "failed to find third move of mate in 2"
+        );
+        println!("Testing board:\n{}", board);
+    }
+
+    #[test]
+    fn test_contempt_scores_draws() {
+        // Every move completes fifty moves without a capture or a pawn move,
+        // so every line is a draw.
+        let mut board = Board::from_fen("7k/8/8/8/8/8/8/K7 w - - 99 60").unwrap();
+        let mut move_generator = MoveGenerator::new();
+        let mut search_context = SearchContext::new(2);
+        search_context.set_contempt(20);
+
+        alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        assert_eq!(search_context.last_score(), Some(-20));
+
+        // The engine plays whichever side is to move.
+        let mut board = Board::from_fen("7k/8/8/8/8/8/8/K7 b - - 99 60").unwrap();
+        alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        assert_eq!(search_context.last_score(), Some(20));
+    }
+
+    #[test]
+    fn test_stopped_search() {
+        let mut move_generator = MoveGenerator::new();
+        let mut board = Board::starting_position();
+
+        let mut search_context = SearchContext::new(3);
+        search_context.reset_control().stop();
+        assert!(matches!(
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator),
+            Err(SearchError::Stopped)
+        ));
+
+        // A ponder search's clock starts once its move is played.
+        let mut search_context = SearchContext::with_time_limit(2, Duration::from_secs(60));
+        search_context.reset_control().ponderhit();
+        search_context.time_manager_mut().start_pondering();
+        assert!(alpha_beta_search(&mut search_context, &mut board, &mut move_generator).is_ok());
+        assert!(!search_context.time_manager().is_pondering());
+        assert_eq!(search_context.depth_stats().len(), 2);
+    }
+
+    #[test]
+    fn test_time_limit_stops_an_iteration() {
+        let mut board = Board::starting_position();
+        let mut move_generator = MoveGenerator::new();
+
+        // Depth 10 would take far longer than the budget, so the search has to
+        // be cut off partway through an iteration.
+        let mut search_context = SearchContext::with_time_limit(10, Duration::from_millis(50));
+        let started_at = std::time::Instant::now();
+        let result = alpha_beta_search(&mut search_context, &mut board, &mut move_generator);
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+        assert!(result.is_ok());
+        assert!((1..10).contains(&search_context.depth_stats().len()));
+
+        // A ponderhit that arrives mid-iteration starts the clock right away.
+        let control = search_context.reset_control();
+        search_context.time_manager_mut().start_pondering();
+        let ponderhit = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            control.ponderhit();
+        });
+        let started_at = std::time::Instant::now();
+        let result = alpha_beta_search(&mut search_context, &mut board, &mut move_generator);
+        ponderhit.join().unwrap();
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_depth_boost() {
+        let mut move_generator = MoveGenerator::new();
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+
+        let mut search_context = SearchContext::new(1);
+        search_context.set_depth_boost(Some(DepthBoost::default()));
+        alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        assert_eq!(search_context.depth_stats().last().unwrap().depth, 3);
+
+        // The opening has too many pieces to be boosted.
+        let mut board = Board::starting_position();
+        alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        assert_eq!(search_context.depth_stats().last().unwrap().depth, 1);
+    }
+
+    #[test]
+    fn test_node_limited_search() {
+        let mut board = chess_position! {
+            .Q......
+            ........
+            ........
+            ........
+            ........
+            ........
+            k.K.....
+            ........
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        let mut move_generator = MoveGenerator::new();
+
+        let mut search_context = SearchContext::new(8).with_node_limit(100);
+        let chess_move =
+            alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        assert!(chess_move.to_uci().starts_with("b8"));
+        assert!((1..8).contains(&search_context.depth_stats().len()));
+        assert!(search_context.searched_position_count() >= 100);
+    }
+
+    #[test]
+    fn test_watchdog_aborts_search() {
+        let mut board = Board::starting_position();
+        let mut move_generator = MoveGenerator::new();
+
+        // Searching the starting position to depth 10 would take far longer.
+        let mut search_context = SearchContext::new(10);
+        search_context.set_watchdog(Some(Watchdog {
+            multiple: 2,
+            default_budget: Some(Duration::from_millis(50)),
+        }));
+        let started_at = std::time::Instant::now();
+        let result = alpha_beta_search(&mut search_context, &mut board, &mut move_generator);
+        assert!(started_at.elapsed() < Duration::from_secs(30));
+        assert!(matches!(result, Ok(_) | Err(SearchError::Stopped)));
+        assert!(*search_context.watchdog_tripped.read().unwrap());
+        assert!(search_context.depth_stats().is_empty());
+
+        // The next search starts with the watchdog reset.
+        search_context.set_search_depth(1);
+        search_context.set_watchdog(Some(Watchdog {
+            default_budget: Some(Duration::from_secs(60)),
+            ..Default::default()
+        }));
+        alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+        assert!(!*search_context.watchdog_tripped.read().unwrap());
+        assert_eq!(search_context.depth_stats().len(), 1);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::chess_move::chess_move::ChessMove;
+
+/// An "easy move" has been the best move for at least this many iterations in a
+/// row...
+const EASY_MOVE_ITERATIONS: u8 = 3;
+/// ...and scores at least this many centipawns better than the second best move.
+const EASY_MOVE_MARGIN: i16 = 150;
+/// The share of the budget an easy move gets. Iterative deepening won't start an
+/// iteration it can't finish, so this usually ends the search right away.
+const EASY_MOVE_BUDGET_SCALE: f64 = 0.4;
+/// How much of the budget each recent change of the best move adds.
+const INSTABILITY_BUDGET_SCALE: f64 = 0.75;
+/// A hard move can take at most this many times its budget.
+const MAX_BUDGET_SCALE: f64 = 2.0;
+
+/// Tracks how the best move changes over the iterations of a search, to decide
+/// how much of its time budget the move deserves. When the best move stays the
+/// same and stays well ahead of the alternatives, deeper iterations are unlikely
+/// to change it, so the search can stop early. When the best move keeps
+/// changing, the search hasn't settled on it yet, so it's given extra time.
+#[derive(Clone, Debug, Default)]
+pub struct MoveStability {
+    best_move: Option<ChessMove>,
+    /// How many iterations in a row have found the same best move.
+    stable_iterations: u8,
+    /// Goes up by one each time the best move changes, and halves each
+    /// iteration, so that recent changes count the most.
+    instability: f64,
+    /// How far ahead of the second best move the best move scored in the last
+    /// iteration.
+    margin: Option<i16>,
+}
+
+impl MoveStability {
+    /// Records the best move of a completed iteration, and how far ahead of the
+    /// second best move it scored (`None` when it's the only legal move).
+    pub fn update(&mut self, best_move: &ChessMove, margin: Option<i16>) {
+        self.instability /= 2.0;
+        match &self.best_move {
+            Some(previous) if previous == best_move => {
+                self.stable_iterations = self.stable_iterations.saturating_add(1);
+            }
+            Some(_) => {
+                self.stable_iterations = 1;
+                self.instability += 1.0;
+            }
+            None => self.stable_iterations = 1,
+        }
+        self.best_move = Some(best_move.clone());
+        self.margin = margin;
+    }
+
+    pub fn is_easy_move(&self) -> bool {
+        match self.margin {
+            None => self.best_move.is_some(),
+            Some(margin) => {
+                self.stable_iterations >= EASY_MOVE_ITERATIONS && margin >= EASY_MOVE_MARGIN
+            }
+        }
+    }
+
+    /// The multiple of its time budget the move deserves.
+    pub fn budget_scale(&self) -> f64 {
+        if self.is_easy_move() {
+            return EASY_MOVE_BUDGET_SCALE;
+        }
+        (1.0 + self.instability * INSTABILITY_BUDGET_SCALE).min(MAX_BUDGET_SCALE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::std_move;
+    use common::bitboard::square::*;
+
+    #[test]
+    fn test_move_stability() {
+        let e4 = std_move!(E2, E4);
+        let d4 = std_move!(D2, D4);
+
+        let mut stability = MoveStability::default();
+        assert_eq!(stability.budget_scale(), 1.0);
+
+        // A move well ahead of the rest is easy once it has held for a few
+        // iterations.
+        stability.update(&e4, Some(200));
+        stability.update(&e4, Some(200));
+        assert!(!stability.is_easy_move());
+        stability.update(&e4, Some(200));
+        assert!(stability.is_easy_move());
+        assert_eq!(stability.budget_scale(), EASY_MOVE_BUDGET_SCALE);
+
+        // A close second keeps it from being easy.
+        stability.update(&e4, Some(20));
+        assert_eq!(stability.budget_scale(), 1.0);
+
+        // Each change of the best move extends the budget, up to the maximum.
+        stability.update(&d4, Some(20));
+        assert_eq!(stability.budget_scale(), 1.75);
+        stability.update(&e4, Some(20));
+        assert_eq!(stability.budget_scale(), MAX_BUDGET_SCALE);
+
+        // Once the best move settles, the extra time wears off.
+        for _ in 0..8 {
+            stability.update(&e4, Some(20));
+        }
+        assert!(stability.budget_scale() < 1.01);
+
+        // The only legal move is always easy.
+        let mut stability = MoveStability::default();
+        stability.update(&e4, None);
+        assert!(stability.is_easy_move());
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::ops::Sub;
+
+/// How a searched node's score relates to the window it was searched with.
+/// Only nodes whose moves were searched are classified; leaves and cache hits
+/// aren't.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeType {
+    /// The score fell inside the window, so it is exact. These nodes make up
+    /// the principal variation, and every move had to be searched.
+    Pv,
+    /// A move was good enough to cut the search off (a beta cutoff).
+    Cut,
+    /// No move improved on the window (a fail low), so every move had to be
+    /// searched only to learn that the position is worse than an alternative.
+    All,
+}
+
+impl NodeType {
+    /// Classifies a node from its score, the window it was searched with, and
+    /// whether the search of its moves was cut off.
+    pub fn classify(score: i16, alpha: i16, beta: i16, cutoff: bool) -> Self {
+        if cutoff {
+            NodeType::Cut
+        } else if alpha < score && score < beta {
+            NodeType::Pv
+        } else {
+            NodeType::All
+        }
+    }
+}
+
+/// Counts of each type of node. With good move ordering, nearly all nodes are
+/// cut nodes, and most of those cut off on their first move.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NodeStats {
+    pub pv: usize,
+    pub cut: usize,
+    pub all: usize,
+    /// How many of the cut nodes cut off on the first move searched.
+    pub first_move_cutoffs: usize,
+}
+
+impl NodeStats {
+    pub fn record(&mut self, node_type: NodeType) {
+        match node_type {
+            NodeType::Pv => self.pv += 1,
+            NodeType::Cut => self.cut += 1,
+            NodeType::All => self.all += 1,
+        }
+    }
+
+    /// Records which of a cut node's moves, counting from 0, cut it off.
+    pub fn record_cutoff(&mut self, move_index: usize) {
+        if move_index == 0 {
+            self.first_move_cutoffs += 1;
+        }
+    }
+
+    /// The percentage of cut nodes that cut off on their first move, which
+    /// measures how well the moves are ordered.
+    pub fn first_move_cutoff_percentage(&self) -> f64 {
+        self.first_move_cutoffs as f64 * 100.0 / self.cut.max(1) as f64
+    }
+
+    pub fn total(&self) -> usize {
+        self.pv + self.cut + self.all
+    }
+
+    /// The percentage of classified nodes of each type, as (pv, cut, all).
+    pub fn percentages(&self) -> (f64, f64, f64) {
+        let total = self.total().max(1) as f64;
+        let percentage = |count: usize| count as f64 * 100.0 / total;
+        (
+            percentage(self.pv),
+            percentage(self.cut),
+            percentage(self.all),
+        )
+    }
+}
+
+impl Sub for NodeStats {
+    type Output = NodeStats;
+
+    fn sub(self, other: NodeStats) -> NodeStats {
+        NodeStats {
+            pv: self.pv - other.pv,
+            cut: self.cut - other.cut,
+            all: self.all - other.all,
+            first_move_cutoffs: self.first_move_cutoffs - other.first_move_cutoffs,
+        }
+    }
+}
+
+impl fmt::Display for NodeStats {
+    /// e.g. "2/71/27" for 2% PV, 71% cut, and 27% all nodes.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (pv, cut, all) = self.percentages();
+        write!(f, "{:.0}/{:.0}/{:.0}", pv, cut, all)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_stats() {
+        assert_eq!(NodeType::classify(10, 0, 20, false), NodeType::Pv);
+        assert_eq!(NodeType::classify(20, 0, 20, true), NodeType::Cut);
+        assert_eq!(NodeType::classify(0, 0, 20, false), NodeType::All);
+        // A minimizing node fails low by not lowering beta.
+        assert_eq!(NodeType::classify(25, 0, 20, false), NodeType::All);
+
+        let mut stats = NodeStats::default();
+        let before = stats;
+        stats.record(NodeType::Pv);
+        stats.record(NodeType::Cut);
+        stats.record_cutoff(0);
+        stats.record(NodeType::Cut);
+        stats.record_cutoff(3);
+        stats.record(NodeType::All);
+        assert_eq!(stats.total(), 4);
+        assert_eq!(stats.percentages(), (25.0, 50.0, 25.0));
+        assert_eq!(stats.to_string(), "25/50/25");
+        assert_eq!(stats.first_move_cutoff_percentage(), 50.0);
+        assert_eq!(
+            stats - before,
+            NodeStats {
+                pv: 1,
+                cut: 2,
+                all: 1,
+                first_move_cutoffs: 1
+            }
+        );
+        assert_eq!(NodeStats::default().to_string(), "0/0/0");
+    }
+}
@@ -1,13 +1,16 @@
 use crate::board::piece::Piece;
 use crate::board::Board;
+use crate::chess_move::capture::Capture;
 use crate::chess_move::chess_move::ChessMove;
 use crate::chess_move::chess_move_effect::ChessMoveEffect;
+use crate::evaluate::material_value;
 use crate::move_generator::ChessMoveList;
 
 /// Sorts a list of chess moves according to the following priority (from first to last):
 /// 1. Checkmates
 /// 2. Checks
-/// 3. Captures
+/// 3. Captures, most valuable victim first, then least valuable attacker first
+///    (MVV-LVA), so that e.g. pawn takes queen is tried before queen takes pawn
 /// 4. Pawn promotions
 /// 5. Rook moves
 /// 6. Knight moves
@@ -30,7 +33,8 @@ fn compare_moves(a: &ChessMove, b: &ChessMove, board: &Board) -> std::cmp::Order
         _ => match (is_capture(a), is_capture(b)) {
             (true, false) => Ordering::Less,
             (false, true) => Ordering::Greater,
-            _ => compare_move_types(a, b, board),
+            (true, true) => compare_captures(a, b, board),
+            (false, false) => compare_move_types(a, b, board),
         },
     }
 }
@@ -47,6 +51,44 @@ fn is_capture(chess_move: &ChessMove) -> bool {
     chess_move.captures().is_some()
 }
 
+/// Orders captures by most valuable victim, then least valuable attacker.
+fn compare_captures(a: &ChessMove, b: &ChessMove, board: &Board) -> std::cmp::Ordering {
+    let victim_value = |chess_move: &ChessMove| match chess_move.captures() {
+        Some(Capture(piece)) => material_value(piece),
+        None => 0,
+    };
+    let attacker_value = |chess_move: &ChessMove| match get_piece_type(chess_move, board) {
+        Some(piece) => material_value(piece),
+        None => 0,
+    };
+
+    victim_value(b)
+        .cmp(&victim_value(a))
+        .then_with(|| attacker_value(a).cmp(&attacker_value(b)))
+}
+
+/// Whether a move neither captures, promotes, nor gives check. `sort_chess_moves`
+/// puts these last, and the search orders them further by what caused cutoffs.
+pub fn is_quiet(chess_move: &ChessMove) -> bool {
+    chess_move.captures().is_none()
+        && !matches!(chess_move, ChessMove::PawnPromotion(_))
+        && chess_move.effect() == ChessMoveEffect::None
+}
+
+/// Sorts root moves by how they scored in the previous iteration of iterative
+/// deepening, best first. `previous_scores` is in the order the search keeps
+/// them, with the best move last. Moves it doesn't include keep their order,
+/// after the rest.
+pub fn sort_by_previous_scores(moves: &mut ChessMoveList, previous_scores: &[(i16, ChessMove)]) {
+    moves.sort_by_key(|chess_move| {
+        previous_scores
+            .iter()
+            .rev()
+            .position(|(_, previous_move)| previous_move == chess_move)
+            .unwrap_or(previous_scores.len())
+    });
+}
+
 fn compare_move_types(a: &ChessMove, b: &ChessMove, board: &Board) -> std::cmp::Ordering {
     use std::cmp::Ordering;
 
@@ -155,6 +197,32 @@ mod tests {
         assert!(matches!(moves[10], ChessMove::Standard(_)));
     }
 
+    #[test]
+    fn test_sort_by_previous_scores() {
+        let mut moves = ChessMoveList::new();
+        moves.push(std_move!(E4, E5));
+        moves.push(std_move!(F3, G5));
+        moves.push(std_move!(A1, A3));
+        moves.push(std_move!(C3, E5));
+
+        // The best move comes last, as the search leaves them.
+        let previous_scores = vec![
+            (-50, std_move!(F3, G5)),
+            (10, std_move!(C3, E5)),
+            (30, std_move!(E4, E5)),
+        ];
+        sort_by_previous_scores(&mut moves, &previous_scores);
+        assert_eq!(
+            moves.to_vec(),
+            vec![
+                std_move!(E4, E5),
+                std_move!(C3, E5),
+                std_move!(F3, G5),
+                std_move!(A1, A3),
+            ]
+        );
+    }
+
     #[test]
     fn test_sort_only_standard_moves() {
         let board = create_test_board();
@@ -199,7 +267,8 @@ mod tests {
         sort_chess_moves(&mut moves, &board);
         println!("after sort: {:?}", moves);
 
-        // Checks should come before captures, but order within checks and captures should be preserved
+        // Checks should come before captures. The order within checks is preserved,
+        // and captures of the same piece are ordered by least valuable attacker.
         assert_eq!(moves[0].effect(), ChessMoveEffect::Check);
         assert_eq!(moves[1].effect(), ChessMoveEffect::Check);
         assert!(moves[2].captures().is_some());
@@ -220,17 +289,47 @@ mod tests {
         );
         assert_eq!(
             moves[2].from_square(),
-            F3,
+            E4,
             "expected:\n{}\nbut got:\n{}",
-            F3,
+            E4,
             moves[2].from_square()
         );
         assert_eq!(
             moves[3].from_square(),
-            E4,
+            F3,
             "expected:\n{}\nbut got:\n{}",
-            E4,
+            F3,
             moves[3].from_square()
         );
     }
+
+    #[test]
+    fn test_sort_captures_by_mvv_lva() {
+        let board = chess_position! {
+            ....k...
+            ........
+            ...r.q..
+            ....P...
+            .......Q
+            .....N..
+            ........
+            ....K...
+        };
+        let mut moves = ChessMoveList::new();
+
+        moves.push(std_move!(F3, D2));
+        moves.push(std_move!(H4, F4));
+        moves.push(std_move!(E5, D6, Capture(Piece::Rook)));
+        moves.push(std_move!(H4, E4));
+        moves.push(std_move!(H4, F6, Capture(Piece::Queen)));
+        moves.push(std_move!(E5, F6, Capture(Piece::Queen)));
+
+        sort_chess_moves(&mut moves, &board);
+
+        // Queen captures first, the pawn's before the queen's, then the rook capture.
+        let from_squares: Vec<_> = moves.iter().take(3).map(|m| m.from_square()).collect();
+        assert_eq!(from_squares, vec![E5, H4, E5]);
+        assert_eq!(moves[0].to_square(), F6);
+        assert_eq!(moves[2].to_square(), D6);
+    }
 }
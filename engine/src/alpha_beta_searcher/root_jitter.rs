@@ -0,0 +1,74 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Adds variety to the engine's play by choosing randomly among the root moves
+/// that score within `window` centipawns of the best move, rather than always
+/// playing the single best-scoring move. With a fixed seed, the choices are
+/// reproducible across runs.
+#[derive(Clone)]
+pub struct RootJitter {
+    window: i16,
+    rng: StdRng,
+}
+
+impl RootJitter {
+    pub fn new(window: i16, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self { window, rng }
+    }
+
+    pub fn window(&self) -> i16 {
+        self.window
+    }
+
+    /// Given scores sorted so that the best score is last, returns the index of
+    /// the score to play.
+    pub fn choose<T>(&mut self, sorted_scores: &[(i16, T)]) -> usize {
+        let last = sorted_scores.len() - 1;
+        let best_score = sorted_scores[last].0 as i32;
+        let close_count = sorted_scores
+            .iter()
+            .rev()
+            .take_while(|(score, _)| (best_score - *score as i32).abs() <= self.window as i32)
+            .count();
+
+        last - self.rng.gen_range(0..close_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_window_chooses_best() {
+        let mut jitter = RootJitter::new(0, Some(1));
+        let scores = [(-40, 'a'), (10, 'b'), (25, 'c'), (30, 'd')];
+        for _ in 0..10 {
+            assert_eq!(jitter.choose(&scores), 3);
+        }
+    }
+
+    #[test]
+    fn test_chooses_within_window() {
+        let mut jitter = RootJitter::new(10, None);
+        let scores = [(-40, 'a'), (10, 'b'), (25, 'c'), (30, 'd')];
+        for _ in 0..50 {
+            let choice = jitter.choose(&scores);
+            assert!(choice == 2 || choice == 3);
+        }
+    }
+
+    #[test]
+    fn test_seeded_jitter_is_reproducible() {
+        let mut jitter1 = RootJitter::new(100, Some(42));
+        let mut jitter2 = RootJitter::new(100, Some(42));
+        let scores = [(-40, 'a'), (10, 'b'), (25, 'c'), (30, 'd')];
+        for _ in 0..20 {
+            assert_eq!(jitter1.choose(&scores), jitter2.choose(&scores));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+/// Steers a search from another thread. `stop` ends the search early, keeping the
+/// best move from its last completed iteration, and `ponderhit` tells a search
+/// that is pondering that the predicted move was played, so that its time budget
+/// starts running. Clones share the same signals.
+#[derive(Clone, Debug, Default)]
+pub struct SearchControl {
+    signals: Arc<Signals>,
+}
+
+#[derive(Debug, Default)]
+struct Signals {
+    stopped: AtomicBool,
+    ponderhit_at: Mutex<Option<Instant>>,
+    changed: Condvar,
+}
+
+impl SearchControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stop(&self) {
+        self.signals.stopped.store(true, Ordering::Relaxed);
+        let _ponderhit_at = self.signals.ponderhit_at.lock().unwrap();
+        self.signals.changed.notify_all();
+    }
+
+    /// Checked at every node, so it needs to be cheap.
+    #[inline(always)]
+    pub fn is_stopped(&self) -> bool {
+        self.signals.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Records that the predicted move was played. Only the first call counts.
+    pub fn ponderhit(&self) {
+        let mut ponderhit_at = self.signals.ponderhit_at.lock().unwrap();
+        ponderhit_at.get_or_insert_with(Instant::now);
+        self.signals.changed.notify_all();
+    }
+
+    /// When `ponderhit` was called, if it has been.
+    pub fn ponderhit_at(&self) -> Option<Instant> {
+        *self.signals.ponderhit_at.lock().unwrap()
+    }
+
+    /// Blocks until `ponderhit` or `stop` is called, or returns right away if
+    /// either already was. A search that finishes while pondering has to wait
+    /// here before reporting its move.
+    pub fn wait_for_ponderhit_or_stop(&self) {
+        let ponderhit_at = self.signals.ponderhit_at.lock().unwrap();
+        let _ponderhit_at = self
+            .signals
+            .changed
+            .wait_while(ponderhit_at, |ponderhit_at| {
+                ponderhit_at.is_none() && !self.is_stopped()
+            })
+            .unwrap();
+    }
+
+    /// Blocks until `stop` is called, or returns right away if it already was.
+    /// An infinite search that finishes has to wait here before reporting its
+    /// move.
+    pub fn wait_for_stop(&self) {
+        let ponderhit_at = self.signals.ponderhit_at.lock().unwrap();
+        let _ponderhit_at = self
+            .signals
+            .changed
+            .wait_while(ponderhit_at, |_| !self.is_stopped())
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_wait_for_ponderhit_or_stop() {
+        let control = SearchControl::new();
+        let waiter = control.clone();
+        let handle = thread::spawn(move || waiter.wait_for_ponderhit_or_stop());
+        thread::sleep(Duration::from_millis(20));
+        assert!(!handle.is_finished());
+
+        control.ponderhit();
+        handle.join().unwrap();
+        let first_hit = control.ponderhit_at().unwrap();
+        control.ponderhit();
+        assert_eq!(control.ponderhit_at(), Some(first_hit));
+        assert!(!control.is_stopped());
+
+        let control = SearchControl::new();
+        control.stop();
+        assert!(control.is_stopped());
+        control.wait_for_ponderhit_or_stop();
+    }
+
+    #[test]
+    fn test_wait_for_stop() {
+        let control = SearchControl::new();
+        let waiter = control.clone();
+        let handle = thread::spawn(move || waiter.wait_for_stop());
+        control.ponderhit();
+        thread::sleep(Duration::from_millis(20));
+        assert!(!handle.is_finished());
+
+        control.stop();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use std::time::{Duration, Instant};
+
+/// Owns the clock for a single engine "think". Callers start the clock when the
+/// engine begins working on a move, and read back the elapsed time and the
+/// remaining budget from here rather than sampling the system clock themselves.
+///
+/// When pondering (thinking on the opponent's time), the elapsed time is not
+/// charged against the engine. On a ponder-hit, the budget is measured from the
+/// moment the opponent's move arrived, not from when pondering started, and the
+/// normal time allocation takes over without restarting the search.
+///
+/// The search can scale the budget for the current move, to spend less time on
+/// an easy move and more on a hard one, unless the budget is fixed.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeManager {
+    started_at: Instant,
+    ponder_started_at: Option<Instant>,
+    pondered: Duration,
+    budget: Option<Duration>,
+    budget_scale: f64,
+    fixed_budget: bool,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            ponder_started_at: None,
+            pondered: Duration::ZERO,
+            budget: None,
+            budget_scale: 1.0,
+            fixed_budget: false,
+        }
+    }
+}
+
+impl TimeManager {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_budget(budget: Duration) -> Self {
+        Self {
+            budget: Some(budget),
+            ..Default::default()
+        }
+    }
+
+    /// Starts the clock for a normal search. If the engine is currently pondering,
+    /// the clock keeps running on the opponent's time until `ponderhit` is called.
+    pub fn start(&mut self) {
+        if self.is_pondering() {
+            return;
+        }
+        self.started_at = Instant::now();
+        self.pondered = Duration::ZERO;
+        self.budget_scale = 1.0;
+    }
+
+    /// Starts a speculative search on the opponent's time.
+    pub fn start_pondering(&mut self) {
+        self.ponder_started_at = Some(Instant::now());
+        self.pondered = Duration::ZERO;
+        self.budget_scale = 1.0;
+    }
+
+    /// The opponent played the predicted move. From here on, time is charged
+    /// against the engine's own budget.
+    pub fn ponderhit(&mut self) {
+        self.ponderhit_at(Instant::now());
+    }
+
+    /// Like `ponderhit`, for a move that arrived at `at`, while the search was
+    /// busy.
+    pub fn ponderhit_at(&mut self, at: Instant) {
+        if let Some(ponder_started_at) = self.ponder_started_at.take() {
+            self.pondered = at.saturating_duration_since(ponder_started_at);
+        }
+        self.started_at = at;
+    }
+
+    /// Abandons the ponder search (e.g. the opponent played a different move).
+    pub fn stop_pondering(&mut self) {
+        self.ponder_started_at = None;
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponder_started_at.is_some()
+    }
+
+    /// Time charged against the engine for the current move. Always zero while pondering.
+    pub fn elapsed(&self) -> Duration {
+        if self.is_pondering() {
+            return Duration::ZERO;
+        }
+        self.started_at.elapsed()
+    }
+
+    /// Time spent thinking on the opponent's clock before the last ponder-hit.
+    pub fn pondered(&self) -> Duration {
+        match self.ponder_started_at {
+            Some(ponder_started_at) => ponder_started_at.elapsed(),
+            None => self.pondered,
+        }
+    }
+
+    pub fn budget(&self) -> Option<Duration> {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget = budget;
+    }
+
+    /// Whether the budget must be used as is (e.g. a UCI `movetime`), rather
+    /// than scaled to how hard the move is.
+    pub fn set_fixed_budget(&mut self, fixed_budget: bool) {
+        self.fixed_budget = fixed_budget;
+    }
+
+    /// Scales the budget for the current move, until the clock is restarted.
+    pub fn set_budget_scale(&mut self, budget_scale: f64) {
+        self.budget_scale = budget_scale;
+    }
+
+    /// The budget for the current move, after scaling.
+    pub fn allotted(&self) -> Option<Duration> {
+        if self.fixed_budget {
+            self.budget
+        } else {
+            self.budget.map(|budget| budget.mul_f64(self.budget_scale))
+        }
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.allotted()
+            .map(|allotted| allotted.saturating_sub(self.elapsed()))
+    }
+
+    /// Whether the budget for the current move has been used up. A search that
+    /// is pondering, or that has no budget, never runs out of time. This is the
+    /// hard limit: the search checks it at every node, and abandons the
+    /// iteration it's in once it's true.
+    pub fn is_out_of_time(&self) -> bool {
+        match self.allotted() {
+            Some(allotted) => !self.is_pondering() && self.elapsed() >= allotted,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn test_pondering_is_not_charged() {
+        let mut time_manager = TimeManager::with_budget(Duration::from_millis(10));
+        time_manager.start_pondering();
+        sleep(Duration::from_millis(20));
+
+        assert_eq!(time_manager.elapsed(), Duration::ZERO);
+        assert!(!time_manager.is_out_of_time());
+        assert!(time_manager.pondered() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_ponderhit_measures_budget_from_opponent_move() {
+        let mut time_manager = TimeManager::with_budget(Duration::from_millis(50));
+        time_manager.start_pondering();
+        sleep(Duration::from_millis(60));
+        time_manager.ponderhit();
+
+        assert!(!time_manager.is_pondering());
+        assert!(!time_manager.is_out_of_time());
+        assert!(time_manager.elapsed() < Duration::from_millis(50));
+        assert!(time_manager.pondered() >= Duration::from_millis(60));
+
+        sleep(Duration::from_millis(60));
+        assert!(time_manager.is_out_of_time());
+    }
+
+    #[test]
+    fn test_budget_scale() {
+        let mut time_manager = TimeManager::with_budget(Duration::from_secs(10));
+        time_manager.set_budget_scale(1.5);
+        assert_eq!(time_manager.allotted(), Some(Duration::from_secs(15)));
+        assert_eq!(time_manager.budget(), Some(Duration::from_secs(10)));
+
+        time_manager.set_fixed_budget(true);
+        assert_eq!(time_manager.allotted(), Some(Duration::from_secs(10)));
+
+        time_manager.set_fixed_budget(false);
+        time_manager.start();
+        assert_eq!(time_manager.allotted(), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_start_does_not_interrupt_pondering() {
+        let mut time_manager = TimeManager::new();
+        time_manager.start_pondering();
+        time_manager.start();
+        assert!(time_manager.is_pondering());
+
+        time_manager.stop_pondering();
+        time_manager.start();
+        assert!(!time_manager.is_pondering());
+    }
+}
@@ -0,0 +1,222 @@
+use std::mem::size_of;
+
+use super::SearchNode;
+
+/// The table size, in megabytes, unless one is configured with `--hash`.
+pub const DEFAULT_HASH_MB: usize = 16;
+
+/// The largest table size, in megabytes, that can be configured.
+pub const MAX_HASH_MB: usize = 4096;
+
+const ENTRIES_PER_BUCKET: usize = 4;
+
+/// A cached search result. A generation of 0 marks an empty slot; searches are
+/// numbered from 1.
+#[derive(Clone, Copy, Debug, Default)]
+struct Entry {
+    position_hash: u64,
+    alpha: i16,
+    beta: i16,
+    score: i16,
+    depth: u8,
+    generation: u8,
+}
+
+impl Entry {
+    fn is_empty(&self) -> bool {
+        self.generation == 0
+    }
+
+    fn matches(&self, (position_hash, alpha, beta, depth): SearchNode) -> bool {
+        !self.is_empty()
+            && self.position_hash == position_hash
+            && self.alpha == alpha
+            && self.beta == beta
+            && self.depth == depth
+    }
+}
+
+/// A bucket fills one 64-byte cache line.
+#[derive(Clone, Copy, Debug, Default)]
+struct Bucket {
+    entries: [Entry; ENTRIES_PER_BUCKET],
+}
+
+/// Search results from previous nodes, kept in a fixed number of buckets so
+/// that its memory doesn't grow over a long session. A position's results all
+/// share a bucket, and when the bucket is full, a new result replaces the entry
+/// left by the oldest search, and among those, the one searched the shallowest.
+/// The buckets aren't allocated until the first result is stored.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    bucket_count: usize,
+    generation: u8,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    /// A table that takes up to `megabytes` of memory, and at least one bucket.
+    pub fn new(megabytes: usize) -> Self {
+        Self::with_bucket_count(megabytes.min(MAX_HASH_MB) * 1024 * 1024 / size_of::<Bucket>())
+    }
+
+    fn with_bucket_count(bucket_count: usize) -> Self {
+        Self {
+            buckets: Vec::new(),
+            bucket_count: bucket_count.max(1),
+            generation: 1,
+        }
+    }
+
+    /// How much memory the table takes once it's allocated, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bucket_count * size_of::<Bucket>()
+    }
+
+    /// Starts a new generation, so that results from earlier searches are
+    /// replaced before those from the current one.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.checked_add(1).unwrap_or(1);
+    }
+
+    pub fn probe(&self, search_node: SearchNode) -> Option<i16> {
+        let bucket = self.buckets.get(self.bucket_index(search_node.0))?;
+        bucket
+            .entries
+            .iter()
+            .find(|entry| entry.matches(search_node))
+            .map(|entry| entry.score)
+    }
+
+    pub fn store(&mut self, search_node: SearchNode, score: i16) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![Bucket::default(); self.bucket_count];
+        }
+        let index = self.bucket_index(search_node.0);
+        let generation = self.generation;
+        let entries = &mut self.buckets[index].entries;
+        let slot = match entries.iter().position(|entry| entry.matches(search_node)) {
+            Some(slot) => slot,
+            None => (0..ENTRIES_PER_BUCKET)
+                .min_by_key(|&slot| {
+                    let entry = &entries[slot];
+                    (
+                        !entry.is_empty(),
+                        entry.generation == generation,
+                        entry.depth,
+                    )
+                })
+                .unwrap(),
+        };
+        let (position_hash, alpha, beta, depth) = search_node;
+        entries[slot] = Entry {
+            position_hash,
+            alpha,
+            beta,
+            score,
+            depth,
+            generation,
+        };
+    }
+
+    /// Forgets every result, and frees the buckets until the next one is stored.
+    pub fn clear(&mut self) {
+        self.buckets = Vec::new();
+    }
+
+    /// Forgets every result and starts the generations over, for a new game,
+    /// keeping the buckets allocated.
+    pub fn reset(&mut self) {
+        self.buckets.fill(Bucket::default());
+        self.generation = 1;
+    }
+
+    /// How many results the table holds.
+    pub fn len(&self) -> usize {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.entries.iter())
+            .filter(|entry| !entry.is_empty())
+            .count()
+    }
+
+    /// How full the table is, as a percentage of the results it can hold.
+    pub fn fill_percentage(&self) -> f64 {
+        self.len() as f64 * 100.0 / (self.bucket_count * ENTRIES_PER_BUCKET) as f64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn bucket_index(&self, position_hash: u64) -> usize {
+        (position_hash % self.bucket_count as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_is_bounded() {
+        assert_eq!(size_of::<Bucket>(), 64);
+        let table = TranspositionTable::new(1);
+        assert_eq!(table.size_in_bytes(), 1024 * 1024);
+        assert_eq!(TranspositionTable::new(0).size_in_bytes(), 64);
+
+        let mut table = TranspositionTable::with_bucket_count(2);
+        for position_hash in 0..100 {
+            table.store((position_hash, -100, 100, 3), 10);
+        }
+        assert_eq!(table.len(), 2 * ENTRIES_PER_BUCKET);
+        assert_eq!(table.fill_percentage(), 100.0);
+        assert_eq!(table.probe((99, -100, 100, 3)), Some(10));
+        assert_eq!(table.probe((99, -100, 101, 3)), None);
+        table.clear();
+        assert!(table.is_empty());
+        assert_eq!(table.probe((99, -100, 100, 3)), None);
+    }
+
+    #[test]
+    fn test_reset_keeps_the_buckets() {
+        let mut table = TranspositionTable::with_bucket_count(2);
+        table.store((1, 0, 0, 1), 1);
+        table.new_search();
+        table.reset();
+        assert!(table.is_empty());
+        assert_eq!(table.buckets.len(), 2);
+        assert_eq!(table.generation, 1);
+        assert_eq!(table.probe((1, 0, 0, 1)), None);
+    }
+
+    #[test]
+    fn test_replaces_stale_then_shallow_entries() {
+        let mut table = TranspositionTable::with_bucket_count(1);
+        for depth in 1..=4 {
+            table.store((depth as u64, 0, 0, depth), depth as i16);
+        }
+        // The bucket is full, so the shallowest entry gives way.
+        table.store((5, 0, 0, 5), 5);
+        assert_eq!(table.probe((1, 0, 0, 1)), None);
+        assert_eq!(table.probe((2, 0, 0, 2)), Some(2));
+
+        // Entries from an earlier search give way first, however deep.
+        table.store((4, 0, 0, 4), 40);
+        table.new_search();
+        table.store((6, 0, 0, 1), 6);
+        assert_eq!(table.probe((2, 0, 0, 2)), None);
+        table.store((7, 0, 0, 1), 7);
+        assert_eq!(table.probe((3, 0, 0, 3)), None);
+        assert_eq!(table.probe((4, 0, 0, 4)), Some(40));
+        assert_eq!(table.probe((5, 0, 0, 5)), Some(5));
+        assert_eq!(table.probe((6, 0, 0, 1)), Some(6));
+        assert_eq!(table.probe((7, 0, 0, 1)), Some(7));
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+/// Aborts a search that has run far past its time budget, e.g. because the
+/// position blows up the search tree, so that the game doesn't wait on it
+/// indefinitely. An aborted search is stopped, as it would be through its
+/// control, and logs what it was searching. Node limits need no watchdog, since
+/// they're enforced exactly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Watchdog {
+    /// A search is aborted once it has taken this many times its budget.
+    pub multiple: u32,
+    /// The budget for a search without one of its own, e.g. a search to a fixed
+    /// depth. With `None`, such searches are never aborted.
+    pub default_budget: Option<Duration>,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            multiple: 10,
+            default_budget: None,
+        }
+    }
+}
+
+impl Watchdog {
+    /// How long a search with `budget` may take before it's aborted, if it
+    /// can be.
+    pub fn time_limit(&self, budget: Option<Duration>) -> Option<Duration> {
+        budget
+            .or(self.default_budget)
+            .map(|budget| budget * self.multiple)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_limit() {
+        let watchdog = Watchdog::default();
+        assert_eq!(
+            watchdog.time_limit(Some(Duration::from_secs(2))),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(watchdog.time_limit(None), None);
+
+        let watchdog = Watchdog {
+            multiple: 4,
+            default_budget: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(
+            watchdog.time_limit(Some(Duration::from_secs(2))),
+            Some(Duration::from_secs(8))
+        );
+        assert_eq!(watchdog.time_limit(None), Some(Duration::from_secs(120)));
+    }
+}
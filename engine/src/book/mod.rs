@@ -4,6 +4,8 @@ use common::bitboard::bitboard::Bitboard;
 use common::bitboard::square::{square_string_to_bitboard, to_algebraic};
 use rustc_hash::FxHashMap;
 
+pub mod traps;
+
 include!(concat!(env!("OUT_DIR"), "/opening_book.rs"));
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -110,11 +112,7 @@ impl Book {
         let mut curr_node = &self.root;
 
         for book_move in line {
-            let next = curr_node.lines.get(&book_move);
-            if next.is_none() {
-                return None;
-            }
-            curr_node = next.unwrap();
+            curr_node = curr_node.lines.get(&book_move)?;
         }
 
         curr_node.line_name.clone()
@@ -0,0 +1,165 @@
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+
+use rustc_hash::FxHashMap;
+
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+
+/// A well-known opening trap: a line into a position where one side has just
+/// gone wrong, and the reply that punishes it (or, for a threat, that parries
+/// it).
+#[derive(Debug, PartialEq)]
+pub struct OpeningTrap {
+    pub name: &'static str,
+    /// The moves from the starting position into the trap, in UCI.
+    pub moves: &'static str,
+    /// The best reply in the trap's position, in SAN.
+    pub refutation: &'static str,
+    /// What the refutation wins, or what the tempting alternative loses.
+    pub explanation: &'static str,
+}
+
+impl Display for OpeningTrap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}! {}",
+            self.name, self.refutation, self.explanation
+        )
+    }
+}
+
+pub const OPENING_TRAPS: [OpeningTrap; 8] = [
+    OpeningTrap {
+        name: "Fool's Mate",
+        moves: "f2f3 e7e5 g2g4",
+        refutation: "Qh4#",
+        explanation: "White's f- and g-pawns have left the king's diagonal open.",
+    },
+    OpeningTrap {
+        name: "Scholar's Mate",
+        moves: "e2e4 e7e5 f1c4 b8c6 d1h5",
+        refutation: "g6",
+        explanation: "blocks the queen's diagonal to f7; ...Nf6?? allows Qxf7#.",
+    },
+    OpeningTrap {
+        name: "Legal's Mate",
+        moves: "e2e4 e7e5 g1f3 d7d6 f1c4 c8g4 b1c3 g7g6 f3e5",
+        refutation: "dxe5",
+        explanation: "gives up only a pawn after Qxg4; ...Bxd1?? loses to Bxf7+ Ke7 Nd5#.",
+    },
+    OpeningTrap {
+        name: "Blackburne Shilling Gambit",
+        moves: "e2e4 e7e5 g1f3 b8c6 f1c4 c6d4 f3e5",
+        refutation: "Qg5",
+        explanation: "forks the knight and g2; Nxf7?? loses to Qxg2 Rf1 Qxe4+ Be2 Nf3#.",
+    },
+    OpeningTrap {
+        name: "Elephant Trap",
+        moves: "d2d4 d7d5 c2c4 e7e6 b1c3 g8f6 c1g5 b8d7 c4d5 e6d5 c3d5",
+        refutation: "Nxd5",
+        explanation: "Bxd8 is met by Bb4+ Qd2 Bxd2+ Kxd2 Kxd8, and Black is a piece up.",
+    },
+    OpeningTrap {
+        name: "Lasker Trap",
+        moves: "d2d4 d7d5 c2c4 e7e5 d4e5 d5d4 e2e3",
+        refutation: "Bb4+",
+        explanation: "and after Bd2 dxe3, Bxb4?? exf2+ Ke2 fxg1=N+ wins for Black.",
+    },
+    OpeningTrap {
+        name: "Englund Gambit Trap",
+        moves: "d2d4 e7e5 d4e5 b8c6 g1f3 d8e7 c1f4 e7b4 f4d2 b4b2 d2c3",
+        refutation: "Bb4",
+        explanation: "pins the bishop; Qd2 Bxc3 Qxc3 Qc1# follows.",
+    },
+    OpeningTrap {
+        name: "Noah's Ark Trap",
+        moves: "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 d7d6 d2d4 b7b5 a4b3 c6d4 f3d4 e5d4 d1d4",
+        refutation: "c5",
+        explanation: "and ...c4 traps White's bishop on b3.",
+    },
+];
+
+/// Plays the trap's moves from the starting position. Panics if one of them
+/// isn't legal, which the tests rule out.
+pub fn trap_position(trap: &OpeningTrap) -> Board {
+    let mut board = Board::starting_position();
+    let mut move_generator = MoveGenerator::new();
+    for uci in trap.moves.split(' ') {
+        let turn = board.turn();
+        let chess_move = move_generator
+            .generate_moves(&mut board, turn)
+            .into_iter()
+            .find(|chess_move| chess_move.to_uci() == uci)
+            .unwrap_or_else(|| panic!("{} is not legal in the {}", uci, trap.name));
+        chess_move.apply(&mut board).unwrap();
+        board.toggle_turn();
+    }
+    board
+}
+
+/// The trap set in the board's position, if it's a known one. Traps are found
+/// by the position's hash, so they're found however the game got there.
+pub fn find_trap(board: &Board) -> Option<&'static OpeningTrap> {
+    static TRAPS: OnceLock<FxHashMap<u64, &'static OpeningTrap>> = OnceLock::new();
+    TRAPS
+        .get_or_init(|| {
+            OPENING_TRAPS
+                .iter()
+                .map(|trap| (trap_position(trap).current_position_hash(), trap))
+                .collect()
+        })
+        .get(&board.current_position_hash())
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::algebraic_notation::{
+        enumerate_candidate_moves_with_algebraic_notation, find_move_by_san,
+    };
+    use crate::game::game::Game;
+
+    #[test]
+    fn test_refutations_are_legal() {
+        let mut move_generator = MoveGenerator::new();
+        for trap in &OPENING_TRAPS {
+            let mut board = trap_position(trap);
+            let turn = board.turn();
+            let candidates = enumerate_candidate_moves_with_algebraic_notation(
+                &mut board,
+                turn,
+                &mut move_generator,
+            );
+            assert!(
+                find_move_by_san(trap.refutation, &candidates).is_some(),
+                "{}",
+                trap.name
+            );
+            assert_eq!(find_trap(&board), Some(trap));
+        }
+    }
+
+    #[test]
+    fn test_find_trap_by_transposition() {
+        // The queen comes out before the bishop, but the position is the same.
+        let mut game = Game::new(1);
+        for san in ["e4", "e5", "Qh5", "Nc6"] {
+            game.apply_chess_move_from_raw_algebraic_notation(san.to_string())
+                .unwrap();
+            game.board_mut().toggle_turn();
+            assert_eq!(find_trap(game.board()), None);
+        }
+        game.apply_chess_move_from_raw_algebraic_notation("Bc4".to_string())
+            .unwrap();
+        game.board_mut().toggle_turn();
+        let trap = find_trap(game.board()).unwrap();
+        assert_eq!(trap.name, "Scholar's Mate");
+        assert_eq!(
+            trap.to_string(),
+            "Scholar's Mate: g6! blocks the queen's diagonal to f7; ...Nf6?? allows Qxf7#."
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use crate::board::zobrist_version::ZOBRIST_TABLE_VERSION;
+
+/// The name the engine goes by in UCI, PGN tags, and match results, whichever
+/// crate it is built into.
+const ENGINE_NAME: &str = "chess";
+
+/// The optional cargo features this crate can be built with, and whether each
+/// was enabled in this build. The CLI's own features don't change how the engine
+/// plays, so they aren't listed.
+const OPTIONAL_FEATURES: [(&str, bool); 2] = [
+    ("nnue", cfg!(feature = "nnue")),
+    ("parallel", cfg!(feature = "parallel")),
+];
+
+/// Identifies the exact build of the engine, so that games and match results
+/// can be traced back to the version and feature set that produced them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// The authors' names, without their email addresses.
+    pub authors: Vec<&'static str>,
+    /// The optional cargo features this build was compiled with.
+    pub features: Vec<&'static str>,
+    /// The version of the zobrist tables generated for this build. Position
+    /// hashes are only comparable between builds with the same version.
+    pub zobrist_version: u64,
+}
+
+impl EngineInfo {
+    /// The build that is running.
+    pub fn current() -> Self {
+        Self {
+            name: ENGINE_NAME,
+            version: env!("CARGO_PKG_VERSION"),
+            authors: env!("CARGO_PKG_AUTHORS")
+                .split(':')
+                .map(|author| author.split(" <").next().unwrap_or(author).trim())
+                .collect(),
+            features: OPTIONAL_FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(feature, _)| *feature)
+                .collect(),
+            zobrist_version: ZOBRIST_TABLE_VERSION,
+        }
+    }
+
+    /// The name and version, e.g. "chess 1.0.0".
+    pub fn full_name(&self) -> String {
+        format!("{} {}", self.name, self.version)
+    }
+
+    pub fn author(&self) -> String {
+        self.authors.join(", ")
+    }
+
+    /// The enabled features, comma separated, or "none".
+    pub fn feature_list(&self) -> String {
+        if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        }
+    }
+}
+
+impl fmt::Display for EngineInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (features: {})",
+            self.full_name(),
+            self.feature_list()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_engine_info() {
+        let info = EngineInfo::current();
+        assert_eq!(
+            info.full_name(),
+            format!("chess {}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(info.author(), "Cody Joseph Krainock");
+        assert_eq!(
+            info.features.contains(&"parallel"),
+            cfg!(feature = "parallel")
+        );
+        assert!(info
+            .to_string()
+            .ends_with(&format!("(features: {})", info.feature_list())));
+    }
+}
@@ -0,0 +1,177 @@
+use rustc_hash::FxHashSet;
+
+use crate::board::color::Color;
+use crate::board::epd::Epd;
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+use crate::move_generator::MoveGenerator;
+
+use super::tune::split_result;
+
+/// A corpus of positions, one per line, normalized for the tuner and the
+/// benchmarks: each position written in the same form, and each written once.
+pub struct NormalizedCorpus {
+    /// The normalized positions, in the order they were first seen, each with
+    /// its game's result if it was labeled with one.
+    pub lines: Vec<String>,
+    /// How many positions were read.
+    pub read: usize,
+    /// Positions that were dropped for repeating an earlier one.
+    pub duplicates: usize,
+    /// Lines that couldn't be read as a position, by line number.
+    pub invalid: Vec<(usize, String)>,
+}
+
+/// Normalizes a corpus of positions, one per line, each a FEN or EPD, and
+/// optionally labeled with its game's result as `load_positions` reads them.
+/// Each position is written back as a full FEN:
+///
+/// - without castling rights its king and rook aren't in place for, and
+///   without an en passant target unless the capture is legal, so that the
+///   same position is always written the same way;
+/// - once, at its first appearance: later positions with the same hash,
+///   which only differ in their clocks (or labels), are dropped;
+/// - with `white_to_move`, mirrored with the colors swapped if black is to
+///   move, and with its result swapped to match.
+///
+/// EPD operations other than a `c9` result are dropped. Blank lines and lines
+/// starting with `#` are skipped.
+pub fn normalize_corpus(contents: &str, white_to_move: bool) -> NormalizedCorpus {
+    let mut move_generator = MoveGenerator::new();
+    let mut seen = FxHashSet::default();
+    let mut corpus = NormalizedCorpus {
+        lines: Vec::new(),
+        read: 0,
+        duplicates: 0,
+        invalid: Vec::new(),
+    };
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        corpus.read += 1;
+        let (mut board, mut result) = match parse_position(line) {
+            Ok(position) => position,
+            Err(error) => {
+                corpus.invalid.push((i + 1, error));
+                continue;
+            }
+        };
+        drop_impossible_en_passant(&mut board, &mut move_generator);
+        if white_to_move && board.turn() == Color::Black {
+            board = board.mirrored();
+            result = result.map(|result| 1.0 - result);
+        }
+        if !seen.insert(board.current_position_hash()) {
+            corpus.duplicates += 1;
+            continue;
+        }
+        corpus.lines.push(match result {
+            Some(result) => format!("{} {}", board.to_fen(), format_result(result)),
+            None => board.to_fen(),
+        });
+    }
+    corpus
+}
+
+/// Reads a position, and its game's result if it's labeled with one. A line
+/// that ends in a fullmove number could be a labeled EPD or an unlabeled FEN,
+/// so it's tried both ways.
+fn parse_position(line: &str) -> Result<(Board, Option<f64>), String> {
+    if let Some((fen, result)) = split_result(line) {
+        if let Ok(board) = Board::from_fen_lenient(&fen) {
+            return Ok((board, Some(result)));
+        }
+    }
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let fen = match Board::from_fen_lenient(&fields[..fields.len().min(6)].join(" ")) {
+        Ok(board) => return Ok((board, None)),
+        Err(error) => error,
+    };
+    if fields.len() > 4 {
+        if let Ok(epd) = Epd::parse(line) {
+            return Ok((epd.board, None));
+        }
+    }
+    Err(fen.to_string())
+}
+
+/// Clears the en passant target unless the side to move can make the capture,
+/// as most FEN writers do; a target that can't be captured on doesn't change
+/// the position.
+fn drop_impossible_en_passant(board: &mut Board, move_generator: &mut MoveGenerator) {
+    if board.peek_en_passant_target().is_empty() {
+        return;
+    }
+    let turn = board.turn();
+    let can_capture = move_generator
+        .generate_moves(board, turn)
+        .iter()
+        .any(|chess_move| matches!(chess_move, ChessMove::EnPassant(_)));
+    if !can_capture {
+        board.pop_en_passant_target();
+    }
+}
+
+fn format_result(result: f64) -> String {
+    if result == 1.0 {
+        "1-0".to_string()
+    } else if result == 0.5 {
+        "1/2-1/2".to_string()
+    } else if result == 0.0 {
+        "0-1".to_string()
+    } else {
+        result.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_corpus() {
+        let contents = "\
+# Castling rights without the rook, and a target nothing can capture on.
+rnbqkbn1/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 [0.5]
+# The same position, later in another game.
+rnbqkbn1/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQq - 4 9 [1.0]
+rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3
+4k3/8/8/8/8/8/4P3/4K3 w - - bm e4; id \"kpk\";
+not a position
+4k3/8/8/8/8/8/4P3/4K3 w - - c9 \"1-0\";
+";
+        let corpus = normalize_corpus(contents, false);
+        assert_eq!(corpus.read, 6);
+        assert_eq!(corpus.duplicates, 2);
+        assert_eq!(corpus.invalid.len(), 1);
+        assert_eq!(corpus.invalid[0].0, 7);
+        assert_eq!(
+            corpus.lines,
+            vec![
+                "rnbqkbn1/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQq - 0 1 1/2-1/2",
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_corpus_to_white_to_move() {
+        let contents = "\
+4k3/4p3/8/8/8/8/8/4K3 b - - 0 1 0-1
+4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 0.25
+4k3/8/8/8/8/8/8/4K3 b - - 0 1
+";
+        let corpus = normalize_corpus(contents, true);
+        assert_eq!(corpus.duplicates, 1);
+        assert_eq!(
+            corpus.lines,
+            vec![
+                "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 1-0",
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            ]
+        );
+    }
+}
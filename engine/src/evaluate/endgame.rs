@@ -0,0 +1,360 @@
+use std::sync::OnceLock;
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+
+use super::{material_value, CLAIMABLE_HALFMOVE_CLOCK};
+
+/// Added to the score of an ending that's known to be won, so that the search
+/// heads for it over any position the generic evaluation merely likes. A known
+/// win never scores less than this, however near the fifty-move rule is.
+pub const KNOWN_WIN: i16 = 1000;
+
+/// Past this many plies without a capture or a pawn move, a known win scores
+/// `FIFTY_MOVE_URGENCY` less for each further ply, so that as the fifty-move
+/// rule draws near, the search prefers the moves that reset the count, and
+/// converts the win before the defender can claim a draw. Before then, any
+/// winning move will do. There are no tablebases to probe for the moves that
+/// reset the count soonest; this only covers the endings scored here.
+const URGENT_HALFMOVE_CLOCK: u16 = 40;
+const FIFTY_MOVE_URGENCY: i16 = 4;
+/// Known wins score this much more with the clock fresh, so that the urgency
+/// can take it away again without dropping them below `KNOWN_WIN`.
+const MAX_URGENCY: i16 =
+    FIFTY_MOVE_URGENCY * (CLAIMABLE_HALFMOVE_CLOCK - URGENT_HALFMOVE_CLOCK) as i16;
+
+/// The score, from white's perspective, of a basic ending that the generic
+/// evaluation can't be trusted to convert, or `None` for any other position.
+/// King and pawn against king is scored exactly, as won or drawn. A lone king
+/// against a rook or queen is scored to drive the lone king into a corner,
+/// where it can be mated. Either win is scored lower once the fifty-move rule
+/// draws near.
+pub fn endgame_score(board: &Board) -> Option<i16> {
+    let (strong, weak) = if board.pieces(Color::Black).occupied().count_ones() == 1 {
+        (Color::White, Color::Black)
+    } else if board.pieces(Color::White).occupied().count_ones() == 1 {
+        (Color::Black, Color::White)
+    } else {
+        return None;
+    };
+
+    let pieces = board.pieces(strong);
+    let score = match pieces.occupied().count_ones() {
+        // Two bare kings.
+        1 => 0,
+        2 if pieces.locate(Piece::Pawn).count_ones() == 1 => kpk_score(board, strong),
+        _ if !(pieces.locate(Piece::Rook) | pieces.locate(Piece::Queen)).is_empty() => {
+            mop_up_score(board, strong, weak)
+        }
+        _ => return None,
+    };
+    let score = if score >= KNOWN_WIN {
+        let plies = board
+            .halfmove_clock()
+            .clamp(URGENT_HALFMOVE_CLOCK, CLAIMABLE_HALFMOVE_CLOCK)
+            - URGENT_HALFMOVE_CLOCK;
+        score + MAX_URGENCY - FIFTY_MOVE_URGENCY * plies as i16
+    } else {
+        score
+    };
+    Some(match strong {
+        Color::White => score,
+        Color::Black => -score,
+    })
+}
+
+/// From the strong side's perspective: a known win, plus the material to mate
+/// with, plus a bonus for cornering the lone king and bringing the strong king
+/// close enough to help.
+fn mop_up_score(board: &Board, strong: Color, weak: Color) -> i16 {
+    let material = board
+        .pieces(strong)
+        .occupied()
+        .squares()
+        .into_iter()
+        .filter_map(|square| board.get(square))
+        .filter(|&(piece, _)| piece != Piece::King)
+        .map(|(piece, _)| material_value(piece))
+        .sum::<i16>();
+    let strong_king = king_square(board, strong);
+    let weak_king = king_square(board, weak);
+    KNOWN_WIN
+        + material
+        + 20 * center_distance(weak_king)
+        + 10 * (7 - distance(strong_king, weak_king))
+}
+
+/// From the strong side's perspective: a known win, plus the pawn, plus a
+/// little for each rank it has advanced so that the search makes progress, or
+/// 0 when the defending king can hold the draw.
+fn kpk_score(board: &Board, strong: Color) -> i16 {
+    // Look up the position as if the strong side were white and the pawn were
+    // on the queenside, flipping the board as needed.
+    let flip = match strong {
+        Color::White => 0,
+        Color::Black => 56,
+    };
+    let pawn = board.pieces(strong).locate(Piece::Pawn).trailing_zeros() as usize ^ flip;
+    let mirror = if pawn % 8 >= 4 { 7 } else { 0 };
+    let pawn = pawn ^ mirror;
+    let strong_king = king_square(board, strong) ^ flip ^ mirror;
+    let weak_king = king_square(board, strong.opposite()) ^ flip ^ mirror;
+
+    let index = kpk_index(board.turn() == strong, weak_king, strong_king, pawn);
+    if kpk_bitbase()[index / 64] & (1 << (index % 64)) == 0 {
+        return 0;
+    }
+    KNOWN_WIN + material_value(Piece::Pawn) + 10 * (pawn / 8) as i16
+}
+
+fn king_square(board: &Board, color: Color) -> usize {
+    board.pieces(color).locate(Piece::King).trailing_zeros() as usize
+}
+
+/// How many king moves apart two squares are.
+pub(super) fn distance(a: usize, b: usize) -> i16 {
+    let files = (a % 8) as i16 - (b % 8) as i16;
+    let ranks = (a / 8) as i16 - (b / 8) as i16;
+    files.abs().max(ranks.abs())
+}
+
+/// How far a square is from the four center squares, counting files and ranks
+/// separately, from 0 in the center to 6 in a corner.
+fn center_distance(square: usize) -> i16 {
+    let from_center = |line: i16| (3 - line).max(line - 4);
+    from_center((square % 8) as i16) + from_center((square / 8) as i16)
+}
+
+fn king_moves(square: usize) -> impl Iterator<Item = usize> {
+    let (file, rank) = ((square % 8) as i8, (square / 8) as i8);
+    (-1..=1)
+        .flat_map(move |files| (-1..=1).map(move |ranks| (file + files, rank + ranks)))
+        .filter(move |&to| to != (file, rank))
+        .filter(|&(file, rank)| (0..8).contains(&file) && (0..8).contains(&rank))
+        .map(|(file, rank)| (rank * 8 + file) as usize)
+}
+
+/// Whether a white pawn on `pawn` attacks `square`.
+fn pawn_attacks(pawn: usize, square: usize) -> bool {
+    square / 8 == pawn / 8 + 1 && (square % 8).abs_diff(pawn % 8) == 1
+}
+
+/// Every position of the king and pawn ending, with white as the strong side and
+/// the pawn on files a through d and ranks 2 through 7: who's to move, the weak
+/// king, the strong king, and the pawn.
+const KPK_POSITIONS: usize = 2 * 64 * 64 * 24;
+
+fn kpk_index(strong_to_move: bool, weak_king: usize, strong_king: usize, pawn: usize) -> usize {
+    let pawn = (pawn % 8) * 6 + pawn / 8 - 1;
+    ((pawn * 64 + strong_king) * 64 + weak_king) * 2 + strong_to_move as usize
+}
+
+// A position's result while the bitbase is being built. They're bits, so that a
+// position's result can be worked out from the union of its successors'.
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+/// Whether the strong side wins each king and pawn position, one bit per
+/// `kpk_index`. It's built by retrograde analysis the first time it's needed.
+fn kpk_bitbase() -> &'static [u64] {
+    static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+    BITBASE.get_or_init(|| {
+        let mut positions = vec![INVALID; KPK_POSITIONS];
+        let mut each_position = Vec::with_capacity(KPK_POSITIONS);
+        for pawn in (8..56).filter(|pawn| pawn % 8 < 4) {
+            for strong_king in 0..64 {
+                for weak_king in 0..64 {
+                    for strong_to_move in [false, true] {
+                        let position = (strong_to_move, weak_king, strong_king, pawn);
+                        let index = kpk_index(strong_to_move, weak_king, strong_king, pawn);
+                        positions[index] = classify_kpk(position);
+                        each_position.push((index, position));
+                    }
+                }
+            }
+        }
+
+        // Settle the unknown positions from their successors until none changes.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &(index, position) in &each_position {
+                if positions[index] == UNKNOWN {
+                    positions[index] = settle_kpk(&positions, position);
+                    changed |= positions[index] != UNKNOWN;
+                }
+            }
+        }
+
+        let mut bitbase = vec![0; KPK_POSITIONS / 64];
+        for (index, &result) in positions.iter().enumerate() {
+            if result == WIN {
+                bitbase[index / 64] |= 1 << (index % 64);
+            }
+        }
+        bitbase
+    })
+}
+
+/// A position's result without looking ahead: invalid, a win when the pawn
+/// promotes safely, a draw when the weak king is stalemated or takes the pawn,
+/// or otherwise unknown.
+fn classify_kpk((strong_to_move, weak_king, strong_king, pawn): (bool, usize, usize, usize)) -> u8 {
+    if distance(weak_king, strong_king) <= 1
+        || pawn == weak_king
+        || pawn == strong_king
+        || (strong_to_move && pawn_attacks(pawn, weak_king))
+    {
+        return INVALID;
+    }
+
+    let promotion = pawn + 8;
+    if strong_to_move
+        && pawn / 8 == 6
+        && strong_king != promotion
+        && (distance(weak_king, promotion) > 1 || distance(strong_king, promotion) == 1)
+    {
+        return WIN;
+    }
+
+    if !strong_to_move {
+        let defended =
+            |square: usize| distance(strong_king, square) == 1 || pawn_attacks(pawn, square);
+        if king_moves(weak_king).all(defended) {
+            return DRAW;
+        }
+        if distance(weak_king, pawn) == 1 && distance(strong_king, pawn) > 1 {
+            return DRAW;
+        }
+    }
+    UNKNOWN
+}
+
+/// A position's result from its successors': the side to move gets its best
+/// result if any move reaches it, and its worst once every move is known to.
+fn settle_kpk(
+    positions: &[u8],
+    (strong_to_move, weak_king, strong_king, pawn): (bool, usize, usize, usize),
+) -> u8 {
+    let mut successors = INVALID;
+    if strong_to_move {
+        for to in king_moves(strong_king) {
+            successors |= positions[kpk_index(false, weak_king, to, pawn)];
+        }
+        if pawn / 8 < 6 {
+            successors |= positions[kpk_index(false, weak_king, strong_king, pawn + 8)];
+        }
+        if pawn / 8 == 1 && pawn + 8 != strong_king && pawn + 8 != weak_king {
+            successors |= positions[kpk_index(false, weak_king, strong_king, pawn + 16)];
+        }
+    } else {
+        for to in king_moves(weak_king) {
+            successors |= positions[kpk_index(true, to, strong_king, pawn)];
+        }
+    }
+
+    let (best, worst) = if strong_to_move {
+        (WIN, DRAW)
+    } else {
+        (DRAW, WIN)
+    };
+    if successors & best != 0 {
+        best
+    } else if successors & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        worst
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(fen: &str) -> Option<i16> {
+        endgame_score(&Board::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_kpk() {
+        // The strong king in front of its pawn on the sixth rank wins, whoever
+        // is to move.
+        assert!(score("4k3/8/4K3/8/4P3/8/8/8 w - - 0 1").unwrap() > KNOWN_WIN);
+        assert!(score("4k3/8/4K3/8/4P3/8/8/8 b - - 0 1").unwrap() > KNOWN_WIN);
+        // The same for black.
+        assert!(score("8/8/8/3p4/8/3k4/8/3K4 b - - 0 1").unwrap() < -KNOWN_WIN);
+
+        // With the pawn on the seventh, it's a win if the strong king can step
+        // aside, but a stalemate if the weak king has to move.
+        assert!(score("4k3/4P3/4K3/8/8/8/8/8 w - - 0 1").unwrap() > KNOWN_WIN);
+        assert_eq!(score("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(0));
+        // The defending king in front of the pawn, with the opposition, holds.
+        assert_eq!(score("8/8/8/4k3/8/4K3/4P3/8 w - - 0 1"), Some(0));
+        assert!(score("8/8/8/4k3/8/4K3/4P3/8 b - - 0 1").unwrap() > KNOWN_WIN);
+        // A rook pawn with the defending king in the corner is a draw.
+        assert_eq!(score("7k/8/8/8/8/8/7P/7K w - - 0 1"), Some(0));
+        assert_eq!(score("k7/8/8/8/8/8/P7/K7 w - - 0 1"), Some(0));
+        // The defending king takes the pawn.
+        assert_eq!(score("8/8/8/8/8/8/3kP3/7K b - - 0 1"), Some(0));
+        // The pawn outruns the defending king.
+        assert!(score("k7/8/8/8/7P/8/8/K7 w - - 0 1").unwrap() > KNOWN_WIN);
+        assert_eq!(score("6k1/8/8/8/7P/8/8/K7 w - - 0 1"), Some(0));
+    }
+
+    #[test]
+    fn test_mop_up() {
+        // The lone king is worse off in the corner than in the center.
+        let center = score("8/8/8/3k4/8/8/8/R3K3 w - - 0 1").unwrap();
+        let corner = score("k7/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert!(center > KNOWN_WIN);
+        assert!(corner > center);
+        // And with the strong king closer.
+        let closer = score("k7/8/1K6/8/8/8/8/R7 w - - 0 1").unwrap();
+        assert!(closer > corner);
+
+        // A queen is worth more than a rook, and black can have the extra piece.
+        let queen = score("8/8/8/3k4/8/8/8/Q3K3 w - - 0 1").unwrap();
+        assert!(queen > center);
+        assert_eq!(score("q3k3/8/8/8/3K4/8/8/8 w - - 0 1"), Some(-queen));
+    }
+
+    #[test]
+    fn test_fifty_move_urgency() {
+        // A win scores lower as the fifty-move rule draws near, so pushing the
+        // pawn, which resets the count, beats shuffling the king.
+        let fresh = score("4k3/8/4K3/8/4P3/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(score("4k3/8/4K3/8/4P3/8/8/8 w - - 40 60"), Some(fresh));
+        let late = score("4k3/8/4K3/8/4P3/8/8/8 w - - 90 60").unwrap();
+        assert!(KNOWN_WIN < late && late < fresh);
+        // Even on the last ply before a draw can be claimed, a known win scores
+        // above `KNOWN_WIN`.
+        let latest = score("4k3/8/4K3/8/4P3/8/8/8 w - - 99 60").unwrap();
+        assert!(KNOWN_WIN < latest && latest < late);
+        assert!(score("k7/8/8/8/8/8/8/R3K3 w - - 99 60").unwrap() > KNOWN_WIN);
+        let king_move = score("4k3/8/3K4/8/4P3/8/8/8 b - - 91 60").unwrap();
+        let pawn_push = score("4k3/8/4K3/4P3/8/8/8/8 b - - 0 60").unwrap();
+        assert!(pawn_push > king_move);
+
+        // Black's wins draw towards 0 the same way.
+        assert_eq!(
+            score("8/8/8/3p4/8/3k4/8/3K4 b - - 90 60"),
+            score("3k4/8/3K4/8/3P4/8/8/8 w - - 90 60").map(|score| -score)
+        );
+        // Draws are left alone.
+        assert_eq!(score("4k3/4P3/4K3/8/8/8/8/8 b - - 90 60"), Some(0));
+    }
+
+    #[test]
+    fn test_other_positions_use_the_generic_evaluation() {
+        assert_eq!(score("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), Some(0));
+        assert_eq!(score("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), None);
+        assert_eq!(score("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1"), None);
+        assert_eq!(
+            score("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            None
+        );
+    }
+}
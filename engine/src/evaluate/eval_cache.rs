@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// The number of entries, which at 8 bytes each keeps the cache small enough
+/// to stay mostly in the CPU's caches.
+const ENTRIES: usize = 1 << 16;
+/// Stands in for a king safety score that hasn't been worked out.
+const NO_KING_SAFETY: i16 = i16::MIN;
+
+/// The static evaluation of a position, short of its endgame knowledge: the
+/// material score, and the king safety score if the position has needed it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CachedEval {
+    pub material: i16,
+    pub king_safety: Option<i16>,
+}
+
+/// Static evaluations by position hash, since the search scores the same
+/// positions again and again: through transpositions, and in each iteration
+/// of iterative deepening. An entry is one atomic word, holding the upper half
+/// of the hash and both scores, so the search's threads share the cache
+/// without locking it, and an entry is never read half-written. Entries
+/// aren't allocated until the first evaluation is stored.
+#[derive(Debug, Default)]
+pub struct EvalCache {
+    entries: OnceLock<Box<[AtomicU64]>>,
+}
+
+impl EvalCache {
+    pub fn probe(&self, position_hash: u64) -> Option<CachedEval> {
+        let entry = self.entries.get()?[index(position_hash)].load(Ordering::Relaxed);
+        if entry >> 32 != key(position_hash) {
+            return None;
+        }
+        let king_safety = (entry >> 16) as u16 as i16;
+        Some(CachedEval {
+            material: entry as u16 as i16,
+            king_safety: (king_safety != NO_KING_SAFETY).then_some(king_safety),
+        })
+    }
+
+    pub fn store(&self, position_hash: u64, eval: CachedEval) {
+        let entries = self
+            .entries
+            .get_or_init(|| (0..ENTRIES).map(|_| AtomicU64::new(0)).collect());
+        let king_safety = eval.king_safety.unwrap_or(NO_KING_SAFETY);
+        let entry = key(position_hash) << 32
+            | (king_safety as u16 as u64) << 16
+            | eval.material as u16 as u64;
+        entries[index(position_hash)].store(entry, Ordering::Relaxed);
+    }
+
+    /// Forgets every evaluation, e.g. once the evaluation terms have changed.
+    pub fn clear(&self) {
+        if let Some(entries) = self.entries.get() {
+            for entry in entries.iter() {
+                entry.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn index(position_hash: u64) -> usize {
+    position_hash as usize % ENTRIES
+}
+
+/// The upper half of the hash, with its lowest bit set so that an empty entry
+/// never matches.
+fn key(position_hash: u64) -> u64 {
+    position_hash >> 32 | 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_and_store() {
+        let cache = EvalCache::default();
+        let hash = 0x1234_5678_9abc_def0;
+        assert_eq!(cache.probe(hash), None);
+
+        let eval = CachedEval {
+            material: -250,
+            king_safety: None,
+        };
+        cache.store(hash, eval);
+        assert_eq!(cache.probe(hash), Some(eval));
+        // Another position in the same entry doesn't match.
+        assert_eq!(cache.probe(hash ^ 1 << 40), None);
+
+        let eval = CachedEval {
+            material: 37,
+            king_safety: Some(-12),
+        };
+        cache.store(hash, eval);
+        assert_eq!(cache.probe(hash), Some(eval));
+
+        cache.clear();
+        assert_eq!(cache.probe(hash), None);
+        assert_eq!(cache.probe(0), None);
+    }
+}
@@ -2,18 +2,6 @@
 /// [pawn, knight, bishop, rook, queen, king]
 pub const MATERIAL_VALUES: [i16; 6] = [100, 320, 330, 500, 900, 20000];
 
-/// [midgame, endgame] tables for
-/// [pawn, knight, bishop, rook, queen, king]
-#[rustfmt::skip]
-pub const BONUS_TABLES: [[[i16; 64]; 2]; 6] = [
-    [PAWN_BONUSES,          PAWN_BONUSES],
-    [KNIGHT_BONUSES,        KNIGHT_BONUSES],
-    [BISHOP_BONUSES,        BISHOP_BONUSES],
-    [ROOK_BONUSES,          ROOK_BONUSES],
-    [QUEEN_BONUSES,         QUEEN_BONUSES],
-    [KING_MIDGAME_BONUSES,  KING_ENDGAME_BONUSES],
-];
-
 #[rustfmt::skip]
 pub const SQUARE_TO_WHITE_BONUS_INDEX: [usize; 64] = [
     56, 57, 58, 59, 60, 61, 62, 63,
@@ -26,16 +14,19 @@ pub const SQUARE_TO_WHITE_BONUS_INDEX: [usize; 64] = [
      0,  1,  2,  3,  4,  5,  6,  7,
 ];
 
+/// Black sees the tables from its own side of the board: flipped top to
+/// bottom, but not side to side, so that a black piece on d8 gets the bonus a
+/// white piece gets on d1.
 #[rustfmt::skip]
 pub const SQUARE_TO_BLACK_BONUS_INDEX: [usize; 64] = [
-     7,  6,  5,  4,  3,  2,  1,  0,
-    15, 14, 13, 12, 11, 10,  9,  8,
-    23, 22, 21, 20, 19, 18, 17, 16,
-    31, 30, 29, 28, 27, 26, 25, 24,
-    39, 38, 37, 36, 35, 34, 33, 32,
-    47, 46, 45, 44, 43, 42, 41, 40,
-    55, 54, 53, 52, 51, 50, 49, 48,
-    63, 62, 61, 60, 59, 58, 57, 56,
+     0,  1,  2,  3,  4,  5,  6,  7,
+     8,  9, 10, 11, 12, 13, 14, 15,
+    16, 17, 18, 19, 20, 21, 22, 23,
+    24, 25, 26, 27, 28, 29, 30, 31,
+    32, 33, 34, 35, 36, 37, 38, 39,
+    40, 41, 42, 43, 44, 45, 46, 47,
+    48, 49, 50, 51, 52, 53, 54, 55,
+    56, 57, 58, 59, 60, 61, 62, 63,
 ];
 
 #[rustfmt::skip]
@@ -0,0 +1,117 @@
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+
+use super::weights::EvaluationWeights;
+
+/// The most a king's exposure can cost, so that the term never outweighs the
+/// material it takes to mount the attack.
+pub const MAX_KING_DANGER: i16 = 500;
+
+/// How much each attack on a square around the king counts toward its danger,
+/// by the attacking piece. Pawns and the king don't mount attacks on their own.
+/// [pawn, knight, bishop, rook, queen, king]
+pub const KING_ATTACK_WEIGHTS: [i16; 6] = [0, 2, 2, 3, 5, 0];
+
+/// The penalty, in centipawns, for the enemy pieces attacking the squares
+/// around `color`'s king. Each attack adds its piece's weight in attack units,
+/// and the penalty grows with the square of the units, since attackers that
+/// coordinate are far more dangerous than the sum of their parts. A lone
+/// attacker is no threat.
+pub fn king_safety_penalty(
+    board: &Board,
+    move_generator: &MoveGenerator,
+    color: Color,
+    weights: &EvaluationWeights,
+) -> i16 {
+    let king = board.pieces(color).locate(Piece::King);
+    if king.is_empty() {
+        return 0;
+    }
+    let zone = move_generator.get_king_zone(king);
+
+    let mut attackers = 0;
+    let mut attack_units = 0;
+    for (square, targets) in move_generator.get_piece_attack_targets(board, color.opposite()) {
+        let attacks = (targets & zone).count_ones() as i16;
+        let weight = match board.get(square) {
+            Some((piece, _)) => weights.king_attack_weights[piece as usize],
+            None => 0,
+        };
+        if attacks == 0 || weight == 0 {
+            continue;
+        }
+        attackers += 1;
+        attack_units += attacks * weight;
+    }
+
+    if attackers < 2 {
+        return 0;
+    }
+    (attack_units * attack_units / 4).min(weights.max_king_danger)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_position;
+    use common::bitboard::bitboard::Bitboard;
+
+    #[test]
+    fn test_starting_position_is_safe() {
+        let board = Board::starting_position();
+        let move_generator = MoveGenerator::new();
+        let weights = EvaluationWeights::default();
+        assert_eq!(
+            king_safety_penalty(&board, &move_generator, Color::White, &weights),
+            0
+        );
+        assert_eq!(
+            king_safety_penalty(&board, &move_generator, Color::Black, &weights),
+            0
+        );
+    }
+
+    #[test]
+    fn test_coordinated_attackers() {
+        let move_generator = MoveGenerator::new();
+        let weights = EvaluationWeights::default();
+
+        let board = chess_position! {
+            ......k.
+            .....ppp
+            .......N
+            ........
+            ........
+            ........
+            ........
+            ......K.
+        };
+        // The knight attacks f7 and g8, but alone it's no threat.
+        assert_eq!(
+            king_safety_penalty(&board, &move_generator, Color::Black, &weights),
+            0
+        );
+
+        let board = chess_position! {
+            ......k.
+            .....ppp
+            .......N
+            ........
+            ........
+            ...Q....
+            ........
+            ......K.
+        };
+        // With the queen attacking h7 too, that's 2 * 2 + 5 = 9 attack units.
+        assert_eq!(
+            king_safety_penalty(&board, &move_generator, Color::Black, &weights),
+            9 * 9 / 4
+        );
+        assert_eq!(
+            king_safety_penalty(&board, &move_generator, Color::White, &weights),
+            0
+        );
+    }
+}
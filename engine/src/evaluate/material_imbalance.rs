@@ -0,0 +1,115 @@
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+
+use super::pawn_structure::knight_outposts;
+use super::weights::EvaluationWeights;
+
+/// For having both bishops, which together cover squares of both colors.
+pub const BISHOP_PAIR_BONUS: i16 = 30;
+/// With this many pawns on the board, knights and bishops are worth the same.
+const BALANCED_PAWN_COUNT: i16 = 12;
+/// For each pawn above `BALANCED_PAWN_COUNT`, a knight gains this much on a
+/// bishop, since pawns block the bishop's diagonals but not the knight's jumps.
+/// Below it, as the position opens up, the bishop gains on the knight.
+pub const KNIGHT_PAWN_ADJUSTMENT: i16 = 3;
+
+/// For each knight on an outpost, where it can't be driven away by a pawn.
+pub const KNIGHT_OUTPOST_BONUS: i16 = 20;
+
+/// What the minor piece weights apply to, for one player: whether they have the
+/// bishop pair, their knights' surplus over their bishops, times the pawns on
+/// the board beyond `BALANCED_PAWN_COUNT`, and how many of their knights are on
+/// outposts.
+pub struct MinorPieceFeatures {
+    pub bishop_pair: i16,
+    pub knight_pawn_factor: i16,
+    pub knight_outposts: i16,
+}
+
+pub fn minor_piece_features(board: &Board, color: Color) -> MinorPieceFeatures {
+    let pieces = board.pieces(color);
+    let bishops = pieces.locate(Piece::Bishop).count_ones() as i16;
+    let knights = pieces.locate(Piece::Knight).count_ones() as i16;
+    let pawns = (board.pieces(Color::White).locate(Piece::Pawn)
+        | board.pieces(Color::Black).locate(Piece::Pawn))
+    .count_ones() as i16;
+
+    MinorPieceFeatures {
+        bishop_pair: (bishops >= 2) as i16,
+        knight_pawn_factor: (knights - bishops) * (pawns - BALANCED_PAWN_COUNT),
+        knight_outposts: knight_outposts(board, color),
+    }
+}
+
+/// The bonus, in centipawns, for how `color`'s minor pieces suit the position:
+/// the bishop pair, knights or bishops depending on how closed it is, and
+/// knights on outposts.
+pub fn material_imbalance_bonus(board: &Board, color: Color, weights: &EvaluationWeights) -> i16 {
+    let features = minor_piece_features(board, color);
+    features.bishop_pair * weights.bishop_pair_bonus
+        + features.knight_pawn_factor * weights.knight_pawn_adjustment
+        + features.knight_outposts * weights.knight_outpost_bonus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_position;
+    use common::bitboard::bitboard::Bitboard;
+
+    #[test]
+    fn test_bishop_pair() {
+        let weights = EvaluationWeights::default();
+        let board = Board::starting_position();
+        assert_eq!(
+            material_imbalance_bonus(&board, Color::White, &weights),
+            BISHOP_PAIR_BONUS
+        );
+
+        let board = chess_position! {
+            ..b.k...
+            ........
+            ........
+            ........
+            ........
+            ........
+            ........
+            ..B.KB..
+        };
+        // No pawns, so each bishop also gains on the missing knights.
+        assert_eq!(
+            material_imbalance_bonus(&board, Color::White, &weights),
+            BISHOP_PAIR_BONUS + 2 * BALANCED_PAWN_COUNT * KNIGHT_PAWN_ADJUSTMENT
+        );
+        assert_eq!(
+            material_imbalance_bonus(&board, Color::Black, &weights),
+            BALANCED_PAWN_COUNT * KNIGHT_PAWN_ADJUSTMENT
+        );
+    }
+
+    #[test]
+    fn test_knight_in_closed_position() {
+        let weights = EvaluationWeights::default();
+        let board = chess_position! {
+            ....k...
+            pppppppp
+            ..b.....
+            ........
+            ........
+            ..N.....
+            PPPPPPPP
+            ....K...
+        };
+        // 16 pawns: the knight is worth more than the bishop.
+        let bonus = 4 * KNIGHT_PAWN_ADJUSTMENT;
+        assert_eq!(
+            material_imbalance_bonus(&board, Color::White, &weights),
+            bonus
+        );
+        assert_eq!(
+            material_imbalance_bonus(&board, Color::Black, &weights),
+            -bonus
+        );
+    }
+}
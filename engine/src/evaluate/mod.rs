@@ -0,0 +1,847 @@
+use std::sync::{Arc, OnceLock};
+
+use tracing::trace_span;
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::piece_square_values::{PieceSquareScore, PieceSquareValues};
+use crate::board::Board;
+use crate::move_generator::{player_is_in_check, MoveGenerator};
+
+use self::endgame::endgame_score;
+use self::eval_cache::{CachedEval, EvalCache};
+use self::king_safety::king_safety_penalty;
+use self::material_imbalance::material_imbalance_bonus;
+use self::pawn_structure::{passed_pawn_bonus, pawn_structure_penalty};
+use self::weights::EvaluationWeights;
+
+use self::evaluation_tables::MATERIAL_VALUES;
+
+pub mod corpus;
+mod endgame;
+pub mod eval_cache;
+mod evaluation_tables;
+mod king_safety;
+mod material_imbalance;
+#[cfg(feature = "nnue")]
+pub mod nnue;
+mod pawn_structure;
+pub mod sparring;
+pub mod symmetry;
+pub mod tune;
+pub mod weights;
+
+/// The score of a checkmate on the board, from the winner's perspective. It's
+/// significantly larger than any possible material value, and therefore will
+/// incentivize the engine to select for (or against) their own (or the
+/// opponent's) win condition. A checkmate `ply` plies into a search scores
+/// `MATE - ply`, so the engine prefers the shortest mate, and when it is being
+/// mated, the longest defense.
+pub const MATE: i16 = i16::MAX / 2;
+/// No search goes deeper than this, so any score within this many plies of
+/// `MATE` is a checkmate.
+const MAX_MATE_PLY: i16 = u8::MAX as i16 + 1;
+const BLACK_WINS: i16 = -MATE;
+const WHITE_WINS: i16 = MATE;
+/// For having the move, which is worth something in all but a few positions
+/// (zugzwang).
+pub const TEMPO_BONUS: i16 = 10;
+
+/// Toggles for the individual terms of the evaluation, and the weights they're
+/// scored with. Everything is enabled by default; sparring profiles disable or
+/// perturb terms to give the engine deliberate weaknesses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluationTerms {
+    /// Piece placement bonuses outside of the endgame.
+    pub middlegame_bonuses: bool,
+    /// Piece placement bonuses in the endgame.
+    pub endgame_bonuses: bool,
+    /// The king's middlegame placement bonus, which rewards keeping it sheltered,
+    /// and the middlegame penalty for enemy pieces attacking the squares around it.
+    pub king_safety: bool,
+    /// Penalties for doubled, isolated, and backward pawns, and bonuses for
+    /// passed pawns.
+    pub pawn_structure: bool,
+    /// Exact or near-exact scores for basic endings: king and pawn against
+    /// king, and a lone king against a rook or queen.
+    pub endgame_knowledge: bool,
+    /// When nonzero, each position's score is offset by a pseudo-random amount
+    /// of up to this many centipawns in either direction.
+    pub noise: i16,
+    /// The score of a stalemate or a draw by the rules, from white's
+    /// perspective. Even, unless the search has contempt (see
+    /// `SearchContext::set_contempt`).
+    pub draw_score: i16,
+    pub weights: Arc<EvaluationWeights>,
+}
+
+impl Default for EvaluationTerms {
+    fn default() -> Self {
+        Self {
+            middlegame_bonuses: true,
+            endgame_bonuses: true,
+            king_safety: true,
+            pawn_structure: true,
+            endgame_knowledge: true,
+            noise: 0,
+            draw_score: 0,
+            weights: Arc::new(EvaluationWeights::default()),
+        }
+    }
+}
+
+/// The default terms, shared so that scoring a position doesn't build a fresh
+/// copy of the weights each time.
+fn default_terms() -> &'static EvaluationTerms {
+    static DEFAULT_TERMS: OnceLock<EvaluationTerms> = OnceLock::new();
+    DEFAULT_TERMS.get_or_init(EvaluationTerms::default)
+}
+
+/// How a game has ended. A draw is either by an automatic rule (fivefold
+/// repetition, or the seventy-five-move rule) or claimed (see `DrawClaim`).
+#[derive(Debug)]
+pub enum GameEnding {
+    Checkmate,
+    Stalemate,
+    Draw,
+}
+
+/// A draw that either player may claim, but that doesn't end the game until
+/// one of them does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawClaim {
+    ThreefoldRepetition,
+    FiftyMoveRule,
+}
+
+impl std::fmt::Display for DrawClaim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawClaim::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawClaim::FiftyMoveRule => write!(f, "the fifty-move rule"),
+        }
+    }
+}
+
+/// A draw can be claimed once a position has been seen this many times, and
+/// the game is drawn automatically once it's been seen `AUTOMATIC_REPETITIONS`
+/// times.
+const CLAIMABLE_REPETITIONS: u8 = 3;
+const AUTOMATIC_REPETITIONS: u8 = 5;
+/// Likewise, once this many plies have been played without a capture or a
+/// pawn move: fifty moves each, then seventy-five.
+const CLAIMABLE_HALFMOVE_CLOCK: u16 = 100;
+const AUTOMATIC_HALFMOVE_CLOCK: u16 = 150;
+
+/// The draw that can be claimed in the position, if any.
+#[inline(always)]
+pub fn claimable_draw(board: &Board) -> Option<DrawClaim> {
+    if board.max_seen_position_count() >= CLAIMABLE_REPETITIONS {
+        Some(DrawClaim::ThreefoldRepetition)
+    } else if board.halfmove_clock() >= CLAIMABLE_HALFMOVE_CLOCK {
+        Some(DrawClaim::FiftyMoveRule)
+    } else {
+        None
+    }
+}
+
+#[inline(always)]
+pub fn current_player_is_in_check(board: &Board, move_generator: &mut MoveGenerator) -> bool {
+    let current_player = board.turn();
+    player_is_in_check(board, move_generator, current_player)
+}
+
+/// Returns the game ending state if the game has ended, otherwise returns None.
+/// Only the automatic draws end a game here; a claimable one goes on until a
+/// player claims it (see `claimable_draw`).
+#[inline(always)]
+pub fn game_ending(
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    current_turn: Color,
+) -> Option<GameEnding> {
+    let candidates = move_generator.generate_moves(board, current_turn);
+    let check = current_player_is_in_check(board, move_generator);
+
+    if candidates.is_empty() {
+        if check {
+            return Some(GameEnding::Checkmate);
+        } else {
+            return Some(GameEnding::Stalemate);
+        }
+    }
+
+    // A checkmate on the move that reaches either limit still stands.
+    if board.max_seen_position_count() >= AUTOMATIC_REPETITIONS
+        || board.halfmove_clock() >= AUTOMATIC_HALFMOVE_CLOCK
+    {
+        return Some(GameEnding::Draw);
+    }
+
+    None
+}
+
+/// Returns the score of the board from white's perspective, for a position `ply`
+/// plies into a search (which only matters when it's checkmate).
+#[inline(always)]
+pub fn score(
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    current_turn: Color,
+    ply: u8,
+) -> i16 {
+    score_with_terms(board, move_generator, current_turn, ply, default_terms())
+}
+
+/// Like `score`, but only counts the enabled evaluation terms.
+#[inline(always)]
+pub fn score_with_terms(
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    current_turn: Color,
+    ply: u8,
+    terms: &EvaluationTerms,
+) -> i16 {
+    score_in_window(
+        board,
+        move_generator,
+        current_turn,
+        ply,
+        terms,
+        i16::MIN,
+        i16::MAX,
+        None,
+    )
+}
+
+/// Like `score_with_terms`, for a search that only needs to know how the score
+/// compares with the window between `alpha` and `beta`. The cheap terms are
+/// scored first, and when they put the score so far outside the window that
+/// the expensive ones couldn't bring it back, the expensive ones are skipped.
+/// A score outside the window is then only a bound, but it's on the same side
+/// of the window as the exact score, so the search's result is the same. With
+/// a cache, the terms already scored for the position are looked up rather
+/// than scored again, and those scored here are stored.
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+pub fn score_in_window(
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    current_turn: Color,
+    ply: u8,
+    terms: &EvaluationTerms,
+    alpha: i16,
+    beta: i16,
+    cache: Option<&EvalCache>,
+) -> i16 {
+    let _span = trace_span!("eval").entered();
+    // Whichever player would be worse off playing on claims the draw, so the
+    // search scores it as drawn.
+    if claimable_draw(board).is_some() {
+        return terms.draw_score;
+    }
+    score_playing_on_in_window(
+        board,
+        move_generator,
+        current_turn,
+        ply,
+        terms,
+        alpha,
+        beta,
+        cache,
+    )
+}
+
+/// Like `score_with_terms`, but scores a position with a claimable draw as if
+/// neither player claimed it, e.g. to decide whether to claim it.
+pub fn score_playing_on(
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    current_turn: Color,
+    terms: &EvaluationTerms,
+) -> i16 {
+    score_playing_on_in_window(
+        board,
+        move_generator,
+        current_turn,
+        0,
+        terms,
+        i16::MIN,
+        i16::MAX,
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+fn score_playing_on_in_window(
+    board: &mut Board,
+    move_generator: &mut MoveGenerator,
+    current_turn: Color,
+    ply: u8,
+    terms: &EvaluationTerms,
+    alpha: i16,
+    beta: i16,
+    cache: Option<&EvalCache>,
+) -> i16 {
+    match game_ending(board, move_generator, current_turn) {
+        Some(GameEnding::Checkmate) => {
+            if current_turn == Color::White {
+                // Black wins, but sooner is better for Black
+                BLACK_WINS + ply as i16
+            } else {
+                // White wins, but sooner is better for White
+                WHITE_WINS - ply as i16
+            }
+        }
+        Some(GameEnding::Stalemate) | Some(GameEnding::Draw) => terms.draw_score,
+        _ => {
+            if terms.endgame_knowledge {
+                if let Some(score) = endgame_score(board) {
+                    return score;
+                }
+            }
+            #[cfg(feature = "nnue")]
+            if let Some(score) = board.nnue_score() {
+                return score;
+            }
+            let position_hash = board.current_position_hash();
+            let cached = cache.and_then(|cache| cache.probe(position_hash));
+            let score = match cached {
+                Some(cached) => cached.material,
+                None => {
+                    // From here on, the board keeps the totals of the pieces'
+                    // values as the search moves them, rather than each
+                    // position adding them up square by square.
+                    if board
+                        .piece_square_score(Color::White, &terms.weights)
+                        .is_none()
+                    {
+                        let weights: Arc<dyn PieceSquareValues> = terms.weights.clone();
+                        board.set_piece_square_values(Some(weights));
+                    }
+                    board_material_score_with_terms(board, terms)
+                }
+            };
+            // King safety is the only term left, and it can't move the score by
+            // more than either king's maximum danger.
+            let margin = terms.weights.max_king_danger;
+            let king_safety =
+                if score.saturating_add(margin) <= alpha || score.saturating_sub(margin) >= beta {
+                    None
+                } else {
+                    Some(match cached.and_then(|cached| cached.king_safety) {
+                        Some(king_safety) => king_safety,
+                        None => king_safety_score(board, move_generator, terms),
+                    })
+                };
+            let newly_scored = match cached {
+                Some(cached) => cached.king_safety.is_none() && king_safety.is_some(),
+                None => true,
+            };
+            if let (Some(cache), true) = (cache, newly_scored) {
+                let eval = CachedEval {
+                    material: score,
+                    king_safety,
+                };
+                cache.store(position_hash, eval);
+            }
+            score + king_safety.unwrap_or(0)
+        }
+    }
+}
+
+/// The difference between the players' king safety penalties, from white's
+/// perspective. Kings are only hunted in the middlegame; in the endgame, the
+/// king belongs in the fight.
+#[inline(always)]
+fn king_safety_score(
+    board: &Board,
+    move_generator: &MoveGenerator,
+    terms: &EvaluationTerms,
+) -> i16 {
+    if !terms.king_safety || is_endgame(board) {
+        return 0;
+    }
+    king_safety_penalty(board, move_generator, Color::Black, &terms.weights)
+        - king_safety_penalty(board, move_generator, Color::White, &terms.weights)
+}
+
+#[inline(always)]
+/// The value of a piece in centipawns, not counting any positional bonus.
+pub fn material_value(piece: Piece) -> i16 {
+    MATERIAL_VALUES[piece as usize]
+}
+
+pub fn board_material_score(board: &Board) -> i16 {
+    board_material_score_with_terms(board, default_terms())
+}
+
+#[inline(always)]
+pub(crate) fn board_material_score_with_terms(board: &Board, terms: &EvaluationTerms) -> i16 {
+    let white_material = player_material_score(board, Color::White, terms);
+    let black_material = player_material_score(board, Color::Black, terms);
+    let pawn_structure = if terms.pawn_structure {
+        pawn_structure_penalty(board, Color::Black, &terms.weights)
+            - pawn_structure_penalty(board, Color::White, &terms.weights)
+            + passed_pawn_bonus(board, Color::White, &terms.weights)
+            - passed_pawn_bonus(board, Color::Black, &terms.weights)
+    } else {
+        0
+    };
+    let tempo = match board.turn() {
+        Color::White => terms.weights.tempo_bonus,
+        Color::Black => -terms.weights.tempo_bonus,
+    };
+    let noise = match terms.noise {
+        0 => 0,
+        // The position hash is a cheap, repeatable source of noise, so the same
+        // position always gets the same offset.
+        noise => {
+            let range = 2 * noise as u64 + 1;
+            (board.current_position_hash() % range) as i16 - noise
+        }
+    };
+    white_material - black_material + pawn_structure + tempo + noise
+}
+
+/// Returns the material score of the board for the given player. The bonus tables
+/// incentivize the placement of pieces on specific parts of the board (e.g.
+/// knights towards the center, bishops on long diagonals, etc.). Minor pieces
+/// are also valued by how well they suit the position.
+#[inline(always)]
+fn player_material_score(board: &Board, color: Color, terms: &EvaluationTerms) -> i16 {
+    let weights = &terms.weights;
+    let is_endgame = is_endgame(board);
+    let bonuses_enabled = if is_endgame {
+        terms.endgame_bonuses
+    } else {
+        terms.middlegame_bonuses
+    };
+    let score = match board.piece_square_score(color, weights) {
+        Some(score) => score,
+        None => add_up_piece_square_score(board, color, weights),
+    };
+
+    let mut material = material_imbalance_bonus(board, color, weights) + score.material;
+    if !bonuses_enabled {
+        return material;
+    }
+    if is_endgame {
+        material += score.endgame_bonuses;
+    } else {
+        material += score.middlegame_bonuses;
+        if !terms.king_safety {
+            let king = board.pieces(color).locate(Piece::King);
+            if !king.is_empty() {
+                material -= weights.bonuses(Piece::King, color, king).0;
+            }
+        }
+    }
+    material
+}
+
+/// The player's totals of their pieces' values, added up square by square, for
+/// a board that doesn't keep them with `weights` (see
+/// `Board::set_piece_square_values`).
+fn add_up_piece_square_score(
+    board: &Board,
+    color: Color,
+    weights: &EvaluationWeights,
+) -> PieceSquareScore {
+    let mut score = PieceSquareScore::default();
+    for (square, piece, piece_color) in board.iter() {
+        if piece_color != color {
+            continue;
+        }
+        score.add(weights, piece, color, square);
+    }
+    score
+}
+
+/// Slope of the logistic curve mapping centipawns to expected score. This is the
+/// calibration Lichess fits against its game database, so a score of +100 maps
+/// to roughly a 59% expected score.
+pub(crate) const WIN_PROBABILITY_SLOPE: f64 = 0.00368208;
+
+/// Converts a centipawn score (from white's perspective) into white's expected
+/// score, between 0.0 (black wins) and 1.0 (white wins), using a logistic model.
+/// Draws count as half a point, so a level position maps to 0.5.
+pub fn win_probability(score: i16) -> f64 {
+    1.0 / (1.0 + (-WIN_PROBABILITY_SLOPE * score as f64).exp())
+}
+
+/// Whether the score is a forced win for one side, rather than a material evaluation.
+pub fn is_checkmate_score(score: i16) -> bool {
+    score >= WHITE_WINS - MAX_MATE_PLY || score <= BLACK_WINS + MAX_MATE_PLY
+}
+
+/// For a checkmate score, returns how many plies from the root of the search
+/// the checkmate occurs.
+pub fn checkmate_distance(score: i16) -> Option<u8> {
+    if !is_checkmate_score(score) {
+        return None;
+    }
+    Some((MATE - score.saturating_abs()).max(0) as u8)
+}
+
+/// For a checkmate score, returns how many moves away the checkmate is: positive
+/// when the side the score favors delivers it, e.g. 1 for a mate on this move.
+pub fn mate_in(score: i16) -> Option<i16> {
+    let moves = (checkmate_distance(score)? as i16 + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+/// Formats a score from white's perspective, in centipawns, or as e.g. "mate in
+/// 3 (black)" when it is a checkmate.
+pub fn format_score(score: i16) -> String {
+    match mate_in(score) {
+        Some(moves) => {
+            let winner = if moves > 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            format!("mate in {} ({})", moves.abs(), winner)
+        }
+        None => score.to_string(),
+    }
+}
+
+/// Endgame conditions:
+/// 1. Both sides have no queens or
+/// 2. Every side which has a queen has additionally no other pieces or one minorpiece maximum.
+#[inline(always)]
+pub fn is_endgame(board: &Board) -> bool {
+    let white_queen = board.pieces(Color::White).locate(Piece::Queen);
+    let black_queen = board.pieces(Color::Black).locate(Piece::Queen);
+    let white_king = board.pieces(Color::White).locate(Piece::King);
+    let black_king = board.pieces(Color::Black).locate(Piece::King);
+
+    let white_non_queen_pieces = board.pieces(Color::White).occupied() & !white_queen & !white_king;
+    let black_non_queen_pieces = board.pieces(Color::Black).occupied() & !black_queen & !black_king;
+    let white_minor_pieces = white_non_queen_pieces & !white_king;
+    let black_minor_pieces = black_non_queen_pieces & !black_king;
+
+    let both_sides_have_no_queens = white_queen.is_empty() && black_queen.is_empty();
+    let white_has_no_queen_or_one_minor_piece =
+        white_queen.is_empty() && white_minor_pieces.count_ones() <= 1;
+    let black_has_no_queen_or_one_minor_piece =
+        black_queen.is_empty() && black_minor_pieces.count_ones() <= 1;
+
+    both_sides_have_no_queens
+        || (white_has_no_queen_or_one_minor_piece && black_has_no_queen_or_one_minor_piece)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate::king_safety::MAX_KING_DANGER;
+    use crate::evaluate::sparring::SparringProfile;
+    use crate::{
+        board::{castle_rights_bitmask::ALL_CASTLE_RIGHTS, Board},
+        chess_position,
+    };
+    use common::bitboard::bitboard::Bitboard;
+    use common::bitboard::square::*;
+
+    #[test]
+    fn test_starting_player_material_score() {
+        let board = Board::starting_position();
+        println!("Testing board:\n{}", board);
+
+        let white_score = player_material_score(&board, Color::White, &EvaluationTerms::default());
+        assert_eq!(white_score, 23935);
+
+        let black_score = player_material_score(&board, Color::Black, &EvaluationTerms::default());
+        assert_eq!(black_score, 23935);
+    }
+
+    #[test]
+    fn test_win_probability() {
+        assert_eq!(win_probability(0), 0.5);
+        assert!((win_probability(100) - 0.591).abs() < 0.001);
+        assert!((win_probability(100) + win_probability(-100) - 1.0).abs() < f64::EPSILON);
+        assert!(win_probability(WHITE_WINS) > 0.999);
+        assert!(win_probability(BLACK_WINS) < 0.001);
+    }
+
+    #[test]
+    fn test_mate_scores() {
+        assert_eq!(checkmate_distance(MATE - 3), Some(3));
+        assert_eq!(checkmate_distance(-MATE + 4), Some(4));
+        assert_eq!(checkmate_distance(900), None);
+
+        assert_eq!(mate_in(MATE - 1), Some(1));
+        assert_eq!(mate_in(MATE - 3), Some(2));
+        assert_eq!(mate_in(-MATE + 4), Some(-2));
+        assert_eq!(mate_in(-250), None);
+
+        assert_eq!(format_score(MATE - 5), "mate in 3 (white)");
+        assert_eq!(format_score(-MATE + 2), "mate in 1 (black)");
+        assert_eq!(format_score(-250), "-250");
+        assert!(!is_checkmate_score(30_000 / 4));
+    }
+
+    #[test]
+    fn test_endgame_knowledge() {
+        // The defending king holds the rook pawn in the corner, though white is
+        // a pawn up.
+        let mut board = Board::from_fen("7k/8/8/8/8/8/7P/7K w - - 0 1").unwrap();
+        let mut move_generator = MoveGenerator::new();
+        assert_eq!(score(&mut board, &mut move_generator, Color::White, 0), 0);
+
+        let terms = SparringProfile::WeakEndgame.terms();
+        assert!(score_with_terms(&mut board, &mut move_generator, Color::White, 0, &terms) > 0);
+    }
+
+    #[test]
+    fn test_lazy_evaluation() {
+        // The knight and queen bear down on black's king.
+        let mut board = chess_position! {
+            ......k.
+            .....ppp
+            .......N
+            ........
+            ........
+            ...Q....
+            ........
+            ......K.
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        let mut move_generator = MoveGenerator::new();
+        let terms = EvaluationTerms::default();
+        let exact = score_with_terms(&mut board, &mut move_generator, Color::White, 0, &terms);
+        let material = board_material_score_with_terms(&board, &terms);
+        assert!(exact > material);
+
+        let mut score = |alpha, beta| {
+            score_in_window(
+                &mut board,
+                &mut move_generator,
+                Color::White,
+                0,
+                &terms,
+                alpha,
+                beta,
+                None,
+            )
+        };
+        // Within reach of the window, the king's danger counts.
+        assert_eq!(score(exact - 1, exact + 1), exact);
+        assert_eq!(score(exact + 100, exact + 200), exact);
+        // Too far below it to matter, it's skipped, but the score is still below.
+        let alpha = material + MAX_KING_DANGER;
+        assert_eq!(score(alpha, alpha + 1), material);
+        let beta = material - MAX_KING_DANGER;
+        assert_eq!(score(beta - 1, beta), material);
+    }
+
+    #[test]
+    fn test_kept_piece_square_scores() {
+        let mut move_generator = MoveGenerator::new();
+        let ignores_king_safety = SparringProfile::IgnoresKingSafety.terms();
+        for terms in [EvaluationTerms::default(), ignores_king_safety] {
+            let mut board = Board::from_fen(
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            )
+            .unwrap();
+            let added_up = board_material_score_with_terms(&board, &terms);
+            // Scoring the position starts the board keeping the totals.
+            score_with_terms(&mut board, &mut move_generator, Color::White, 0, &terms);
+            assert!(board
+                .piece_square_score(Color::White, &terms.weights)
+                .is_some());
+            assert_eq!(board_material_score_with_terms(&board, &terms), added_up);
+
+            // The totals follow the pieces as they move.
+            for chess_move in move_generator.generate_moves(&mut board, Color::White) {
+                chess_move.apply(&mut board).unwrap();
+                let fresh = Board::from_fen(&board.to_fen()).unwrap();
+                assert_eq!(
+                    board_material_score_with_terms(&board, &terms),
+                    board_material_score_with_terms(&fresh, &terms),
+                    "{}",
+                    chess_move
+                );
+                chess_move.undo(&mut board).unwrap();
+            }
+            assert_eq!(board_material_score_with_terms(&board, &terms), added_up);
+        }
+    }
+
+    #[test]
+    fn test_cached_evaluation() {
+        let mut board = chess_position! {
+            ......k.
+            .....ppp
+            .......N
+            ........
+            ........
+            ...Q....
+            ........
+            ......K.
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        let mut move_generator = MoveGenerator::new();
+        let terms = EvaluationTerms::default();
+        let exact = score_with_terms(&mut board, &mut move_generator, Color::White, 0, &terms);
+        let material = board_material_score_with_terms(&board, &terms);
+        let hash = board.current_position_hash();
+
+        let cache = EvalCache::default();
+        let mut score = |alpha, beta| {
+            score_in_window(
+                &mut board,
+                &mut move_generator,
+                Color::White,
+                0,
+                &terms,
+                alpha,
+                beta,
+                Some(&cache),
+            )
+        };
+        // A lazy evaluation caches only the material.
+        let alpha = material + MAX_KING_DANGER;
+        assert_eq!(score(alpha, alpha + 1), material);
+        let cached = CachedEval {
+            material,
+            king_safety: None,
+        };
+        assert_eq!(cache.probe(hash), Some(cached));
+        // A full one adds the king safety, and scores the same either way.
+        assert_eq!(score(i16::MIN, i16::MAX), exact);
+        assert_eq!(score(i16::MIN, i16::MAX), exact);
+        assert_eq!(score(alpha, alpha + 1), material);
+        let cached = CachedEval {
+            material,
+            king_safety: Some(exact - material),
+        };
+        assert_eq!(cache.probe(hash), Some(cached));
+    }
+
+    #[test]
+    fn test_game_ending_stalemate() {
+        let mut board = chess_position! {
+            .......k
+            ........
+            ........
+            ........
+            ........
+            ........
+            ........
+            K.......
+        };
+        board.set_turn(Color::Black);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        println!("Testing board:\n{}", board);
+
+        let ending = game_ending(&mut board, &mut MoveGenerator::new(), Color::Black);
+        matches!(ending, Some(GameEnding::Stalemate));
+    }
+
+    #[test]
+    fn test_game_ending_checkmate() {
+        let mut board = chess_position! {
+            .......k
+            .......q
+            ........
+            ........
+            ........
+            ........
+            ........
+            K.......
+        };
+        board.set_turn(Color::Black);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+        println!("Testing board:\n{}", board);
+
+        let ending = game_ending(&mut board, &mut MoveGenerator::new(), Color::Black);
+        matches!(ending, Some(GameEnding::Checkmate));
+    }
+
+    #[test]
+    fn test_game_ending_fifty_move_rule() {
+        let mut move_generator = MoveGenerator::new();
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 100 80").unwrap();
+        assert_eq!(claimable_draw(&board), Some(DrawClaim::FiftyMoveRule));
+        assert!(game_ending(&mut board, &mut move_generator, Color::White).is_none());
+
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 150 105").unwrap();
+        let ending = game_ending(&mut board, &mut move_generator, Color::White);
+        assert!(matches!(ending, Some(GameEnding::Draw)));
+    }
+
+    #[test]
+    fn test_is_endgame_one_minor_piece() {
+        let mut board = chess_position! {
+            .......k
+            .......b
+            ........
+            ...q....
+            ........
+            ........
+            ........
+            K.......
+        };
+        println!("Testing board:\n{}", board);
+
+        assert!(!is_endgame(&board));
+
+        board.remove(D5);
+        println!("Testing board:\n{}", board);
+        assert!(is_endgame(&board));
+    }
+
+    #[test]
+    fn test_is_endgame_both_sides_no_queens() {
+        let mut board = chess_position! {
+            .......k
+            .......q
+            ........
+            ...b....
+            ........
+            ........
+            .Q......
+            K.......
+        };
+        println!("Testing board:\n{}", board);
+
+        assert!(!is_endgame(&board));
+
+        board.remove(H7);
+        board.remove(B2);
+        println!("Testing board:\n{}", board);
+        assert!(is_endgame(&board));
+    }
+
+    #[test]
+    fn test_starting_position_is_not_endgame() {
+        let board = Board::starting_position();
+        assert!(!is_endgame(&board));
+    }
+
+    #[test]
+    fn test_pawn_material_bonus_on_final_rank() {
+        let board = chess_position! {
+            ........
+            .......P
+            ........
+            ........
+            ........
+            ........
+            .......p
+            ........
+        };
+        println!("Testing board:\n{}", board);
+
+        let white_score = player_material_score(&board, Color::White, &EvaluationTerms::default());
+        assert_eq!(white_score, 150);
+
+        let black_score = player_material_score(&board, Color::Black, &EvaluationTerms::default());
+        assert_eq!(black_score, 150);
+    }
+}
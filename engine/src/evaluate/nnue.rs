@@ -0,0 +1,136 @@
+//! Reads and writes networks for the NNUE evaluation. The network itself, and
+//! the accumulator the board keeps up to date with it, are in the core crate.
+//!
+//! The file format is little-endian throughout:
+//!
+//! ```text
+//! magic            b"CNUE"
+//! version          u32, 1
+//! hidden size      u32, H
+//! feature weights  i16 × 40960 × H, input by input
+//! feature biases   i16 × H
+//! output weights   i16 × 2H, for the side to move's half, then the other's
+//! output bias      i32
+//! ```
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use thiserror::Error;
+
+pub use chess_core::nnue::Network;
+use chess_core::nnue::INPUTS;
+
+const MAGIC: &[u8; 4] = b"CNUE";
+const VERSION: u32 = 1;
+/// Larger hidden layers are almost certainly a corrupt file, not a network.
+const MAX_HIDDEN: usize = 4096;
+
+#[derive(Error, Debug)]
+pub enum NnueError {
+    #[error("could not read the network: {error}")]
+    Io { error: String },
+    #[error("invalid network: {error}")]
+    InvalidNetwork { error: String },
+}
+
+pub fn load_network(path: &Path) -> Result<Network, NnueError> {
+    let bytes = fs::read(path).map_err(|error| NnueError::Io {
+        error: error.to_string(),
+    })?;
+    network_from_bytes(&bytes)
+}
+
+pub fn network_from_bytes(bytes: &[u8]) -> Result<Network, NnueError> {
+    let invalid = |error: &str| NnueError::InvalidNetwork {
+        error: error.to_string(),
+    };
+    if bytes.len() < 12 || &bytes[..4] != MAGIC {
+        return Err(invalid("missing header"));
+    }
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if version != VERSION {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+    let hidden = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+    if hidden == 0 || hidden > MAX_HIDDEN {
+        return Err(invalid(&format!("unsupported hidden size {}", hidden)));
+    }
+    let expected = 12 + 2 * (INPUTS * hidden + hidden + 2 * hidden) + 4;
+    if bytes.len() != expected {
+        return Err(invalid(&format!(
+            "expected {} bytes for a hidden size of {}, found {}",
+            expected,
+            hidden,
+            bytes.len()
+        )));
+    }
+
+    let mut offset = 12;
+    let mut read_i16s = |count: usize| {
+        let values = bytes[offset..offset + 2 * count]
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        offset += 2 * count;
+        values
+    };
+    let feature_weights = read_i16s(INPUTS * hidden);
+    let feature_biases = read_i16s(hidden);
+    let output_weights = read_i16s(2 * hidden);
+    let output_bias = i32::from_le_bytes(bytes[expected - 4..].try_into().unwrap());
+
+    Ok(Network::new(
+        hidden,
+        feature_weights,
+        feature_biases,
+        output_weights,
+        output_bias,
+    ))
+}
+
+pub fn network_to_bytes(network: &Network) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(VERSION.to_le_bytes());
+    bytes.extend((network.hidden() as u32).to_le_bytes());
+    for values in [
+        network.feature_weights(),
+        network.feature_biases(),
+        network.output_weights(),
+    ] {
+        bytes.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+    }
+    bytes.extend(network.output_bias().to_le_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_round_trip() {
+        let hidden = 4;
+        let arbitrary = |count: usize, seed: usize| {
+            (0..count)
+                .map(|i| ((i * 7919 + seed) % 61) as i16 - 30)
+                .collect::<Vec<_>>()
+        };
+        let network = Network::new(
+            hidden,
+            arbitrary(INPUTS * hidden, 1),
+            arbitrary(hidden, 2),
+            arbitrary(2 * hidden, 3),
+            100,
+        );
+        let bytes = network_to_bytes(&network);
+        assert_eq!(network_from_bytes(&bytes).unwrap(), network);
+
+        assert!(network_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(network_from_bytes(b"not a network").is_err());
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 2;
+        assert!(network_from_bytes(&wrong_version).is_err());
+    }
+}
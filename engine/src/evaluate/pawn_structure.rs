@@ -0,0 +1,404 @@
+use common::bitboard::bitboard::Bitboard;
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+
+use super::endgame::distance;
+use super::is_endgame;
+use super::weights::EvaluationWeights;
+
+const FILES: [Bitboard; 8] = [
+    Bitboard::A_FILE,
+    Bitboard::B_FILE,
+    Bitboard::C_FILE,
+    Bitboard::D_FILE,
+    Bitboard::E_FILE,
+    Bitboard::F_FILE,
+    Bitboard::G_FILE,
+    Bitboard::H_FILE,
+];
+
+/// For each pawn beyond the first on a file. Doubled pawns can't defend each
+/// other, and the one in front blocks the one behind.
+pub const DOUBLED_PAWN_PENALTY: i16 = 15;
+/// For each pawn with no pawns of its own on the files beside it, so that no
+/// pawn can ever defend it.
+pub const ISOLATED_PAWN_PENALTY: i16 = 15;
+/// For each pawn that has fallen behind the pawns beside it, and can't advance
+/// without being taken by a pawn.
+pub const BACKWARD_PAWN_PENALTY: i16 = 10;
+
+/// For each passed pawn, by its rank, counting from its side. A blockaded
+/// passed pawn, with an enemy piece in front of it, earns half. The bonuses
+/// are even, so that half of one is exact.
+pub const PASSED_PAWN_BONUSES: [i16; 8] = [0, 6, 10, 20, 36, 60, 100, 0];
+/// In the endgame, for each square closer the player's king is than the enemy
+/// king to the square in front of each of their passed pawns, since the king
+/// escorts the pawn in, and the enemy king is the last thing that can stop it.
+pub const PASSED_PAWN_KING_PROXIMITY: i16 = 5;
+
+/// How many of a player's pawns are doubled, isolated, and backward.
+#[derive(Debug, Default, PartialEq)]
+pub struct PawnWeaknesses {
+    pub doubled: i16,
+    pub isolated: i16,
+    pub backward: i16,
+}
+
+/// The penalty, in centipawns, for the doubled, isolated, and backward pawns in
+/// `color`'s pawn structure.
+pub fn pawn_structure_penalty(board: &Board, color: Color, weights: &EvaluationWeights) -> i16 {
+    let weaknesses = pawn_weaknesses(board, color);
+    weaknesses.doubled * weights.doubled_pawn_penalty
+        + weaknesses.isolated * weights.isolated_pawn_penalty
+        + weaknesses.backward * weights.backward_pawn_penalty
+}
+
+pub fn pawn_weaknesses(board: &Board, color: Color) -> PawnWeaknesses {
+    let pawns = board.pieces(color).locate(Piece::Pawn);
+    let enemy_pawn_attacks = pawn_attacks(
+        board.pieces(color.opposite()).locate(Piece::Pawn),
+        color.opposite(),
+    );
+
+    let mut weaknesses = PawnWeaknesses::default();
+    for (file, &file_mask) in FILES.iter().enumerate() {
+        let mut file_pawns = pawns & file_mask;
+        let count = file_pawns.count_ones() as i16;
+        if count == 0 {
+            continue;
+        }
+        weaknesses.doubled += count - 1;
+
+        let neighbors = pawns & adjacent_files(file);
+        if neighbors.is_empty() {
+            weaknesses.isolated += count;
+            continue;
+        }
+        while !file_pawns.is_empty() {
+            let pawn = file_pawns.pop_lsb();
+            if is_backward(pawn, neighbors, enemy_pawn_attacks, color) {
+                weaknesses.backward += 1;
+            }
+        }
+    }
+    weaknesses
+}
+
+/// How many of `color`'s knights are on outposts: squares on the fourth to
+/// sixth ranks, counting from the player's side, that one of their pawns
+/// defends and that no enemy pawn can ever attack, since there are none ahead
+/// of the knight on the files beside it.
+pub fn knight_outposts(board: &Board, color: Color) -> i16 {
+    let pawns = board.pieces(color).locate(Piece::Pawn);
+    let enemy_pawns = board.pieces(color.opposite()).locate(Piece::Pawn);
+    let outpost_ranks = match color {
+        Color::White => Bitboard::RANK_4 | Bitboard::RANK_5 | Bitboard::RANK_6,
+        Color::Black => Bitboard::RANK_3 | Bitboard::RANK_4 | Bitboard::RANK_5,
+    };
+    let mut knights =
+        board.pieces(color).locate(Piece::Knight) & outpost_ranks & pawn_attacks(pawns, color);
+
+    let mut outposts = 0;
+    while !knights.is_empty() {
+        let knight = knights.pop_lsb();
+        let square = knight.trailing_zeros() as usize;
+        let ahead = ranks_ahead(square / 8, color);
+        if !enemy_pawns.overlaps(adjacent_files(square % 8) & ahead) {
+            outposts += 1;
+        }
+    }
+    outposts
+}
+
+/// What the passed pawn weights apply to, for one player: their passed pawns
+/// by rank, counting from their side, in halves, since a blockaded one counts
+/// half; and, in the endgame, how much closer their king is than the enemy
+/// king to the squares in front of them.
+#[derive(Debug, Default, PartialEq)]
+pub struct PassedPawnFeatures {
+    pub half_counts_by_rank: [i16; 8],
+    pub king_proximity: i16,
+}
+
+/// The bonus, in centipawns, for `color`'s passed pawns.
+pub fn passed_pawn_bonus(board: &Board, color: Color, weights: &EvaluationWeights) -> i16 {
+    let features = passed_pawn_features(board, color);
+    let half_bonus: i16 = features
+        .half_counts_by_rank
+        .iter()
+        .zip(weights.passed_pawn_bonuses)
+        .map(|(&half_count, bonus)| half_count * bonus)
+        .sum();
+    half_bonus / 2 + features.king_proximity * weights.passed_pawn_king_proximity
+}
+
+/// Finds `color`'s passed pawns: those with no enemy pawns ahead of them on
+/// their file or the files beside it (their front span), and none of their own
+/// ahead on their file, so that only the front one of doubled pawns counts.
+pub fn passed_pawn_features(board: &Board, color: Color) -> PassedPawnFeatures {
+    let own_pawns = board.pieces(color).locate(Piece::Pawn);
+    let enemy_pawns = board.pieces(color.opposite()).locate(Piece::Pawn);
+    let enemies = board.pieces(color.opposite()).occupied();
+    let kings = [color, color.opposite()].map(|color| board.pieces(color).locate(Piece::King));
+    let kings_counted = kings.iter().all(|king| king.count_ones() == 1) && is_endgame(board);
+
+    let mut features = PassedPawnFeatures::default();
+    for pawn in board.pieces(color).iter_pieces(Piece::Pawn) {
+        let square = pawn.trailing_zeros() as usize;
+        let (file, rank) = (square % 8, square / 8);
+        let ahead = ranks_ahead(rank, color);
+        if enemy_pawns.overlaps((FILES[file] | adjacent_files(file)) & ahead)
+            || own_pawns.overlaps(FILES[file] & ahead)
+        {
+            continue;
+        }
+
+        let (relative_rank, stop_square) = match color {
+            Color::White => (rank, pawn << 8),
+            Color::Black => (7 - rank, pawn >> 8),
+        };
+        features.half_counts_by_rank[relative_rank] +=
+            if enemies.overlaps(stop_square) { 1 } else { 2 };
+        if kings_counted {
+            let stop_square = stop_square.trailing_zeros() as usize;
+            let [own_king, enemy_king] =
+                kings.map(|king| distance(king.trailing_zeros() as usize, stop_square));
+            features.king_proximity += enemy_king - own_king;
+        }
+    }
+    features
+}
+
+/// The ranks ahead of `rank`, from `color`'s side of the board.
+fn ranks_ahead(rank: usize, color: Color) -> Bitboard {
+    match color {
+        Color::White => Bitboard(u64::MAX.checked_shl(8 * (rank as u32 + 1)).unwrap_or(0)),
+        Color::Black => Bitboard(u64::MAX.checked_shr(8 * (8 - rank as u32)).unwrap_or(0)),
+    }
+}
+
+fn adjacent_files(file: usize) -> Bitboard {
+    let left = file
+        .checked_sub(1)
+        .map_or(Bitboard::EMPTY, |file| FILES[file]);
+    let right = FILES.get(file + 1).copied().unwrap_or(Bitboard::EMPTY);
+    left | right
+}
+
+/// The squares attacked by `pawns` of the given color.
+fn pawn_attacks(pawns: Bitboard, color: Color) -> Bitboard {
+    let west = pawns & !Bitboard::A_FILE;
+    let east = pawns & !Bitboard::H_FILE;
+    match color {
+        Color::White => (west << 7) | (east << 9),
+        Color::Black => (west >> 9) | (east >> 7),
+    }
+}
+
+/// Whether every pawn beside `pawn` has advanced past it, and the square in
+/// front of it is attacked by an enemy pawn.
+fn is_backward(
+    pawn: Bitboard,
+    neighbors: Bitboard,
+    enemy_pawn_attacks: Bitboard,
+    color: Color,
+) -> bool {
+    let rank = pawn.trailing_zeros() as usize / 8;
+    let (level_or_behind, stop_square) = match color {
+        Color::White => (Bitboard(u64::MAX >> (8 * (7 - rank))), pawn << 8),
+        Color::Black => (Bitboard(u64::MAX << (8 * rank)), pawn >> 8),
+    };
+    !neighbors.overlaps(level_or_behind) && stop_square.overlaps(enemy_pawn_attacks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_position;
+
+    #[test]
+    fn test_starting_position_has_no_weaknesses() {
+        let weights = EvaluationWeights::default();
+        let board = Board::starting_position();
+        assert_eq!(pawn_structure_penalty(&board, Color::White, &weights), 0);
+        assert_eq!(pawn_structure_penalty(&board, Color::Black, &weights), 0);
+    }
+
+    #[test]
+    fn test_doubled_and_isolated_pawns() {
+        let weights = EvaluationWeights::default();
+        let board = chess_position! {
+            ....k...
+            ........
+            ........
+            ........
+            ........
+            P.......
+            P....PP.
+            ....K...
+        };
+        // The a-pawns are doubled and isolated; the f- and g-pawns are neither.
+        assert_eq!(
+            pawn_structure_penalty(&board, Color::White, &weights),
+            DOUBLED_PAWN_PENALTY + 2 * ISOLATED_PAWN_PENALTY
+        );
+    }
+
+    #[test]
+    fn test_passed_pawns() {
+        let weights = EvaluationWeights::default();
+        let board = chess_position! {
+            ........
+            p.......
+            ...P....
+            ........
+            .....p..
+            ....P...
+            ....P...
+            ........
+        };
+        // d6 is passed. The front e-pawn has f4 ahead of it on the f-file, and
+        // the back one has its own pawn in front of it. Likewise, f4 has the
+        // e-pawns ahead of it, but a7 is passed, on Black's second rank.
+        let features = passed_pawn_features(&board, Color::White);
+        assert_eq!(features.half_counts_by_rank, [0, 0, 0, 0, 0, 2, 0, 0]);
+        assert_eq!(passed_pawn_bonus(&board, Color::White, &weights), 60);
+        assert_eq!(
+            passed_pawn_features(&board, Color::Black).half_counts_by_rank,
+            [0, 2, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(passed_pawn_bonus(&board, Color::Black, &weights), 6);
+
+        let board = chess_position! {
+            ........
+            ........
+            ...n....
+            ...P....
+            ........
+            ........
+            ........
+            ........
+        };
+        // Blockaded, and with no kings, nothing for their distance.
+        assert_eq!(passed_pawn_bonus(&board, Color::White, &weights), 18);
+    }
+
+    #[test]
+    fn test_passed_pawn_king_proximity() {
+        let weights = EvaluationWeights::default();
+        let board = chess_position! {
+            k.......
+            ........
+            ........
+            ...PK...
+            ........
+            ........
+            ........
+            ........
+        };
+        // The white king is next to d6, and the black king three moves away.
+        let features = passed_pawn_features(&board, Color::White);
+        assert_eq!(features.king_proximity, 3 - 1);
+        assert_eq!(
+            passed_pawn_bonus(&board, Color::White, &weights),
+            36 + 2 * PASSED_PAWN_KING_PROXIMITY
+        );
+    }
+
+    #[test]
+    fn test_knight_outposts() {
+        let board = chess_position! {
+            ....k...
+            .p...p..
+            ........
+            ...N....
+            ..P.P...
+            .....N..
+            ........
+            ....K...
+        };
+        // The f3 knight is too far back, and nothing defends it.
+        assert_eq!(knight_outposts(&board, Color::White), 1);
+
+        let board = chess_position! {
+            ....k...
+            .pp..p..
+            ........
+            ...N....
+            ..P.P...
+            ........
+            ........
+            ....K...
+        };
+        // ...c6 would drive the knight away.
+        assert_eq!(knight_outposts(&board, Color::White), 0);
+
+        let board = chess_position! {
+            ....k...
+            ........
+            ........
+            ........
+            ..p.P...
+            ...n....
+            ........
+            ....K...
+        };
+        // Black's outposts are counted from Black's side, where the e4 pawn is
+        // behind the knight.
+        assert_eq!(knight_outposts(&board, Color::Black), 1);
+    }
+
+    #[test]
+    fn test_backward_pawn() {
+        let weights = EvaluationWeights::default();
+        let board = chess_position! {
+            ....k...
+            ........
+            ...p....
+            ........
+            ..P.P...
+            ...P....
+            ........
+            ....K...
+        };
+        // The d3 pawn is behind both of its neighbors, but no pawn attacks d4.
+        assert_eq!(pawn_structure_penalty(&board, Color::White, &weights), 0);
+
+        let board = chess_position! {
+            ....k...
+            ........
+            ........
+            ..p.....
+            ........
+            ..P.P...
+            ...P....
+            ....K...
+        };
+        // The d2 pawn is behind both of its neighbors, and c5 attacks d4, but
+        // not d3, its stop square.
+        assert_eq!(pawn_structure_penalty(&board, Color::White, &weights), 0);
+
+        let board = chess_position! {
+            ....k...
+            ........
+            ........
+            ........
+            ..p.....
+            ..P.P...
+            ...P....
+            ....K...
+        };
+        // Now c4 attacks d3.
+        assert_eq!(
+            pawn_structure_penalty(&board, Color::White, &weights),
+            BACKWARD_PAWN_PENALTY
+        );
+        // The black pawn on c4 has no neighbors.
+        assert_eq!(
+            pawn_structure_penalty(&board, Color::Black, &weights),
+            ISOLATED_PAWN_PENALTY
+        );
+    }
+}
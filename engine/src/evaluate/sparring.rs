@@ -0,0 +1,112 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::EvaluationTerms;
+
+/// A practice opponent with a deliberate, themed weakness. Each profile turns
+/// off (or perturbs) part of the evaluation the engine uses for its own play.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SparringProfile {
+    /// Doesn't know where pieces belong in the endgame, so it won't centralize
+    /// its king or push passed pawns with any purpose, and doesn't know how to
+    /// win basic endings.
+    WeakEndgame,
+    /// Happily walks its king into the center during the middlegame.
+    IgnoresKingSafety,
+    /// Counts material, but has no sense of piece placement, development, or
+    /// pawn structure.
+    Materialist,
+    /// Misjudges every position by up to a pawn in either direction.
+    Careless,
+}
+
+impl SparringProfile {
+    pub fn terms(&self) -> EvaluationTerms {
+        let terms = EvaluationTerms::default();
+        match self {
+            SparringProfile::WeakEndgame => EvaluationTerms {
+                endgame_bonuses: false,
+                endgame_knowledge: false,
+                ..terms
+            },
+            SparringProfile::IgnoresKingSafety => EvaluationTerms {
+                king_safety: false,
+                ..terms
+            },
+            SparringProfile::Materialist => EvaluationTerms {
+                middlegame_bonuses: false,
+                endgame_bonuses: false,
+                pawn_structure: false,
+                ..terms
+            },
+            SparringProfile::Careless => EvaluationTerms {
+                noise: 100,
+                ..terms
+            },
+        }
+    }
+}
+
+impl FromStr for SparringProfile {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "weak-endgame" => Ok(SparringProfile::WeakEndgame),
+            "ignores-king-safety" => Ok(SparringProfile::IgnoresKingSafety),
+            "materialist" => Ok(SparringProfile::Materialist),
+            "careless" => Ok(SparringProfile::Careless),
+            _ => Err("invalid sparring profile; options are: weak-endgame, ignores-king-safety, materialist, careless"),
+        }
+    }
+}
+
+impl fmt::Display for SparringProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SparringProfile::WeakEndgame => "weak-endgame",
+            SparringProfile::IgnoresKingSafety => "ignores-king-safety",
+            SparringProfile::Materialist => "materialist",
+            SparringProfile::Careless => "careless",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::evaluate::board_material_score_with_terms;
+
+    #[test]
+    fn test_parse_sparring_profile() {
+        assert_eq!(
+            SparringProfile::from_str("weak-endgame"),
+            Ok(SparringProfile::WeakEndgame)
+        );
+        assert!(SparringProfile::from_str("strong").is_err());
+        let profile = SparringProfile::IgnoresKingSafety;
+        assert_eq!(SparringProfile::from_str(&profile.to_string()), Ok(profile));
+    }
+
+    #[test]
+    fn test_sparring_terms() {
+        assert!(!SparringProfile::IgnoresKingSafety.terms().king_safety);
+        assert!(
+            SparringProfile::IgnoresKingSafety
+                .terms()
+                .middlegame_bonuses
+        );
+
+        // The starting position is symmetric, so only the move and noise can
+        // tip the balance.
+        let board = Board::starting_position();
+        let materialist = SparringProfile::Materialist.terms();
+        let tempo = materialist.weights.tempo_bonus;
+        assert_eq!(board_material_score_with_terms(&board, &materialist), tempo);
+        let careless = SparringProfile::Careless.terms();
+        let score = board_material_score_with_terms(&board, &careless);
+        assert!((score - tempo).abs() <= careless.noise);
+        assert_eq!(score, board_material_score_with_terms(&board, &careless));
+    }
+}
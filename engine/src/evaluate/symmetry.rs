@@ -0,0 +1,104 @@
+use crate::board::Board;
+use crate::move_generator::MoveGenerator;
+
+use super::{score_with_terms, EvaluationTerms};
+
+/// Positions that exercise each term of the evaluation: openings and
+/// middlegames with castle rights and en passant targets, pawn weaknesses,
+/// king attacks, and the endings it has special knowledge of.
+pub const SYMMETRY_POSITIONS: [&str; 10] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkb1r/ppp1pppp/5n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 8",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+    "6k1/5ppp/7N/8/8/3Q4/8/6K1 b - - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "8/5k2/2p5/1pP5/1P6/3K4/5B2/2n5 b - - 0 50",
+    "8/8/8/4k3/8/8/4P3/4K3 w - - 0 1",
+    "8/8/8/8/3k4/8/8/R3K3 b Q - 0 1",
+];
+
+/// A position that the evaluation scores differently from its mirror image,
+/// with both scores from the perspective of the position's white.
+#[derive(Debug, PartialEq)]
+pub struct Asymmetry {
+    pub fen: String,
+    pub score: i16,
+    pub mirrored_score: i16,
+}
+
+/// Checks that the evaluation scores the position the same as its mirror
+/// image (see `Board::mirrored`), once the mirror's score is negated. An
+/// evaluation term that favors one color over the other shows up here, where
+/// it would otherwise only show up as a slightly weaker engine.
+pub fn check_symmetry(
+    board: &Board,
+    move_generator: &mut MoveGenerator,
+    terms: &EvaluationTerms,
+) -> Option<Asymmetry> {
+    let evaluate = |board: &Board, move_generator: &mut MoveGenerator| {
+        let mut board = board.clone();
+        let turn = board.turn();
+        score_with_terms(&mut board, move_generator, turn, 0, terms)
+    };
+    let score = evaluate(board, move_generator);
+    let mirrored_score = -evaluate(&board.mirrored(), move_generator);
+    (score != mirrored_score).then(|| Asymmetry {
+        fen: board.to_fen(),
+        score,
+        mirrored_score,
+    })
+}
+
+/// Checks the symmetry of each position, and of every position one move from
+/// it. Returns how many positions were checked, and the asymmetric ones.
+pub fn verify_symmetry(boards: &[Board], terms: &EvaluationTerms) -> (usize, Vec<Asymmetry>) {
+    let mut move_generator = MoveGenerator::new();
+    let mut checked = 0;
+    let mut asymmetries = Vec::new();
+    for board in boards {
+        let mut positions = vec![board.clone()];
+        let mut board = board.clone();
+        let turn = board.turn();
+        for chess_move in move_generator.generate_moves(&mut board, turn) {
+            let mut child = board.clone();
+            chess_move.apply(&mut child).unwrap();
+            child.toggle_turn();
+            positions.push(child);
+        }
+        for position in &positions {
+            checked += 1;
+            asymmetries.extend(check_symmetry(position, &mut move_generator, terms));
+        }
+    }
+    (checked, asymmetries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluation_is_symmetric() {
+        let boards: Vec<Board> = SYMMETRY_POSITIONS
+            .iter()
+            .map(|fen| Board::from_fen(fen).unwrap())
+            .collect();
+        let (checked, asymmetries) = verify_symmetry(&boards, &EvaluationTerms::default());
+        assert!(checked > boards.len());
+        assert_eq!(asymmetries, vec![]);
+    }
+
+    #[test]
+    fn test_asymmetric_terms_are_caught() {
+        // Noise depends on the position's hash, which the mirror doesn't share.
+        let terms = EvaluationTerms {
+            noise: 50,
+            ..Default::default()
+        };
+        let board = Board::starting_position();
+        let asymmetry = check_symmetry(&board, &mut MoveGenerator::new(), &terms).unwrap();
+        assert_ne!(asymmetry.score, asymmetry.mirrored_score);
+    }
+}
@@ -0,0 +1,406 @@
+use std::fs;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+
+use super::evaluation_tables::{SQUARE_TO_BLACK_BONUS_INDEX, SQUARE_TO_WHITE_BONUS_INDEX};
+use super::material_imbalance::minor_piece_features;
+use super::pawn_structure::{passed_pawn_features, pawn_weaknesses};
+use super::weights::EvaluationWeights;
+use super::{is_endgame, WIN_PROBABILITY_SLOPE};
+
+/// Where each tuned weight sits in the flat list of parameters: material, then
+/// the middlegame and endgame bonus tables, then the pawn structure and minor
+/// piece weights, then the tempo and knight outpost bonuses, then the passed
+/// pawn weights. King safety depends on the attacks in the position rather than
+/// on a sum of features, so it isn't tuned.
+const MATERIAL: usize = 0;
+const MIDDLEGAME_BONUSES: usize = MATERIAL + 6;
+const ENDGAME_BONUSES: usize = MIDDLEGAME_BONUSES + 6 * 64;
+const DOUBLED_PAWN: usize = ENDGAME_BONUSES + 6 * 64;
+const ISOLATED_PAWN: usize = DOUBLED_PAWN + 1;
+const BACKWARD_PAWN: usize = ISOLATED_PAWN + 1;
+const BISHOP_PAIR: usize = BACKWARD_PAWN + 1;
+const KNIGHT_PAWN: usize = BISHOP_PAIR + 1;
+const TEMPO: usize = KNIGHT_PAWN + 1;
+const KNIGHT_OUTPOST: usize = TEMPO + 1;
+const PASSED_PAWN: usize = KNIGHT_OUTPOST + 1;
+const PASSED_PAWN_KING: usize = PASSED_PAWN + 8;
+const PARAMETER_COUNT: usize = PASSED_PAWN_KING + 1;
+
+/// Adam's step size, in centipawns, and its decay rates.
+const LEARNING_RATE: f64 = 1.0;
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+#[derive(Error, Debug)]
+pub enum TuneError {
+    #[error("could not read {path}: {error}")]
+    Io { path: String, error: String },
+    #[error("line {line}: {error}")]
+    InvalidPosition { line: usize, error: String },
+    #[error("no positions to tune on")]
+    NoPositions,
+}
+
+/// A position from a real game, and how that game ended: 1.0 if white won, 0.5
+/// for a draw, and 0.0 if black won.
+pub struct LabeledPosition {
+    pub board: Board,
+    pub result: f64,
+}
+
+/// A position reduced to what the tuned weights apply to: the count of each
+/// feature, from white's perspective, by its parameter index.
+struct Features {
+    counts: Vec<(usize, f64)>,
+    result: f64,
+}
+
+/// How well the evaluation predicts the results it was tuned on, before and
+/// after tuning, as the mean squared error of its expected scores.
+pub struct TuneReport {
+    pub weights: EvaluationWeights,
+    pub initial_error: f64,
+    pub final_error: f64,
+}
+
+/// Reads labeled positions, one per line: a FEN and the game's result, in
+/// either order. Results may be written `1-0`, `1/2-1/2`, `0-1`, or as white's
+/// score, optionally wrapped in brackets or quotes, as most tuning sets have
+/// them. Blank lines and lines starting with `#` are skipped.
+pub fn load_positions(path: &Path) -> Result<Vec<LabeledPosition>, TuneError> {
+    let contents = fs::read_to_string(path).map_err(|error| TuneError::Io {
+        path: path.display().to_string(),
+        error: error.to_string(),
+    })?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            parse_labeled_position(line)
+                .map_err(|error| TuneError::InvalidPosition { line: i + 1, error })
+        })
+        .collect()
+}
+
+fn parse_labeled_position(line: &str) -> Result<LabeledPosition, String> {
+    let (fen, result) =
+        split_result(line).ok_or_else(|| format!("no game result in {:?}", line))?;
+    let board = Board::from_fen(&fen).map_err(|error| error.to_string())?;
+    Ok(LabeledPosition { board, result })
+}
+
+/// Splits a labeled position's line into its FEN and its game's result, if it
+/// has one.
+pub(super) fn split_result(line: &str) -> Option<(String, f64)> {
+    // EPD files label positions with the `c9` opcode, e.g. `c9 "1-0";`.
+    let mut fields: Vec<&str> = line
+        .split_whitespace()
+        .filter(|&field| field != "c9")
+        .collect();
+    // A FEN never starts with a result, but it can end with a fullmove number
+    // that reads like one, so a leading result is looked for first.
+    let result = match (fields.first(), fields.last()) {
+        (Some(first), _) if parse_result(first).is_some() => {
+            let result = parse_result(first);
+            fields.remove(0);
+            result
+        }
+        (_, Some(last)) if parse_result(last).is_some() => {
+            let result = parse_result(last);
+            fields.pop();
+            result
+        }
+        _ => None,
+    }?;
+    Some((fields.join(" "), result))
+}
+
+fn parse_result(field: &str) -> Option<f64> {
+    let field = field.trim_matches(|c| matches!(c, '[' | ']' | '"' | ';'));
+    match field {
+        "1-0" => Some(1.0),
+        "1/2-1/2" => Some(0.5),
+        "0-1" => Some(0.0),
+        score => score
+            .parse::<f64>()
+            .ok()
+            .filter(|score| (0.0..=1.0).contains(score)),
+    }
+}
+
+/// Fits the weights to the positions' results by logistic regression: gradient
+/// descent on the mean squared error between each game's result and the
+/// expected score `win_probability` gives the position's evaluation. The
+/// weights start from `initial`, and those that aren't tuned are kept as they
+/// are.
+pub fn tune(
+    positions: &[LabeledPosition],
+    initial: &EvaluationWeights,
+    iterations: usize,
+) -> Result<TuneReport, TuneError> {
+    if positions.is_empty() {
+        return Err(TuneError::NoPositions);
+    }
+    let features: Vec<Features> = positions.iter().map(features).collect();
+    let mut parameters = to_parameters(initial);
+    let initial_error = mean_squared_error(&features, &parameters);
+
+    let mut gradient = vec![0.0; PARAMETER_COUNT];
+    let mut first_moment = vec![0.0; PARAMETER_COUNT];
+    let mut second_moment = vec![0.0; PARAMETER_COUNT];
+    for iteration in 1..=iterations {
+        gradient.iter_mut().for_each(|g| *g = 0.0);
+        for position in &features {
+            let expected = expected_score(position, &parameters);
+            // The derivative of the squared error with respect to the score.
+            let slope = -2.0
+                * (position.result - expected)
+                * expected
+                * (1.0 - expected)
+                * WIN_PROBABILITY_SLOPE;
+            for &(index, count) in &position.counts {
+                gradient[index] += slope * count;
+            }
+        }
+
+        for i in 0..PARAMETER_COUNT {
+            let g = gradient[i] / features.len() as f64;
+            first_moment[i] = BETA1 * first_moment[i] + (1.0 - BETA1) * g;
+            second_moment[i] = BETA2 * second_moment[i] + (1.0 - BETA2) * g * g;
+            let first = first_moment[i] / (1.0 - BETA1.powi(iteration as i32));
+            let second = second_moment[i] / (1.0 - BETA2.powi(iteration as i32));
+            parameters[i] -= LEARNING_RATE * first / (second.sqrt() + EPSILON);
+        }
+    }
+
+    let weights = from_parameters(initial, &parameters);
+    let final_error = mean_squared_error(&features, &to_parameters(&weights));
+    Ok(TuneReport {
+        weights,
+        initial_error,
+        final_error,
+    })
+}
+
+fn mean_squared_error(features: &[Features], parameters: &[f64]) -> f64 {
+    features
+        .iter()
+        .map(|position| (position.result - expected_score(position, parameters)).powi(2))
+        .sum::<f64>()
+        / features.len() as f64
+}
+
+fn expected_score(position: &Features, parameters: &[f64]) -> f64 {
+    1.0 / (1.0 + (-WIN_PROBABILITY_SLOPE * linear_score(position, parameters)).exp())
+}
+
+fn linear_score(position: &Features, parameters: &[f64]) -> f64 {
+    position
+        .counts
+        .iter()
+        .map(|&(index, count)| parameters[index] * count)
+        .sum()
+}
+
+/// The features that `board_material_score` weighs, mirroring how it weighs
+/// them: white's count less black's, or black's less white's for penalties.
+fn features(position: &LabeledPosition) -> Features {
+    let board = &position.board;
+    let is_endgame = is_endgame(board);
+    let bonuses = if is_endgame {
+        ENDGAME_BONUSES
+    } else {
+        MIDDLEGAME_BONUSES
+    };
+
+    let mut counts = Vec::new();
+    for (square, piece, color) in board.iter() {
+        let (sign, index_lookup) = match color {
+            Color::White => (1.0, SQUARE_TO_WHITE_BONUS_INDEX),
+            Color::Black => (-1.0, SQUARE_TO_BLACK_BONUS_INDEX),
+        };
+        let bonus_index = index_lookup[square.trailing_zeros() as usize];
+        counts.push((MATERIAL + piece as usize, sign));
+        counts.push((bonuses + piece as usize * 64 + bonus_index, sign));
+    }
+
+    for (color, sign) in [(Color::White, 1.0), (Color::Black, -1.0)] {
+        let weaknesses = pawn_weaknesses(board, color);
+        counts.push((DOUBLED_PAWN, -sign * weaknesses.doubled as f64));
+        counts.push((ISOLATED_PAWN, -sign * weaknesses.isolated as f64));
+        counts.push((BACKWARD_PAWN, -sign * weaknesses.backward as f64));
+
+        let minor_pieces = minor_piece_features(board, color);
+        counts.push((BISHOP_PAIR, sign * minor_pieces.bishop_pair as f64));
+        counts.push((KNIGHT_PAWN, sign * minor_pieces.knight_pawn_factor as f64));
+        counts.push((KNIGHT_OUTPOST, sign * minor_pieces.knight_outposts as f64));
+
+        let passed_pawns = passed_pawn_features(board, color);
+        for (rank, &half_count) in passed_pawns.half_counts_by_rank.iter().enumerate() {
+            counts.push((PASSED_PAWN + rank, sign * half_count as f64 / 2.0));
+        }
+        counts.push((PASSED_PAWN_KING, sign * passed_pawns.king_proximity as f64));
+    }
+    let tempo = match board.turn() {
+        Color::White => 1.0,
+        Color::Black => -1.0,
+    };
+    counts.push((TEMPO, tempo));
+    counts.retain(|&(_, count)| count != 0.0);
+
+    Features {
+        counts,
+        result: position.result,
+    }
+}
+
+fn to_parameters(weights: &EvaluationWeights) -> Vec<f64> {
+    let mut parameters = vec![0.0; PARAMETER_COUNT];
+    for piece in 0..6 {
+        parameters[MATERIAL + piece] = weights.material[piece] as f64;
+        for index in 0..64 {
+            parameters[MIDDLEGAME_BONUSES + piece * 64 + index] =
+                weights.middlegame_bonuses[piece][index / 8][index % 8] as f64;
+            parameters[ENDGAME_BONUSES + piece * 64 + index] =
+                weights.endgame_bonuses[piece][index / 8][index % 8] as f64;
+        }
+    }
+    parameters[DOUBLED_PAWN] = weights.doubled_pawn_penalty as f64;
+    parameters[ISOLATED_PAWN] = weights.isolated_pawn_penalty as f64;
+    parameters[BACKWARD_PAWN] = weights.backward_pawn_penalty as f64;
+    parameters[BISHOP_PAIR] = weights.bishop_pair_bonus as f64;
+    parameters[KNIGHT_PAWN] = weights.knight_pawn_adjustment as f64;
+    parameters[TEMPO] = weights.tempo_bonus as f64;
+    parameters[KNIGHT_OUTPOST] = weights.knight_outpost_bonus as f64;
+    for rank in 0..8 {
+        parameters[PASSED_PAWN + rank] = weights.passed_pawn_bonuses[rank] as f64;
+    }
+    parameters[PASSED_PAWN_KING] = weights.passed_pawn_king_proximity as f64;
+    parameters
+}
+
+fn from_parameters(initial: &EvaluationWeights, parameters: &[f64]) -> EvaluationWeights {
+    let weight = |index: usize| parameters[index].round() as i16;
+    let mut weights = initial.clone();
+    for piece in 0..6 {
+        // The kings' material always cancels out, so it can't be fit.
+        if piece != Piece::King as usize {
+            weights.material[piece] = weight(MATERIAL + piece);
+        }
+        for index in 0..64 {
+            weights.middlegame_bonuses[piece][index / 8][index % 8] =
+                weight(MIDDLEGAME_BONUSES + piece * 64 + index);
+            weights.endgame_bonuses[piece][index / 8][index % 8] =
+                weight(ENDGAME_BONUSES + piece * 64 + index);
+        }
+    }
+    weights.doubled_pawn_penalty = weight(DOUBLED_PAWN);
+    weights.isolated_pawn_penalty = weight(ISOLATED_PAWN);
+    weights.backward_pawn_penalty = weight(BACKWARD_PAWN);
+    weights.bishop_pair_bonus = weight(BISHOP_PAIR);
+    weights.knight_pawn_adjustment = weight(KNIGHT_PAWN);
+    weights.tempo_bonus = weight(TEMPO);
+    weights.knight_outpost_bonus = weight(KNIGHT_OUTPOST);
+    for rank in 0..8 {
+        weights.passed_pawn_bonuses[rank] = weight(PASSED_PAWN + rank);
+    }
+    weights.passed_pawn_king_proximity = weight(PASSED_PAWN_KING);
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate::board_material_score;
+
+    #[test]
+    fn test_parse_labeled_position() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        for (line, result) in [
+            (format!("{} [1.0]", start), 1.0),
+            (format!("{} 1/2-1/2", start), 0.5),
+            (format!("0-1 {}", start), 0.0),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - c9 \"1-0\";".to_string(),
+                1.0,
+            ),
+        ] {
+            let position = parse_labeled_position(&line).unwrap();
+            assert_eq!(position.result, result, "{}", line);
+            assert_eq!(position.board.to_fen(), start);
+        }
+
+        assert!(parse_labeled_position(start).is_err());
+        assert!(parse_labeled_position(&format!("{} 2.0", start)).is_err());
+        assert!(parse_labeled_position("8/8/8 w - - 1-0").is_err());
+    }
+
+    #[test]
+    fn test_features_match_the_evaluation() {
+        let weights = EvaluationWeights::default();
+        let parameters = to_parameters(&weights);
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            "8/5k2/2p5/1pP5/1P6/3K4/5B2/2n5 b - - 0 50",
+            "4k3/pp3ppp/8/8/8/8/PP1P1PPP/4K3 w - - 0 1",
+            "r1bqkb1r/pp3ppp/2n5/3Np3/4P3/8/PPP2PPP/R2QKB1R b KQkq - 0 8",
+            "8/5k2/1p1n4/1P1P4/8/2K5/8/8 w - - 0 40",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let score = board_material_score(&board);
+            let position = features(&LabeledPosition { board, result: 0.5 });
+            assert_eq!(
+                linear_score(&position, &parameters),
+                score as f64,
+                "{}",
+                fen
+            );
+        }
+        assert_eq!(from_parameters(&weights, &parameters), weights);
+    }
+
+    #[test]
+    fn test_tune_fits_the_results() {
+        // White wins every game up a pawn, so the pawn should gain value.
+        let positions: Vec<LabeledPosition> = [
+            "4k3/8/8/8/8/8/3PP3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/2P1P3/4K3 b - - 0 1",
+            "4k3/8/8/4P3/8/8/3P4/4K3 w - - 0 1",
+        ]
+        .iter()
+        .map(|fen| LabeledPosition {
+            board: Board::from_fen(fen).unwrap(),
+            result: 1.0,
+        })
+        .collect();
+
+        let initial = EvaluationWeights::default();
+        let report = tune(&positions, &initial, 50).unwrap();
+        assert!(report.final_error < report.initial_error);
+        assert!(
+            report.weights.material[Piece::Pawn as usize] > initial.material[Piece::Pawn as usize]
+        );
+        assert_eq!(
+            report.weights.material[Piece::King as usize],
+            initial.material[Piece::King as usize]
+        );
+        assert_eq!(
+            report.weights.king_attack_weights,
+            initial.king_attack_weights
+        );
+
+        assert!(matches!(
+            tune(&[], &initial, 10),
+            Err(TuneError::NoPositions)
+        ));
+    }
+}
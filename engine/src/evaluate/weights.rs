@@ -0,0 +1,204 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use common::bitboard::bitboard::Bitboard;
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::piece_square_values::PieceSquareValues;
+
+use super::evaluation_tables::{
+    BISHOP_BONUSES, KING_ENDGAME_BONUSES, KING_MIDGAME_BONUSES, KNIGHT_BONUSES, MATERIAL_VALUES,
+    PAWN_BONUSES, QUEEN_BONUSES, ROOK_BONUSES, SQUARE_TO_BLACK_BONUS_INDEX,
+    SQUARE_TO_WHITE_BONUS_INDEX,
+};
+use super::king_safety::{KING_ATTACK_WEIGHTS, MAX_KING_DANGER};
+use super::material_imbalance::{BISHOP_PAIR_BONUS, KNIGHT_OUTPOST_BONUS, KNIGHT_PAWN_ADJUSTMENT};
+use super::pawn_structure::{
+    BACKWARD_PAWN_PENALTY, DOUBLED_PAWN_PENALTY, ISOLATED_PAWN_PENALTY, PASSED_PAWN_BONUSES,
+    PASSED_PAWN_KING_PROXIMITY,
+};
+use super::TEMPO_BONUS;
+
+/// A piece's placement bonuses, as seen from white's side of the board: the
+/// first row is the eighth rank. Black's bonuses are read from the mirrored
+/// square.
+pub type BonusTable = [[i16; 8]; 8];
+
+/// Every number the evaluation weighs a position with, in centipawns. The
+/// defaults are hand-picked; `tune` fits them to the results of real games, and
+/// tuned weights can be saved and loaded in their place.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EvaluationWeights {
+    /// By `Piece`.
+    pub material: [i16; 6],
+    /// By `Piece`.
+    pub middlegame_bonuses: [BonusTable; 6],
+    /// By `Piece`.
+    pub endgame_bonuses: [BonusTable; 6],
+    pub doubled_pawn_penalty: i16,
+    pub isolated_pawn_penalty: i16,
+    pub backward_pawn_penalty: i16,
+    pub bishop_pair_bonus: i16,
+    pub knight_pawn_adjustment: i16,
+    /// By `Piece`: how much each of the piece's attacks on the squares around
+    /// the enemy king counts toward the king's danger.
+    pub king_attack_weights: [i16; 6],
+    pub max_king_danger: i16,
+    /// For the side to move. Weights saved before it was added load with the
+    /// default.
+    #[serde(default = "default_tempo_bonus")]
+    pub tempo_bonus: i16,
+    /// Also added after weights were first saved.
+    #[serde(default = "default_knight_outpost_bonus")]
+    pub knight_outpost_bonus: i16,
+    /// By rank, counting from the pawn's side.
+    #[serde(default = "default_passed_pawn_bonuses")]
+    pub passed_pawn_bonuses: [i16; 8],
+    #[serde(default = "default_passed_pawn_king_proximity")]
+    pub passed_pawn_king_proximity: i16,
+}
+
+fn default_tempo_bonus() -> i16 {
+    TEMPO_BONUS
+}
+
+fn default_knight_outpost_bonus() -> i16 {
+    KNIGHT_OUTPOST_BONUS
+}
+
+fn default_passed_pawn_bonuses() -> [i16; 8] {
+    PASSED_PAWN_BONUSES
+}
+
+fn default_passed_pawn_king_proximity() -> i16 {
+    PASSED_PAWN_KING_PROXIMITY
+}
+
+impl Default for EvaluationWeights {
+    fn default() -> Self {
+        let table = |bonuses: [i16; 64]| {
+            let mut table = [[0; 8]; 8];
+            for (i, &bonus) in bonuses.iter().enumerate() {
+                table[i / 8][i % 8] = bonus;
+            }
+            table
+        };
+        Self {
+            material: MATERIAL_VALUES,
+            middlegame_bonuses: [
+                table(PAWN_BONUSES),
+                table(KNIGHT_BONUSES),
+                table(BISHOP_BONUSES),
+                table(ROOK_BONUSES),
+                table(QUEEN_BONUSES),
+                table(KING_MIDGAME_BONUSES),
+            ],
+            endgame_bonuses: [
+                table(PAWN_BONUSES),
+                table(KNIGHT_BONUSES),
+                table(BISHOP_BONUSES),
+                table(ROOK_BONUSES),
+                table(QUEEN_BONUSES),
+                table(KING_ENDGAME_BONUSES),
+            ],
+            doubled_pawn_penalty: DOUBLED_PAWN_PENALTY,
+            isolated_pawn_penalty: ISOLATED_PAWN_PENALTY,
+            backward_pawn_penalty: BACKWARD_PAWN_PENALTY,
+            bishop_pair_bonus: BISHOP_PAIR_BONUS,
+            knight_pawn_adjustment: KNIGHT_PAWN_ADJUSTMENT,
+            king_attack_weights: KING_ATTACK_WEIGHTS,
+            max_king_danger: MAX_KING_DANGER,
+            tempo_bonus: TEMPO_BONUS,
+            knight_outpost_bonus: KNIGHT_OUTPOST_BONUS,
+            passed_pawn_bonuses: PASSED_PAWN_BONUSES,
+            passed_pawn_king_proximity: PASSED_PAWN_KING_PROXIMITY,
+        }
+    }
+}
+
+impl EvaluationWeights {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::write(path, json)
+    }
+
+    /// The piece's placement bonus on the square at `index` of its bonus table
+    /// (see `SQUARE_TO_WHITE_BONUS_INDEX`).
+    #[inline(always)]
+    pub fn bonus(&self, piece: Piece, is_endgame: bool, index: usize) -> i16 {
+        let tables = if is_endgame {
+            &self.endgame_bonuses
+        } else {
+            &self.middlegame_bonuses
+        };
+        tables[piece as usize][index / 8][index % 8]
+    }
+}
+
+impl PieceSquareValues for EvaluationWeights {
+    #[inline(always)]
+    fn material(&self, piece: Piece) -> i16 {
+        self.material[piece as usize]
+    }
+
+    #[inline(always)]
+    fn bonuses(&self, piece: Piece, color: Color, square: Bitboard) -> (i16, i16) {
+        let index_lookup = match color {
+            Color::White => SQUARE_TO_WHITE_BONUS_INDEX,
+            Color::Black => SQUARE_TO_BLACK_BONUS_INDEX,
+        };
+        let index = index_lookup[square.trailing_zeros() as usize];
+        (
+            self.bonus(piece, false, index),
+            self.bonus(piece, true, index),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bonus_tables_keep_their_layout() {
+        let weights = EvaluationWeights::default();
+        // The first row of the pawn table is the eighth rank.
+        assert_eq!(weights.bonus(Piece::Pawn, false, 8), PAWN_BONUSES[8]);
+        assert_eq!(weights.middlegame_bonuses[Piece::Pawn as usize][1], [50; 8]);
+        assert_eq!(
+            weights.bonus(Piece::King, true, 63),
+            KING_ENDGAME_BONUSES[63]
+        );
+        assert_ne!(
+            weights.bonus(Piece::King, false, 63),
+            weights.bonus(Piece::King, true, 63)
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("chess-weights-{}.json", std::process::id()));
+        let mut weights = EvaluationWeights {
+            bishop_pair_bonus: 45,
+            ..Default::default()
+        };
+        weights.endgame_bonuses[Piece::Rook as usize][3][4] = 12;
+        weights.save(&path).unwrap();
+        assert_eq!(EvaluationWeights::load(&path).unwrap(), weights);
+
+        fs::write(&path, "{}").unwrap();
+        assert!(EvaluationWeights::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
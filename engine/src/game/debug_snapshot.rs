@@ -0,0 +1,34 @@
+use serde::Serialize;
+
+/// Everything needed to reproduce a game's state in a bug report: the position,
+/// how the game got there, and the board's internal bookkeeping, which is where
+/// bugs tend to hide. Hashes are formatted in hex, and are only comparable
+/// between builds with the same `zobrist_version`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DebugSnapshot {
+    pub fen: String,
+    pub starting_fen: String,
+    pub moves_uci: Vec<String>,
+    /// `None` if the moves can't be replayed from the starting position.
+    pub moves_san: Option<Vec<String>>,
+    pub halfmove_clock_stack: Vec<u16>,
+    pub fullmove_clock: u16,
+    pub castle_rights_stack: Vec<u8>,
+    /// The board's repetition table: how many times each position has been
+    /// seen, by hash.
+    pub position_counts: Vec<(String, u8)>,
+    /// The hash of each position the game has reached, oldest first.
+    pub position_history: Vec<String>,
+    pub position_hash: String,
+    pub zobrist_version: String,
+}
+
+impl DebugSnapshot {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+pub(crate) fn format_hash(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,278 @@
+use std::time::Duration;
+
+use crate::alpha_beta_searcher::depth_boost::DepthBoost;
+use crate::board::Board;
+use crate::evaluate;
+
+/// The stage a game has reached, for the settings that change as it goes on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+/// Ends a game as a draw once the engines' scores have stayed close to level
+/// for long enough, rather than playing it out to the move limit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawAdjudication {
+    /// No game is adjudicated before this many plies have been played.
+    pub min_ply: usize,
+    /// Scores within this many centipawns of 0 count as level.
+    pub max_score: i16,
+    /// How many searched moves in a row have to score level.
+    pub moves: usize,
+}
+
+/// Has the engines agree to a draw once each has scored the game close to level
+/// for long enough, late enough in the game that neither is likely to find a
+/// way through.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawAgreement {
+    /// No draw is agreed before this many plies have been played.
+    pub min_ply: usize,
+    /// Scores within this many centipawns of 0 count as level.
+    pub max_score: i16,
+    /// How many of its own searched moves in a row each engine has to have
+    /// scored level.
+    pub moves: usize,
+}
+
+/// How much of its remaining clock the engine spends on a move in each phase:
+/// the remaining time divided by the phase's number, plus the increment. A
+/// larger number plays faster, e.g. in the opening, where the engine knows
+/// more and there's more of the game left to save time for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeAllocation {
+    pub opening: u32,
+    pub middlegame: u32,
+    pub endgame: u32,
+}
+
+impl TimeAllocation {
+    pub fn budget(&self, phase: GamePhase, remaining: Duration, increment: Duration) -> Duration {
+        let moves = match phase {
+            GamePhase::Opening => self.opening,
+            GamePhase::Middlegame => self.middlegame,
+            GamePhase::Endgame => self.endgame,
+        };
+        remaining / moves.max(1) + increment
+    }
+}
+
+/// Varies the engine's games, so that games between engines reach more
+/// different positions, e.g. when they're played to generate training data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveNoise {
+    /// The engine plays a random legal move at each of the game's first this
+    /// many plies, in place of its book or its search.
+    pub random_plies: usize,
+    /// After those, the engine chooses randomly among the moves its search
+    /// scores within this many centipawns of the best (see `RootJitter`).
+    pub jitter: i16,
+}
+
+/// How the engine conducts a game, beyond how deeply it searches: when it leaves
+/// its opening book, when it gives up or a game is called a draw, how it
+/// spends its clock in each phase, how much deeper it searches once few
+/// pieces are left, and how much randomness it plays with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EngineConfig {
+    /// The opening lasts this many plies, unless the endgame arrives first.
+    pub opening_plies: usize,
+    /// The engine stops playing book moves after this many plies. `None`
+    /// follows the book for as long as it has moves.
+    pub book_max_ply: Option<usize>,
+    /// The engine resigns once it has scored at least this many centipawns
+    /// behind for `resign_moves` of its searched moves in a row. `None` never
+    /// resigns.
+    pub resign_threshold: Option<i16>,
+    pub resign_moves: usize,
+    /// `None` plays every game out.
+    pub draw_adjudication: Option<DrawAdjudication>,
+    /// `None` never agrees to a draw.
+    pub draw_agreement: Option<DrawAgreement>,
+    pub time_allocation: TimeAllocation,
+    /// `None` searches every position to the nominal depth.
+    pub depth_boost: Option<DepthBoost>,
+    /// `None` plays every move as well as the engine can.
+    pub move_noise: Option<MoveNoise>,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            opening_plies: 20,
+            book_max_ply: None,
+            resign_threshold: None,
+            resign_moves: 3,
+            draw_adjudication: None,
+            draw_agreement: None,
+            time_allocation: TimeAllocation {
+                opening: 40,
+                middlegame: 25,
+                endgame: 30,
+            },
+            depth_boost: Some(DepthBoost::default()),
+            move_noise: None,
+        }
+    }
+}
+
+impl EngineConfig {
+    /// The phase of a game that has reached `board` after `ply` plies.
+    pub fn phase(&self, board: &Board, ply: usize) -> GamePhase {
+        if evaluate::is_endgame(board) {
+            GamePhase::Endgame
+        } else if ply < self.opening_plies {
+            GamePhase::Opening
+        } else {
+            GamePhase::Middlegame
+        }
+    }
+
+    /// Whether the engine plays a random move at `ply` rather than choosing
+    /// one.
+    pub fn plays_random_move(&self, ply: usize) -> bool {
+        self.move_noise
+            .is_some_and(|noise| ply < noise.random_plies)
+    }
+
+    pub fn allows_book_move(&self, ply: usize) -> bool {
+        self.book_max_ply.is_none_or(|max_ply| ply < max_ply)
+    }
+
+    /// Whether an engine whose searched moves scored `scores`, from its own
+    /// point of view and oldest first, should resign.
+    pub fn should_resign(&self, scores: &[i16]) -> bool {
+        let threshold = match self.resign_threshold {
+            Some(threshold) => threshold,
+            None => return false,
+        };
+        scores.len() >= self.resign_moves
+            && scores
+                .iter()
+                .rev()
+                .take(self.resign_moves)
+                .all(|&score| score <= -threshold)
+    }
+
+    /// Whether a game at `ply`, whose searched moves scored `scores`, oldest
+    /// first, should be adjudicated a draw.
+    pub fn is_adjudicated_draw(&self, ply: usize, scores: &[i16]) -> bool {
+        let adjudication = match self.draw_adjudication {
+            Some(adjudication) => adjudication,
+            None => return false,
+        };
+        ply >= adjudication.min_ply
+            && scores.len() >= adjudication.moves
+            && scores
+                .iter()
+                .rev()
+                .take(adjudication.moves)
+                .all(|score| score.abs() <= adjudication.max_score)
+    }
+
+    /// Whether an engine whose searched moves scored `scores`, from its own
+    /// point of view and oldest first, would agree to a draw at `ply`. The game
+    /// is only drawn once both engines would.
+    pub fn agrees_to_draw(&self, ply: usize, scores: &[i16]) -> bool {
+        let agreement = match self.draw_agreement {
+            Some(agreement) => agreement,
+            None => return false,
+        };
+        ply >= agreement.min_ply
+            && scores.len() >= agreement.moves
+            && scores
+                .iter()
+                .rev()
+                .take(agreement.moves)
+                .all(|score| score.abs() <= agreement.max_score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_and_time_allocation() {
+        let config = EngineConfig::default();
+        let board = Board::starting_position();
+        assert_eq!(config.phase(&board, 0), GamePhase::Opening);
+        assert_eq!(config.phase(&board, 20), GamePhase::Middlegame);
+        assert_eq!(
+            config.phase(
+                &Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap(),
+                0
+            ),
+            GamePhase::Endgame
+        );
+
+        let remaining = Duration::from_secs(100);
+        let increment = Duration::from_secs(1);
+        let budget = |phase| config.time_allocation.budget(phase, remaining, increment);
+        assert_eq!(budget(GamePhase::Opening), Duration::from_millis(3500));
+        assert_eq!(budget(GamePhase::Middlegame), Duration::from_secs(5));
+        assert!(budget(GamePhase::Middlegame) > budget(GamePhase::Endgame));
+    }
+
+    #[test]
+    fn test_book_resign_and_draw_thresholds() {
+        let config = EngineConfig {
+            book_max_ply: Some(8),
+            resign_threshold: Some(500),
+            resign_moves: 2,
+            draw_adjudication: Some(DrawAdjudication {
+                min_ply: 40,
+                max_score: 10,
+                moves: 3,
+            }),
+            ..Default::default()
+        };
+        assert!(config.allows_book_move(7));
+        assert!(!config.allows_book_move(8));
+        assert!(EngineConfig::default().allows_book_move(1000));
+        assert!(!config.plays_random_move(0));
+
+        assert!(!config.should_resign(&[-600]));
+        assert!(!config.should_resign(&[-600, -100]));
+        assert!(config.should_resign(&[0, -500, -900]));
+        assert!(!EngineConfig::default().should_resign(&[-5000, -5000, -5000]));
+
+        assert!(!config.is_adjudicated_draw(30, &[0, 0, 0]));
+        assert!(config.is_adjudicated_draw(40, &[300, 5, -10, 0]));
+        assert!(!config.is_adjudicated_draw(40, &[5, -11, 0]));
+    }
+
+    #[test]
+    fn test_draw_agreement() {
+        let config = EngineConfig {
+            draw_agreement: Some(DrawAgreement {
+                min_ply: 80,
+                max_score: 15,
+                moves: 2,
+            }),
+            ..Default::default()
+        };
+        assert!(!config.agrees_to_draw(79, &[0, 0]));
+        assert!(config.agrees_to_draw(80, &[200, -15, 10]));
+        assert!(!config.agrees_to_draw(80, &[0, 20, 0]));
+        assert!(!config.agrees_to_draw(80, &[0]));
+        assert!(!EngineConfig::default().agrees_to_draw(200, &[0, 0, 0]));
+    }
+
+    #[test]
+    fn test_move_noise() {
+        let config = EngineConfig {
+            move_noise: Some(MoveNoise {
+                random_plies: 4,
+                jitter: 20,
+            }),
+            ..Default::default()
+        };
+        assert!(config.plays_random_move(0));
+        assert!(config.plays_random_move(3));
+        assert!(!config.plays_random_move(4));
+    }
+}
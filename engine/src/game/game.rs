@@ -0,0 +1,1304 @@
+use crate::alpha_beta_searcher::root_jitter::RootJitter;
+use crate::alpha_beta_searcher::search_control::SearchControl;
+use crate::alpha_beta_searcher::time_manager::TimeManager;
+use crate::alpha_beta_searcher::watchdog::Watchdog;
+use crate::alpha_beta_searcher::{alpha_beta_search, DepthStats, SearchContext, SearchError};
+use crate::board::color::Color;
+use crate::board::error::BoardError;
+use crate::board::fen::STARTING_POSITION_FEN;
+use crate::board::piece::Piece;
+use crate::board::zobrist_version::ZOBRIST_TABLE_VERSION;
+use crate::board::Board;
+use crate::book::{Book, BookMove};
+use crate::chess_move::algebraic_notation::{
+    algebraic_notation_for, enumerate_candidate_moves_with_algebraic_notation, normalize_san,
+    suggest_moves,
+};
+use crate::chess_move::chess_move::ChessMove;
+use crate::engine_info::EngineInfo;
+use crate::evaluate::{self, DrawClaim, EvaluationTerms, GameEnding};
+use crate::game::debug_snapshot::{format_hash, DebugSnapshot};
+use crate::game::engine_config::{EngineConfig, GamePhase};
+use crate::game::opening_explorer::OpeningExplorer;
+use crate::game::ponder::PonderSearch;
+use crate::move_generator::legal_moves::LegalMoves;
+use crate::move_generator::MoveGenerator;
+use crate::pgn::{PgnGame, PgnMove};
+use common::bitboard::bitboard::Bitboard;
+use common::bitboard::square::to_algebraic;
+use rand::{self, Rng};
+use thiserror::Error;
+
+/// Represents the state and control of a chess game.
+pub struct Game {
+    board: Board,
+    /// The position the game started from, for replaying its moves.
+    starting_board: Board,
+    move_history: Vec<ChessMove>,
+    /// The hash of every position reached in the game, starting with the initial one.
+    position_history: Vec<u64>,
+    book: Book,
+    move_generator: MoveGenerator,
+    search_context: SearchContext,
+    engine_config: EngineConfig,
+    /// Which sides the engine plays, indexed by color, for naming the players
+    /// when the game is written as PGN.
+    engine_players: [bool; 2],
+    ponder: Option<PonderSearch>,
+    /// Consulted when the book runs out, if the engine may go online.
+    opening_explorer: Option<OpeningExplorer>,
+    /// Whether a move that promotes a pawn without naming the piece promotes
+    /// it to a queen, rather than asking which piece.
+    auto_queen: bool,
+    /// The draw a player claimed, which ended the game.
+    claimed_draw: Option<DrawClaim>,
+}
+
+/// How deep to search for the opponent's likely reply when pondering. A shallow
+/// search finds the obvious replies, and is quick enough not to hold anything up.
+const PREDICTION_DEPTH: u8 = 2;
+
+/// The number of legal moves available to each of a player's piece types.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Mobility([usize; 6]);
+
+impl Mobility {
+    pub fn for_piece(&self, piece: Piece) -> usize {
+        self.0[piece as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GameError {
+    #[error("that is not a valid move")]
+    InvalidMove,
+    #[error("{input:?} is not a legal move{}", format_suggestions(.suggestions))]
+    IllegalMove {
+        input: String,
+        suggestions: Vec<String>,
+    },
+    #[error("{input:?} promotes a pawn; which piece? {}", .choices.join(", "))]
+    AmbiguousPromotion { input: String, choices: Vec<String> },
+    #[error("board error: {error:?}")]
+    BoardError { error: BoardError },
+    #[error("search error: {error:?}")]
+    SearchError { error: SearchError },
+    #[error("there is no draw to claim")]
+    NoDrawToClaim,
+}
+
+impl GameError {
+    /// Whether the error comes from the engine itself, rather than from a move
+    /// it was given.
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self,
+            GameError::BoardError { .. } | GameError::SearchError { .. }
+        )
+    }
+}
+
+/// The piece a move promotes a pawn to, if it's a promotion.
+fn promotion_piece(chess_move: &ChessMove) -> Option<Piece> {
+    match chess_move {
+        ChessMove::PawnPromotion(promotion) => Some(promotion.promote_to_piece()),
+        _ => None,
+    }
+}
+
+fn format_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!("; did you mean {}?", suggestions.join(", "))
+    }
+}
+
+/// Counts the position the game starts from towards repetitions, unless the
+/// board already counts the positions it has been through. Moves are counted
+/// before the turn passes, so the position is counted as if it had just been
+/// reached by the other side's move.
+fn count_starting_position(board: &mut Board) {
+    if !board.position_counts().is_empty() {
+        return;
+    }
+    board.toggle_turn();
+    board.count_current_position();
+    board.toggle_turn();
+}
+
+impl Game {
+    pub fn new(search_depth: u8) -> Self {
+        Self::from_board(Board::starting_position(), search_depth)
+    }
+
+    pub fn from_board(mut board: Board, search_depth: u8) -> Self {
+        count_starting_position(&mut board);
+        let engine_config = EngineConfig::default();
+        let mut search_context = SearchContext::new(search_depth);
+        search_context.set_depth_boost(engine_config.depth_boost);
+        Self {
+            position_history: vec![board.current_position_hash()],
+            starting_board: board.clone(),
+            board,
+            move_history: Vec::new(),
+            book: Book::default(),
+            move_generator: MoveGenerator::new(),
+            search_context,
+            engine_config,
+            engine_players: [false; 2],
+            ponder: None,
+            opening_explorer: None,
+            auto_queen: false,
+            claimed_draw: None,
+        }
+    }
+
+    /// Starts the game over from `board`, forgetting the moves made so far and
+    /// the positions they reached. What the engine has learned from its
+    /// searches is kept, as it is from one move to the next.
+    pub fn set_position(&mut self, mut board: Board) {
+        count_starting_position(&mut board);
+        self.position_history = vec![board.current_position_hash()];
+        self.starting_board = board.clone();
+        self.board = board;
+        self.move_history.clear();
+        self.ponder = None;
+        self.claimed_draw = None;
+    }
+
+    /// Starts a new game from `board`: sets up the position (see
+    /// `set_position`), and resets the engine's search state and the opening
+    /// explorer, reusing their memory rather than reallocating it. The
+    /// engine's settings are kept.
+    pub fn new_game(&mut self, board: Board) {
+        self.set_position(board);
+        self.search_context.new_game();
+        if let Some(opening_explorer) = &mut self.opening_explorer {
+            opening_explorer.new_game();
+        }
+    }
+
+    /// Caps the engine's transposition table at `megabytes`, forgetting what it
+    /// has cached so far.
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.search_context.set_hash_size(megabytes);
+    }
+
+    /// Has the engine play out each checkmate it finds before announcing it
+    /// (see `SearchContext::set_verify_mates`).
+    pub fn set_verify_mates(&mut self, verify_mates: bool) {
+        self.search_context.set_verify_mates(verify_mates);
+    }
+
+    /// Aborts engine searches that run far past their budget (see
+    /// `SearchContext::set_watchdog`).
+    pub fn set_watchdog(&mut self, watchdog: Option<Watchdog>) {
+        self.search_context.set_watchdog(watchdog);
+    }
+
+    /// Has the engine follow master games from the Lichess opening explorer once
+    /// its own book runs out, and search once the explorer's do too. The
+    /// explorer fetches positions however its creator chose; `None` turns it
+    /// off.
+    pub fn set_opening_explorer(&mut self, opening_explorer: Option<OpeningExplorer>) {
+        self.opening_explorer = opening_explorer;
+    }
+
+    /// Promotes pawns to queens when a move doesn't say which piece to promote
+    /// to, e.g. "e8" or "e7e8", rather than failing with the choices. Naming the
+    /// piece, e.g. "e8=N" or "e7e8n", still underpromotes.
+    pub fn set_auto_queen(&mut self, auto_queen: bool) {
+        self.auto_queen = auto_queen;
+    }
+
+    /// Has the engine value a draw `contempt` centipawns below even for the
+    /// side it's moving for (see `SearchContext::set_contempt`).
+    pub fn set_contempt(&mut self, contempt: i16) {
+        self.search_context.set_contempt(contempt);
+    }
+
+    pub fn mate_verified(&self) -> Option<bool> {
+        self.search_context.mate_verified()
+    }
+
+    pub fn set_root_jitter(&mut self, root_jitter: Option<RootJitter>) {
+        self.search_context.set_root_jitter(root_jitter);
+    }
+
+    pub fn evaluation_terms(&self) -> &EvaluationTerms {
+        self.search_context.evaluation_terms()
+    }
+
+    /// Sets the evaluation terms the engine uses when searching for its own moves.
+    pub fn set_evaluation_terms(&mut self, evaluation_terms: EvaluationTerms) {
+        self.search_context.set_evaluation_terms(evaluation_terms);
+    }
+
+    pub fn engine_config(&self) -> &EngineConfig {
+        &self.engine_config
+    }
+
+    /// Sets how the engine conducts the game. With move noise, this replaces
+    /// the root jitter.
+    pub fn set_engine_config(&mut self, engine_config: EngineConfig) {
+        self.search_context
+            .set_depth_boost(engine_config.depth_boost);
+        if let Some(noise) = engine_config.move_noise {
+            self.search_context
+                .set_root_jitter((noise.jitter > 0).then(|| RootJitter::new(noise.jitter, None)));
+        }
+        self.engine_config = engine_config;
+    }
+
+    /// The phase the game is in, as the engine's config defines them.
+    pub fn phase(&self) -> GamePhase {
+        self.engine_config
+            .phase(&self.board, self.move_history.len())
+    }
+
+    /// Marks `color` as played by the engine.
+    pub fn set_engine_player(&mut self, color: Color) {
+        self.engine_players[color as usize] = true;
+    }
+
+    /// Gives the search a new control, and returns it, for stopping the next
+    /// search or ending its ponder from another thread.
+    pub fn reset_search_control(&mut self) -> SearchControl {
+        self.search_context.reset_control()
+    }
+
+    pub fn search_control(&self) -> &SearchControl {
+        self.search_context.control()
+    }
+
+    /// Starts thinking on the opponent's time: predicts the reply of the player
+    /// to move, and searches the position after it in the background. If the
+    /// reply is played, the engine's next move comes from that search. Returns
+    /// the predicted reply.
+    pub fn start_pondering(&mut self) -> Option<ChessMove> {
+        self.ponder = None;
+        let predicted_reply = self.predict_reply(self.board.clone())?;
+        let context = self.search_context.clone();
+        self.ponder = PonderSearch::start(&self.board, &predicted_reply, context).ok();
+        Some(predicted_reply)
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponder.is_some()
+    }
+
+    /// The opponent's likely reply if the player to move plays `chess_move`.
+    pub fn predict_reply_to(&mut self, chess_move: &ChessMove) -> Option<ChessMove> {
+        let mut board = self.board.clone();
+        chess_move.apply(&mut board).ok()?;
+        board.toggle_turn();
+        self.predict_reply(board)
+    }
+
+    fn predict_reply(&mut self, mut board: Board) -> Option<ChessMove> {
+        let mut context = SearchContext::new(PREDICTION_DEPTH);
+        alpha_beta_search(&mut context, &mut board, &mut self.move_generator).ok()
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    pub fn move_generator(&self) -> &MoveGenerator {
+        &self.move_generator
+    }
+
+    pub fn move_generator_mut(&mut self) -> &mut MoveGenerator {
+        &mut self.move_generator
+    }
+
+    pub fn enumerated_candidate_moves(&mut self) -> Vec<(ChessMove, String)> {
+        let board = &mut self.board;
+        let current_turn = board.turn();
+        let move_generator = &mut self.move_generator;
+        enumerate_candidate_moves_with_algebraic_notation(board, current_turn, move_generator)
+    }
+
+    /// The legal moves for the player to move, without collecting them into a
+    /// list (see `LegalMoves`).
+    pub fn legal_moves(&mut self) -> LegalMoves<'_> {
+        self.move_generator.legal_moves(&mut self.board)
+    }
+
+    /// The number of legal moves for the player to move. Move generation is
+    /// cached, so this is cheap to call repeatedly for the same position.
+    pub fn legal_move_count(&mut self) -> usize {
+        let turn = self.board.turn();
+        self.move_generator
+            .generate_moves(&mut self.board, turn)
+            .len()
+    }
+
+    /// How many legal moves each of the player to move's pieces has.
+    pub fn mobility(&mut self) -> Mobility {
+        let turn = self.board.turn();
+        let moves = self.move_generator.generate_moves(&mut self.board, turn);
+        let mut mobility = Mobility::default();
+        for chess_move in moves.iter() {
+            if let Some((piece, _)) = self.board.get(chess_move.from_square()) {
+                mobility.0[piece as usize] += 1;
+            }
+        }
+        mobility
+    }
+
+    /// How the game has ended, if it has: by checkmate, stalemate, an
+    /// automatic draw, or a draw a player has claimed.
+    pub fn check_game_over_for_current_turn(&mut self) -> Option<GameEnding> {
+        if self.claimed_draw.is_some() {
+            return Some(GameEnding::Draw);
+        }
+        let turn = self.board.turn();
+        evaluate::game_ending(&mut self.board, &mut self.move_generator, turn)
+    }
+
+    /// The draw either player may claim in the current position, if any.
+    pub fn claimable_draw(&self) -> Option<DrawClaim> {
+        evaluate::claimable_draw(&self.board)
+    }
+
+    /// Claims the draw that's claimable in the current position, ending the
+    /// game.
+    pub fn claim_draw(&mut self) -> Result<DrawClaim, GameError> {
+        let claim = self.claimable_draw().ok_or(GameError::NoDrawToClaim)?;
+        self.claimed_draw = Some(claim);
+        Ok(claim)
+    }
+
+    /// The draw that ended the game, if a player claimed one.
+    pub fn claimed_draw(&self) -> Option<DrawClaim> {
+        self.claimed_draw
+    }
+
+    /// Claims a draw on behalf of the engine, which has just made a move, if
+    /// the move reached a claimable draw and playing on scores worse for the
+    /// engine than the draw. The search scores claimable draws as drawn, so
+    /// the position is scored as if the game went on.
+    fn claim_draw_if_advantageous(&mut self) {
+        let claim = match self.claimable_draw() {
+            Some(claim) => claim,
+            None => return,
+        };
+        // The turn hasn't passed yet, so it's still the engine's.
+        let engine = self.board.turn();
+        let terms = self.search_context.evaluation_terms();
+        self.board.toggle_turn();
+        let score = evaluate::score_playing_on(
+            &mut self.board,
+            &mut self.move_generator,
+            engine.opposite(),
+            terms,
+        );
+        self.board.toggle_turn();
+        let advantage = match engine {
+            Color::White => score - terms.draw_score,
+            Color::Black => terms.draw_score - score,
+        };
+        if advantage < 0 {
+            self.claimed_draw = Some(claim);
+        }
+    }
+
+    pub fn save_move(&mut self, chess_move: ChessMove) {
+        self.move_history.push(chess_move);
+        // The move has been made, but the turn hasn't passed yet, and the hash
+        // covers whose turn it is.
+        self.board.toggle_turn();
+        self.position_history
+            .push(self.board.current_position_hash());
+        self.board.toggle_turn();
+    }
+
+    /// Every move made in the game, in order.
+    pub fn move_history(&self) -> &[ChessMove] {
+        &self.move_history
+    }
+
+    /// Writes the moves played so far as a PGN game. Positions other than the
+    /// standard starting position are recorded with the `SetUp` and `FEN` tags.
+    /// Sides played by the engine are named after its version, and the
+    /// features it was built with are recorded in an `EngineFeatures` tag.
+    pub fn to_pgn(&self) -> Result<PgnGame, GameError> {
+        let mut pgn_game = PgnGame {
+            result: "*".to_string(),
+            ..Default::default()
+        };
+        let seven_tag_roster = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", "*"),
+        ];
+        for (name, value) in seven_tag_roster {
+            pgn_game.set_tag(name, value);
+        }
+        if self.engine_players.contains(&true) {
+            let info = EngineInfo::current();
+            for (color, tag) in [(Color::White, "White"), (Color::Black, "Black")] {
+                if self.engine_players[color as usize] {
+                    pgn_game.set_tag(tag, &info.full_name());
+                }
+            }
+            pgn_game.set_tag("EngineFeatures", &info.feature_list());
+        }
+        let starting_fen = self.starting_board.to_fen();
+        if starting_fen != STARTING_POSITION_FEN {
+            pgn_game.set_tag("SetUp", "1");
+            pgn_game.set_tag("FEN", &starting_fen);
+        }
+
+        for san in self.move_history_san()? {
+            pgn_game.moves.push(PgnMove::new(&san));
+        }
+
+        Ok(pgn_game)
+    }
+
+    /// Every move made in the game, in algebraic notation, found by replaying
+    /// them from the starting position.
+    pub fn move_history_san(&self) -> Result<Vec<String>, GameError> {
+        let mut board = self.starting_board.clone();
+        let mut move_generator = MoveGenerator::new();
+        let mut sans = Vec::with_capacity(self.move_history.len());
+        for chess_move in &self.move_history {
+            let turn = board.turn();
+            let enumerated_candidate_moves = enumerate_candidate_moves_with_algebraic_notation(
+                &mut board,
+                turn,
+                &mut move_generator,
+            );
+            sans.push(algebraic_notation_for(
+                chess_move,
+                &enumerated_candidate_moves,
+            ));
+            chess_move
+                .apply(&mut board)
+                .map_err(|error| GameError::BoardError { error })?;
+            board.toggle_turn();
+        }
+        Ok(sans)
+    }
+
+    /// The game's state, for a bug report (see `DebugSnapshot`).
+    pub fn debug_snapshot(&self) -> DebugSnapshot {
+        let mut position_counts: Vec<(u64, u8)> = self
+            .board
+            .position_counts()
+            .iter()
+            .filter(|(_, &count)| count > 0)
+            .map(|(&hash, &count)| (hash, count))
+            .collect();
+        position_counts.sort();
+        DebugSnapshot {
+            fen: self.board.to_fen(),
+            starting_fen: self.starting_board.to_fen(),
+            moves_uci: self
+                .move_history
+                .iter()
+                .map(|chess_move| chess_move.to_uci())
+                .collect(),
+            // The history may be what's broken, in which case the algebraic
+            // notation can't be worked out.
+            moves_san: self.move_history_san().ok(),
+            halfmove_clock_stack: self.board.halfmove_clock_stack().to_vec(),
+            fullmove_clock: self.board.fullmove_clock(),
+            castle_rights_stack: self.board.castle_rights_stack().to_vec(),
+            position_counts: position_counts
+                .into_iter()
+                .map(|(hash, count)| (format_hash(hash), count))
+                .collect(),
+            position_history: self
+                .position_history
+                .iter()
+                .map(|&hash| format_hash(hash))
+                .collect(),
+            position_hash: format_hash(self.board.current_position_hash()),
+            zobrist_version: format_hash(ZOBRIST_TABLE_VERSION),
+        }
+    }
+
+    /// Writes a debug snapshot of the game to stderr if `error` is a bug,
+    /// rather than e.g. an illegal move entered by the player.
+    pub fn report_error(&self, error: &GameError) {
+        if error.is_internal() {
+            eprintln!("debug snapshot: {}", self.debug_snapshot().to_json());
+        }
+    }
+
+    /// In debug builds, checks the engine's move against the position worked
+    /// out from scratch: that the move was legal, and that the board's
+    /// incrementally updated state (its hash, and the pieces its material is
+    /// scored from) matches the same position read back from its FEN. Panics
+    /// with a debug snapshot if not. The turn hasn't passed yet.
+    #[cfg(debug_assertions)]
+    fn assert_consistent_after_engine_move(&mut self, chess_move: &ChessMove) {
+        let fail = |game: &Game, problem: String| -> ! {
+            panic!(
+                "{} after the engine played {}; debug snapshot: {}",
+                problem,
+                chess_move.to_uci(),
+                game.debug_snapshot().to_json()
+            )
+        };
+
+        let mut before = self.board.clone();
+        if let Err(error) = chess_move.undo(&mut before) {
+            fail(self, format!("the move can't be undone ({:?})", error));
+        }
+        let turn = before.turn();
+        let legal = self
+            .move_generator
+            .generate_moves(&mut before, turn)
+            .iter()
+            .any(|legal_move| legal_move.to_uci() == chess_move.to_uci());
+        if !legal {
+            fail(self, "the move isn't legal".to_string());
+        }
+
+        let fen = self.board.to_fen();
+        let rebuilt = match Board::from_fen(&fen) {
+            Ok(rebuilt) => rebuilt,
+            Err(error) => fail(self, format!("{:?} can't be read back ({})", fen, error)),
+        };
+        if rebuilt.current_position_hash() != self.board.current_position_hash() {
+            fail(
+                self,
+                format!(
+                    "the hash is {}, but {}",
+                    format_hash(self.board.current_position_hash()),
+                    format_hash(rebuilt.current_position_hash())
+                ),
+            );
+        }
+        for color in [Color::White, Color::Black] {
+            if rebuilt.pieces(color) != self.board.pieces(color) {
+                fail(self, format!("the {} pieces are out of sync", color));
+            }
+        }
+        let material = evaluate::board_material_score(&self.board);
+        let rebuilt_material = evaluate::board_material_score(&rebuilt);
+        if material != rebuilt_material {
+            fail(
+                self,
+                format!("the material scores {}, but {}", material, rebuilt_material),
+            );
+        }
+    }
+
+    pub fn most_recent_move(&self) -> Option<ChessMove> {
+        self.move_history.iter().last().cloned()
+    }
+
+    /// Applies the move between the squares. A promotion is made to
+    /// `promote_to`, or if it's `None`, see `set_auto_queen`.
+    pub fn apply_chess_move_by_from_to_coordinates(
+        &mut self,
+        from_square: Bitboard,
+        to_square: Bitboard,
+        promote_to: Option<Piece>,
+    ) -> Result<ChessMove, GameError> {
+        let enumerated_candidate_moves = self.enumerated_candidate_moves();
+        let matching: Vec<(ChessMove, String)> = enumerated_candidate_moves
+            .iter()
+            .filter(|(m, _)| m.from_square() == from_square && m.to_square() == to_square)
+            .filter(|(m, _)| promote_to.is_none() || promotion_piece(m) == promote_to)
+            .cloned()
+            .collect();
+        let input = format!(
+            "{}{}{}",
+            to_algebraic(from_square),
+            to_algebraic(to_square),
+            promote_to.map_or(String::new(), |piece| piece
+                .to_algebraic_str()
+                .to_lowercase())
+        );
+        let chess_move = match matching.len() {
+            0 => {
+                let suggestions = suggest_moves(&input, &enumerated_candidate_moves);
+                return Err(GameError::IllegalMove { input, suggestions });
+            }
+            1 => matching[0].0.clone(),
+            _ => self.choose_promotion(input, matching)?,
+        };
+        self.apply_chess_move(chess_move.clone())?;
+        Ok(chess_move)
+    }
+
+    /// Picks the queen among a pawn's promotions, if the player has asked for
+    /// that, or else fails with the choices so that they can be asked.
+    fn choose_promotion(
+        &self,
+        input: String,
+        promotions: Vec<(ChessMove, String)>,
+    ) -> Result<ChessMove, GameError> {
+        match promotions
+            .iter()
+            .find(|(m, _)| promotion_piece(m) == Some(Piece::Queen))
+        {
+            Some((queen, _)) if self.auto_queen => Ok(queen.clone()),
+            _ => Err(GameError::AmbiguousPromotion {
+                input,
+                choices: promotions.into_iter().map(|(_, san)| san).collect(),
+            }),
+        }
+    }
+
+    pub fn apply_chess_move(&mut self, chess_move: ChessMove) -> Result<(), GameError> {
+        match chess_move.apply(&mut self.board) {
+            Ok(_capture) => {
+                self.save_move(chess_move.clone());
+                Ok(())
+            }
+            Err(error) => Err(GameError::BoardError { error }),
+        }
+    }
+
+    pub fn apply_chess_move_from_raw_algebraic_notation(
+        &mut self,
+        algebraic: String,
+    ) -> Result<ChessMove, GameError> {
+        let board = &mut self.board;
+        let current_turn = board.turn();
+        let move_generator = &mut self.move_generator;
+        let enumerated_candidate_moves =
+            enumerate_candidate_moves_with_algebraic_notation(board, current_turn, move_generator);
+        if let Some((chess_move, _)) = enumerated_candidate_moves.iter().find(|m| m.1 == algebraic)
+        {
+            let chess_move = chess_move.clone();
+            self.apply_chess_move(chess_move.clone())?;
+            return Ok(chess_move);
+        }
+
+        // A pawn move to the last rank that doesn't name a piece, e.g. "e8".
+        let normalized = normalize_san(&algebraic);
+        let promotions: Vec<(ChessMove, String)> = enumerated_candidate_moves
+            .iter()
+            .filter(|(_, san)| {
+                let san = normalize_san(san);
+                match san.split_once('=') {
+                    Some((pawn_move, _)) => pawn_move == normalized,
+                    None => false,
+                }
+            })
+            .cloned()
+            .collect();
+        if promotions.is_empty() {
+            return Err(GameError::IllegalMove {
+                suggestions: suggest_moves(&algebraic, &enumerated_candidate_moves),
+                input: algebraic,
+            });
+        }
+        let chess_move = self.choose_promotion(algebraic, promotions)?;
+        self.apply_chess_move(chess_move.clone())?;
+        Ok(chess_move)
+    }
+
+    pub fn select_alpha_beta_best_move(&mut self) -> Result<ChessMove, GameError> {
+        alpha_beta_search(
+            &mut self.search_context,
+            &mut self.board,
+            &mut self.move_generator,
+        )
+        .map_err(|err| GameError::SearchError { error: err })
+    }
+
+    pub fn make_alpha_beta_best_move(&mut self) -> Result<ChessMove, GameError> {
+        let best_move = self.select_alpha_beta_best_move()?;
+        best_move
+            .apply(&mut self.board)
+            .map_err(|error| GameError::BoardError { error })?;
+        self.save_move(best_move.clone());
+        #[cfg(debug_assertions)]
+        self.assert_consistent_after_engine_move(&best_move);
+        self.claim_draw_if_advantageous();
+        Ok(best_move)
+    }
+
+    pub fn select_waterfall_book_then_alpha_beta_best_move(
+        &mut self,
+    ) -> Result<ChessMove, GameError> {
+        // If the opponent played the predicted reply, the ponder search has been
+        // searching this position all along. Otherwise, dropping it stops it.
+        if let Some(ponder) = self.ponder.take() {
+            if ponder.is_searching(&self.board) {
+                let (result, context) = ponder.ponderhit();
+                self.search_context = context;
+                return result.map_err(|error| GameError::SearchError { error });
+            }
+        }
+
+        self.search_context.time_manager_mut().start();
+        if self
+            .engine_config
+            .plays_random_move(self.move_history.len())
+        {
+            if let Some(chess_move) = self.select_random_move() {
+                return Ok(chess_move);
+            }
+        }
+        if !self.engine_config.allows_book_move(self.move_history.len()) {
+            return self.select_alpha_beta_best_move();
+        }
+        let current_turn = self.board.turn();
+        let line = self.get_book_line();
+        let candidate_book_moves = self.book.get_next_moves(line);
+
+        if candidate_book_moves.is_empty() {
+            if let Some(chess_move) = self.select_opening_explorer_move() {
+                return Ok(chess_move);
+            }
+            return self.select_alpha_beta_best_move();
+        }
+
+        let candidates = self
+            .move_generator
+            .generate_moves_and_lazily_update_chess_move_effects(&mut self.board, current_turn);
+
+        let mut book_chess_moves = Vec::with_capacity(candidate_book_moves.len());
+        for (book_move, _line_name) in &candidate_book_moves {
+            let chess_move = candidates
+                .iter()
+                .find(|m| {
+                    m.from_square() == book_move.from_square()
+                        && m.to_square() == book_move.to_square()
+                })
+                .ok_or(GameError::InvalidMove)?;
+            book_chess_moves.push(chess_move.clone());
+        }
+
+        // Some lines transpose back into positions that were already played.
+        // Following them risks drawing by shuffling through known positions, so
+        // leave the book and let the search take over instead.
+        if book_chess_moves
+            .iter()
+            .any(|chess_move| self.repeats_position(chess_move))
+        {
+            return self.select_alpha_beta_best_move();
+        }
+
+        let rng = rand::thread_rng().gen_range(0..book_chess_moves.len());
+        Ok(book_chess_moves.swap_remove(rng))
+    }
+
+    /// A legal move chosen at random, if there is one.
+    fn select_random_move(&mut self) -> Option<ChessMove> {
+        let current_turn = self.board.turn();
+        let mut candidates = self
+            .move_generator
+            .generate_moves_and_lazily_update_chess_move_effects(&mut self.board, current_turn);
+        if candidates.is_empty() {
+            return None;
+        }
+        let rng = rand::thread_rng().gen_range(0..candidates.len());
+        Some(candidates.swap_remove(rng))
+    }
+
+    /// A move masters have played in this position, if the opening explorer is
+    /// enabled and still has games for it.
+    fn select_opening_explorer_move(&mut self) -> Option<ChessMove> {
+        let uci = self
+            .opening_explorer
+            .as_mut()?
+            .choose_move(&self.board.to_fen())?;
+        let current_turn = self.board.turn();
+        let chess_move = self
+            .move_generator
+            .generate_moves_and_lazily_update_chess_move_effects(&mut self.board, current_turn)
+            .into_iter()
+            .find(|chess_move| chess_move.to_uci() == uci)?;
+        if self.repeats_position(&chess_move) {
+            return None;
+        }
+        Some(chess_move)
+    }
+
+    /// Whether playing the move would return to a position already seen in this game.
+    fn repeats_position(&self, chess_move: &ChessMove) -> bool {
+        let mut board = self.board.clone();
+        match chess_move.apply(&mut board) {
+            Ok(_capture) => {
+                board.toggle_turn();
+                self.position_history
+                    .contains(&board.current_position_hash())
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Makes the engine's move, and claims a draw with it if that's better
+    /// than playing on.
+    pub fn make_waterfall_book_then_alpha_beta_move(&mut self) -> Result<ChessMove, GameError> {
+        let chess_move = self.select_waterfall_book_then_alpha_beta_best_move()?;
+        match chess_move.apply(&mut self.board) {
+            Ok(_capture) => {
+                self.save_move(chess_move.clone());
+                #[cfg(debug_assertions)]
+                self.assert_consistent_after_engine_move(&chess_move);
+                self.claim_draw_if_advantageous();
+                Ok(chess_move.clone())
+            }
+            Err(error) => Err(GameError::BoardError { error }),
+        }
+    }
+
+    pub fn score(&mut self, current_turn: Color) -> i16 {
+        evaluate::score(&mut self.board, &mut self.move_generator, current_turn, 0)
+    }
+
+    pub fn fullmove_clock(&self) -> u16 {
+        self.board.fullmove_clock()
+    }
+
+    pub fn searched_position_count(&self) -> usize {
+        self.search_context.searched_position_count()
+    }
+
+    pub fn alpha_beta_cache_hit_count(&self) -> usize {
+        self.search_context.cache_hit_count()
+    }
+
+    pub fn alpha_beta_termination_count(&self) -> usize {
+        self.search_context.termination_count()
+    }
+
+    pub fn search_depth(&self) -> u8 {
+        self.search_context.search_depth()
+    }
+
+    pub fn set_search_depth(&mut self, search_depth: u8) {
+        self.search_context.set_search_depth(search_depth);
+    }
+
+    /// The clock for the engine's most recent (or current) move.
+    pub fn time_manager(&self) -> &TimeManager {
+        self.search_context.time_manager()
+    }
+
+    pub fn time_manager_mut(&mut self) -> &mut TimeManager {
+        self.search_context.time_manager_mut()
+    }
+
+    pub fn alpha_beta_score(&self) -> Option<i16> {
+        self.search_context.last_score()
+    }
+
+    pub fn depth_stats(&self) -> &[DepthStats] {
+        self.search_context.depth_stats()
+    }
+
+    pub fn hash_fill_percentage(&self) -> f64 {
+        self.search_context.hash_fill_percentage()
+    }
+
+    pub fn move_generator_cache_hit_count(&self) -> usize {
+        self.move_generator.cache_hit_count()
+    }
+
+    pub fn move_genereator_cache_entry_count(&self) -> usize {
+        self.move_generator.cache_entry_count()
+    }
+
+    pub fn reset_move_generator_cache_hit_count(&mut self) {
+        self.move_generator.reset_cache_hit_count();
+    }
+
+    pub fn last_move(&self) -> Option<ChessMove> {
+        self.move_history.last().cloned()
+    }
+
+    pub fn get_book_line_name(&self) -> Option<String> {
+        let line = self.get_book_line();
+        self.book.get_line(line)
+    }
+
+    fn get_book_line(&self) -> Vec<BookMove> {
+        self.move_history
+            .iter()
+            .map(|m| BookMove::new(m.from_square(), m.to_square()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::castle_rights_bitmask::{
+        ALL_CASTLE_RIGHTS, BLACK_KINGSIDE_RIGHTS, BLACK_QUEENSIDE_RIGHTS,
+    };
+    use crate::chess_move::chess_move::ChessMove;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::{chess_position, std_move};
+    use common::bitboard::square;
+
+    #[test]
+    fn test_score() {
+        let mut game = Game::new(0);
+        game.apply_chess_move_by_from_to_coordinates(square::E2, square::E4, None)
+            .unwrap();
+        game.board.toggle_turn();
+        assert!(game.check_game_over_for_current_turn().is_none());
+    }
+
+    #[test]
+    fn test_checkmate() {
+        let mut game = Game::new(0);
+        game.apply_chess_move_by_from_to_coordinates(square::F2, square::F3, None)
+            .unwrap();
+        game.board.toggle_turn();
+        game.apply_chess_move_by_from_to_coordinates(square::E7, square::E6, None)
+            .unwrap();
+        game.board.toggle_turn();
+        game.apply_chess_move_by_from_to_coordinates(square::G2, square::G4, None)
+            .unwrap();
+        game.board.toggle_turn();
+        game.apply_chess_move_by_from_to_coordinates(square::D8, square::H4, None)
+            .unwrap();
+        game.board.toggle_turn();
+        println!("Testing board:\n{}", game.board);
+        matches!(
+            game.check_game_over_for_current_turn(),
+            Some(GameEnding::Checkmate)
+        );
+    }
+
+    #[test]
+    fn test_promotion_without_a_piece() {
+        let promotion = || {
+            let board = Board::from_fen("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+            Game::from_board(board, 0)
+        };
+        let promoted_to = |game: &Game, square| game.board.get(square).unwrap().0;
+
+        let mut game = promotion();
+        match game.apply_chess_move_from_raw_algebraic_notation("e8".to_string()) {
+            Err(GameError::AmbiguousPromotion { choices, .. }) => {
+                assert_eq!(choices, ["e8=Q+", "e8=R+", "e8=B", "e8=N"]);
+            }
+            result => panic!("expected the promotion choices, got {:?}", result),
+        }
+        assert!(matches!(
+            game.apply_chess_move_by_from_to_coordinates(square::E7, square::D8, None),
+            Err(GameError::AmbiguousPromotion { .. })
+        ));
+
+        let mut game = promotion();
+        game.set_auto_queen(true);
+        game.apply_chess_move_from_raw_algebraic_notation("exd8".to_string())
+            .unwrap();
+        assert_eq!(promoted_to(&game, square::D8), Piece::Queen);
+
+        // Naming the piece still underpromotes.
+        let mut game = promotion();
+        game.set_auto_queen(true);
+        game.apply_chess_move_from_raw_algebraic_notation("e8=N".to_string())
+            .unwrap();
+        assert_eq!(promoted_to(&game, square::E8), Piece::Knight);
+
+        let mut game = promotion();
+        game.set_auto_queen(true);
+        game.apply_chess_move_by_from_to_coordinates(square::E7, square::D8, Some(Piece::Rook))
+            .unwrap();
+        assert_eq!(promoted_to(&game, square::D8), Piece::Rook);
+    }
+
+    #[test]
+    fn test_draw_from_repetition() {
+        let mut board = chess_position! {
+            .......k
+            .......r
+            ........
+            ........
+            ........
+            ........
+            K.......
+            R.......
+        };
+        board.set_turn(Color::White);
+        board.lose_castle_rights(ALL_CASTLE_RIGHTS);
+
+        let mut game = Game::from_board(board, 0);
+        println!("Testing board:\n{}", game.board);
+        assert!(matches!(game.claim_draw(), Err(GameError::NoDrawToClaim)));
+
+        let moves = [
+            std_move!(square::A2, square::A3),
+            std_move!(square::H8, square::G8),
+            std_move!(square::A3, square::A2),
+            std_move!(square::G8, square::H8),
+        ];
+
+        for m in moves.iter() {
+            m.apply(&mut game.board).unwrap();
+            game.board.toggle_turn();
+        }
+
+        // back in starting position for second time
+        assert_eq!(game.claimable_draw(), None);
+        assert!(game.check_game_over_for_current_turn().is_none());
+
+        for m in moves.iter() {
+            m.apply(&mut game.board).unwrap();
+            game.board.toggle_turn();
+        }
+
+        // back in starting position for third time, which can be claimed as a
+        // draw, but isn't one until it is
+        assert_eq!(game.claimable_draw(), Some(DrawClaim::ThreefoldRepetition));
+        assert!(game.check_game_over_for_current_turn().is_none());
+        assert_eq!(game.claim_draw().unwrap(), DrawClaim::ThreefoldRepetition);
+        assert!(matches!(
+            game.check_game_over_for_current_turn(),
+            Some(GameEnding::Draw)
+        ));
+    }
+
+    #[test]
+    fn test_draw_from_fivefold_repetition() {
+        let mut game = Game::new(0);
+        for _ in 0..4 {
+            for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                game.apply_chess_move_from_raw_algebraic_notation(san.to_string())
+                    .unwrap();
+                game.board_mut().toggle_turn();
+            }
+        }
+        assert!(matches!(
+            game.check_game_over_for_current_turn(),
+            Some(GameEnding::Draw)
+        ));
+        assert_eq!(game.claimed_draw(), None);
+    }
+
+    #[test]
+    fn test_engine_claims_draw_only_when_behind() {
+        // Every black move completes fifty moves without a capture or a pawn
+        // move. Black is two queens down, so it claims the draw.
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/QQ2K3 b - - 99 80").unwrap();
+        let mut game = Game::from_board(board, 1);
+        game.make_alpha_beta_best_move().unwrap();
+        assert_eq!(game.claimed_draw(), Some(DrawClaim::FiftyMoveRule));
+
+        // White is winning, so it plays on.
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/QQ2K3 w - - 99 80").unwrap();
+        let mut game = Game::from_board(board, 1);
+        game.make_alpha_beta_best_move().unwrap();
+        assert_eq!(game.claimed_draw(), None);
+    }
+
+    #[test]
+    fn test_repeats_position() {
+        let mut game = Game::new(0);
+        for san in ["Nf3", "Nf6", "Ng1"] {
+            game.apply_chess_move_from_raw_algebraic_notation(san.to_string())
+                .unwrap();
+            game.board.toggle_turn();
+        }
+
+        let candidates = game.enumerated_candidate_moves();
+        let find = |san: &str| {
+            candidates
+                .iter()
+                .find(|(_, notation)| notation == san)
+                .unwrap()
+                .0
+                .clone()
+        };
+        assert!(game.repeats_position(&find("Ng8")));
+        assert!(!game.repeats_position(&find("e5")));
+    }
+
+    #[test]
+    fn test_debug_snapshot() {
+        let mut game = Game::new(0);
+        for san in ["e4", "e5", "Ke2"] {
+            game.apply_chess_move_from_raw_algebraic_notation(san.to_string())
+                .unwrap();
+            game.board.toggle_turn();
+        }
+
+        let snapshot = game.debug_snapshot();
+        assert_eq!(snapshot.fen, game.board.to_fen());
+        assert_eq!(snapshot.starting_fen, STARTING_POSITION_FEN);
+        assert_eq!(snapshot.moves_uci, vec!["e2e4", "e7e5", "e1e2"]);
+        assert_eq!(
+            snapshot.moves_san,
+            Some(vec!["e4".to_string(), "e5".to_string(), "Ke2".to_string()])
+        );
+        assert_eq!(snapshot.halfmove_clock_stack, vec![0, 0, 0, 1]);
+        assert_eq!(snapshot.fullmove_clock, game.board.fullmove_clock());
+        let black_castle_rights = BLACK_KINGSIDE_RIGHTS | BLACK_QUEENSIDE_RIGHTS;
+        assert_eq!(
+            snapshot.castle_rights_stack,
+            vec![
+                ALL_CASTLE_RIGHTS,
+                ALL_CASTLE_RIGHTS,
+                ALL_CASTLE_RIGHTS,
+                black_castle_rights
+            ]
+        );
+        assert_eq!(snapshot.position_history.len(), 4);
+        assert_eq!(
+            snapshot.position_history.last(),
+            Some(&snapshot.position_hash)
+        );
+        assert!(snapshot.to_json().contains("\"moves_uci\":[\"e2e4\""));
+
+        assert!(!GameError::InvalidMove.is_internal());
+        assert!(GameError::SearchError {
+            error: SearchError::Stopped
+        }
+        .is_internal());
+    }
+
+    #[test]
+    fn test_legal_move_count_and_mobility() {
+        let mut game = Game::new(0);
+        assert_eq!(game.legal_move_count(), 20);
+
+        let mobility = game.mobility();
+        assert_eq!(mobility.for_piece(Piece::Pawn), 16);
+        assert_eq!(mobility.for_piece(Piece::Knight), 4);
+        assert_eq!(mobility.for_piece(Piece::Bishop), 0);
+        assert_eq!(mobility.total(), 20);
+
+        for san in ["e4", "e5"] {
+            game.apply_chess_move_from_raw_algebraic_notation(san.to_string())
+                .unwrap();
+            game.board.toggle_turn();
+        }
+        assert_eq!(game.legal_move_count(), 29);
+        let mobility = game.mobility();
+        assert_eq!(mobility.for_piece(Piece::Bishop), 5);
+        assert_eq!(mobility.for_piece(Piece::Queen), 4);
+        assert_eq!(mobility.for_piece(Piece::King), 1);
+    }
+
+    #[test]
+    fn test_to_pgn() {
+        let mut game = Game::new(0);
+        for san in ["e4", "e5", "Nf3"] {
+            game.apply_chess_move_from_raw_algebraic_notation(san.to_string())
+                .unwrap();
+            game.board.toggle_turn();
+        }
+        let pgn_game = game.to_pgn().unwrap();
+        assert_eq!(pgn_game.tag("Result"), Some("*"));
+        assert_eq!(pgn_game.tag("FEN"), None);
+        assert_eq!(pgn_game.tag("White"), Some("?"));
+        assert_eq!(pgn_game.tag("EngineFeatures"), None);
+        assert!(pgn_game.to_string().ends_with("\n\n1. e4 e5 2. Nf3 *\n"));
+
+        let board = Board::from_fen("8/8/8/8/8/8/4P3/K6k w - - 0 1").unwrap();
+        let mut game = Game::from_board(board, 0);
+        game.set_engine_player(Color::Black);
+        let pgn_game = game.to_pgn().unwrap();
+        let info = EngineInfo::current();
+        assert_eq!(pgn_game.tag("White"), Some("?"));
+        assert_eq!(pgn_game.tag("Black"), Some(info.full_name().as_str()));
+        assert_eq!(
+            pgn_game.tag("EngineFeatures"),
+            Some(info.feature_list().as_str())
+        );
+        assert_eq!(pgn_game.tag("SetUp"), Some("1"));
+        assert_eq!(pgn_game.tag("FEN"), Some("8/8/8/8/8/8/4P3/K6k w - - 0 1"));
+    }
+
+    #[test]
+    fn test_new_game() {
+        let mut game = Game::new(2);
+        game.set_hash_size(1);
+        game.make_alpha_beta_best_move().unwrap();
+        game.board.toggle_turn();
+        assert!(game.hash_fill_percentage() > 0.0);
+        assert!(game.alpha_beta_score().is_some());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        game.new_game(board.clone());
+        assert_eq!(game.board().to_fen(), board.to_fen());
+        assert!(game.move_history().is_empty());
+        assert_eq!(game.position_history, vec![board.current_position_hash()]);
+        assert_eq!(game.hash_fill_percentage(), 0.0);
+        assert_eq!(game.alpha_beta_score(), None);
+        assert_eq!(game.searched_position_count(), 0);
+        assert_eq!(game.search_depth(), 2);
+        assert!(game.to_pgn().unwrap().tag("FEN").is_some());
+    }
+
+    #[test]
+    fn test_ponder() {
+        let fen = "4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1";
+        let mut game = Game::from_board(Board::from_fen(fen).unwrap(), 2);
+        let predicted_reply = game.start_pondering().unwrap();
+        assert!(game.is_pondering());
+        game.apply_chess_move(predicted_reply.clone()).unwrap();
+        game.board.toggle_turn();
+
+        let mut fresh_game = Game::from_board(game.board().clone(), 2);
+        let expected = fresh_game.select_alpha_beta_best_move().unwrap();
+        let chess_move = game
+            .select_waterfall_book_then_alpha_beta_best_move()
+            .unwrap();
+        assert_eq!(chess_move, expected);
+        assert!(!game.is_pondering());
+        assert!(!game.time_manager().is_pondering());
+
+        // Another reply abandons the ponder search and searches from scratch.
+        let mut game = Game::from_board(Board::from_fen(fen).unwrap(), 2);
+        let predicted_reply = game.start_pondering().unwrap();
+        let other_reply = game
+            .enumerated_candidate_moves()
+            .into_iter()
+            .map(|(chess_move, _)| chess_move)
+            .find(|chess_move| *chess_move != predicted_reply)
+            .unwrap();
+        game.apply_chess_move(other_reply).unwrap();
+        game.board.toggle_turn();
+        assert!(game
+            .select_waterfall_book_then_alpha_beta_best_move()
+            .is_ok());
+        assert!(!game.is_pondering());
+    }
+
+    #[test]
+    fn test_illegal_move_suggestions() {
+        let mut game = Game::new(0);
+        let error = game
+            .apply_chess_move_by_from_to_coordinates(square::G1, square::G3, None)
+            .unwrap_err();
+        let GameError::IllegalMove { input, suggestions } = &error else {
+            panic!("expected an illegal move error, got {:?}", error);
+        };
+        assert_eq!(input, "g1g3");
+        assert_eq!(suggestions[0], "g3");
+        assert!(suggestions.contains(&"Nf3".to_string()));
+        assert!(error
+            .to_string()
+            .starts_with("\"g1g3\" is not a legal move; did you mean g3, "));
+
+        let error = game
+            .apply_chess_move_from_raw_algebraic_notation("Nf3+".to_string())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "\"Nf3+\" is not a legal move; did you mean Nf3?"
+        );
+    }
+}
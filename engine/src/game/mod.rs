@@ -0,0 +1,6 @@
+pub mod debug_snapshot;
+pub mod engine_config;
+#[allow(clippy::module_inception)]
+pub mod game;
+pub mod opening_explorer;
+mod ponder;
@@ -0,0 +1,214 @@
+use log::warn;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// A position needs this many master games before the engine follows them.
+/// With fewer, the games are too few to say much, and the book has run out.
+const MIN_GAMES: u64 = 50;
+
+/// A move masters have played in a position, and how their games turned out.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ExplorerMove {
+    pub uci: String,
+    pub san: String,
+    pub white: u64,
+    pub draws: u64,
+    pub black: u64,
+}
+
+impl ExplorerMove {
+    pub fn games(&self) -> u64 {
+        self.white + self.draws + self.black
+    }
+}
+
+#[derive(Deserialize)]
+struct ExplorerResponse {
+    moves: Vec<ExplorerMove>,
+}
+
+#[derive(Error, Debug)]
+pub enum ExplorerError {
+    #[error("the request failed: {error}")]
+    RequestFailed { error: String },
+    #[error("unexpected response: {error}")]
+    InvalidResponse { error: String },
+}
+
+/// Fetches the explorer's JSON response for a position, given as a FEN. The
+/// engine doesn't reach the network itself, so whoever enables the explorer
+/// supplies this.
+pub type FetchResponse = fn(&str) -> Result<Vec<u8>, ExplorerError>;
+
+/// An online opening book: the moves masters played in each position, from the
+/// Lichess opening explorer, chosen in proportion to how often they were played.
+/// Positions are fetched with `fetch`, and each is only fetched once. Once a
+/// position has too few games, or the explorer can't be reached, the book has
+/// run out for the rest of the game, and the engine searches instead.
+pub struct OpeningExplorer {
+    fetch: FetchResponse,
+    moves_by_fen: FxHashMap<String, Vec<ExplorerMove>>,
+    out_of_book: bool,
+}
+
+impl OpeningExplorer {
+    pub fn new(fetch: FetchResponse) -> Self {
+        Self {
+            fetch,
+            moves_by_fen: FxHashMap::default(),
+            out_of_book: false,
+        }
+    }
+
+    /// Follows the explorer again after it ran out in the last game. Positions
+    /// already fetched stay cached.
+    pub fn new_game(&mut self) {
+        self.out_of_book = false;
+    }
+
+    /// A move for the position, in UCI notation, or `None` if the book has run
+    /// out.
+    pub fn choose_move(&mut self, fen: &str) -> Option<String> {
+        if self.out_of_book {
+            return None;
+        }
+        let choice = match self.moves(fen) {
+            Ok(moves) => {
+                let total = moves.iter().map(ExplorerMove::games).sum::<u64>();
+                let roll = rand::thread_rng().gen_range(0..total.max(1));
+                choose_weighted(moves, roll).map(|explorer_move| explorer_move.uci.clone())
+            }
+            Err(error) => {
+                warn!("opening explorer unavailable: {}", error);
+                None
+            }
+        };
+        if choice.is_none() {
+            self.out_of_book = true;
+        }
+        choice
+    }
+
+    fn moves(&mut self, fen: &str) -> Result<&[ExplorerMove], ExplorerError> {
+        if !self.moves_by_fen.contains_key(fen) {
+            let moves = parse_moves(&(self.fetch)(fen)?)?;
+            self.moves_by_fen.insert(fen.to_string(), moves);
+        }
+        Ok(&self.moves_by_fen[fen])
+    }
+}
+
+fn parse_moves(body: &[u8]) -> Result<Vec<ExplorerMove>, ExplorerError> {
+    let response: ExplorerResponse =
+        serde_json::from_slice(body).map_err(|error| ExplorerError::InvalidResponse {
+            error: error.to_string(),
+        })?;
+    Ok(response
+        .moves
+        .into_iter()
+        .map(|explorer_move| match explorer_move.san.starts_with("O-O") {
+            true => ExplorerMove {
+                uci: castle_to_uci(&explorer_move.uci),
+                ..explorer_move
+            },
+            false => explorer_move,
+        })
+        .collect())
+}
+
+/// The explorer writes castling as the king taking its own rook, e.g. "e1h1",
+/// rather than as the king's move, "e1g1".
+fn castle_to_uci(uci: &str) -> String {
+    match uci {
+        "e1h1" => "e1g1",
+        "e1a1" => "e1c1",
+        "e8h8" => "e8g8",
+        "e8a8" => "e8c8",
+        uci => uci,
+    }
+    .to_string()
+}
+
+/// The move that `roll`, between 0 and the total number of games, lands on when
+/// each move is given as many numbers as it has games. `None` if there are
+/// fewer than `MIN_GAMES` games.
+fn choose_weighted(moves: &[ExplorerMove], mut roll: u64) -> Option<&ExplorerMove> {
+    let total = moves.iter().map(ExplorerMove::games).sum::<u64>();
+    if total < MIN_GAMES {
+        return None;
+    }
+    moves.iter().find(|explorer_move| {
+        if roll < explorer_move.games() {
+            return true;
+        }
+        roll -= explorer_move.games();
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"{
+        "white": 900, "draws": 700, "black": 400,
+        "moves": [
+            {"uci": "e1h1", "san": "O-O", "averageRating": 2500, "white": 600, "draws": 500, "black": 300, "game": null},
+            {"uci": "d2d4", "san": "d4", "averageRating": 2480, "white": 300, "draws": 200, "black": 100, "game": null}
+        ],
+        "topGames": []
+    }"#;
+
+    #[test]
+    fn test_parse_moves() {
+        let moves = parse_moves(RESPONSE.as_bytes()).unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].uci, "e1g1");
+        assert_eq!(moves[0].games(), 1400);
+        assert_eq!(moves[1].uci, "d2d4");
+
+        // Only castling is rewritten; a rook can still move from e1 to h1.
+        let rook_move =
+            r#"{"moves": [{"uci": "e1h1", "san": "Rh1", "white": 1, "draws": 0, "black": 0}]}"#;
+        assert_eq!(parse_moves(rook_move.as_bytes()).unwrap()[0].uci, "e1h1");
+
+        assert!(parse_moves(b"<html>").is_err());
+    }
+
+    #[test]
+    fn test_choose_move() {
+        let mut explorer = OpeningExplorer::new(|_| Ok(RESPONSE.as_bytes().to_vec()));
+        let fen = "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let chess_move = explorer.choose_move(fen).unwrap();
+        assert!(["e1g1", "d2d4"].contains(&chess_move.as_str()));
+
+        // Without the explorer, the engine searches instead.
+        let mut explorer = OpeningExplorer::new(|_| {
+            Err(ExplorerError::RequestFailed {
+                error: "offline".to_string(),
+            })
+        });
+        assert_eq!(explorer.choose_move(fen), None);
+    }
+
+    #[test]
+    fn test_choose_weighted() {
+        let moves = parse_moves(RESPONSE.as_bytes()).unwrap();
+        assert_eq!(choose_weighted(&moves, 0).unwrap().uci, "e1g1");
+        assert_eq!(choose_weighted(&moves, 1399).unwrap().uci, "e1g1");
+        assert_eq!(choose_weighted(&moves, 1400).unwrap().uci, "d2d4");
+        assert_eq!(choose_weighted(&moves, 1999).unwrap().uci, "d2d4");
+
+        let rare = [ExplorerMove {
+            uci: "g2g4".to_string(),
+            san: "g4".to_string(),
+            white: 10,
+            draws: 0,
+            black: 20,
+        }];
+        assert_eq!(choose_weighted(&rare, 0), None);
+        assert_eq!(choose_weighted(&[], 0), None);
+    }
+}
@@ -0,0 +1,77 @@
+use std::thread::{self, JoinHandle};
+
+use crate::alpha_beta_searcher::search_control::SearchControl;
+use crate::alpha_beta_searcher::{alpha_beta_search, SearchContext, SearchError};
+use crate::board::error::BoardError;
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+use crate::move_generator::MoveGenerator;
+
+type PonderResult = (Result<ChessMove, SearchError>, SearchContext);
+
+/// A search of the position after the opponent's predicted reply, run in the
+/// background on the opponent's time. Dropping it stops the search.
+pub struct PonderSearch {
+    /// The hash of the position being searched, after the predicted reply.
+    position_hash: u64,
+    control: SearchControl,
+    handle: Option<JoinHandle<PonderResult>>,
+}
+
+impl PonderSearch {
+    /// Starts searching the position after `predicted_reply` is played on
+    /// `board`. `context` should be a clone of the engine's own, so that the
+    /// results it caches carry over to the engine's next search.
+    pub fn start(
+        board: &Board,
+        predicted_reply: &ChessMove,
+        mut context: SearchContext,
+    ) -> Result<Self, BoardError> {
+        let mut board = board.clone();
+        predicted_reply.apply(&mut board)?;
+        board.toggle_turn();
+        let position_hash = board.current_position_hash();
+
+        let control = context.reset_control();
+        context.time_manager_mut().start_pondering();
+        let handle = thread::spawn(move || {
+            let mut move_generator = MoveGenerator::new();
+            let result = alpha_beta_search(&mut context, &mut board, &mut move_generator);
+            (result, context)
+        });
+
+        Ok(Self {
+            position_hash,
+            control,
+            handle: Some(handle),
+        })
+    }
+
+    /// Whether this is a search of `board`, i.e. the predicted reply was played.
+    pub fn is_searching(&self, board: &Board) -> bool {
+        self.position_hash == board.current_position_hash()
+    }
+
+    /// The predicted reply was played. Waits for the search to finish, and
+    /// returns its result along with its context, whose clock has been charging
+    /// the engine since the reply was played.
+    pub fn ponderhit(mut self) -> PonderResult {
+        self.control.ponderhit();
+        let handle = self.handle.take().expect("the search is joined only once");
+        let (result, mut context) = handle.join().expect("the ponder search panicked");
+        // The search may have finished before the reply was played.
+        if context.time_manager().is_pondering() {
+            if let Some(ponderhit_at) = self.control.ponderhit_at() {
+                context.time_manager_mut().ponderhit_at(ponderhit_at);
+            }
+        }
+        (result, context)
+    }
+}
+
+impl Drop for PonderSearch {
+    /// Stops the search without waiting for it, since its result isn't needed.
+    fn drop(&mut self) {
+        self.control.stop();
+    }
+}
@@ -0,0 +1,12 @@
+pub use chess_core::{board, chess_move, move_generator};
+pub use chess_core::{
+    castle_kingside, castle_queenside, check_move, checkmate_move, chess_position, en_passant_move,
+    promotion, std_move,
+};
+
+pub mod alpha_beta_searcher;
+pub mod book;
+pub mod engine_info;
+pub mod evaluate;
+pub mod game;
+pub mod pgn;
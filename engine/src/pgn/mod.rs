@@ -0,0 +1,524 @@
+use std::fmt;
+use std::io::BufRead;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Results that terminate the movetext of a game.
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Movetext lines are wrapped at this width when writing PGN, per the PGN spec.
+const MAX_LINE_LENGTH: usize = 79;
+
+/// How many games `visit_pgn` reads before parsing them together.
+const GAMES_PER_CHUNK: usize = 1024;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PgnError {
+    #[error("unterminated tag: {tag:?}")]
+    UnterminatedTag { tag: String },
+    #[error("malformed tag: {tag:?}")]
+    MalformedTag { tag: String },
+    #[error("unterminated comment")]
+    UnterminatedComment,
+}
+
+#[derive(Error, Debug)]
+pub enum PgnReadError {
+    #[error("io error: {error:?}")]
+    IOError { error: std::io::Error },
+    #[error("pgn error in the game starting at line {line}: {error}")]
+    PgnError { line: usize, error: PgnError },
+}
+
+/// A single move in a game's movetext, along with any annotations attached to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    pub san: String,
+    /// Numeric annotation glyphs, e.g. `2` for `?` and `4` for `??`.
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+}
+
+impl PgnMove {
+    pub fn new(san: &str) -> Self {
+        Self {
+            san: san.to_string(),
+            nags: Vec::new(),
+            comment: None,
+        }
+    }
+}
+
+/// A game parsed from (or to be written as) PGN. Variations are not preserved.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.moves.is_empty() && self.result.is_empty()
+    }
+}
+
+/// Parses every game in a PGN database. Comments and NAGs that follow a move
+/// are attached to it; variations are skipped.
+pub fn parse_pgn(input: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut chars = input.chars();
+    let mut token = String::new();
+    let mut variation_depth = 0;
+
+    loop {
+        let c = chars.next();
+        let ends_token = match c {
+            None => true,
+            Some(c) => c.is_whitespace() || "[{;()".contains(c),
+        };
+        if ends_token && !token.is_empty() {
+            if variation_depth == 0 && push_movetext_token(&mut game, &token) {
+                games.push(std::mem::take(&mut game));
+            }
+            token.clear();
+        }
+
+        let c = match c {
+            Some(c) => c,
+            None => break,
+        };
+
+        match c {
+            '[' if variation_depth == 0 => {
+                let mut tag = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => tag.push(c),
+                        None => return Err(PgnError::UnterminatedTag { tag }),
+                    }
+                }
+                // A tag after movetext starts a new game (the previous one
+                // was missing its result).
+                if !game.moves.is_empty() {
+                    games.push(std::mem::take(&mut game));
+                }
+                game.tags.push(parse_tag(&tag)?);
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+                if variation_depth == 0 {
+                    if let Some(last_move) = game.moves.last_mut() {
+                        last_move.comment = Some(comment.trim().to_string());
+                    }
+                }
+            }
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            c if c.is_whitespace() => (),
+            c => token.push(c),
+        }
+    }
+
+    if !game.is_empty() {
+        games.push(game);
+    }
+
+    Ok(games)
+}
+
+/// Streams a PGN database from `reader`, and calls `visit` with each game, in
+/// the order they appear. Games are split apart as they're read, and parsed a
+/// chunk at a time (on every core, with the `parallel` feature), so only one
+/// chunk of the database is ever held in memory. Returns how many games were
+/// visited.
+pub fn visit_pgn<R: BufRead>(
+    reader: R,
+    mut visit: impl FnMut(PgnGame),
+) -> Result<usize, PgnReadError> {
+    let mut splitter = GameSplitter::default();
+    let mut chunk = Vec::with_capacity(GAMES_PER_CHUNK);
+    let mut visited = 0;
+
+    for line in reader.lines() {
+        let line = line.map_err(|error| PgnReadError::IOError { error })?;
+        if let Some(game_text) = splitter.push_line(&line) {
+            chunk.push(game_text);
+            if chunk.len() == GAMES_PER_CHUNK {
+                visited += visit_chunk(&mut chunk, &mut visit)?;
+            }
+        }
+    }
+    chunk.extend(splitter.finish());
+    visited += visit_chunk(&mut chunk, &mut visit)?;
+
+    Ok(visited)
+}
+
+/// Parses the text of each game in the chunk, then visits the games in order.
+/// The chunk is left empty.
+fn visit_chunk(
+    chunk: &mut Vec<(usize, String)>,
+    visit: &mut impl FnMut(PgnGame),
+) -> Result<usize, PgnReadError> {
+    #[cfg(feature = "parallel")]
+    let game_texts = chunk.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let game_texts = chunk.iter();
+    let parsed: Vec<_> = game_texts
+        .map(|(line, text)| {
+            parse_pgn(text).map_err(|error| PgnReadError::PgnError { line: *line, error })
+        })
+        .collect();
+    chunk.clear();
+
+    let mut visited = 0;
+    for games in parsed {
+        for game in games? {
+            visit(game);
+            visited += 1;
+        }
+    }
+    Ok(visited)
+}
+
+/// Splits a PGN database into the text of each game, line by line. A game ends
+/// where a tag line follows movetext.
+#[derive(Default)]
+struct GameSplitter {
+    text: String,
+    start_line: usize,
+    line_count: usize,
+    has_movetext: bool,
+    in_comment: bool,
+}
+
+impl GameSplitter {
+    /// Adds the next line of the database. Returns the previous game's first line
+    /// number and text if this line starts a new game.
+    fn push_line(&mut self, line: &str) -> Option<(usize, String)> {
+        self.line_count += 1;
+        let is_tag = !self.in_comment && line.trim_start().starts_with('[');
+        let finished_game = match is_tag && self.has_movetext {
+            true => self.finish(),
+            false => None,
+        };
+
+        if self.text.is_empty() {
+            self.start_line = self.line_count;
+        }
+        if !is_tag {
+            self.scan_movetext(line);
+        }
+        self.text.push_str(line);
+        self.text.push('\n');
+
+        finished_game
+    }
+
+    /// Takes the text of the game in progress, unless it's blank.
+    fn finish(&mut self) -> Option<(usize, String)> {
+        self.has_movetext = false;
+        let text = std::mem::take(&mut self.text);
+        (!text.trim().is_empty()).then_some((self.start_line, text))
+    }
+
+    /// Notes whether the line has any movetext outside of comments, and whether
+    /// it leaves a comment open, since a comment line may start with `[`.
+    fn scan_movetext(&mut self, line: &str) {
+        for c in line.chars() {
+            match c {
+                '}' if self.in_comment => self.in_comment = false,
+                _ if self.in_comment => (),
+                '{' => self.in_comment = true,
+                ';' => break,
+                c if !c.is_whitespace() => self.has_movetext = true,
+                _ => (),
+            }
+        }
+    }
+}
+
+fn parse_tag(tag: &str) -> Result<(String, String), PgnError> {
+    let malformed = || PgnError::MalformedTag {
+        tag: tag.to_string(),
+    };
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(malformed)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(malformed)?;
+    Ok((name.to_string(), value.replace("\\\"", "\"")))
+}
+
+/// Adds a movetext token to the game. Returns true if the token ended the game.
+fn push_movetext_token(game: &mut PgnGame, token: &str) -> bool {
+    if RESULTS.contains(&token) {
+        game.result = token.to_string();
+        return true;
+    }
+
+    if let Some(nag) = token.strip_prefix('$') {
+        if let (Some(last_move), Ok(nag)) = (game.moves.last_mut(), nag.parse()) {
+            last_move.nags.push(nag);
+        }
+        return false;
+    }
+
+    // Strip move numbers, which may be attached to the move (e.g. `1.e4`).
+    let digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    let san = match digits.strip_prefix('.') {
+        Some(rest) => rest.trim_start_matches('.'),
+        None => token,
+    };
+    if san.is_empty() {
+        return false;
+    }
+
+    // Move suffix annotations are equivalent to NAGs.
+    let trimmed = san.trim_end_matches(['!', '?']);
+    let nag = match &san[trimmed.len()..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    let mut pgn_move = PgnMove::new(trimmed);
+    pgn_move.nags.extend(nag);
+    game.moves.push(pgn_move);
+
+    false
+}
+
+/// A PGN `Date` tag value, e.g. "2024.03.09", for the UTC day `time` falls on.
+pub fn pgn_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400) as i64;
+    // Converts days since 1970-01-01 to a civil date, counting in 400-year eras
+    // of 146,097 days that start on March 1st, so that leap days fall last.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('"', "\\\""))?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(f)?;
+        }
+
+        let mut tokens = Vec::new();
+        for (i, pgn_move) in self.moves.iter().enumerate() {
+            if i % 2 == 0 {
+                tokens.push(format!("{}.", i / 2 + 1));
+            } else if i > 0 && self.moves[i - 1].comment.is_some() {
+                tokens.push(format!("{}...", i / 2 + 1));
+            }
+            tokens.push(pgn_move.san.clone());
+            for nag in &pgn_move.nags {
+                tokens.push(format!("${}", nag));
+            }
+            if let Some(comment) = &pgn_move.comment {
+                tokens.push(format!("{{ {} }}", comment));
+            }
+        }
+        let result = if self.result.is_empty() {
+            "*"
+        } else {
+            &self.result
+        };
+        tokens.push(result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHOLARS_MATE: &str = r#"[Event "Casual game"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Bc4 {the bishop eyes f7} Nc6 3. Qh5 Nf6?? (3... g6 4. Qf3) 4. Qxf7# 1-0
+
+[Event "Second game"]
+
+1. d4 d5 $1 2. c4 *
+"#;
+
+    #[test]
+    fn test_parse_pgn() {
+        let games = parse_pgn(SCHOLARS_MATE).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let game = &games[0];
+        assert_eq!(game.tag("White"), Some("Alice"));
+        assert_eq!(game.tag("Result"), Some("1-0"));
+        assert_eq!(game.result, "1-0");
+        let sans: Vec<&str> = game.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(
+            sans,
+            vec!["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6", "Qxf7#"],
+            "variations should be skipped"
+        );
+        assert_eq!(
+            game.moves[2].comment,
+            Some("the bishop eyes f7".to_string())
+        );
+        assert_eq!(game.moves[5].nags, vec![4]);
+
+        let game = &games[1];
+        assert_eq!(game.tag("Event"), Some("Second game"));
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.moves[1].nags, vec![1]);
+        assert_eq!(game.result, "*");
+    }
+
+    #[test]
+    fn test_write_pgn_round_trips() {
+        let games = parse_pgn(SCHOLARS_MATE).unwrap();
+        let written = games[0].to_string();
+        assert_eq!(
+            written,
+            "[Event \"Casual game\"]\n[White \"Alice\"]\n[Black \"Bob\"]\n[Result \"1-0\"]\n\n\
+             1. e4 e5 2. Bc4 { the bishop eyes f7 } 2... Nc6 3. Qh5 Nf6 $4 4. Qxf7# 1-0\n"
+        );
+        assert_eq!(parse_pgn(&written).unwrap()[0], games[0]);
+    }
+
+    #[test]
+    fn test_parse_malformed_pgn() {
+        assert_eq!(
+            parse_pgn("[Event \"unterminated"),
+            Err(PgnError::UnterminatedTag {
+                tag: "Event \"unterminated".to_string()
+            })
+        );
+        assert_eq!(
+            parse_pgn("1. e4 { unterminated"),
+            Err(PgnError::UnterminatedComment)
+        );
+    }
+
+    #[test]
+    fn test_visit_pgn() {
+        let database = SCHOLARS_MATE.repeat(GAMES_PER_CHUNK);
+        let mut events = Vec::new();
+        let visited = visit_pgn(database.as_bytes(), |game| {
+            events.push(game.tag("Event").unwrap().to_string())
+        })
+        .unwrap();
+        assert_eq!(visited, GAMES_PER_CHUNK * 2);
+        assert_eq!(events.len(), visited);
+        assert!(events
+            .chunks(2)
+            .all(|pair| pair == ["Casual game", "Second game"]));
+
+        // A comment line that starts like a tag doesn't split the game.
+        let mut games = Vec::new();
+        let database = "1. e4 {a comment\n[not a tag]} e5 *\n\n[Event \"Next\"]\n\n1. d4 *\n";
+        visit_pgn(database.as_bytes(), |game| games.push(game)).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves.len(), 2);
+        assert_eq!(games[1].tag("Event"), Some("Next"));
+
+        let result = visit_pgn(
+            "1. e4 *\n\n[Event \"Broken\"]\n\n1. e4 {".as_bytes(),
+            |_| (),
+        );
+        assert!(matches!(
+            result,
+            Err(PgnReadError::PgnError {
+                line: 3,
+                error: PgnError::UnterminatedComment
+            })
+        ));
+    }
+
+    #[test]
+    fn test_pgn_date() {
+        let date = |seconds| pgn_date(UNIX_EPOCH + std::time::Duration::from_secs(seconds));
+        assert_eq!(date(0), "1970.01.01");
+        assert_eq!(date(951_782_400), "2000.02.29");
+        assert_eq!(date(1_709_942_399), "2024.03.08");
+        assert_eq!(date(1_709_942_400), "2024.03.09");
+    }
+
+    #[test]
+    fn test_parse_castling_with_zeros() {
+        let games = parse_pgn("1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 4.0-0 *").unwrap();
+        assert_eq!(games[0].moves[0].san, "e4");
+        assert_eq!(games[0].moves[6].san, "0-0");
+    }
+}
@@ -9,6 +9,16 @@ fn file_exists_in_build_cache(file_name: &str) -> bool {
     out.exists()
 }
 
+/// Tables generated before they were versioned, or before they covered the side
+/// to move, have to be regenerated, since the engine refers to both.
+fn zobrist_tables_are_current(file_name: &str) -> bool {
+    let mut out: PathBuf = std::env::var("OUT_DIR").unwrap().into();
+    out.push(file_name);
+    std::fs::read_to_string(out).is_ok_and(|tables| {
+        tables.contains("ZOBRIST_TABLE_VERSION") && tables.contains("ZOBRIST_BLACK_TO_MOVE")
+    })
+}
+
 fn build_zobrist_tables(filename: &str) {
     let mut out: PathBuf = std::env::var("OUT_DIR").unwrap().into();
     out.push(filename);
@@ -30,22 +40,30 @@ fn build_opening_book(filename: &str) {
     generate_opening_book("opening_lines.txt", &mut out).unwrap();
 }
 
+/// Each crate generates only the tables it includes: the rules need the zobrist
+/// and magic tables, and the engine needs the opening book.
 fn main() {
-    if !file_exists_in_build_cache("zobrist_table.rs") {
-        println!("cargo:warning=Building zobrist tables...");
-        build_zobrist_tables("zobrist_table.rs");
-        println!("cargo:warning=Finished building zobrist tables.");
-    }
-
-    if !file_exists_in_build_cache("magic_table.rs") {
-        println!("cargo:warning=Building magic tables...");
-        build_magics_tables("magic_table.rs");
-        println!("cargo:warning=Finished building magic tables.");
-    }
+    match std::env::var("CARGO_PKG_NAME").unwrap().as_str() {
+        "chess-core" => {
+            if !zobrist_tables_are_current("zobrist_table.rs") {
+                println!("cargo:warning=Building zobrist tables...");
+                build_zobrist_tables("zobrist_table.rs");
+                println!("cargo:warning=Finished building zobrist tables.");
+            }
 
-    if !file_exists_in_build_cache("opening_book.rs") {
-        println!("cargo:warning=Building opening book...");
-        build_opening_book("opening_book.rs");
-        println!("cargo:warning=Finished building opening book.");
+            if !file_exists_in_build_cache("magic_table.rs") {
+                println!("cargo:warning=Building magic tables...");
+                build_magics_tables("magic_table.rs");
+                println!("cargo:warning=Finished building magic tables.");
+            }
+        }
+        "chess-engine" => {
+            if !file_exists_in_build_cache("opening_book.rs") {
+                println!("cargo:warning=Building opening book...");
+                build_opening_book("opening_book.rs");
+                println!("cargo:warning=Finished building opening book.");
+            }
+        }
+        package => panic!("no tables to build for {}", package),
     }
 }
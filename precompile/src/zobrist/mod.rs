@@ -8,7 +8,11 @@ use crate::random_number_generator::generate_random_u64;
 const PIECES: [&str; 6] = ["pawn", "rook", "knight", "bishop", "king", "queen"];
 const SQUARES: usize = 64;
 
-/// Generates three tables of random u64s for Zobrist hashing and writes them to a file
+/// Bump this whenever the layout of the generated tables changes, so that the
+/// fingerprint changes with it even if the random values were somehow reused.
+const GENERATOR_VERSION: u64 = 2;
+
+/// Generates three tables of random u64s, and one for black to move, for Zobrist hashing and writes them to a file
 /// that is included in the project's primary module.
 pub fn write_zobrist_tables(out: &mut BufWriter<File>) -> std::io::Result<()> {
     // Generate ZOBRIST_PIECES_TABLE
@@ -33,9 +37,25 @@ pub fn write_zobrist_tables(out: &mut BufWriter<File>) -> std::io::Result<()> {
         zobrist_en_passant[i] = generate_random_u64();
     }
 
+    // Generate ZOBRIST_BLACK_TO_MOVE
+    let zobrist_black_to_move = generate_random_u64();
+
+    let version = fingerprint(
+        zobrist_table
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(zobrist_castling_rights.iter())
+            .chain(zobrist_en_passant.iter())
+            .chain(std::iter::once(&zobrist_black_to_move)),
+    );
+
     // Write the generated values into a format that can be used in a Rust module
     writeln!(out, "#[rustfmt::skip]")?;
-    writeln!(out, "pub const ZOBRIST_PIECES_TABLE: [[[u64; 2]; 64]; 6] = [")?;
+    writeln!(
+        out,
+        "pub const ZOBRIST_PIECES_TABLE: [[[u64; 2]; 64]; 6] = ["
+    )?;
     for piece_index in 0..PIECES.len() {
         writeln!(out, "    [  // {}", PIECES[piece_index])?;
         for square_index in 0..SQUARES {
@@ -52,7 +72,10 @@ pub fn write_zobrist_tables(out: &mut BufWriter<File>) -> std::io::Result<()> {
     writeln!(out, "];")?;
 
     writeln!(out, "\n#[rustfmt::skip]")?;
-    writeln!(out, "pub const ZOBRIST_CASTLING_RIGHTS_TABLE: [u64; 16] = [")?;
+    writeln!(
+        out,
+        "pub const ZOBRIST_CASTLING_RIGHTS_TABLE: [u64; 16] = ["
+    )?;
     for rights in zobrist_castling_rights.iter() {
         writeln!(out, "    {},", rights)?;
     }
@@ -65,5 +88,34 @@ pub fn write_zobrist_tables(out: &mut BufWriter<File>) -> std::io::Result<()> {
     }
     writeln!(out, "];")?;
 
+    writeln!(
+        out,
+        "\npub const ZOBRIST_BLACK_TO_MOVE: u64 = {};",
+        zobrist_black_to_move
+    )?;
+
+    writeln!(
+        out,
+        "\n/// Fingerprints the tables above. Hashes are only comparable between builds"
+    )?;
+    writeln!(out, "/// whose tables have the same version.")?;
+    writeln!(
+        out,
+        "pub const ZOBRIST_TABLE_VERSION: u64 = {:#018x};",
+        version
+    )?;
+
     Ok(())
 }
+
+/// Folds the generator version and every table value into one u64 (FNV-1a over
+/// whole words), so that any change to the tables changes the result.
+fn fingerprint<'a>(values: impl Iterator<Item = &'a u64>) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    std::iter::once(&GENERATOR_VERSION)
+        .chain(values)
+        .fold(FNV_OFFSET_BASIS, |hash, value| {
+            (hash ^ value).wrapping_mul(FNV_PRIME)
+        })
+}
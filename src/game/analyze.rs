@@ -0,0 +1,267 @@
+use std::thread;
+
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::{alpha_beta_search, SearchContext, SearchError};
+use crate::board::color::Color;
+use crate::board::fen::FenError;
+use crate::board::Board;
+use crate::book::traps::find_trap;
+use crate::chess_move::algebraic_notation::{
+    algebraic_notation_for, enumerate_candidate_moves_with_algebraic_notation,
+};
+use crate::evaluate::{format_score, is_checkmate_score, mate_in};
+use crate::move_generator::MoveGenerator;
+
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Checkmate scores are drawn as this many centipawns, so that they don't
+/// flatten the rest of the trend.
+const SPARKLINE_SCORE_LIMIT: i16 = 1000;
+
+/// Analysis that's left running stops deepening here.
+const MAX_ANALYSIS_DEPTH: u8 = 20;
+
+#[derive(Error, Debug)]
+pub enum AnalyzeError {
+    #[error("invalid fen: {error}")]
+    FenError { error: FenError },
+    #[error("search error: {error}")]
+    SearchError { error: SearchError },
+}
+
+/// A root move's score at each depth, from the perspective of the side to move.
+pub struct ScoreTrend {
+    pub uci: String,
+    pub scores: Vec<i16>,
+}
+
+/// Searches the position at each depth up to `depth`, and prints the best move
+/// at each depth, along with a sparkline of how the scores of the `top` best
+/// root moves changed as the search deepened. Scores that settle down suggest
+/// the evaluation has converged; scores that swing suggest it needs more depth.
+pub fn analyze(fen: &str, depth: u8, top: usize) -> Result<(), AnalyzeError> {
+    let mut board = Board::from_fen(fen).map_err(|error| AnalyzeError::FenError { error })?;
+    let mut move_generator = MoveGenerator::new();
+    let turn = board.turn();
+    let enumerated_candidate_moves =
+        enumerate_candidate_moves_with_algebraic_notation(&mut board, turn, &mut move_generator);
+
+    if let Some(trap) = find_trap(&board) {
+        println!("Opening trap: {}\n", trap);
+    }
+
+    // Each depth's root scores, from the side to move's perspective.
+    let mut root_scores_by_depth = Vec::new();
+    let mut context = SearchContext::new(depth);
+    println!("{:>7} {:>8} {:>20}", "depth", "best", "score");
+    for search_depth in 1..=depth {
+        context.set_search_depth(search_depth);
+        let best_move = alpha_beta_search(&mut context, &mut board, &mut move_generator)
+            .map_err(|error| AnalyzeError::SearchError { error })?;
+        println!(
+            "{:>7} {:>8} {:>20}",
+            search_depth,
+            algebraic_notation_for(&best_move, &enumerated_candidate_moves),
+            format_score(context.last_score().unwrap_or_default())
+        );
+        let root_scores: Vec<(String, i16)> = context
+            .root_scores()
+            .iter()
+            .map(|(score, chess_move)| (chess_move.to_uci(), perspective(*score, turn)))
+            .collect();
+        root_scores_by_depth.push(root_scores);
+    }
+
+    println!(
+        "\nScore trend by depth ({}'s perspective, #N is mate in N):",
+        turn
+    );
+    for trend in score_trends(&root_scores_by_depth, top) {
+        let san = enumerated_candidate_moves
+            .iter()
+            .find(|(chess_move, _)| chess_move.to_uci() == trend.uci)
+            .map_or(trend.uci.clone(), |(_, san)| san.clone());
+        let scores: Vec<String> = trend
+            .scores
+            .iter()
+            .map(|&score| match mate_in(score) {
+                Some(moves) => format!("#{}", moves),
+                None => score.to_string(),
+            })
+            .collect();
+        println!(
+            "{:>8} {}  {}",
+            san,
+            sparkline(&trend.scores),
+            scores.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Analyzes the position on a background thread, one depth at a time, and
+/// prints the `lines` best moves and their scores at each depth, until `wait`
+/// returns. The board is copied, and the search has its own context, so
+/// nothing about the game being analyzed changes. Returns the deepest depth
+/// completed.
+pub fn analyze_until(board: &Board, lines: usize, wait: impl FnOnce()) -> Result<u8, AnalyzeError> {
+    let mut board = board.clone();
+    let mut context = SearchContext::new(1);
+    let control = context.reset_control();
+    let search = thread::spawn(move || {
+        let mut move_generator = MoveGenerator::new();
+        let turn = board.turn();
+        let enumerated_candidate_moves = enumerate_candidate_moves_with_algebraic_notation(
+            &mut board,
+            turn,
+            &mut move_generator,
+        );
+        let mut completed_depth = 0;
+        for depth in 1..=MAX_ANALYSIS_DEPTH {
+            context.set_search_depth(depth);
+            let result = alpha_beta_search(&mut context, &mut board, &mut move_generator);
+            // A stopped search's result is only partial.
+            if context.control().is_stopped() {
+                break;
+            }
+            result.map_err(|error| AnalyzeError::SearchError { error })?;
+            completed_depth = depth;
+            // The root scores are best move last.
+            let best: Vec<String> = context
+                .root_scores()
+                .iter()
+                .rev()
+                .take(lines)
+                .map(|(score, chess_move)| {
+                    format!(
+                        "{} {}",
+                        algebraic_notation_for(chess_move, &enumerated_candidate_moves),
+                        format_score(*score)
+                    )
+                })
+                .collect();
+            println!("{:>7}  {}", depth, best.join(", "));
+        }
+        Ok(completed_depth)
+    });
+
+    wait();
+    control.stop();
+    search.join().expect("the analysis panicked")
+}
+
+/// Flips a score from white's perspective to `color`'s.
+fn perspective(score: i16, color: Color) -> i16 {
+    match color {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// The score trends of the `top` moves that scored best at the deepest depth,
+/// best first. `root_scores_by_depth` holds each depth's root moves, by UCI, and
+/// their scores. Every depth scores every root move, so each trend has a score
+/// for each depth.
+pub fn score_trends(root_scores_by_depth: &[Vec<(String, i16)>], top: usize) -> Vec<ScoreTrend> {
+    let deepest = match root_scores_by_depth.last() {
+        Some(deepest) => deepest,
+        None => return Vec::new(),
+    };
+    let mut ranked = deepest.clone();
+    ranked.sort_by(|(_, a), (_, b)| b.cmp(a));
+    ranked
+        .into_iter()
+        .take(top)
+        .map(|(uci, _)| ScoreTrend {
+            scores: root_scores_by_depth
+                .iter()
+                .filter_map(|root_scores| {
+                    root_scores
+                        .iter()
+                        .find(|(other, _)| *other == uci)
+                        .map(|(_, score)| *score)
+                })
+                .collect(),
+            uci,
+        })
+        .collect()
+}
+
+/// Draws the scores as a row of bars, from lowest to highest, clamping checkmate
+/// scores to a large but finite score.
+pub fn sparkline(scores: &[i16]) -> String {
+    let clamp = |score: i16| {
+        if is_checkmate_score(score) {
+            score.signum() * SPARKLINE_SCORE_LIMIT
+        } else {
+            score.clamp(-SPARKLINE_SCORE_LIMIT, SPARKLINE_SCORE_LIMIT)
+        }
+    };
+    let scores: Vec<i16> = scores.iter().map(|&score| clamp(score)).collect();
+    let (min, max) = match (scores.iter().min(), scores.iter().max()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => return String::new(),
+    };
+    // A flat trend sits in the middle rather than on the floor.
+    if min == max {
+        return SPARKLINE_BARS[SPARKLINE_BARS.len() / 2]
+            .to_string()
+            .repeat(scores.len());
+    }
+    let range = (max - min) as usize;
+    scores
+        .iter()
+        .map(|&score| SPARKLINE_BARS[(score - min) as usize * (SPARKLINE_BARS.len() - 1) / range])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate::MATE;
+
+    #[test]
+    fn test_analyze_until() {
+        let board = Board::from_fen("7k/6pp/8/8/8/8/8/K6R w - - 0 1").unwrap();
+        let depth = analyze_until(&board, 2, || {
+            thread::sleep(std::time::Duration::from_millis(100))
+        })
+        .unwrap();
+        assert!(depth <= MAX_ANALYSIS_DEPTH);
+        // The board analyzed was a copy.
+        assert_eq!(board.to_fen(), "7k/6pp/8/8/8/8/8/K6R w - - 0 1");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[0, 70, 35]), "▁█▄");
+        assert_eq!(sparkline(&[20, 20]), "▅▅");
+        assert_eq!(sparkline(&[0, 1000, MATE - 3]), "▁██");
+    }
+
+    #[test]
+    fn test_score_trends() {
+        let depth = |scores: &[(&str, i16)]| {
+            scores
+                .iter()
+                .map(|(uci, score)| (uci.to_string(), *score))
+                .collect::<Vec<_>>()
+        };
+        let root_scores_by_depth = vec![
+            depth(&[("g1f3", 5), ("d2d4", 10), ("e2e4", 30)]),
+            depth(&[("e2e4", 20), ("g1f3", 25), ("d2d4", 40)]),
+        ];
+
+        let trends = score_trends(&root_scores_by_depth, 2);
+        assert_eq!(trends.len(), 2);
+        assert_eq!(trends[0].uci, "d2d4");
+        assert_eq!(trends[0].scores, vec![10, 40]);
+        assert_eq!(trends[1].uci, "g1f3");
+        assert_eq!(trends[1].scores, vec![5, 25]);
+
+        assert!(score_trends(&[], 3).is_empty());
+    }
+}
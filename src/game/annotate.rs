@@ -0,0 +1,439 @@
+use std::fs;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use thiserror::Error;
+
+use crate::board::color::Color;
+use crate::book::traps::{find_trap, OpeningTrap};
+use crate::chess_move::algebraic_notation::{
+    algebraic_notation_for, find_move_by_san, normalize_san,
+};
+use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::{mate_in, win_probability};
+use crate::game::game::{Game, GameError};
+use crate::pgn::{parse_pgn, PgnError, PgnGame};
+
+/// Drops in the mover's win probability at or above these thresholds are
+/// annotated as inaccuracies (`?!`), mistakes (`?`), and blunders (`??`).
+const INACCURACY_THRESHOLD: f64 = 0.10;
+const MISTAKE_THRESHOLD: f64 = 0.20;
+const BLUNDER_THRESHOLD: f64 = 0.30;
+
+/// Scores are clamped to this many centipawns when computing centipawn loss, so
+/// that a single missed mate doesn't dominate a player's average.
+const CENTIPAWN_LOSS_CAP: i16 = 1000;
+
+const NAG_MISTAKE: u8 = 2;
+const NAG_BLUNDER: u8 = 4;
+const NAG_INACCURACY: u8 = 6;
+
+#[derive(Error, Debug)]
+pub enum AnnotateError {
+    #[error("io error: {error:?}")]
+    IOError { error: std::io::Error },
+    #[error("pgn error: {error}")]
+    PgnError { error: PgnError },
+    #[error("illegal move {san:?} at ply {ply}")]
+    IllegalMove { san: String, ply: usize },
+    #[error("games with a custom starting position (FEN) are not supported")]
+    UnsupportedStartingPosition,
+    #[error("game error: {error}")]
+    GameError { error: GameError },
+}
+
+impl From<GameError> for AnnotateError {
+    fn from(error: GameError) -> Self {
+        AnnotateError::GameError { error }
+    }
+}
+
+/// The engine's view of a single move that was played in a game.
+#[derive(Clone, Debug)]
+pub struct MoveAnalysis {
+    pub color: Color,
+    pub san: String,
+    /// The engine's preferred move in the position the move was played from.
+    pub best_san: String,
+    /// Scores (from white's perspective) of the positions before and after the move.
+    pub score_before: i16,
+    pub score_after: i16,
+    /// The known opening trap the move set, if any.
+    pub trap: Option<&'static OpeningTrap>,
+}
+
+impl MoveAnalysis {
+    /// How much the move lowered the mover's chances of winning, between 0 and 1.
+    pub fn win_probability_loss(&self) -> f64 {
+        let loss = win_probability(self.score_before) - win_probability(self.score_after);
+        let loss = match self.color {
+            Color::White => loss,
+            Color::Black => -loss,
+        };
+        loss.max(0.0)
+    }
+
+    /// How many centipawns the move gave up, from the mover's perspective.
+    pub fn centipawn_loss(&self) -> i16 {
+        let before = self
+            .score_before
+            .clamp(-CENTIPAWN_LOSS_CAP, CENTIPAWN_LOSS_CAP);
+        let after = self
+            .score_after
+            .clamp(-CENTIPAWN_LOSS_CAP, CENTIPAWN_LOSS_CAP);
+        let loss = match self.color {
+            Color::White => before - after,
+            Color::Black => after - before,
+        };
+        loss.max(0)
+    }
+
+    /// Lichess-style move accuracy, between 0 and 100, derived from the drop in
+    /// the mover's win percentage.
+    pub fn accuracy(&self) -> f64 {
+        let win_percentage_loss = self.win_probability_loss() * 100.0;
+        (103.1668 * (-0.04354 * win_percentage_loss).exp() - 3.1669).clamp(0.0, 100.0)
+    }
+
+    pub fn is_best_move(&self) -> bool {
+        normalize_san(&self.san) == normalize_san(&self.best_san)
+    }
+
+    /// The move assessment glyph the engine would give this move, if any.
+    pub fn nag(&self) -> Option<u8> {
+        if self.is_best_move() {
+            return None;
+        }
+        match self.win_probability_loss() {
+            loss if loss >= BLUNDER_THRESHOLD => Some(NAG_BLUNDER),
+            loss if loss >= MISTAKE_THRESHOLD => Some(NAG_MISTAKE),
+            loss if loss >= INACCURACY_THRESHOLD => Some(NAG_INACCURACY),
+            _ => None,
+        }
+    }
+
+    /// The move suffix for the move's assessment glyph, e.g. "??" for a blunder.
+    pub fn glyph(&self) -> Option<&'static str> {
+        match self.nag()? {
+            NAG_MISTAKE => Some("?"),
+            NAG_BLUNDER => Some("??"),
+            NAG_INACCURACY => Some("?!"),
+            _ => None,
+        }
+    }
+}
+
+/// How well one player played over a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerSummary {
+    pub color: Color,
+    pub moves: usize,
+    pub average_centipawn_loss: f64,
+    /// Between 0 and 100. The mean of the arithmetic and harmonic means of the
+    /// move accuracies, so that a few blunders pull it down sharply.
+    pub accuracy: f64,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+}
+
+impl PlayerSummary {
+    pub fn new(analyses: &[MoveAnalysis], color: Color) -> Self {
+        let moves: Vec<&MoveAnalysis> = analyses
+            .iter()
+            .filter(|analysis| analysis.color == color)
+            .collect();
+        let count_nags = |nag| {
+            moves
+                .iter()
+                .filter(|analysis| analysis.nag() == Some(nag))
+                .count()
+        };
+
+        let (average_centipawn_loss, accuracy) = if moves.is_empty() {
+            (0.0, 100.0)
+        } else {
+            let n = moves.len() as f64;
+            let total_loss: f64 = moves.iter().map(|m| m.centipawn_loss() as f64).sum();
+            let accuracies: Vec<f64> = moves.iter().map(|m| m.accuracy()).collect();
+            let mean = accuracies.iter().sum::<f64>() / n;
+            // Offset by one to keep a 0% move from zeroing out the harmonic mean.
+            let harmonic_mean = n / accuracies.iter().map(|a| 1.0 / (a + 1.0)).sum::<f64>() - 1.0;
+            (total_loss / n, (mean + harmonic_mean) / 2.0)
+        };
+
+        Self {
+            color,
+            moves: moves.len(),
+            average_centipawn_loss,
+            accuracy,
+            inaccuracies: count_nags(NAG_INACCURACY),
+            mistakes: count_nags(NAG_MISTAKE),
+            blunders: count_nags(NAG_BLUNDER),
+        }
+    }
+}
+
+/// Prints average centipawn loss, accuracy, and error counts for both players.
+pub fn print_game_summary(pgn_game: &PgnGame, analyses: &[MoveAnalysis]) {
+    eprintln!(
+        "{} vs. {} ({})",
+        pgn_game.tag("White").unwrap_or("?"),
+        pgn_game.tag("Black").unwrap_or("?"),
+        pgn_game.tag("Result").unwrap_or(&pgn_game.result),
+    );
+    eprintln!(
+        "{:>7} {:>6} {:>6} {:>9} {:>12} {:>9} {:>9}",
+        "player", "moves", "acpl", "accuracy", "inaccuracies", "mistakes", "blunders"
+    );
+    for color in [Color::White, Color::Black] {
+        let summary = PlayerSummary::new(analyses, color);
+        eprintln!(
+            "{:>7} {:>6} {:>6.0} {:>8.1}% {:>12} {:>9} {:>9}",
+            summary.color.to_string(),
+            summary.moves,
+            summary.average_centipawn_loss,
+            summary.accuracy,
+            summary.inaccuracies,
+            summary.mistakes,
+            summary.blunders,
+        );
+    }
+    eprintln!();
+}
+
+/// Reads every game in the PGN file at `path`, analyzes each position at the
+/// given depth, and returns the games as annotated PGN. Games are analyzed in
+/// parallel with the `parallel` feature; a game that can't be analyzed is returned unannotated, with a
+/// warning printed to stderr. A summary of each game is also printed to stderr,
+/// so that stdout remains valid PGN.
+pub fn annotate_pgn_file(path: &str, depth: u8) -> Result<String, AnnotateError> {
+    let input = fs::read_to_string(path).map_err(|error| AnnotateError::IOError { error })?;
+    let games = parse_pgn(&input).map_err(|error| AnnotateError::PgnError { error })?;
+
+    #[cfg(feature = "parallel")]
+    let game_iter = games.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let game_iter = games.iter();
+    let analyzed = game_iter
+        .map(|pgn_game| analyze_game(pgn_game, depth))
+        .collect::<Vec<_>>();
+
+    let mut annotated = Vec::with_capacity(games.len());
+    for (i, (pgn_game, analyses)) in games.iter().zip(analyzed).enumerate() {
+        match analyses {
+            Ok(analyses) => {
+                print_game_summary(pgn_game, &analyses);
+                annotated.push(annotate_game(pgn_game, &analyses, depth).to_string());
+            }
+            Err(error) => {
+                eprintln!("warning: could not annotate game {}: {}", i + 1, error);
+                annotated.push(pgn_game.to_string());
+            }
+        }
+    }
+
+    Ok(annotated.join("\n"))
+}
+
+/// Replays the game from the starting position, searching every position to
+/// find the engine's best move and score.
+pub fn analyze_game(pgn_game: &PgnGame, depth: u8) -> Result<Vec<MoveAnalysis>, AnnotateError> {
+    if pgn_game.tag("FEN").is_some() {
+        return Err(AnnotateError::UnsupportedStartingPosition);
+    }
+
+    let mut game = Game::new(depth);
+    let mut analyses = Vec::with_capacity(pgn_game.moves.len());
+    let mut evaluation = evaluate_position(&mut game)?;
+
+    for (ply, pgn_move) in pgn_game.moves.iter().enumerate() {
+        let enumerated_candidate_moves = game.enumerated_candidate_moves();
+        let chess_move = find_move_by_san(&pgn_move.san, &enumerated_candidate_moves).ok_or(
+            AnnotateError::IllegalMove {
+                san: pgn_move.san.clone(),
+                ply,
+            },
+        )?;
+        analyses.push(analyze_move(
+            &mut game,
+            chess_move,
+            pgn_move.san.clone(),
+            &mut evaluation,
+        )?);
+    }
+
+    Ok(analyses)
+}
+
+/// Plays `chess_move`, written `san`, and analyzes it against `evaluation`, the
+/// score and best move of the position it was played from, which then become
+/// those of the position it leads to.
+pub(crate) fn analyze_move(
+    game: &mut Game,
+    chess_move: ChessMove,
+    san: String,
+    evaluation: &mut (i16, Option<String>),
+) -> Result<MoveAnalysis, AnnotateError> {
+    let color = game.board().turn();
+    game.apply_chess_move(chess_move)?;
+    game.board_mut().toggle_turn();
+
+    let (score_before, best_san) = std::mem::replace(evaluation, evaluate_position(game)?);
+    Ok(MoveAnalysis {
+        color,
+        san,
+        best_san: best_san.unwrap_or_default(),
+        score_before,
+        score_after: evaluation.0,
+        trap: find_trap(game.board()),
+    })
+}
+
+/// Returns the score of the current position, and the engine's best move if
+/// the game isn't over.
+pub(crate) fn evaluate_position(game: &mut Game) -> Result<(i16, Option<String>), AnnotateError> {
+    if game.check_game_over_for_current_turn().is_some() {
+        let turn = game.board().turn();
+        return Ok((game.score(turn), None));
+    }
+
+    let enumerated_candidate_moves = game.enumerated_candidate_moves();
+    let best_move = game.select_alpha_beta_best_move()?;
+    let score = game.alpha_beta_score().unwrap_or_default();
+    Ok((
+        score,
+        Some(algebraic_notation_for(
+            &best_move,
+            &enumerated_candidate_moves,
+        )),
+    ))
+}
+
+/// Adds an evaluation comment to every move, and a move assessment glyph
+/// (replacing any existing one) to inaccuracies, mistakes, and blunders. Moves
+/// into a known opening trap are also given the trap's refutation.
+pub fn annotate_game(pgn_game: &PgnGame, analyses: &[MoveAnalysis], depth: u8) -> PgnGame {
+    let mut annotated = pgn_game.clone();
+    annotated.set_tag(
+        "Annotator",
+        &format!("chess {} (depth {})", env!("CARGO_PKG_VERSION"), depth),
+    );
+
+    for (pgn_move, analysis) in annotated.moves.iter_mut().zip(analyses) {
+        let mut comment = format_eval(analysis.score_after);
+        if let Some(nag) = analysis.nag() {
+            pgn_move.nags.retain(|nag| *nag > NAG_INACCURACY);
+            pgn_move.nags.insert(0, nag);
+            comment = format!("{} {} was best.", comment, analysis.best_san);
+        }
+        if let Some(trap) = analysis.trap {
+            comment = format!("{} {}", comment, trap);
+        }
+        pgn_move.comment = Some(match &pgn_move.comment {
+            Some(existing) => format!("{} {}", existing, comment),
+            None => comment,
+        });
+    }
+
+    annotated
+}
+
+fn format_eval(score: i16) -> String {
+    if let Some(moves) = mate_in(score) {
+        let winner = if score > 0 { "White" } else { "Black" };
+        return format!("{} mates in {}.", winner, moves.abs());
+    }
+    format!("[%eval {:.2}]", score as f64 / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_blunder() {
+        let games = parse_pgn("1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0").unwrap();
+        let analyses = analyze_game(&games[0], 2).unwrap();
+        assert_eq!(analyses.len(), 7);
+
+        let blunder = &analyses[5];
+        assert_eq!(blunder.color, Color::Black);
+        assert_eq!(blunder.nag(), Some(NAG_BLUNDER));
+        assert_eq!(mate_in(blunder.score_after), Some(1));
+        assert_eq!(format_eval(blunder.score_after), "White mates in 1.");
+
+        let mate = &analyses[6];
+        assert!(mate.is_best_move());
+        assert_eq!(mate.nag(), None);
+
+        let annotated = annotate_game(&games[0], &analyses, 2);
+        assert_eq!(annotated.moves[5].nags, vec![NAG_BLUNDER]);
+        assert!(annotated.moves[5]
+            .comment
+            .as_ref()
+            .unwrap()
+            .ends_with("was best."));
+        assert!(annotated.tag("Annotator").is_some());
+
+        let black = PlayerSummary::new(&analyses, Color::Black);
+        assert_eq!(black.moves, 3);
+        assert_eq!(black.blunders, 1);
+        assert!(black.average_centipawn_loss > 300.0);
+        let white = PlayerSummary::new(&analyses, Color::White);
+        assert_eq!(white.moves, 4);
+        assert!(white.accuracy > black.accuracy);
+    }
+
+    #[test]
+    fn test_annotate_opening_trap() {
+        let games = parse_pgn("1. e4 e5 2. Qh5 Nc6 3. Bc4 g6 *").unwrap();
+        let analyses = analyze_game(&games[0], 1).unwrap();
+        assert_eq!(
+            analyses[4].trap.map(|trap| trap.name),
+            Some("Scholar's Mate")
+        );
+        assert!(
+            analyses
+                .iter()
+                .filter(|analysis| analysis.trap.is_some())
+                .count()
+                == 1
+        );
+
+        let annotated = annotate_game(&games[0], &analyses, 1);
+        let comment = annotated.moves[4].comment.as_ref().unwrap();
+        assert!(comment.ends_with(
+            "Scholar's Mate: g6! blocks the queen's diagonal to f7; ...Nf6?? allows Qxf7#."
+        ));
+    }
+
+    #[test]
+    fn test_move_accuracy() {
+        let analysis = |score_before, score_after| MoveAnalysis {
+            color: Color::White,
+            san: "e4".to_string(),
+            best_san: "d4".to_string(),
+            score_before,
+            score_after,
+            trap: None,
+        };
+        assert!(analysis(50, 50).accuracy() > 99.9);
+        assert_eq!(analysis(50, 80).centipawn_loss(), 0);
+        assert_eq!(analysis(50, -150).centipawn_loss(), 200);
+        assert!(analysis(50, -150).accuracy() < 60.0);
+        assert_eq!(
+            analysis(i16::MAX / 2, 0).centipawn_loss(),
+            CENTIPAWN_LOSS_CAP
+        );
+    }
+
+    #[test]
+    fn test_analyze_illegal_move() {
+        let games = parse_pgn("1. e4 e4 *").unwrap();
+        assert!(matches!(
+            analyze_game(&games[0], 1),
+            Err(AnnotateError::IllegalMove { ply: 1, .. })
+        ));
+    }
+}
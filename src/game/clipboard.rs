@@ -0,0 +1,30 @@
+use std::sync::Mutex;
+
+use arboard::Clipboard;
+use thiserror::Error;
+
+/// The clipboard, kept open once it's first used. On X11 and Wayland the copied
+/// text is served by the program that copied it, so closing the clipboard
+/// would take the text with it.
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    #[error("could not open the clipboard: {error}")]
+    Unavailable { error: arboard::Error },
+    #[error("could not copy to the clipboard: {error}")]
+    CopyFailed { error: arboard::Error },
+}
+
+/// Copies `text` to the system clipboard.
+pub fn copy_to_clipboard(text: &str) -> Result<(), ClipboardError> {
+    let mut clipboard = CLIPBOARD.lock().unwrap_or_else(|error| error.into_inner());
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new().map_err(|error| ClipboardError::Unavailable { error })?);
+    }
+    clipboard
+        .as_mut()
+        .unwrap()
+        .set_text(text)
+        .map_err(|error| ClipboardError::CopyFailed { error })
+}
@@ -1,10 +1,72 @@
 use common::bitboard::square::square_string_to_bitboard;
+use thiserror::Error;
 
+use crate::board::piece::Piece;
 use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::GameEnding;
+use crate::game::analyze::analyze_until;
+#[cfg(feature = "clipboard")]
+use crate::game::clipboard::copy_to_clipboard;
+use crate::input_handler::command_registry::NamedCommand;
+use crate::input_handler::{read_line, InputError};
 
 use super::game::{Game, GameError};
 
-type CommandResult = Result<ChessMove, GameError>;
+type CommandResult = Result<CommandOutcome, CommandError>;
+
+/// What executing a command did to the game, so that callers can react to it.
+/// A command that makes a move leaves it to the caller to pass the turn.
+#[derive(Debug)]
+pub enum CommandOutcome {
+    /// A move was made, and the game goes on.
+    MoveApplied { chess_move: ChessMove },
+    /// A move was made, and it ended the game.
+    GameEnded {
+        chess_move: ChessMove,
+        ending: GameEnding,
+    },
+    /// The game changed without a move being made, e.g. a position was set up.
+    StateChanged,
+}
+
+impl CommandOutcome {
+    /// The outcome of making `chess_move`, which ended the game if the player
+    /// to move next has no way to continue it.
+    fn after_move(game: &mut Game, chess_move: ChessMove) -> Self {
+        game.board_mut().toggle_turn();
+        let ending = game.check_game_over_for_current_turn();
+        game.board_mut().toggle_turn();
+        match ending {
+            Some(ending) => CommandOutcome::GameEnded { chess_move, ending },
+            None => CommandOutcome::MoveApplied { chess_move },
+        }
+    }
+
+    /// The move the command made, if it made one.
+    pub fn chess_move(&self) -> Option<&ChessMove> {
+        match self {
+            CommandOutcome::MoveApplied { chess_move }
+            | CommandOutcome::GameEnded { chess_move, .. } => Some(chess_move),
+            CommandOutcome::StateChanged => None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("the game is over")]
+    GameOver { ending: GameEnding },
+    #[error("{error}")]
+    GameError { error: GameError },
+}
+
+impl CommandError {
+    /// Whether the error comes from the engine itself, rather than from the
+    /// command it was given.
+    pub fn is_internal(&self) -> bool {
+        matches!(self, CommandError::GameError { error } if error.is_internal())
+    }
+}
 
 /// Represents a command that can be executed on a game. This separates the parsing
 /// of the command from the execution of the command itself.
@@ -12,12 +74,23 @@ pub trait Command {
     fn execute(&self, game: &mut Game) -> CommandResult;
 }
 
+/// Fails with `CommandError::GameOver` if the game has already ended, since no
+/// more moves can be made.
+fn ensure_game_is_ongoing(game: &mut Game) -> Result<(), CommandError> {
+    match game.check_game_over_for_current_turn() {
+        Some(ending) => Err(CommandError::GameOver { ending }),
+        None => Ok(()),
+    }
+}
+
 /// Represents a command to make a move on the board.
 pub enum MakeMove {
-    /// Represents a move based on the from and to coordinates, e.g. "e2e4".
+    /// Represents a move based on the from and to coordinates, e.g. "e2e4", and
+    /// for a promotion, optionally the piece, e.g. "e7e8n".
     Coordinate {
         from_square: String,
         to_square: String,
+        promote_to: Option<Piece>,
     },
 
     /// Represents a move in algebraic notation, e.g. "e4".
@@ -26,18 +99,23 @@ pub enum MakeMove {
 
 impl Command for MakeMove {
     fn execute(&self, game: &mut Game) -> CommandResult {
-        match self {
+        ensure_game_is_ongoing(game)?;
+        let chess_move = match self {
             MakeMove::Coordinate {
                 from_square,
                 to_square,
+                promote_to,
             } => game.apply_chess_move_by_from_to_coordinates(
                 square_string_to_bitboard(from_square),
                 square_string_to_bitboard(to_square),
+                *promote_to,
             ),
             MakeMove::Algebraic { algebraic } => {
                 game.apply_chess_move_from_raw_algebraic_notation(algebraic.to_string())
             }
         }
+        .map_err(|error| CommandError::GameError { error })?;
+        Ok(CommandOutcome::after_move(game, chess_move))
     }
 }
 
@@ -49,6 +127,203 @@ pub struct MakeWaterfallMove {}
 
 impl Command for MakeWaterfallMove {
     fn execute(&self, game: &mut Game) -> CommandResult {
-        game.make_waterfall_book_then_alpha_beta_move()
+        ensure_game_is_ongoing(game)?;
+        let chess_move = game
+            .make_waterfall_book_then_alpha_beta_move()
+            .map_err(|error| CommandError::GameError { error })?;
+        Ok(CommandOutcome::after_move(game, chess_move))
+    }
+}
+
+/// Prints the current position in FEN, so that it can be copied into other tools.
+pub const SHOW_FEN: NamedCommand = NamedCommand {
+    name: "fen",
+    aliases: &[],
+    arguments: "[moves]",
+    description: "print the position's FEN, and with \"moves\", the moves so far in UCI notation",
+    handler: show_fen,
+};
+
+fn show_fen(game: &mut Game, arguments: &[String]) -> Result<String, InputError> {
+    let fen = game.board().to_fen();
+    match arguments {
+        [] => Ok(fen),
+        [argument] if argument == "moves" => {
+            let moves: Vec<String> = game
+                .move_history()
+                .iter()
+                .map(|chess_move| chess_move.to_uci())
+                .collect();
+            Ok(format!("{}\nmoves: {}", fen, moves.join(" ")))
+        }
+        _ => Err(SHOW_FEN.usage_error()),
+    }
+}
+
+/// Claims a draw by threefold repetition or the fifty-move rule, which either
+/// player may do on their turn, ending the game.
+pub const CLAIM_DRAW: NamedCommand = NamedCommand {
+    name: "draw",
+    aliases: &["claim"],
+    arguments: "",
+    description: "claim a draw by threefold repetition or the fifty-move rule",
+    handler: claim_draw,
+};
+
+fn claim_draw(game: &mut Game, arguments: &[String]) -> Result<String, InputError> {
+    if !arguments.is_empty() {
+        return Err(CLAIM_DRAW.usage_error());
+    }
+    let claim = game.claim_draw()?;
+    Ok(format!("You claimed a draw by {}.", claim))
+}
+
+/// How many of the best moves `analyze` shows at each depth, unless it's told.
+const DEFAULT_ANALYSIS_LINES: usize = 3;
+
+/// Pauses the game to analyze the position, for as long as the player likes,
+/// then picks the game back up where it left off.
+pub const ANALYZE: NamedCommand = NamedCommand {
+    name: "analyze",
+    aliases: &[],
+    arguments: "[lines]",
+    description: "pause the game and analyze the position, showing the best moves at each depth, until you press enter",
+    handler: analyze,
+};
+
+fn analyze(game: &mut Game, arguments: &[String]) -> Result<String, InputError> {
+    let lines = match arguments {
+        [] => DEFAULT_ANALYSIS_LINES,
+        [lines] => match lines.parse() {
+            Ok(lines) if lines > 0 => lines,
+            _ => return Err(ANALYZE.usage_error()),
+        },
+        _ => return Err(ANALYZE.usage_error()),
+    };
+    println!("Analyzing; press enter to go back to the game.");
+    println!("{:>7}  best moves (white's perspective)", "depth");
+    let depth = analyze_until(game.board(), lines, || {
+        let _ = read_line();
+    })
+    .map_err(|error| InputError::AnalyzeError { error })?;
+    Ok(format!(
+        "Analyzed to depth {}. Back to the game; it's {}'s move.",
+        depth,
+        game.board().turn()
+    ))
+}
+
+/// Copies the game's PGN or the position's FEN to the system clipboard, for
+/// analyzing it in another tool.
+#[cfg(feature = "clipboard")]
+pub const COPY: NamedCommand = NamedCommand {
+    name: "copy",
+    aliases: &[],
+    arguments: "pgn|fen",
+    description: "copy the game's PGN or the position's FEN to the clipboard",
+    handler: copy,
+};
+
+#[cfg(feature = "clipboard")]
+fn copy(game: &mut Game, arguments: &[String]) -> Result<String, InputError> {
+    let (name, text) = match arguments {
+        [argument] if argument == "pgn" => ("PGN", game.to_pgn()?.to_string()),
+        [argument] if argument == "fen" => ("FEN", game.board().to_fen()),
+        _ => return Err(COPY.usage_error()),
+    };
+    copy_to_clipboard(&text).map_err(|error| InputError::ClipboardError { error })?;
+    Ok(format!("Copied the {} to the clipboard.", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show_fen() {
+        let mut game = Game::new(0);
+        for algebraic in ["e4", "c5"] {
+            MakeMove::Algebraic {
+                algebraic: algebraic.to_string(),
+            }
+            .execute(&mut game)
+            .unwrap();
+            game.board_mut().toggle_turn();
+        }
+
+        let fen = SHOW_FEN.run(&mut game, &[]).unwrap();
+        assert_eq!(
+            fen,
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"
+        );
+
+        let with_moves = SHOW_FEN.run(&mut game, &["moves".to_string()]).unwrap();
+        assert_eq!(with_moves, format!("{}\nmoves: e2e4 c7c5", fen));
+
+        assert!(SHOW_FEN.run(&mut game, &["all".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_execute_outcomes() {
+        let mut game = Game::new(0);
+        let play = |game: &mut Game, algebraic: &str| {
+            let result = MakeMove::Algebraic {
+                algebraic: algebraic.to_string(),
+            }
+            .execute(game);
+            if result.is_ok() {
+                game.board_mut().toggle_turn();
+            }
+            result
+        };
+
+        for algebraic in ["f3", "e5", "g4"] {
+            let outcome = play(&mut game, algebraic).unwrap();
+            assert!(matches!(outcome, CommandOutcome::MoveApplied { .. }));
+        }
+        let error = play(&mut game, "Qh5").unwrap_err();
+        assert!(matches!(
+            error,
+            CommandError::GameError {
+                error: GameError::IllegalMove { .. }
+            }
+        ));
+        assert!(!error.is_internal());
+
+        let outcome = play(&mut game, "Qh4#").unwrap();
+        assert_eq!(outcome.chess_move().unwrap().to_uci(), "d8h4");
+        assert!(matches!(
+            outcome,
+            CommandOutcome::GameEnded {
+                ending: GameEnding::Checkmate,
+                ..
+            }
+        ));
+
+        let error = play(&mut game, "a3").unwrap_err();
+        assert!(matches!(
+            error,
+            CommandError::GameOver {
+                ending: GameEnding::Checkmate
+            }
+        ));
+    }
+
+    #[test]
+    fn test_analyze_takes_a_number_of_lines() {
+        let mut game = Game::new(0);
+        for arguments in [&["0"][..], &["three"], &["3", "4"]] {
+            let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+            let error = ANALYZE.run(&mut game, &arguments).unwrap_err();
+            assert_eq!(error.to_string(), "usage: analyze [lines]");
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn test_copy_requires_pgn_or_fen() {
+        let mut game = Game::new(0);
+        let error = COPY.run(&mut game, &[]).unwrap_err();
+        assert_eq!(error.to_string(), "usage: copy pgn|fen");
     }
 }
@@ -1,35 +1,47 @@
 use std::thread::sleep;
 use std::time::Duration;
 
-use termion::clear;
-
 use crate::evaluate::GameEnding;
-use crate::game::game::Game;
-use crate::game::util::print_board_and_stats;
+use crate::game::event::{GameEvent, OutputFormat};
+use crate::game::game_options::GameOptions;
+use crate::game::renderer::Renderer;
+use crate::game::util::stats_lines;
 
-pub fn computer_vs_computer(move_limit: u8, sleep_between_turns_in_ms: u64, depth: u8) {
-    let mut game = Game::new(depth);
+pub fn computer_vs_computer(
+    move_limit: u16,
+    sleep_between_turns_in_ms: u64,
+    options: &GameOptions,
+) {
+    let mut game = options.new_game();
+    let output = options.output;
+    let mut renderer = Renderer::with_config(options.display);
 
-    println!("{}", clear::All);
+    match output {
+        OutputFormat::Text => renderer.render_to_stdout(game.board(), &[]),
+        OutputFormat::Json => GameEvent::Start {
+            white: "engine",
+            black: "engine",
+        }
+        .emit(),
+    }
 
     loop {
         sleep(Duration::from_millis(sleep_between_turns_in_ms));
 
-        match game.check_game_over_for_current_turn() {
-            Some(GameEnding::Checkmate) => {
-                println!("checkmate!");
-                break;
-            }
-            Some(GameEnding::Stalemate) => {
-                println!("stalemate!");
-                break;
-            }
-            Some(GameEnding::Draw) => {
-                println!("draw!");
-                break;
+        if let Some(ending) = game.check_game_over_for_current_turn() {
+            match output {
+                OutputFormat::Text => match ending {
+                    GameEnding::Checkmate => println!("checkmate!"),
+                    GameEnding::Stalemate => println!("stalemate!"),
+                    GameEnding::Draw => match game.claimed_draw() {
+                        Some(claim) => println!("draw by {}!", claim),
+                        None => println!("draw!"),
+                    },
+                },
+                OutputFormat::Json => GameEvent::game_over(&ending, game.board().turn()).emit(),
             }
-            _ => (),
-        };
+            break;
+        }
 
         if move_limit > 0 && game.fullmove_clock() > move_limit {
             break;
@@ -43,15 +55,34 @@ pub fn computer_vs_computer(move_limit: u8, sleep_between_turns_in_ms: u64, dept
         let result = game.make_waterfall_book_then_alpha_beta_move();
 
         match result {
-            Ok(_chess_move) => {
-                println!("{}", clear::All);
-                print_board_and_stats(&mut game, enumerated_candidate_moves, current_turn);
+            Ok(chess_move) => {
+                match output {
+                    OutputFormat::Text => {
+                        let lines = stats_lines(&game, enumerated_candidate_moves, current_turn);
+                        renderer.render_to_stdout(game.board(), &lines);
+                    }
+                    OutputFormat::Json => GameEvent::move_made(
+                        &game,
+                        &chess_move,
+                        &enumerated_candidate_moves,
+                        current_turn,
+                        true,
+                    )
+                    .emit(),
+                }
                 game.board_mut().toggle_turn();
                 game.reset_move_generator_cache_hit_count();
                 continue;
             }
             Err(error) => {
-                println!("error: {}", error);
+                match output {
+                    OutputFormat::Text => println!("error: {}", error),
+                    OutputFormat::Json => GameEvent::Error {
+                        message: error.to_string(),
+                    }
+                    .emit(),
+                }
+                game.report_error(&error);
                 break;
             }
         }
@@ -0,0 +1,76 @@
+use std::sync::mpsc::{channel, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::chess_move::algebraic_notation::algebraic_notation_for;
+use crate::chess_move::chess_move::ChessMove;
+use crate::game::annotate::{analyze_move, evaluate_position, AnnotateError, MoveAnalysis};
+use crate::game::game::Game;
+
+/// Grades the moves of a game as it's played, the way `annotate` grades a
+/// finished one: a background thread replays the game on a board of its own,
+/// searching each position at a shallow depth, so that the players never wait
+/// on it.
+pub struct EvalGrader {
+    sender: Sender<ChessMove>,
+    handle: JoinHandle<Result<Vec<MoveAnalysis>, AnnotateError>>,
+}
+
+impl EvalGrader {
+    /// Starts grading a game from the starting position, searching each
+    /// position `depth` plies deep.
+    pub fn start(depth: u8) -> Self {
+        let (sender, receiver) = channel::<ChessMove>();
+        let handle = thread::spawn(move || {
+            let mut game = Game::new(depth);
+            let mut evaluation = evaluate_position(&mut game)?;
+            let mut analyses = Vec::new();
+            for chess_move in receiver {
+                let san = algebraic_notation_for(&chess_move, &game.enumerated_candidate_moves());
+                analyses.push(analyze_move(&mut game, chess_move, san, &mut evaluation)?);
+            }
+            Ok(analyses)
+        });
+        Self { sender, handle }
+    }
+
+    /// Queues the next move played in the game to be graded.
+    pub fn grade(&self, chess_move: &ChessMove) {
+        // If the thread has stopped, `finish` reports why.
+        let _ = self.sender.send(chess_move.clone());
+    }
+
+    /// Waits for every queued move to be graded, and returns their analyses.
+    pub fn finish(self) -> Result<Vec<MoveAnalysis>, AnnotateError> {
+        drop(self.sender);
+        self.handle.join().expect("the grading thread panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grades_moves_in_order() {
+        let grader = EvalGrader::start(2);
+        let mut game = Game::new(0);
+        for san in ["e4", "f6", "d4", "g5", "Qh5#"] {
+            let chess_move = game
+                .apply_chess_move_from_raw_algebraic_notation(san.to_string())
+                .unwrap();
+            game.board_mut().toggle_turn();
+            grader.grade(&chess_move);
+        }
+
+        let analyses = grader.finish().unwrap();
+        let sans: Vec<&str> = analyses
+            .iter()
+            .map(|analysis| analysis.san.as_str())
+            .collect();
+        assert_eq!(sans, ["e4", "f6", "d4", "g5", "Qh5#"]);
+        // Black's last move let white mate in one, which white found.
+        assert!(analyses[3].nag().is_some());
+        assert!(analyses[3].win_probability_loss() > 0.4);
+        assert!(analyses[4].is_best_move());
+    }
+}
@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::board::color::Color;
+use crate::chess_move::algebraic_notation::algebraic_notation_for;
+use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::{win_probability, GameEnding};
+use crate::game::game::Game;
+
+/// How the interactive modes report what is happening in the game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// The human-readable TUI.
+    Text,
+    /// One JSON object per event, one per line, for GUIs wrapping the CLI.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("invalid output format; options are: text, json"),
+        }
+    }
+}
+
+/// A machine-readable game event, emitted when running with `--output json`.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    Start {
+        white: &'static str,
+        black: &'static str,
+    },
+    AwaitingMove {
+        color: String,
+    },
+    Move {
+        color: String,
+        uci: String,
+        san: String,
+        score: Option<i16>,
+        white_win_probability: Option<f64>,
+        depth: Option<u8>,
+        seldepth: Option<u8>,
+        nodes: usize,
+        branching_factor: Option<f64>,
+        first_move_cutoff_percent: Option<f64>,
+        hash_full_percent: Option<f64>,
+        move_time_ms: u128,
+        halfmove_clock: u16,
+        fullmove_clock: u16,
+    },
+    Error {
+        message: String,
+    },
+    GameOver {
+        result: &'static str,
+        winner: Option<String>,
+    },
+}
+
+impl GameEvent {
+    /// Describes the move that was just made. Search details are only included
+    /// for engine moves that were found by searching (i.e. not book moves).
+    pub fn move_made(
+        game: &Game,
+        chess_move: &ChessMove,
+        enumerated_candidate_moves: &[(ChessMove, String)],
+        color: Color,
+        by_engine: bool,
+    ) -> Self {
+        let searched = by_engine && game.searched_position_count() > 0;
+        let score = if searched {
+            game.alpha_beta_score()
+        } else {
+            None
+        };
+        let last_depth = game.depth_stats().last().filter(|_| searched);
+        GameEvent::Move {
+            color: color.to_string(),
+            uci: chess_move.to_uci(),
+            san: algebraic_notation_for(chess_move, enumerated_candidate_moves),
+            score,
+            white_win_probability: score.map(win_probability),
+            depth: if searched {
+                Some(game.search_depth())
+            } else {
+                None
+            },
+            seldepth: last_depth.map(|stats| stats.selective_depth),
+            nodes: if searched {
+                game.searched_position_count()
+            } else {
+                0
+            },
+            branching_factor: last_depth.and_then(|stats| stats.branching_factor),
+            first_move_cutoff_percent: last_depth
+                .map(|stats| stats.node_types.first_move_cutoff_percentage()),
+            hash_full_percent: if searched {
+                Some(game.hash_fill_percentage())
+            } else {
+                None
+            },
+            move_time_ms: if by_engine {
+                game.time_manager().elapsed().as_millis()
+            } else {
+                0
+            },
+            halfmove_clock: game.board().halfmove_clock(),
+            fullmove_clock: game.fullmove_clock(),
+        }
+    }
+
+    /// Describes how the game ended. `turn` is the player to move when the game ended.
+    pub fn game_over(ending: &GameEnding, turn: Color) -> Self {
+        match ending {
+            GameEnding::Checkmate => GameEvent::GameOver {
+                result: "checkmate",
+                winner: Some(turn.opposite().to_string()),
+            },
+            GameEnding::Stalemate => GameEvent::GameOver {
+                result: "stalemate",
+                winner: None,
+            },
+            GameEnding::Draw => GameEvent::GameOver {
+                result: "draw",
+                winner: None,
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn emit(&self) {
+        println!("{}", self.to_json());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!(OutputFormat::from_str("text"), Ok(OutputFormat::Text));
+        assert_eq!(OutputFormat::from_str("json"), Ok(OutputFormat::Json));
+        assert!(OutputFormat::from_str("xml").is_err());
+    }
+
+    #[test]
+    fn test_game_over_json() {
+        let event = GameEvent::game_over(&GameEnding::Checkmate, Color::Black);
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"game_over","result":"checkmate","winner":"white"}"#
+        );
+
+        let event = GameEvent::game_over(&GameEnding::Stalemate, Color::Black);
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"game_over","result":"stalemate","winner":null}"#
+        );
+    }
+
+    #[test]
+    fn test_move_json() {
+        let mut game = Game::new(0);
+        let enumerated_candidate_moves = game.enumerated_candidate_moves();
+        let chess_move = game
+            .apply_chess_move_from_raw_algebraic_notation("e4".to_string())
+            .unwrap();
+
+        let event = GameEvent::move_made(
+            &game,
+            &chess_move,
+            &enumerated_candidate_moves,
+            Color::White,
+            false,
+        );
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"move","color":"white","uci":"e2e4","san":"e4","score":null,"white_win_probability":null,"depth":null,"seldepth":null,"nodes":0,"branching_factor":null,"first_move_cutoff_percent":null,"hash_full_percent":null,"move_time_ms":0,"halfmove_clock":0,"fullmove_clock":1}"#
+        );
+    }
+}
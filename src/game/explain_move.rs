@@ -0,0 +1,240 @@
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::{alpha_beta_search, SearchContext, SearchError};
+use crate::board::color::Color;
+use crate::board::error::BoardError;
+use crate::board::fen::FenError;
+use crate::board::Board;
+use crate::chess_move::algebraic_notation::{
+    algebraic_notation_for, enumerate_candidate_moves_with_algebraic_notation, find_move_by_san,
+};
+use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate;
+use crate::move_generator::MoveGenerator;
+
+#[derive(Error, Debug)]
+pub enum ExplainError {
+    #[error("invalid fen: {error}")]
+    FenError { error: FenError },
+    #[error("{chess_move:?} is not a legal move in this position")]
+    IllegalMove { chess_move: String },
+    #[error("search error: {error}")]
+    SearchError { error: SearchError },
+    #[error("board error: {error}")]
+    BoardError { error: BoardError },
+}
+
+/// A line of play, with the score (from white's perspective) the search
+/// assigned to it.
+pub struct Line {
+    pub moves: Vec<(ChessMove, String)>,
+    pub score: i16,
+}
+
+/// Searches both `chess_move` and the engine's preferred move in the position,
+/// and prints their principal variations, scores, and where they diverge.
+pub fn explain_move(fen: &str, chess_move: &str, depth: u8) -> Result<(), ExplainError> {
+    let mut board = Board::from_fen(fen).map_err(|error| ExplainError::FenError { error })?;
+    let mut move_generator = MoveGenerator::new();
+    let turn = board.turn();
+    let enumerated_candidate_moves =
+        enumerate_candidate_moves_with_algebraic_notation(&mut board, turn, &mut move_generator);
+
+    let player_move = find_move_by_san(chess_move, &enumerated_candidate_moves)
+        .or_else(|| {
+            enumerated_candidate_moves
+                .iter()
+                .find(|(candidate, _)| candidate.to_uci() == chess_move)
+                .map(|(candidate, _)| candidate.clone())
+        })
+        .ok_or(ExplainError::IllegalMove {
+            chess_move: chess_move.to_string(),
+        })?;
+
+    let mut context = SearchContext::new(depth);
+    let best_move = alpha_beta_search(&mut context, &mut board, &mut move_generator)
+        .map_err(|error| ExplainError::SearchError { error })?;
+
+    let player_line = principal_variation(&board, &player_move, depth)?;
+    let best_line = principal_variation(&board, &best_move, depth)?;
+
+    let fullmove = board.fullmove_clock();
+    println!(
+        "Your move:  {} (score: {})",
+        player_line.moves[0].1, player_line.score
+    );
+    println!("  {}", format_line(&player_line, fullmove, turn));
+    println!(
+        "Best move:  {} (score: {})",
+        best_line.moves[0].1, best_line.score
+    );
+    println!("  {}", format_line(&best_line, fullmove, turn));
+
+    match divergence(&player_line, &best_line) {
+        None => println!("\nYour move is the engine's choice."),
+        Some(ply) => {
+            let (number, color) = move_number(fullmove, turn, ply);
+            println!(
+                "\nThe lines diverge at move {} ({}): {} vs. {}",
+                number,
+                color,
+                player_line
+                    .moves
+                    .get(ply)
+                    .map_or("-", |(_, san)| san.as_str()),
+                best_line
+                    .moves
+                    .get(ply)
+                    .map_or("-", |(_, san)| san.as_str()),
+            );
+            let loss = match turn {
+                Color::White => best_line.score as i32 - player_line.score as i32,
+                Color::Black => player_line.score as i32 - best_line.score as i32,
+            };
+            println!("Your move scores {} centipawns worse for {}.", loss, turn);
+        }
+    }
+
+    Ok(())
+}
+
+/// Plays `first_move`, then follows the engine's best reply at each ply, searching
+/// one ply shallower each time so the whole line stays within `depth` plies.
+pub fn principal_variation(
+    board: &Board,
+    first_move: &ChessMove,
+    depth: u8,
+) -> Result<Line, ExplainError> {
+    let mut board = board.clone();
+    let mut move_generator = MoveGenerator::new();
+    let mut moves = Vec::new();
+    let mut score = None;
+
+    let mut next_move = first_move.clone();
+    for remaining_depth in (0..depth).rev() {
+        let turn = board.turn();
+        let enumerated_candidate_moves = enumerate_candidate_moves_with_algebraic_notation(
+            &mut board,
+            turn,
+            &mut move_generator,
+        );
+        let san = algebraic_notation_for(&next_move, &enumerated_candidate_moves);
+        next_move
+            .apply(&mut board)
+            .map_err(|error| ExplainError::BoardError { error })?;
+        board.toggle_turn();
+        moves.push((next_move, san));
+
+        let turn = board.turn();
+        if remaining_depth == 0
+            || evaluate::game_ending(&mut board, &mut move_generator, turn).is_some()
+        {
+            break;
+        }
+
+        let mut context = SearchContext::new(remaining_depth);
+        next_move = alpha_beta_search(&mut context, &mut board, &mut move_generator)
+            .map_err(|error| ExplainError::SearchError { error })?;
+        // The first search after `first_move` sees the furthest, so its score
+        // is the score of the whole line.
+        score = score.or(context.last_score());
+    }
+
+    let score = match score {
+        Some(score) => score,
+        None => {
+            let turn = board.turn();
+            evaluate::score(&mut board, &mut move_generator, turn, 0)
+        }
+    };
+
+    Ok(Line { moves, score })
+}
+
+/// Returns the first ply at which the two lines play different moves.
+fn divergence(line: &Line, other: &Line) -> Option<usize> {
+    let length = line.moves.len().max(other.moves.len());
+    (0..length).find(|&ply| {
+        let uci = |line: &Line| line.moves.get(ply).map(|(m, _)| m.to_uci());
+        uci(line) != uci(other)
+    })
+}
+
+fn move_number(fullmove: u16, turn: Color, ply: usize) -> (usize, Color) {
+    let offset = match turn {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    let ply = ply + offset;
+    let color = if ply.is_multiple_of(2) {
+        Color::White
+    } else {
+        Color::Black
+    };
+    (fullmove as usize + ply / 2, color)
+}
+
+fn format_line(line: &Line, fullmove: u16, turn: Color) -> String {
+    let mut tokens = Vec::new();
+    for (ply, (_, san)) in line.moves.iter().enumerate() {
+        match move_number(fullmove, turn, ply) {
+            (number, Color::White) => tokens.push(format!("{}.", number)),
+            (number, Color::Black) if ply == 0 => tokens.push(format!("{}...", number)),
+            _ => (),
+        }
+        tokens.push(san.clone());
+    }
+    tokens.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_principal_variation() {
+        // White's queen on d4 is undefended, so ...Nxd4 wins it.
+        let board =
+            Board::from_fen("r1bqk2r/pppp1ppp/2n5/8/3Q4/8/PPP2PPP/RNB1KB1R b KQkq - 0 6").unwrap();
+        let mut move_generator = MoveGenerator::new();
+        let mut board_copy = board.clone();
+        let enumerated_candidate_moves = enumerate_candidate_moves_with_algebraic_notation(
+            &mut board_copy,
+            Color::Black,
+            &mut move_generator,
+        );
+        let capture = find_move_by_san("Nxd4", &enumerated_candidate_moves).unwrap();
+        let line = principal_variation(&board, &capture, 2).unwrap();
+
+        assert_eq!(line.moves.len(), 2);
+        assert_eq!(line.moves[0].1, "Nxd4");
+        assert!(line.score < -500, "black should be up a queen");
+        assert_eq!(
+            format_line(&line, 6, Color::Black).split(' ').next(),
+            Some("6...")
+        );
+    }
+
+    #[test]
+    fn test_divergence_and_move_numbers() {
+        let board = Board::starting_position();
+        let mut move_generator = MoveGenerator::new();
+        let mut board_copy = board.clone();
+        let enumerated_candidate_moves = enumerate_candidate_moves_with_algebraic_notation(
+            &mut board_copy,
+            Color::White,
+            &mut move_generator,
+        );
+        let e4 = find_move_by_san("e4", &enumerated_candidate_moves).unwrap();
+        let d4 = find_move_by_san("d4", &enumerated_candidate_moves).unwrap();
+        let line = |chess_move: &ChessMove| Line {
+            moves: vec![(chess_move.clone(), String::new())],
+            score: 0,
+        };
+
+        assert_eq!(divergence(&line(&e4), &line(&e4)), None);
+        assert_eq!(divergence(&line(&e4), &line(&d4)), Some(0));
+        assert_eq!(move_number(1, Color::White, 3), (2, Color::Black));
+        assert_eq!(move_number(6, Color::Black, 1), (7, Color::White));
+    }
+}
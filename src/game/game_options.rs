@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use crate::alpha_beta_searcher::root_jitter::RootJitter;
+use crate::alpha_beta_searcher::watchdog::Watchdog;
+use crate::evaluate::weights::EvaluationWeights;
+use crate::evaluate::EvaluationTerms;
+use crate::game::event::OutputFormat;
+use crate::game::game::Game;
+#[cfg(feature = "opening-explorer")]
+use crate::game::masters_explorer::masters_explorer;
+use crate::game::renderer::DisplayConfig;
+
+/// The settings shared by the modes where the engine plays, `play` and
+/// `watch`: how the engine searches and evaluates, and how the game is shown.
+#[derive(Clone)]
+pub struct GameOptions {
+    pub depth: u8,
+    /// The most memory, in megabytes, for the engine's transposition table.
+    pub hash_mb: usize,
+    pub root_jitter: Option<RootJitter>,
+    /// `None` evaluates with the built-in weights.
+    pub weights: Option<Arc<EvaluationWeights>>,
+    /// Play out each checkmate the engine finds before announcing it.
+    pub verify_mates: bool,
+    /// Abort searches that run far past their budget.
+    pub watchdog: Option<Watchdog>,
+    /// Follow master games from the Lichess opening explorer once the engine's
+    /// book runs out.
+    #[cfg(feature = "opening-explorer")]
+    pub opening_explorer: bool,
+    pub display: DisplayConfig,
+    pub output: OutputFormat,
+}
+
+impl GameOptions {
+    /// Starts a game whose engine searches and evaluates as these options say.
+    pub fn new_game(&self) -> Game {
+        let mut game = Game::new(self.depth);
+        game.set_hash_size(self.hash_mb);
+        game.set_verify_mates(self.verify_mates);
+        game.set_watchdog(self.watchdog);
+        #[cfg(feature = "opening-explorer")]
+        game.set_opening_explorer(self.opening_explorer.then(masters_explorer));
+        game.set_root_jitter(self.root_jitter.clone());
+        if let Some(weights) = &self.weights {
+            game.set_evaluation_terms(EvaluationTerms {
+                weights: weights.clone(),
+                ..Default::default()
+            });
+        }
+        game
+    }
+}
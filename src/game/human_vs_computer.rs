@@ -1,43 +1,196 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
 use crate::board::color::Color;
-use crate::evaluate::GameEnding;
-use crate::game::command::{Command, MakeWaterfallMove};
-use crate::game::game::Game;
-use crate::game::util::{print_board, print_board_and_stats, print_enter_move_prompt};
-use crate::input_handler;
-use std::time::SystemTime;
-use termion::{clear, cursor};
+use crate::evaluate::sparring::SparringProfile;
+use crate::evaluate::{EvaluationTerms, GameEnding};
+#[cfg(feature = "clipboard")]
+use crate::game::command::COPY;
+use crate::game::command::{
+    Command, CommandError, CommandOutcome, MakeMove, MakeWaterfallMove, ANALYZE, CLAIM_DRAW,
+    SHOW_FEN,
+};
+use crate::game::event::{GameEvent, OutputFormat};
+use crate::game::game::{Game, GameError};
+use crate::game::game_options::GameOptions;
+use crate::game::renderer::Renderer;
+use crate::game::session::{GameOutcome, GameRecord, SessionStats};
+use crate::game::util::{print_enter_move_prompt, stats_lines};
+use crate::input_handler::command_registry::CommandRegistry;
+use crate::input_handler::{prompt_promotion, read_line, typed_ahead, PlayerInput};
+
+/// Plays games against the engine until the player quits or declines another
+/// game, then prints statistics for the session. With a `stats_file`, the
+/// session's games are added to those recorded there, and the all-time
+/// statistics are printed too. The engine searches and evaluates as `options`
+/// say, except that with `sparring` it plays with that weakness. With `ponder`,
+/// the engine thinks about its next move while the player thinks about theirs.
+/// With `auto_queen`, the player's pawns promote to queens unless their move
+/// names another piece, rather than asking which piece. A move the player
+/// types while the engine is thinking is played as a premove as soon as the
+/// engine has moved, if it's still legal.
+pub fn play_computer(
+    options: &GameOptions,
+    player_color: Color,
+    sparring: Option<SparringProfile>,
+    ponder: bool,
+    auto_queen: bool,
+    stats_file: Option<PathBuf>,
+) {
+    let output = options.output;
+    let mut session = SessionStats::default();
+    loop {
+        let record = play_game(options, player_color, sparring, ponder, auto_queen);
+        let abandoned = record.outcome == GameOutcome::Abandoned;
+        session.record(record);
+        // JSON output is for a single game driven by another program.
+        if abandoned || output == OutputFormat::Json || !ask_to_play_again() {
+            break;
+        }
+    }
 
-pub fn play_computer(depth: u8, player_color: Color) {
-    let game = &mut Game::new(depth);
+    if output == OutputFormat::Text {
+        print!("\n{}", session.summary("Session"));
+    }
+    if let Some(path) = stats_file {
+        match save_session(&path, &session) {
+            Ok(all_time) if output == OutputFormat::Text => {
+                print!("\n{}", all_time.summary("All time"))
+            }
+            Ok(_) => (),
+            Err(error) => eprintln!("error: could not update {}: {}", path.display(), error),
+        }
+    }
+}
 
-    print!("{}{}", cursor::Goto(1, 1), clear::All);
-    println!("You are {}", player_color);
-    print_board(game.board());
-    print_enter_move_prompt();
+/// Adds the session's games to the stats file, and returns everything recorded.
+fn save_session(path: &Path, session: &SessionStats) -> io::Result<SessionStats> {
+    let mut all_time = SessionStats::load(path)?;
+    all_time.games.extend(session.games.iter().cloned());
+    all_time.save(path)?;
+    Ok(all_time)
+}
+
+fn ask_to_play_again() -> bool {
+    println!("Play again? [y/N]");
+    match read_line() {
+        Ok(Some(answer)) => matches!(answer.trim(), "y" | "Y" | "yes"),
+        _ => false,
+    }
+}
+
+fn play_game(
+    options: &GameOptions,
+    player_color: Color,
+    sparring: Option<SparringProfile>,
+    ponder: bool,
+    auto_queen: bool,
+) -> GameRecord {
+    let output = options.output;
+    let mut record = GameRecord::default();
+    let game = &mut options.new_game();
+    game.set_auto_queen(auto_queen);
+    game.set_engine_player(player_color.opposite());
+    if let Some(sparring) = sparring {
+        let terms = sparring.terms();
+        game.set_evaluation_terms(EvaluationTerms {
+            weights: options.weights.clone().unwrap_or(terms.weights.clone()),
+            ..terms
+        });
+    }
+    let mut renderer = Renderer::with_config(options.display);
+    let registry = CommandRegistry::new()
+        .register(SHOW_FEN)
+        .register(ANALYZE)
+        .register(CLAIM_DRAW);
+    #[cfg(feature = "clipboard")]
+    let registry = registry.register(COPY);
+
+    match output {
+        OutputFormat::Text => {
+            renderer.render_to_stdout(game.board(), &[format!("You are {}", player_color)]);
+            print_enter_move_prompt();
+        }
+        OutputFormat::Json => {
+            let (white, black) = match player_color {
+                Color::White => ("human", "engine"),
+                Color::Black => ("engine", "human"),
+            };
+            GameEvent::Start { white, black }.emit();
+        }
+    }
 
     loop {
-        match game.check_game_over_for_current_turn() {
-            Some(GameEnding::Checkmate) => {
-                println!("checkmate!");
-                break;
-            }
-            Some(GameEnding::Stalemate) => {
-                println!("stalemate!");
-                break;
+        if let Some(ending) = game.check_game_over_for_current_turn() {
+            record.outcome = match ending {
+                // The player to move is the one who was checkmated.
+                GameEnding::Checkmate if game.board().turn() == player_color => GameOutcome::Loss,
+                GameEnding::Checkmate => GameOutcome::Win,
+                _ => GameOutcome::Draw,
+            };
+            match (output, ending) {
+                (OutputFormat::Text, GameEnding::Checkmate) => println!("checkmate!"),
+                (OutputFormat::Text, GameEnding::Stalemate) => println!("stalemate!"),
+                (OutputFormat::Text, GameEnding::Draw) => match game.claimed_draw() {
+                    Some(claim) => println!("draw by {}!", claim),
+                    None => println!("draw!"),
+                },
+                (OutputFormat::Json, ending) => {
+                    GameEvent::game_over(&ending, game.board().turn()).emit()
+                }
             }
-            _ => (),
-        };
+            return record;
+        }
 
         // Precalculate the moves and their algebraic notations, so that we
         // can render it after a move is made.
         let enumerated_candidate_moves = game.enumerated_candidate_moves();
         let current_turn = game.board().turn();
+        let is_player_turn = player_color == current_turn;
+        // A move the player typed while the engine was thinking is played as
+        // soon as the engine's move is in, if it's still legal.
+        let premove = if is_player_turn { typed_ahead() } else { None };
 
-        let command: Box<dyn Command> = if player_color == game.board().turn() {
-            match input_handler::parse_player_move_input() {
-                Ok(command) => command,
+        let command: Box<dyn Command> = if is_player_turn {
+            if output == OutputFormat::Json && premove.is_none() {
+                GameEvent::AwaitingMove {
+                    color: current_turn.to_string(),
+                }
+                .emit();
+            }
+            let input = match &premove {
+                Some(line) => registry.parse(line),
+                None => registry.read_input(),
+            };
+            match input {
+                Ok(PlayerInput::Move(command)) => command,
+                Ok(PlayerInput::Help) => {
+                    if output == OutputFormat::Text {
+                        print!("{}", registry.help_text());
+                    }
+                    continue;
+                }
+                Ok(PlayerInput::Quit) => return record,
+                Ok(PlayerInput::Named { command, arguments }) => {
+                    match (output, command.run(game, &arguments)) {
+                        (OutputFormat::Text, Ok(text)) => println!("{}", text),
+                        (OutputFormat::Text, Err(error)) => println!("error: {}", error),
+                        (OutputFormat::Json, Ok(_)) => (),
+                        (OutputFormat::Json, Err(error)) => GameEvent::Error {
+                            message: error.to_string(),
+                        }
+                        .emit(),
+                    }
+                    continue;
+                }
                 Err(msg) => {
-                    println!("{}", msg);
+                    match output {
+                        OutputFormat::Text => println!("{}", msg),
+                        OutputFormat::Json => GameEvent::Error {
+                            message: msg.to_string(),
+                        }
+                        .emit(),
+                    }
                     continue;
                 }
             }
@@ -45,21 +198,92 @@ pub fn play_computer(depth: u8, player_color: Color) {
             Box::<MakeWaterfallMove>::default()
         };
 
-        let start_time = SystemTime::now();
-        match command.execute(game) {
-            Ok(_chess_move) => {
-                let duration = SystemTime::now().duration_since(start_time).unwrap();
-                print!("{}{}", cursor::Goto(1, 1), clear::All);
+        let result = match command.execute(game) {
+            Err(CommandError::GameError {
+                error: GameError::AmbiguousPromotion { choices, .. },
+            }) if output == OutputFormat::Text => match prompt_promotion(&choices) {
+                Some(algebraic) => MakeMove::Algebraic { algebraic }.execute(game),
+                None => continue,
+            },
+            result => result,
+        };
+        match result {
+            Ok(CommandOutcome::StateChanged) => {
+                if output == OutputFormat::Text {
+                    renderer.render_to_stdout(game.board(), &[]);
+                }
+                continue;
+            }
+            Ok(
+                CommandOutcome::MoveApplied { chess_move }
+                | CommandOutcome::GameEnded { chess_move, .. },
+            ) => {
+                if !is_player_turn {
+                    record_engine_move(&mut record, game);
+                }
+                if output == OutputFormat::Json {
+                    GameEvent::move_made(
+                        game,
+                        &chess_move,
+                        &enumerated_candidate_moves,
+                        current_turn,
+                        !is_player_turn,
+                    )
+                    .emit();
+                    game.board_mut().toggle_turn();
+                    if ponder && !is_player_turn {
+                        game.start_pondering();
+                    }
+                    continue;
+                }
+
+                let duration = game.time_manager().elapsed();
                 game.board_mut().toggle_turn();
 
-                print_board_and_stats(game, enumerated_candidate_moves, current_turn);
-                if player_color == game.board().turn() {
-                    println!("* Move took: {:?}", duration);
+                let mut lines = stats_lines(game, enumerated_candidate_moves, current_turn);
+                let awaiting_player = player_color == game.board().turn();
+                if awaiting_player {
+                    lines.push(format!("* Move took: {:?}", duration));
+                } else {
+                    lines.push("* Thinking... type your next move to premove it".to_string());
+                }
+                renderer.render_to_stdout(game.board(), &lines);
+                if awaiting_player {
                     print_enter_move_prompt();
+                    if ponder {
+                        game.start_pondering();
+                    }
                 }
                 continue;
             }
-            Err(error) => println!("error: {}", error),
+            Err(error) => {
+                match (output, &premove) {
+                    (OutputFormat::Text, Some(premove)) => {
+                        println!("premove {} cancelled: {}", premove.trim(), error);
+                        print_enter_move_prompt();
+                    }
+                    (OutputFormat::Text, None) => println!("error: {}", error),
+                    (OutputFormat::Json, _) => GameEvent::Error {
+                        message: error.to_string(),
+                    }
+                    .emit(),
+                }
+                if let CommandError::GameError { error } = &error {
+                    game.report_error(error);
+                }
+            }
+        }
+    }
+}
+
+fn record_engine_move(record: &mut GameRecord, game: &Game) {
+    record.engine_moves += 1;
+    record.engine_time += game.time_manager().elapsed();
+    // Book moves aren't searched.
+    if game.searched_position_count() > 0 {
+        if let Some(stats) = game.depth_stats().last() {
+            record.searched_moves += 1;
+            record.total_search_depth += stats.depth as usize;
         }
     }
 }
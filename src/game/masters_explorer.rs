@@ -0,0 +1,32 @@
+use std::process::Command;
+
+use crate::game::opening_explorer::{ExplorerError, OpeningExplorer};
+
+/// The Lichess opening explorer's database of games between masters.
+const MASTERS_URL: &str = "https://explorer.lichess.ovh/masters";
+/// How long to wait on the explorer, in seconds, before giving up on it.
+const TIMEOUT_SECONDS: &str = "5";
+
+/// An opening explorer that follows the Lichess masters database, fetching each
+/// position with `curl`.
+pub fn masters_explorer() -> OpeningExplorer {
+    OpeningExplorer::new(fetch_masters_response)
+}
+
+fn fetch_masters_response(fen: &str) -> Result<Vec<u8>, ExplorerError> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail"])
+        .args(["--max-time", TIMEOUT_SECONDS])
+        .args(["--get", MASTERS_URL])
+        .args(["--data-urlencode", &format!("fen={}", fen)])
+        .output()
+        .map_err(|error| ExplorerError::RequestFailed {
+            error: format!("could not run curl: {}", error),
+        })?;
+    if !output.status.success() {
+        return Err(ExplorerError::RequestFailed {
+            error: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
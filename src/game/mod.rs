@@ -1,9 +1,25 @@
+pub use chess_engine::game::{debug_snapshot, engine_config, game, opening_explorer};
+
+pub mod analyze;
+pub mod annotate;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
 pub mod command;
 pub mod computer_vs_computer;
-pub mod game;
+pub mod eval_grader;
+pub mod event;
+pub mod explain_move;
+pub mod game_options;
 pub mod human_vs_computer;
+#[cfg(feature = "opening-explorer")]
+pub mod masters_explorer;
 pub mod player_vs_player;
 pub mod position_counter;
+pub mod rating;
+pub mod renderer;
+pub mod session;
 pub mod stockfish_elo;
 mod stockfish_interface;
+pub mod tournament;
+pub mod trace_search;
 mod util;
@@ -1,14 +1,36 @@
 use crate::evaluate::GameEnding;
-use crate::input_handler;
+use crate::input_handler::command_registry::CommandRegistry;
+use crate::input_handler::{prompt_promotion, PlayerInput};
 
-use super::game::Game;
+use super::analyze::sparkline;
+use super::annotate::print_game_summary;
+#[cfg(feature = "clipboard")]
+use super::command::COPY;
+use super::command::{Command, CommandError, CommandOutcome, MakeMove, CLAIM_DRAW, SHOW_FEN};
+use super::eval_grader::EvalGrader;
+use super::game::{Game, GameError};
+use super::renderer::{DisplayConfig, Renderer};
 
-pub fn player_vs_player() {
+/// Two players take turns at the same terminal. With a `grade_depth`, each move
+/// is graded in the background, searching that deep, and the evaluation graph
+/// and each player's accuracy are shown when the game ends. With `auto_queen`,
+/// pawns promote to queens unless the move names another piece, rather than
+/// asking which piece. The board is drawn as `display` says.
+pub fn player_vs_player(grade_depth: Option<u8>, auto_queen: bool, display: DisplayConfig) {
     let mut game = Game::new(0);
+    game.set_auto_queen(auto_queen);
+    let grader = grade_depth.map(EvalGrader::start);
+    let registry = CommandRegistry::new()
+        .register(SHOW_FEN)
+        .register(CLAIM_DRAW);
+    #[cfg(feature = "clipboard")]
+    let registry = registry.register(COPY);
+    let mut renderer = Renderer::with_config(display);
+    let render = |renderer: &mut Renderer, game: &Game| {
+        renderer.render_to_stdout(game.board(), &[format!("turn: {}", game.board().turn())])
+    };
+    render(&mut renderer, &game);
     loop {
-        println!("turn: {}", game.board().turn());
-        println!("{}", game.board());
-
         match game.check_game_over_for_current_turn() {
             Some(GameEnding::Checkmate) => {
                 println!("checkmate!");
@@ -19,26 +41,104 @@ pub fn player_vs_player() {
                 break;
             }
             Some(GameEnding::Draw) => {
-                println!("draw!");
+                match game.claimed_draw() {
+                    Some(claim) => println!("draw by {}!", claim),
+                    None => println!("draw!"),
+                }
                 break;
             }
             _ => (),
         };
 
-        let command = match input_handler::parse_player_move_input() {
-            Ok(command) => command,
+        let command = match registry.read_input() {
+            Ok(PlayerInput::Move(command)) => command,
+            Ok(PlayerInput::Help) => {
+                print!("{}", registry.help_text());
+                continue;
+            }
+            Ok(PlayerInput::Quit) => break,
+            Ok(PlayerInput::Named { command, arguments }) => {
+                match command.run(&mut game, &arguments) {
+                    Ok(text) => println!("{}", text),
+                    Err(error) => println!("error: {}", error),
+                }
+                continue;
+            }
             Err(msg) => {
                 println!("{}", msg);
                 continue;
             }
         };
 
-        match command.execute(&mut game) {
-            Ok(_chess_move) => {
+        let result = match command.execute(&mut game) {
+            Err(CommandError::GameError {
+                error: GameError::AmbiguousPromotion { choices, .. },
+            }) => match prompt_promotion(&choices) {
+                Some(algebraic) => MakeMove::Algebraic { algebraic }.execute(&mut game),
+                None => continue,
+            },
+            result => result,
+        };
+        match result {
+            Ok(CommandOutcome::StateChanged) => {
+                render(&mut renderer, &game);
+                continue;
+            }
+            Ok(outcome) => {
+                if let (Some(grader), Some(chess_move)) = (&grader, outcome.chess_move()) {
+                    grader.grade(chess_move);
+                }
                 game.board_mut().toggle_turn();
+                render(&mut renderer, &game);
                 continue;
             }
-            Err(error) => println!("error: {}", error),
+            Err(error) => {
+                println!("error: {}", error);
+                if let CommandError::GameError { error } = &error {
+                    game.report_error(error);
+                }
+            }
         }
     }
+
+    if let Some(grader) = grader {
+        print_grades(&game, grader);
+    }
+}
+
+fn print_grades(game: &Game, grader: EvalGrader) {
+    println!("grading moves...");
+    let analyses = match grader.finish() {
+        Ok(analyses) => analyses,
+        Err(error) => {
+            println!("error: could not grade the game: {}", error);
+            return;
+        }
+    };
+    if analyses.is_empty() {
+        return;
+    }
+
+    let scores: Vec<i16> = std::iter::once(analyses[0].score_before)
+        .chain(analyses.iter().map(|analysis| analysis.score_after))
+        .collect();
+    println!("evaluation: {}", sparkline(&scores));
+    if let Ok(pgn_game) = game.to_pgn() {
+        print_game_summary(&pgn_game, &analyses);
+    }
+    for (ply, analysis) in analyses.iter().enumerate() {
+        let glyph = match analysis.glyph() {
+            Some(glyph) => glyph,
+            None => continue,
+        };
+        let dots = if ply % 2 == 0 { "." } else { "..." };
+        println!(
+            "{}{} {}{} ({} was best)",
+            ply / 2 + 1,
+            dots,
+            analysis.san,
+            glyph,
+            analysis.best_san
+        );
+    }
 }
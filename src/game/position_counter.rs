@@ -1,9 +1,10 @@
 use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 
-use crate::alpha_beta_searcher::{alpha_beta_search, SearchContext};
+use crate::alpha_beta_searcher::{alpha_beta_search, DepthStats, SearchContext};
 use crate::board::color::Color;
 use crate::board::Board;
+use crate::game::util::print_depth_stats_table;
 use crate::move_generator::MoveGenerator;
 
 #[derive(Debug)]
@@ -29,6 +30,7 @@ pub fn run_count_positions(depth: u8, strategy: CountPositionsStrategy) {
 
     let mut total_positions = 0;
     let mut total_duration = Duration::from_secs(0);
+    let mut depth_stats: Vec<DepthStats> = Vec::new();
 
     for depth in depths {
         let mut board = Board::starting_position();
@@ -41,6 +43,7 @@ pub fn run_count_positions(depth: u8, strategy: CountPositionsStrategy) {
             CountPositionsStrategy::AlphaBeta => {
                 let mut search_context = SearchContext::new(depth);
                 alpha_beta_search(&mut search_context, &mut board, &mut move_generator).unwrap();
+                depth_stats.extend_from_slice(search_context.depth_stats());
                 search_context.searched_position_count()
             }
         };
@@ -62,4 +65,9 @@ pub fn run_count_positions(depth: u8, strategy: CountPositionsStrategy) {
         total_duration,
         total_positions as f64 / total_duration.as_secs_f64()
     );
+
+    if !depth_stats.is_empty() {
+        println!();
+        print_depth_stats_table(&depth_stats);
+    }
 }
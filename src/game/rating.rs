@@ -0,0 +1,141 @@
+use std::f64::consts::{LN_10, SQRT_2};
+
+/// Ratings are re-estimated until none moves by more than this many points.
+const CONVERGENCE: f64 = 0.01;
+const MAX_ITERATIONS: usize = 1000;
+
+/// A game to rate: the players, by index, and white's score, 1.0 for a win,
+/// 0.5 for a draw, and 0.0 for a loss.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RatedGame {
+    pub white: usize,
+    pub black: usize,
+    pub white_score: f64,
+}
+
+/// The expected score of a player rated `difference` points above their
+/// opponent, by the Elo model.
+fn expected_score(difference: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-difference / 400.0))
+}
+
+/// Estimates each player's Elo rating from the games, relative to the average
+/// player, who is rated 0. The ratings are those under which the results are
+/// most likely, as Ordo and BayesElo estimate them, with draws counting as half
+/// a win. Each player is also given one virtual draw against an average
+/// player, so that a perfect score still has a finite rating, and so that a
+/// player's rating means little until they've played a few games.
+pub fn estimate_ratings(players: usize, games: &[RatedGame]) -> Vec<f64> {
+    let mut ratings = vec![0.0; players];
+    for _ in 0..MAX_ITERATIONS {
+        let mut largest_change: f64 = 0.0;
+        for player in 0..players {
+            // The virtual draw.
+            let mut score = 0.5;
+            let mut expected = expected_score(ratings[player]);
+            let mut slope = expected * (1.0 - expected);
+            for game in games {
+                let (opponent, player_score) = if game.white == player {
+                    (game.black, game.white_score)
+                } else if game.black == player {
+                    (game.white, 1.0 - game.white_score)
+                } else {
+                    continue;
+                };
+                let p = expected_score(ratings[player] - ratings[opponent]);
+                score += player_score;
+                expected += p;
+                slope += p * (1.0 - p);
+            }
+            // A Newton step toward the rating at which the player's expected
+            // score matches their actual score.
+            let change = (score - expected) / (slope * LN_10 / 400.0);
+            ratings[player] += change;
+            largest_change = largest_change.max(change.abs());
+        }
+        if largest_change < CONVERGENCE {
+            break;
+        }
+    }
+
+    let average = ratings.iter().sum::<f64>() / players.max(1) as f64;
+    ratings.iter().map(|rating| rating - average).collect()
+}
+
+/// The likelihood of superiority: how likely it is that a player who won
+/// `wins` and lost `losses` of their games against an opponent is the stronger
+/// of the two. Draws say nothing about which is stronger, so they're left out.
+pub fn likelihood_of_superiority(wins: usize, losses: usize) -> f64 {
+    if wins + losses == 0 {
+        return 0.5;
+    }
+    let difference = wins as f64 - losses as f64;
+    0.5 * (1.0 + erf(difference / (SQRT_2 * ((wins + losses) as f64).sqrt())))
+}
+
+/// The error function, to within 1.5e-7 (Abramowitz and Stegun, 7.1.26).
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - polynomial * (-x * x).exp();
+    y.copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(white: usize, black: usize, white_score: f64) -> RatedGame {
+        RatedGame {
+            white,
+            black,
+            white_score,
+        }
+    }
+
+    #[test]
+    fn test_estimate_ratings() {
+        // Even results leave everyone average.
+        let ratings = estimate_ratings(2, &[game(0, 1, 1.0), game(1, 0, 1.0)]);
+        assert!(ratings.iter().all(|rating| rating.abs() < 0.1));
+
+        // Counting the virtual draws, 3.5 points out of 5 puts the winner about
+        // 165 points ahead.
+        let games = [
+            game(0, 1, 1.0),
+            game(1, 0, 0.0),
+            game(0, 1, 1.0),
+            game(1, 0, 1.0),
+        ];
+        let ratings = estimate_ratings(2, &games);
+        assert!(
+            (ratings[0] - ratings[1] - 164.8).abs() < 1.0,
+            "{:?}",
+            ratings
+        );
+        assert!((ratings[0] + ratings[1]).abs() < 0.1);
+
+        // A perfect score is still finite, and the ratings keep the order of
+        // the scores.
+        let games = [
+            game(0, 1, 1.0),
+            game(0, 2, 1.0),
+            game(1, 2, 0.5),
+            game(2, 1, 1.0),
+        ];
+        let ratings = estimate_ratings(3, &games);
+        assert!(ratings[0].is_finite());
+        assert!(ratings[0] > ratings[2] && ratings[2] > ratings[1]);
+    }
+
+    #[test]
+    fn test_likelihood_of_superiority() {
+        assert_eq!(likelihood_of_superiority(0, 0), 0.5);
+        assert!((likelihood_of_superiority(5, 5) - 0.5).abs() < 1e-6);
+        assert!((likelihood_of_superiority(10, 0) - 0.9992).abs() < 1e-3);
+        assert!((likelihood_of_superiority(6, 4) - 0.7365).abs() < 1e-3);
+        assert!((likelihood_of_superiority(4, 6) - 0.2635).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,308 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use common::bitboard::bitboard::Bitboard;
+use common::bitboard::square::{from_rank_file, ORDERED_SQUARES};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "terminal")]
+use termion::{clear, cursor};
+
+use crate::board::Board;
+
+/// The board takes up 19 rows, followed by a blank one.
+#[cfg(feature = "terminal")]
+const FIRST_LINE_ROW: u16 = 21;
+
+/// How pieces are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PieceStyle {
+    /// Chess symbols: solid for white and outlined for black, which reads best
+    /// on a dark terminal.
+    Unicode,
+    /// The letters FEN uses, uppercase for white and lowercase for black, for
+    /// fonts and terminals without the chess symbols.
+    Letters,
+}
+
+/// How the board is drawn, as loaded from a JSON file. Anything the file leaves
+/// out keeps its default.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// The file letters and rank numbers around the board.
+    pub coordinates: bool,
+    /// What an empty light square is drawn with.
+    pub light_square: char,
+    /// What an empty dark square is drawn with.
+    pub dark_square: char,
+    pub pieces: PieceStyle,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            coordinates: true,
+            light_square: ' ',
+            dark_square: '·',
+            pieces: PieceStyle::Unicode,
+        }
+    }
+}
+
+impl DisplayConfig {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// Draws the board and the lines beneath it, redrawing only what changed since
+/// the last frame. Clearing and reprinting the whole screen every move makes the
+/// board flicker when moves come quickly, such as when watching self-play.
+#[derive(Default)]
+pub struct Renderer {
+    board: Option<Board>,
+    config: DisplayConfig,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: DisplayConfig) -> Self {
+        Self {
+            board: None,
+            config,
+        }
+    }
+
+    /// Draws `board` and `lines`. The first frame clears the screen and draws the
+    /// whole board. After that, only squares whose contents changed are redrawn,
+    /// and the lines are rewritten in place, clearing whatever the previous frame
+    /// (or the user's input) left beneath them. The cursor is left after the last
+    /// line.
+    #[cfg(feature = "terminal")]
+    pub fn render(
+        &mut self,
+        out: &mut impl Write,
+        board: &Board,
+        lines: &[String],
+    ) -> io::Result<()> {
+        match &self.board {
+            None => write!(
+                out,
+                "{}{}{}",
+                cursor::Goto(1, 1),
+                clear::All,
+                board_string(board, &self.config)
+            )?,
+            Some(previous) => {
+                for change in previous.diff(board) {
+                    let (column, row) = square_position(change.square);
+                    write!(
+                        out,
+                        "{}{}",
+                        cursor::Goto(column, row),
+                        square_char(board, change.square, &self.config)
+                    )?;
+                }
+            }
+        }
+        self.board = Some(board.clone());
+
+        write!(out, "{}", cursor::Goto(1, FIRST_LINE_ROW))?;
+        for line in lines {
+            writeln!(out, "{}{}", clear::CurrentLine, line)?;
+        }
+        write!(out, "{}", clear::AfterCursor)?;
+        out.flush()
+    }
+
+    /// Draws `board` and `lines` without moving the cursor, for builds without
+    /// the `terminal` feature. Each frame is printed beneath the last, and the
+    /// board is only printed again when it changed.
+    #[cfg(not(feature = "terminal"))]
+    pub fn render(
+        &mut self,
+        out: &mut impl Write,
+        board: &Board,
+        lines: &[String],
+    ) -> io::Result<()> {
+        let changed = self
+            .board
+            .as_ref()
+            .is_none_or(|previous| !previous.diff(board).is_empty());
+        if changed {
+            write!(out, "{}", board_string(board, &self.config))?;
+        }
+        self.board = Some(board.clone());
+
+        for line in lines {
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()
+    }
+
+    /// Renders to stdout. Rendering is best effort, so write errors are ignored.
+    pub fn render_to_stdout(&mut self, board: &Board, lines: &[String]) {
+        let _ = self.render(&mut io::stdout(), board, lines);
+    }
+}
+
+/// Hidden coordinates are drawn as blanks, so that the squares stay where
+/// `square_position` expects them.
+fn board_string(board: &Board, config: &DisplayConfig) -> String {
+    let files = if config.coordinates {
+        "    a   b   c   d   e   f   g   h\n"
+    } else {
+        "\n"
+    };
+    let rank_label = |rank: u8| match config.coordinates {
+        true => (b'1' + rank) as char,
+        false => ' ',
+    };
+    let mut board_str = String::new();
+    board_str.push_str(files);
+    board_str.push_str("  ┌───┬───┬───┬───┬───┬───┬───┬───┐\n");
+    for rank in (0..8).rev() {
+        board_str.push_str(&format!("{} │", rank_label(rank)));
+        for file in 0..8 {
+            let square = from_rank_file(rank, file);
+            board_str.push_str(&format!(" {} │", square_char(board, square, config)));
+        }
+        board_str.push_str(&format!(" {}\n", rank_label(rank)));
+        if rank > 0 {
+            board_str.push_str("  ├───┼───┼───┼───┼───┼───┼───┼───┤\n");
+        } else {
+            board_str.push_str("  └───┴───┴───┴───┴───┴───┴───┴───┘\n");
+        }
+    }
+    board_str.push_str(files);
+    board_str
+}
+
+fn square_char(board: &Board, square: Bitboard, config: &DisplayConfig) -> char {
+    match board.get(square) {
+        Some((piece, color)) => match config.pieces {
+            PieceStyle::Unicode => piece.to_unicode_piece_char(color),
+            PieceStyle::Letters => piece.to_char(color),
+        },
+        None => {
+            let (column, row) = square_position(square);
+            if (column / 4 + row / 2).is_multiple_of(2) {
+                config.light_square
+            } else {
+                config.dark_square
+            }
+        }
+    }
+}
+
+/// The 1-based terminal column and row of a square's piece.
+fn square_position(square: Bitboard) -> (u16, u16) {
+    let index = ORDERED_SQUARES
+        .iter()
+        .position(|&s| s == square)
+        .expect("square should be a single square") as u16;
+    let (file, rank) = (index / 8, index % 8);
+    (5 + 4 * file, 3 + 2 * (7 - rank))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::chess_move::ChessMove;
+    use crate::chess_move::chess_move_effect::ChessMoveEffect;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::std_move;
+    use common::bitboard::square::*;
+
+    #[test]
+    #[cfg(feature = "terminal")]
+    fn test_render_redraws_only_changed_squares() {
+        let mut renderer = Renderer::new();
+        let mut board = Board::starting_position();
+        let lines = vec!["Last move: -".to_string()];
+
+        let mut first_frame = Vec::new();
+        renderer.render(&mut first_frame, &board, &lines).unwrap();
+        let first_frame = String::from_utf8(first_frame).unwrap();
+        assert!(first_frame.contains(&clear::All.to_string()));
+        assert!(first_frame.contains("8 │ ♖ │ ♘ │"));
+
+        std_move!(E2, E4).apply(&mut board).unwrap();
+        let mut second_frame = Vec::new();
+        renderer.render(&mut second_frame, &board, &lines).unwrap();
+        let second_frame = String::from_utf8(second_frame).unwrap();
+        assert!(!second_frame.contains(&clear::All.to_string()));
+        assert!(!second_frame.contains('┌'));
+        assert_eq!(
+            second_frame,
+            format!(
+                "{} {}♟{}{}Last move: -\n{}",
+                cursor::Goto(21, 15),
+                cursor::Goto(21, 11),
+                cursor::Goto(1, FIRST_LINE_ROW),
+                clear::CurrentLine,
+                clear::AfterCursor
+            )
+        );
+    }
+
+    #[test]
+    fn test_display_config() {
+        let board = Board::starting_position();
+        let default = board_string(&board, &DisplayConfig::default());
+        assert!(default.starts_with("    a   b   c"));
+        assert!(default.contains("8 │ ♖ │ ♘ │"));
+        assert!(default.contains("6 │   │ · │"));
+
+        let config: DisplayConfig = serde_json::from_str(
+            r#"{"coordinates": false, "light_square": ".", "dark_square": ":", "pieces": "letters"}"#,
+        )
+        .unwrap();
+        let configured = board_string(&board, &config);
+        assert!(configured.contains("  │ r │ n │"));
+        assert!(configured.contains("  │ . │ : │"));
+        assert!(!configured.contains('a'));
+        // Hidden coordinates leave the squares where they were.
+        assert_eq!(configured.lines().count(), default.lines().count());
+        assert_eq!(
+            configured.lines().nth(2).unwrap().find('r'),
+            default.lines().nth(2).unwrap().find('♖')
+        );
+
+        // Settings the file leaves out keep their defaults.
+        let config: DisplayConfig = serde_json::from_str(r#"{"pieces": "letters"}"#).unwrap();
+        assert!(config.coordinates);
+        assert_eq!(config.dark_square, '·');
+        assert!(serde_json::from_str::<DisplayConfig>(r#"{"pieces": "emoji"}"#).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "terminal"))]
+    fn test_render_prints_board_only_when_it_changes() {
+        let mut renderer = Renderer::new();
+        let mut board = Board::starting_position();
+        let lines = vec!["Last move: -".to_string()];
+
+        let mut first_frame = Vec::new();
+        renderer.render(&mut first_frame, &board, &lines).unwrap();
+        let first_frame = String::from_utf8(first_frame).unwrap();
+        assert!(first_frame.contains("8 │ ♖ │ ♘ │"));
+        assert!(first_frame.ends_with("Last move: -\n"));
+
+        let mut same_board = Vec::new();
+        renderer.render(&mut same_board, &board, &lines).unwrap();
+        assert_eq!(String::from_utf8(same_board).unwrap(), "Last move: -\n");
+
+        std_move!(E2, E4).apply(&mut board).unwrap();
+        let mut second_frame = Vec::new();
+        renderer.render(&mut second_frame, &board, &lines).unwrap();
+        assert!(String::from_utf8(second_frame).unwrap().contains('┌'));
+    }
+}
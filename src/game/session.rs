@@ -0,0 +1,161 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How a game ended, from the human player's point of view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum GameOutcome {
+    Win,
+    Loss,
+    Draw,
+    /// The player quit before the game was over.
+    #[default]
+    Abandoned,
+}
+
+/// What happened in one game against the engine.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub outcome: GameOutcome,
+    /// Every move the engine made, including book moves.
+    pub engine_moves: usize,
+    /// The engine moves that were searched rather than taken from the book.
+    pub searched_moves: usize,
+    /// The sum of the deepest completed search depth of each searched move.
+    pub total_search_depth: usize,
+    pub engine_time: Duration,
+}
+
+/// Statistics over every game played in a sitting, or, when loaded from a stats
+/// file, over every game ever recorded in it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub games: Vec<GameRecord>,
+}
+
+impl SessionStats {
+    /// Loads the stats recorded in `path`. A missing file has no games yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::write(path, json)
+    }
+
+    pub fn record(&mut self, game: GameRecord) {
+        self.games.push(game);
+    }
+
+    pub fn count(&self, outcome: GameOutcome) -> usize {
+        self.games
+            .iter()
+            .filter(|game| game.outcome == outcome)
+            .count()
+    }
+
+    /// The average deepest search depth over every searched engine move.
+    pub fn average_search_depth(&self) -> Option<f64> {
+        let searched_moves: usize = self.games.iter().map(|game| game.searched_moves).sum();
+        let total_depth: usize = self.games.iter().map(|game| game.total_search_depth).sum();
+        (searched_moves > 0).then(|| total_depth as f64 / searched_moves as f64)
+    }
+
+    /// The average time the engine took to make a move, book moves included.
+    pub fn average_move_time(&self) -> Option<Duration> {
+        let engine_moves: usize = self.games.iter().map(|game| game.engine_moves).sum();
+        let engine_time: Duration = self.games.iter().map(|game| game.engine_time).sum();
+        (engine_moves > 0).then(|| engine_time / engine_moves as u32)
+    }
+
+    /// A table of the results and engine statistics, headed by `title`.
+    pub fn summary(&self, title: &str) -> String {
+        let mut summary = String::new();
+        let _ = writeln!(summary, "{}", title);
+        let _ = writeln!(summary, "{}", "-".repeat(title.len()));
+        let _ = writeln!(summary, "Games played: {}", self.games.len());
+        let _ = writeln!(
+            summary,
+            "Wins / losses / draws: {} / {} / {}",
+            self.count(GameOutcome::Win),
+            self.count(GameOutcome::Loss),
+            self.count(GameOutcome::Draw)
+        );
+        let abandoned = self.count(GameOutcome::Abandoned);
+        if abandoned > 0 {
+            let _ = writeln!(summary, "Unfinished: {}", abandoned);
+        }
+        if let Some(depth) = self.average_search_depth() {
+            let _ = writeln!(summary, "Average engine depth: {:.1}", depth);
+        }
+        if let Some(time) = self.average_move_time() {
+            let _ = writeln!(summary, "Average engine move time: {:.1?}", time);
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        outcome: GameOutcome,
+        searched_moves: usize,
+        total_search_depth: usize,
+    ) -> GameRecord {
+        GameRecord {
+            outcome,
+            engine_moves: 10,
+            searched_moves,
+            total_search_depth,
+            engine_time: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_session_summary() {
+        let mut session = SessionStats::default();
+        assert_eq!(session.average_search_depth(), None);
+
+        session.record(record(GameOutcome::Win, 6, 24));
+        session.record(record(GameOutcome::Loss, 4, 20));
+        session.record(record(GameOutcome::Abandoned, 0, 0));
+
+        assert_eq!(session.count(GameOutcome::Win), 1);
+        assert_eq!(session.average_search_depth(), Some(4.4));
+        assert_eq!(
+            session.average_move_time(),
+            Some(Duration::from_millis(500))
+        );
+
+        let summary = session.summary("Session");
+        assert!(summary.contains("Games played: 3"));
+        assert!(summary.contains("Wins / losses / draws: 1 / 1 / 0"));
+        assert!(summary.contains("Unfinished: 1"));
+        assert!(summary.contains("Average engine depth: 4.4"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("chess-stats-{}.json", std::process::id()));
+        assert_eq!(SessionStats::load(&path).unwrap(), SessionStats::default());
+
+        let mut session = SessionStats::default();
+        session.record(record(GameOutcome::Draw, 3, 9));
+        session.save(&path).unwrap();
+        assert_eq!(SessionStats::load(&path).unwrap(), session);
+        fs::remove_file(&path).unwrap();
+    }
+}
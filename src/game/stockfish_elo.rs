@@ -7,12 +7,15 @@ use crate::chess_move::chess_move::ChessMove;
 use crate::chess_move::en_passant::EnPassantChessMove;
 use crate::chess_move::pawn_promotion::PawnPromotionChessMove;
 use crate::chess_move::standard::StandardChessMove;
+use crate::engine_info::EngineInfo;
 use crate::evaluate::GameEnding;
 use crate::game::game::Game;
+use crate::game::renderer::Renderer;
 use crate::game::stockfish_interface::Stockfish;
-use crate::game::util::print_board_and_stats;
+use crate::game::util::stats_lines;
 use common::bitboard::square::*;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+#[cfg(feature = "terminal")]
 use termion::{clear, cursor};
 
 const GAMES_PER_ELO: usize = 10;
@@ -62,7 +65,11 @@ pub fn determine_stockfish_elo(depth: u8, starting_elo: u32) {
             );
 
             if is_elo_determined(wins, losses, total_games) {
-                println!("\nFinal ELO determination: {}", current_elo);
+                println!(
+                    "\nFinal ELO determination for {}: {}",
+                    EngineInfo::current(),
+                    current_elo
+                );
                 return;
             }
         }
@@ -80,14 +87,14 @@ pub fn determine_stockfish_elo(depth: u8, starting_elo: u32) {
 }
 
 fn play_game(stockfish: &mut Stockfish, depth: u8) -> (GameResult, Duration, Duration) {
+    let engine_color = Color::random();
     let mut game = Game::new(depth);
+    game.set_engine_player(engine_color);
     let mut moves = Vec::new();
     let mut engine_time = Duration::new(0, 0);
     let mut stockfish_time = Duration::new(0, 0);
 
-    let engine_color = Color::random();
-
-    print!("{}{}", clear::All, cursor::Goto(1, 1));
+    let mut renderer = Renderer::new();
 
     loop {
         if let Some(result) = game.check_game_over_for_current_turn() {
@@ -107,7 +114,6 @@ fn play_game(stockfish: &mut Stockfish, depth: u8) -> (GameResult, Duration, Dur
             );
         }
 
-        let start_time = Instant::now();
         let candidate_moves = game.enumerated_candidate_moves().clone();
         let current_turn = game.board().turn();
 
@@ -115,32 +121,32 @@ fn play_game(stockfish: &mut Stockfish, depth: u8) -> (GameResult, Duration, Dur
             let chess_move = game
                 .select_waterfall_book_then_alpha_beta_best_move()
                 .unwrap();
-            engine_time += start_time.elapsed();
+            engine_time += game.time_manager().elapsed();
             chess_move
         } else {
             let (sf_move, sf_time) = stockfish
                 .get_best_move(&moves.join(" "), TIME_LIMIT)
                 .unwrap();
             stockfish_time += Duration::from_millis(sf_time);
-            create_chess_move_from_uci(&sf_move, &game.board())
+            create_chess_move_from_uci(&sf_move, game.board())
         };
 
         game.apply_chess_move(chess_move.clone()).unwrap();
         moves.push(chess_move.to_uci());
 
-        print!("{}{}", clear::All, cursor::Goto(1, 1));
-        print_board_and_stats(&mut game, candidate_moves, current_turn);
-        println!("* Engine color: {}", engine_color);
-        println!("* Stockfish color: {}", engine_color.opposite());
-        println!("* Current Stockfish ELO: {}", stockfish.get_elo());
-        println!(
+        let mut lines = stats_lines(&game, candidate_moves, current_turn);
+        lines.push(format!("* Engine color: {}", engine_color));
+        lines.push(format!("* Stockfish color: {}", engine_color.opposite()));
+        lines.push(format!("* Current Stockfish ELO: {}", stockfish.get_elo()));
+        lines.push(format!(
             "* Engine latency: {:.2}ms",
             engine_time.as_millis() as f32 / TIME_LIMIT as f32
-        );
-        println!(
+        ));
+        lines.push(format!(
             "* Stockfish latency: {:.2}ms",
             stockfish_time.as_millis() as f32 / TIME_LIMIT as f32
-        );
+        ));
+        renderer.render_to_stdout(game.board(), &lines);
 
         game.board_mut().toggle_turn();
     }
@@ -190,6 +196,7 @@ fn display_progress(
     engine_time: Duration,
     stockfish_time: Duration,
 ) {
+    #[cfg(feature = "terminal")]
     print!("{}{}", clear::All, cursor::Goto(1, 1));
     println!("Determining Stockfish ELO");
     println!("-------------------------");
@@ -0,0 +1,896 @@
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::engine_info::EngineInfo;
+use crate::evaluate::sparring::SparringProfile;
+use crate::evaluate::GameEnding;
+use crate::game::engine_config::EngineConfig;
+use crate::game::game::{Game, GameError};
+use crate::game::rating::{estimate_ratings, likelihood_of_superiority, RatedGame};
+use crate::pgn::{pgn_date, PgnGame};
+
+/// An engine entered in a tournament, written `<depth>` or
+/// `<depth>:<sparring profile>`, e.g. `4` or `3:materialist`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TournamentEngine {
+    pub depth: u8,
+    pub sparring: Option<SparringProfile>,
+}
+
+impl TournamentEngine {
+    /// The engine's name in PGN, which tells it apart from the other entries
+    /// when the games are rated with tools like Ordo or BayesElo.
+    pub fn player_name(&self) -> String {
+        format!("{} {}", EngineInfo::current().full_name(), self)
+    }
+
+    fn new_game(&self, config: EngineConfig) -> Game {
+        let mut game = Game::new(self.depth);
+        game.set_engine_config(config);
+        if let Some(sparring) = self.sparring {
+            game.set_evaluation_terms(sparring.terms());
+        }
+        game
+    }
+}
+
+impl FromStr for TournamentEngine {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (depth, sparring) = match s.split_once(':') {
+            Some((depth, profile)) => (depth, Some(profile.parse()?)),
+            None => (s, None),
+        };
+        let depth = depth
+            .parse()
+            .map_err(|_| format!("invalid engine {:?}; expected <depth>[:<profile>]", s))?;
+        Ok(Self { depth, sparring })
+    }
+}
+
+impl fmt::Display for TournamentEngine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.sparring {
+            Some(sparring) => write!(f, "depth {} ({})", self.depth, sparring),
+            None => write!(f, "depth {}", self.depth),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TournamentFormat {
+    /// Every engine plays every other engine once per cycle.
+    RoundRobin,
+    /// Each round pairs engines with similar scores that haven't met yet.
+    Swiss,
+}
+
+impl FromStr for TournamentFormat {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round-robin" => Ok(TournamentFormat::RoundRobin),
+            "swiss" => Ok(TournamentFormat::Swiss),
+            _ => Err("invalid tournament format; options are: round-robin, swiss"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl MatchResult {
+    /// The half-points scored by white and black.
+    fn half_points(&self) -> (u32, u32) {
+        match self {
+            MatchResult::WhiteWins => (2, 0),
+            MatchResult::BlackWins => (0, 2),
+            MatchResult::Draw => (1, 1),
+        }
+    }
+}
+
+impl fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchResult::WhiteWins => write!(f, "1-0"),
+            MatchResult::BlackWins => write!(f, "0-1"),
+            MatchResult::Draw => write!(f, "1/2-1/2"),
+        }
+    }
+}
+
+/// The games of one round, as (white, black) engine indexes. With an odd
+/// number of engines, one sits out the round and scores a point for the bye.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Round {
+    pub games: Vec<(usize, usize)>,
+    pub bye: Option<usize>,
+}
+
+/// Schedules a single round robin among `players` engines with the circle
+/// method: the first engine stays put while the others rotate around it.
+pub fn round_robin_rounds(players: usize) -> Vec<Round> {
+    let mut seats: Vec<Option<usize>> = (0..players).map(Some).collect();
+    if seats.len() % 2 == 1 {
+        seats.push(None);
+    }
+    let seat_count = seats.len();
+
+    let mut rounds = Vec::new();
+    for round_number in 0..seat_count.saturating_sub(1) {
+        let mut round = Round::default();
+        for i in 0..seat_count / 2 {
+            match (seats[i], seats[seat_count - 1 - i]) {
+                // Alternate the fixed engine's color from round to round.
+                (Some(a), Some(b)) if i == 0 && round_number % 2 == 1 => round.games.push((b, a)),
+                (Some(a), Some(b)) => round.games.push((a, b)),
+                (Some(player), None) | (None, Some(player)) => round.bye = Some(player),
+                (None, None) => {}
+            }
+        }
+        rounds.push(round);
+        seats[1..].rotate_right(1);
+    }
+    rounds
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlayedGame {
+    pub white: usize,
+    pub black: usize,
+    pub result: MatchResult,
+}
+
+/// One engine's line in the final standings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Standing {
+    pub player: usize,
+    pub half_points: u32,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+/// The engines in a tournament and the results of the games played so far.
+pub struct Tournament {
+    engines: Vec<TournamentEngine>,
+    games: Vec<PlayedGame>,
+    byes: Vec<usize>,
+}
+
+impl Tournament {
+    pub fn new(engines: Vec<TournamentEngine>) -> Self {
+        Self {
+            engines,
+            games: Vec::new(),
+            byes: Vec::new(),
+        }
+    }
+
+    pub fn engines(&self) -> &[TournamentEngine] {
+        &self.engines
+    }
+
+    pub fn record(&mut self, white: usize, black: usize, result: MatchResult) {
+        self.games.push(PlayedGame {
+            white,
+            black,
+            result,
+        });
+    }
+
+    pub fn record_bye(&mut self, player: usize) {
+        self.byes.push(player);
+    }
+
+    /// The half-points `player` has scored, a point for each bye included.
+    pub fn half_points(&self, player: usize) -> u32 {
+        let byes = self.byes.iter().filter(|&&bye| bye == player).count() as u32;
+        byes * 2 + self.half_points_against(player, None)
+    }
+
+    /// The half-points `player` scored against `opponent`, or against everyone.
+    fn half_points_against(&self, player: usize, opponent: Option<usize>) -> u32 {
+        self.games
+            .iter()
+            .filter_map(|game| {
+                let (white_points, black_points) = game.result.half_points();
+                if game.white == player && opponent.is_none_or(|o| o == game.black) {
+                    Some(white_points)
+                } else if game.black == player && opponent.is_none_or(|o| o == game.white) {
+                    Some(black_points)
+                } else {
+                    None
+                }
+            })
+            .sum()
+    }
+
+    fn have_met(&self, a: usize, b: usize) -> bool {
+        self.games.iter().any(|game| {
+            (game.white == a && game.black == b) || (game.white == b && game.black == a)
+        })
+    }
+
+    /// How many more games `player` has had white than black.
+    fn color_balance(&self, player: usize) -> i32 {
+        self.games
+            .iter()
+            .map(|game| {
+                if game.white == player {
+                    1
+                } else if game.black == player {
+                    -1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// Pairs the next Swiss round. Engines are ranked by score, and each is
+    /// paired with the highest-ranked engine it hasn't played yet, falling back
+    /// to a rematch when there's no one left. The lowest-ranked engine that
+    /// hasn't had a bye sits out when the number of engines is odd, and the
+    /// engine that has had black more often gets white.
+    pub fn swiss_round(&self) -> Round {
+        let mut ranked: Vec<usize> = (0..self.engines.len()).collect();
+        ranked.sort_by_key(|&player| std::cmp::Reverse(self.half_points(player)));
+
+        let mut round = Round::default();
+        if ranked.len() % 2 == 1 {
+            let bye_index = ranked
+                .iter()
+                .rposition(|player| !self.byes.contains(player))
+                .unwrap_or(ranked.len() - 1);
+            round.bye = Some(ranked.remove(bye_index));
+        }
+
+        while !ranked.is_empty() {
+            let player = ranked.remove(0);
+            let opponent_index = ranked
+                .iter()
+                .position(|&opponent| !self.have_met(player, opponent))
+                .unwrap_or(0);
+            let opponent = ranked.remove(opponent_index);
+            if self.color_balance(opponent) < self.color_balance(player) {
+                round.games.push((opponent, player));
+            } else {
+                round.games.push((player, opponent));
+            }
+        }
+        round
+    }
+
+    /// Every engine's score and record, best first. Ties keep entry order.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (0..self.engines.len())
+            .map(|player| {
+                let mut standing = Standing {
+                    player,
+                    half_points: self.half_points(player),
+                    wins: 0,
+                    draws: 0,
+                    losses: 0,
+                };
+                for game in &self.games {
+                    let points = if game.white == player {
+                        game.result.half_points().0
+                    } else if game.black == player {
+                        game.result.half_points().1
+                    } else {
+                        continue;
+                    };
+                    match points {
+                        2 => standing.wins += 1,
+                        1 => standing.draws += 1,
+                        _ => standing.losses += 1,
+                    }
+                }
+                standing
+            })
+            .collect();
+        standings.sort_by_key(|standing| std::cmp::Reverse(standing.half_points));
+        standings
+    }
+
+    /// A table of each engine's score against every other engine, in entry
+    /// order. Pairings that never played are marked with a dash.
+    pub fn crosstable(&self) -> String {
+        let names: Vec<String> = self
+            .engines
+            .iter()
+            .map(|engine| engine.to_string())
+            .collect();
+        let name_width = names.iter().map(String::len).max().unwrap_or(0).max(6);
+
+        let mut table = String::new();
+        let _ = write!(table, "    {:name_width$}", "Engine");
+        for opponent in 1..=self.engines.len() {
+            let _ = write!(table, " {:>5}", opponent);
+        }
+        let _ = writeln!(table, " {:>6}", "Score");
+
+        for (player, name) in names.iter().enumerate() {
+            let _ = write!(table, "{:>2}. {:name_width$}", player + 1, name);
+            for opponent in 0..self.engines.len() {
+                let cell = if opponent == player {
+                    "x".to_string()
+                } else if self.have_met(player, opponent) {
+                    format_points(self.half_points_against(player, Some(opponent)))
+                } else {
+                    "-".to_string()
+                };
+                let _ = write!(table, " {:>5}", cell);
+            }
+            let _ = writeln!(table, " {:>6}", format_points(self.half_points(player)));
+        }
+        table
+    }
+
+    /// Each engine's estimated Elo rating, in entry order, relative to the
+    /// average engine. Byes aren't games, so they don't count.
+    pub fn ratings(&self) -> Vec<f64> {
+        let games: Vec<RatedGame> = self
+            .games
+            .iter()
+            .map(|game| RatedGame {
+                white: game.white,
+                black: game.black,
+                white_score: game.result.half_points().0 as f64 / 2.0,
+            })
+            .collect();
+        estimate_ratings(self.engines.len(), &games)
+    }
+
+    /// How likely it is that `player` is stronger than `opponent`, from the
+    /// games they've played each other, or `None` if they haven't met.
+    pub fn likelihood_of_superiority(&self, player: usize, opponent: usize) -> Option<f64> {
+        if !self.have_met(player, opponent) {
+            return None;
+        }
+        let (mut wins, mut losses) = (0, 0);
+        for game in &self.games {
+            let points = if (game.white, game.black) == (player, opponent) {
+                game.result.half_points().0
+            } else if (game.white, game.black) == (opponent, player) {
+                game.result.half_points().1
+            } else {
+                continue;
+            };
+            match points {
+                2 => wins += 1,
+                0 => losses += 1,
+                _ => (),
+            }
+        }
+        Some(likelihood_of_superiority(wins, losses))
+    }
+
+    /// The final standings, one line per engine, with each engine's estimated
+    /// rating and its likelihood of superiority over the engine placed below it.
+    pub fn standings_table(&self) -> String {
+        let standings = self.standings();
+        let ratings = self.ratings();
+        let mut table = String::new();
+        for (place, standing) in standings.iter().enumerate() {
+            let _ = write!(
+                table,
+                "{:>2}. {}: {} (+{} ={} -{}), Elo {:+.0}",
+                place + 1,
+                self.engines[standing.player],
+                format_points(standing.half_points),
+                standing.wins,
+                standing.draws,
+                standing.losses,
+                ratings[standing.player]
+            );
+            let next = standings
+                .get(place + 1)
+                .and_then(|next| self.likelihood_of_superiority(standing.player, next.player));
+            if let Some(los) = next {
+                let _ = write!(table, ", LOS {:.0}%", los * 100.0);
+            }
+            let _ = writeln!(table);
+        }
+        table
+    }
+}
+
+fn format_points(half_points: u32) -> String {
+    if half_points.is_multiple_of(2) {
+        format!("{}", half_points / 2)
+    } else {
+        format!("{}.5", half_points / 2)
+    }
+}
+
+/// How a game between two engines ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    /// Checkmate, stalemate, or a draw by the rules.
+    Normal,
+    /// Both engines agreed to a draw.
+    Agreement,
+    /// A resignation, a draw adjudication, or the move limit.
+    Adjudication,
+}
+
+/// A game played between two engines.
+pub struct MatchGame {
+    pub result: MatchResult,
+    pub termination: Termination,
+    /// The moves, before the game's tags are filled in.
+    pgn_game: PgnGame,
+}
+
+impl MatchGame {
+    /// The game in PGN, tagged with the round it was played in and the names
+    /// and version of the engines, for rating tools and GUIs to import.
+    pub fn to_pgn(
+        &self,
+        white: &TournamentEngine,
+        black: &TournamentEngine,
+        round: usize,
+        date: &str,
+    ) -> PgnGame {
+        let info = EngineInfo::current();
+        let mut pgn_game = self.pgn_game.clone();
+        pgn_game.result = self.result.to_string();
+        for (name, value) in [
+            ("Event", "chess tournament".to_string()),
+            ("Date", date.to_string()),
+            ("Round", round.to_string()),
+            ("White", white.player_name()),
+            ("Black", black.player_name()),
+            ("Result", self.result.to_string()),
+            ("EngineVersion", info.version.to_string()),
+            ("EngineFeatures", info.feature_list()),
+            (
+                "Termination",
+                // PGN has no termination for a draw by agreement; it's a
+                // normal end to the game.
+                match self.termination {
+                    Termination::Normal | Termination::Agreement => "normal",
+                    Termination::Adjudication => "adjudication",
+                }
+                .to_string(),
+            ),
+        ] {
+            pgn_game.set_tag(name, &value);
+        }
+        pgn_game
+    }
+}
+
+/// Plays one game between two engines, each searching with its own settings
+/// and its own search state, in its own copy of the game. The copies are
+/// started over with `Game::new_game`, so an engine's copy can be reused from
+/// one game to the next. The game is drawn if it reaches `move_limit` moves
+/// without ending, and `config` decides when an engine resigns, the engines
+/// agree to a draw, or the game is adjudicated a draw before then.
+pub fn play_match_game(
+    white: &mut Game,
+    black: &mut Game,
+    move_limit: u16,
+    config: EngineConfig,
+) -> Result<MatchGame, GameError> {
+    // Indexed by color, like the rest of the engine's per-color tables.
+    let mut games = [black, white];
+    for game in games.iter_mut() {
+        game.new_game(Board::starting_position());
+    }
+    let (result, termination) = play_out(&mut games, move_limit, config)?;
+    Ok(MatchGame {
+        result,
+        termination,
+        pgn_game: games[Color::White as usize].to_pgn()?,
+    })
+}
+
+/// Mutable references to two different elements of `items`.
+fn pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b);
+    if a < b {
+        let (left, right) = items.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = items.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+/// Plays the engines' games out to the end, returning the result and how the
+/// game ended.
+fn play_out(
+    games: &mut [&mut Game; 2],
+    move_limit: u16,
+    config: EngineConfig,
+) -> Result<(MatchResult, Termination), GameError> {
+    // The scores of each engine's searched moves, from its own point of view.
+    let mut engine_scores: [Vec<i16>; 2] = [Vec::new(), Vec::new()];
+    // The scores of every searched move, from white's point of view.
+    let mut scores = Vec::new();
+
+    for ply in 0..move_limit as usize * 2 {
+        let turn = games[0].board().turn();
+        let mover = turn as usize;
+        if let Some(ending) = games[mover].check_game_over_for_current_turn() {
+            let result = match (ending, turn.opposite()) {
+                (GameEnding::Checkmate, Color::White) => MatchResult::WhiteWins,
+                (GameEnding::Checkmate, Color::Black) => MatchResult::BlackWins,
+                (GameEnding::Stalemate | GameEnding::Draw, _) => MatchResult::Draw,
+            };
+            return Ok((result, Termination::Normal));
+        }
+
+        let chess_move = games[mover].make_waterfall_book_then_alpha_beta_move()?;
+        games[turn.opposite() as usize].apply_chess_move(chess_move)?;
+        for game in games.iter_mut() {
+            game.board_mut().toggle_turn();
+        }
+        // Only the engine that claimed the draw knows about it.
+        if games[mover].claimed_draw().is_some() {
+            return Ok((MatchResult::Draw, Termination::Normal));
+        }
+
+        // Book moves aren't searched, so they have no score.
+        let score = match games[mover].searched_position_count() {
+            0 => None,
+            _ => games[mover].alpha_beta_score(),
+        };
+        if let Some(score) = score {
+            scores.push(score);
+            let own_score = if turn == Color::White { score } else { -score };
+            engine_scores[mover].push(own_score);
+            if config.should_resign(&engine_scores[mover]) {
+                let result = match turn {
+                    Color::White => MatchResult::BlackWins,
+                    Color::Black => MatchResult::WhiteWins,
+                };
+                return Ok((result, Termination::Adjudication));
+            }
+        }
+        if engine_scores
+            .iter()
+            .all(|scores| config.agrees_to_draw(ply + 1, scores))
+        {
+            return Ok((MatchResult::Draw, Termination::Agreement));
+        }
+        if config.is_adjudicated_draw(ply + 1, &scores) {
+            return Ok((MatchResult::Draw, Termination::Adjudication));
+        }
+    }
+    Ok((MatchResult::Draw, Termination::Adjudication))
+}
+
+/// Runs a tournament among `engines` and prints each result as it comes in,
+/// followed by the crosstable and final standings. A round robin plays
+/// `rounds` cycles, with colors reversed in every other cycle; a Swiss
+/// tournament plays `rounds` rounds. With a `pgn` path, every game is written
+/// to that file as it finishes.
+pub fn run_tournament(
+    engines: Vec<TournamentEngine>,
+    format: TournamentFormat,
+    rounds: usize,
+    move_limit: u16,
+    config: EngineConfig,
+    pgn: Option<PathBuf>,
+) {
+    let mut pgn_file = match pgn.as_ref().map(File::create).transpose() {
+        Ok(file) => file,
+        Err(error) => {
+            println!(
+                "error: could not create {}: {}",
+                pgn.unwrap().display(),
+                error
+            );
+            return;
+        }
+    };
+    let date = pgn_date(SystemTime::now());
+    // Each engine's game, reused from one of its games to the next.
+    let mut games: Vec<Game> = engines
+        .iter()
+        .map(|engine| engine.new_game(config))
+        .collect();
+    let mut tournament = Tournament::new(engines);
+    println!("Tournament played by {}", EngineInfo::current());
+
+    let schedule: Vec<Round> = match format {
+        TournamentFormat::RoundRobin => {
+            let cycle = round_robin_rounds(tournament.engines().len());
+            (0..rounds)
+                .flat_map(|cycle_number| {
+                    cycle.iter().map(move |round| Round {
+                        games: round
+                            .games
+                            .iter()
+                            .map(|&(white, black)| match cycle_number % 2 {
+                                0 => (white, black),
+                                _ => (black, white),
+                            })
+                            .collect(),
+                        bye: round.bye,
+                    })
+                })
+                .collect()
+        }
+        TournamentFormat::Swiss => Vec::new(),
+    };
+    let round_count = match format {
+        TournamentFormat::RoundRobin => schedule.len(),
+        TournamentFormat::Swiss => rounds,
+    };
+
+    for round_number in 0..round_count {
+        // Swiss rounds can only be paired once the previous round is over.
+        let round = match schedule.get(round_number) {
+            Some(round) => round.clone(),
+            None => tournament.swiss_round(),
+        };
+        println!("Round {}", round_number + 1);
+        for &(white, black) in &round.games {
+            let engines = tournament.engines();
+            let (white_engine, black_engine) = (&engines[white], &engines[black]);
+            let (white_game, black_game) = pair_mut(&mut games, white, black);
+            let match_game = match play_match_game(white_game, black_game, move_limit, config) {
+                Ok(match_game) => match_game,
+                Err(error) => {
+                    println!("error: {}", error);
+                    return;
+                }
+            };
+            match match_game.termination {
+                Termination::Agreement => println!(
+                    "  {} - {}: {} (agreed)",
+                    white_engine, black_engine, match_game.result
+                ),
+                _ => println!(
+                    "  {} - {}: {}",
+                    white_engine, black_engine, match_game.result
+                ),
+            }
+            if let Some(file) = &mut pgn_file {
+                let pgn_game =
+                    match_game.to_pgn(white_engine, black_engine, round_number + 1, &date);
+                if let Err(error) = writeln!(file, "{}", pgn_game) {
+                    println!("error: could not write the game's PGN: {}", error);
+                }
+            }
+            tournament.record(white, black, match_game.result);
+        }
+        if let Some(player) = round.bye {
+            println!("  {}: bye", tournament.engines()[player]);
+            tournament.record_bye(player);
+        }
+    }
+
+    println!();
+    println!("Crosstable");
+    print!("{}", tournament.crosstable());
+    println!();
+    println!("Standings");
+    print!("{}", tournament.standings_table());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::engine_config::{DrawAgreement, MoveNoise};
+
+    fn engines(count: u8) -> Vec<TournamentEngine> {
+        (1..=count)
+            .map(|depth| TournamentEngine {
+                depth,
+                sparring: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_tournament_engine() {
+        assert_eq!(
+            TournamentEngine::from_str("3:materialist"),
+            Ok(TournamentEngine {
+                depth: 3,
+                sparring: Some(SparringProfile::Materialist)
+            })
+        );
+        assert_eq!(
+            TournamentEngine::from_str("4").unwrap().to_string(),
+            "depth 4"
+        );
+        assert!(TournamentEngine::from_str("deep").is_err());
+        assert!(TournamentEngine::from_str("4:strong").is_err());
+    }
+
+    #[test]
+    fn test_round_robin_pairs_everyone_once() {
+        for players in [4, 5] {
+            let rounds = round_robin_rounds(players);
+            let mut pairs: Vec<(usize, usize)> = rounds
+                .iter()
+                .flat_map(|round| round.games.iter())
+                .map(|&(white, black)| (white.min(black), white.max(black)))
+                .collect();
+            pairs.sort();
+            pairs.dedup();
+            assert_eq!(pairs.len(), players * (players - 1) / 2);
+
+            let byes: Vec<_> = rounds.iter().filter_map(|round| round.bye).collect();
+            assert_eq!(byes.len(), if players == 5 { 5 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_swiss_pairs_by_score_without_rematches() {
+        let mut tournament = Tournament::new(engines(5));
+        let first = tournament.swiss_round();
+        assert_eq!(first.bye, Some(4));
+        assert_eq!(first.games, vec![(0, 1), (2, 3)]);
+
+        tournament.record(0, 1, MatchResult::BlackWins);
+        tournament.record(2, 3, MatchResult::Draw);
+        tournament.record_bye(4);
+
+        // 1 and 4 lead with a point each. 4 has had its bye, so the lowest
+        // ranked engine, 0, sits out instead. 2 and 3 have nobody else left,
+        // so they meet again, with 3 taking white this time.
+        let second = tournament.swiss_round();
+        assert_eq!(second.bye, Some(0));
+        assert_eq!(second.games, vec![(1, 4), (3, 2)]);
+    }
+
+    #[test]
+    fn test_match_game_pgn() {
+        let engines = [
+            TournamentEngine {
+                depth: 1,
+                sparring: None,
+            },
+            TournamentEngine {
+                depth: 2,
+                sparring: Some(SparringProfile::Materialist),
+            },
+        ];
+        let config = EngineConfig::default();
+        let mut games = [engines[0].new_game(config), engines[1].new_game(config)];
+        let [white, black] = &mut games;
+        let match_game = play_match_game(white, black, 2, config).unwrap();
+        // Nobody mates in two moves, so the move limit calls it a draw.
+        assert_eq!(match_game.result, MatchResult::Draw);
+        assert_eq!(match_game.termination, Termination::Adjudication);
+
+        let pgn_game = match_game.to_pgn(&engines[0], &engines[1], 3, "2024.03.09");
+        let name = EngineInfo::current().full_name();
+        assert_eq!(pgn_game.tag("Round"), Some("3"));
+        assert_eq!(pgn_game.tag("Date"), Some("2024.03.09"));
+        assert_eq!(
+            pgn_game.tag("White"),
+            Some(format!("{} depth 1", name).as_str())
+        );
+        assert_eq!(
+            pgn_game.tag("Black"),
+            Some(format!("{} depth 2 (materialist)", name).as_str())
+        );
+        assert_eq!(pgn_game.tag("Result"), Some("1/2-1/2"));
+        assert_eq!(pgn_game.tag("Termination"), Some("adjudication"));
+        assert_eq!(pgn_game.moves.len(), 4);
+
+        // The seven tag roster comes first, in order, as PGN readers expect.
+        let roster: Vec<&str> = pgn_game.tags[..7]
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            roster,
+            ["Event", "Site", "Date", "Round", "White", "Black", "Result"]
+        );
+        assert!(pgn_game.to_string().trim_end().ends_with("1/2-1/2"));
+
+        // The engines' games are started over for the rematch.
+        let [white, black] = &mut games;
+        let rematch = play_match_game(black, white, 2, config).unwrap();
+        let pgn_game = rematch.to_pgn(&engines[1], &engines[0], 4, "2024.03.09");
+        assert_eq!(pgn_game.moves.len(), 4);
+        assert_eq!(pgn_game.tag("FEN"), None);
+    }
+
+    #[test]
+    fn test_draw_agreement() {
+        let engine = TournamentEngine {
+            depth: 1,
+            sparring: None,
+        };
+        // Both engines agree to a draw as soon as each has searched a move.
+        let config = EngineConfig {
+            draw_agreement: Some(DrawAgreement {
+                min_ply: 0,
+                max_score: i16::MAX,
+                moves: 1,
+            }),
+            ..Default::default()
+        };
+        let match_game = play_match_game(
+            &mut engine.new_game(config),
+            &mut engine.new_game(config),
+            100,
+            config,
+        )
+        .unwrap();
+        assert_eq!(match_game.result, MatchResult::Draw);
+        assert_eq!(match_game.termination, Termination::Agreement);
+        let pgn_game = match_game.to_pgn(&engine, &engine, 1, "2024.03.09");
+        assert_eq!(pgn_game.tag("Termination"), Some("normal"));
+    }
+
+    #[test]
+    fn test_move_noise() {
+        let engine = TournamentEngine {
+            depth: 1,
+            sparring: None,
+        };
+        let config = EngineConfig {
+            move_noise: Some(MoveNoise {
+                random_plies: 6,
+                jitter: 30,
+            }),
+            ..Default::default()
+        };
+        let match_game = play_match_game(
+            &mut engine.new_game(config),
+            &mut engine.new_game(config),
+            4,
+            config,
+        )
+        .unwrap();
+        // The random moves are legal, and the engines take over after them;
+        // only a blunder among the random moves ends the game early.
+        let pgn_game = match_game.to_pgn(&engine, &engine, 1, "2024.03.09");
+        assert!(pgn_game.moves.len() >= 6);
+    }
+
+    #[test]
+    fn test_crosstable_and_standings() {
+        let mut tournament = Tournament::new(engines(3));
+        tournament.record(0, 1, MatchResult::WhiteWins);
+        tournament.record(1, 0, MatchResult::Draw);
+        tournament.record(2, 0, MatchResult::BlackWins);
+
+        let standings = tournament.standings();
+        assert_eq!(standings[0].player, 0);
+        assert_eq!(standings[0].half_points, 5);
+        assert_eq!((standings[0].wins, standings[0].draws), (2, 1));
+        assert_eq!(standings[1].player, 1);
+
+        let crosstable = tournament.crosstable();
+        let rows: Vec<&str> = crosstable.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1].starts_with(" 1. depth 1"));
+        assert!(rows[1].ends_with("x   1.5     1    2.5"));
+        assert!(rows[2].ends_with("0.5     x     -    0.5"));
+        let table = tournament.standings_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with(" 1. depth 1: 2.5 (+2 =1 -0), Elo +"));
+        // Depth 1 beat depth 2 once and drew once.
+        assert!(lines[0].ends_with(", LOS 84%"), "{}", lines[0]);
+        // Depth 2 and depth 3 never met.
+        assert!(lines[1].starts_with(" 2. depth 2: 0.5 (+0 =1 -1), Elo -"));
+        assert!(!lines[1].contains("LOS"));
+
+        let ratings = tournament.ratings();
+        assert!(ratings[0] > ratings[1] && ratings[0] > ratings[2]);
+    }
+}
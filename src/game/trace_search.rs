@@ -0,0 +1,122 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::{alpha_beta_search, SearchContext, SearchError};
+use crate::board::fen::FenError;
+use crate::board::Board;
+use crate::evaluate::format_score;
+use crate::move_generator::MoveGenerator;
+use crate::search_trace::{chrome_trace, folded_stacks, record, self_time_by_name};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TraceFormat {
+    /// The Chrome trace event format, for `chrome://tracing`, Perfetto, or
+    /// speedscope.
+    Chrome,
+    /// Folded stacks, for `flamegraph.pl` or `inferno-flamegraph`.
+    Folded,
+}
+
+impl FromStr for TraceFormat {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chrome" => Ok(TraceFormat::Chrome),
+            "folded" => Ok(TraceFormat::Folded),
+            _ => Err("invalid trace format; options are: chrome, folded"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TraceSearchError {
+    #[error("invalid fen: {error}")]
+    FenError { error: FenError },
+    #[error("search error: {error}")]
+    SearchError { error: SearchError },
+    #[error("could not write {path}: {error}")]
+    WriteError { path: String, error: std::io::Error },
+}
+
+/// Searches the position once at `depth` while recording where the search spends
+/// its time, writes the recording to `output` in the given format, and prints
+/// the time spent in each part of the search.
+pub fn trace_search(
+    fen: &str,
+    depth: u8,
+    output: &str,
+    format: TraceFormat,
+) -> Result<(), TraceSearchError> {
+    let mut board = Board::from_fen(fen).map_err(|error| TraceSearchError::FenError { error })?;
+    let mut move_generator = MoveGenerator::new();
+    let mut context = SearchContext::new(depth);
+
+    let (result, spans) =
+        record(|| alpha_beta_search(&mut context, &mut board, &mut move_generator));
+    let best_move = result.map_err(|error| TraceSearchError::SearchError { error })?;
+
+    let trace = match format {
+        TraceFormat::Chrome => chrome_trace(&spans),
+        TraceFormat::Folded => folded_stacks(&spans),
+    };
+    std::fs::write(output, trace).map_err(|error| TraceSearchError::WriteError {
+        path: output.to_string(),
+        error,
+    })?;
+
+    println!(
+        "Best move: {} ({} nodes, score: {})",
+        best_move.to_uci(),
+        context.searched_position_count(),
+        format_score(context.last_score().unwrap_or_default())
+    );
+    let node_stats = context.node_stats();
+    println!(
+        "Node types: {} PV, {} cut, {} all ({}%)",
+        node_stats.pv, node_stats.cut, node_stats.all, node_stats
+    );
+    println!("Time spent in each part of the search, excluding the parts nested in it, summed across threads:");
+    for (name, self_time) in self_time_by_name(&spans) {
+        println!(
+            "  {:<14}{:>10.1} ms",
+            name,
+            self_time.as_secs_f64() * 1000.0
+        );
+    }
+    println!("Wrote {} spans to {}", spans.len(), output);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_search() {
+        let path = std::env::temp_dir().join("chess_test_trace_search.folded");
+        let path = path.to_str().unwrap();
+        trace_search(
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+            2,
+            path,
+            TraceFormat::Folded,
+        )
+        .unwrap();
+
+        let folded = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let stacks: Vec<&str> = folded
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0)
+            .collect();
+        assert!(stacks.contains(&"search"));
+        assert!(stacks.contains(&"search;movegen"));
+        assert!(stacks.iter().any(|stack| stack.ends_with("eval")));
+        assert!(stacks.iter().any(|stack| stack.ends_with("tt_probe")));
+
+        assert_eq!(TraceFormat::from_str("chrome"), Ok(TraceFormat::Chrome));
+        assert!(TraceFormat::from_str("svg").is_err());
+    }
+}
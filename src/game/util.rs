@@ -1,21 +1,22 @@
+use crate::alpha_beta_searcher::DepthStats;
 use crate::board::color::Color;
-use crate::board::Board;
+use crate::book::traps::find_trap;
+use crate::chess_move::algebraic_notation::algebraic_notation_for;
 use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::{format_score, win_probability};
 use crate::game::game::Game;
-use common::bitboard::square::from_rank_file;
 
-pub fn print_board_and_stats(
+/// Returns the lines shown beneath the board after a move: the last move, whose
+/// turn it was, what the search found, and how to answer the opening trap the
+/// move set, if it's a known one.
+pub fn stats_lines(
     game: &Game,
     enumerated_candidate_moves: Vec<(ChessMove, String)>,
     current_turn: Color,
-) {
+) -> Vec<String> {
     let board = game.board();
     let last_move_algebraic = match game.last_move() {
-        Some(chess_move) => enumerated_candidate_moves
-            .iter()
-            .find(|(move_, _)| move_ == &chess_move)
-            .map(|(_, notation)| notation.clone())
-            .unwrap_or_else(|| "-".to_string()),
+        Some(chess_move) => algebraic_notation_for(&chess_move, &enumerated_candidate_moves),
         None => "-".to_string(),
     };
     let searched_position_count = game.searched_position_count();
@@ -35,44 +36,70 @@ pub fn print_board_and_stats(
         ),
     };
     let alpha_beta_score = match game.alpha_beta_score() {
-        Some(score) => format!("{}", score),
+        Some(score) if game.mate_verified() == Some(false) => {
+            format!("{} (unverified)", format_score(score))
+        }
+        Some(score) => format!(
+            "{} (white win probability: {:.1}%)",
+            format_score(score),
+            win_probability(score) * 100.0
+        ),
         None => "-".to_string(),
     };
-    print_board(game.board());
-    println!("Last move: {}\n", last_move_algebraic);
-    println!("* Turn: {}", current_turn);
-    println!("* Halfmove clock: {}", board.halfmove_clock());
-    println!("* Score: {}", alpha_beta_score);
-    println!("* Positions searched: {}", searched_position_message);
+    let mut lines = vec![
+        format!("Last move: {}", last_move_algebraic),
+        String::new(),
+        format!("* Turn: {}", current_turn),
+        format!("* Halfmove clock: {}", board.halfmove_clock()),
+        format!("* Score: {}", alpha_beta_score),
+        format!("* Positions searched: {}", searched_position_message),
+    ];
+    if let Some(trap) = find_trap(board) {
+        lines.push(format!("* Opening trap: {}", trap));
+    }
+    if searched_position_count > 0 {
+        lines.push(format!(
+            "* Transposition table: {:.1}% full",
+            game.hash_fill_percentage()
+        ));
+        lines.extend(depth_stats_table_lines(game.depth_stats()));
+    }
+    lines
 }
 
-pub fn print_enter_move_prompt() {
-    println!("Enter your move:");
+/// Prints the nodes, time, and score for each completed search depth, which
+/// makes it easy to spot a depth where the search explodes. Alongside them are
+/// the selective depth, the effective branching factor, and how often cut
+/// nodes cut off on their first move.
+pub fn print_depth_stats_table(depth_stats: &[DepthStats]) {
+    for line in depth_stats_table_lines(depth_stats) {
+        println!("{}", line);
+    }
 }
 
-pub fn print_board(board: &Board) {
-    let mut board_str = String::new();
-    board_str.push_str("    a   b   c   d   e   f   g   h\n");
-    board_str.push_str("  ┌───┬───┬───┬───┬───┬───┬───┬───┐\n");
-    for rank in 0..8 {
-        let transposed_rank = 7 - rank;
-        board_str.push_str(&format!("{} │", transposed_rank + 1));
-        for file in 0..8 {
-            let square = from_rank_file(transposed_rank, file);
-            let piece = board.get(square);
-            let piece_str = match piece {
-                Some((piece, color)) => piece.to_unicode_piece_char(color).to_string(),
-                None => if (rank + file) % 2 == 0 { " " } else { "·" }.to_string(),
-            };
-            board_str.push_str(&format!(" {} │", piece_str));
-        }
-        board_str.push_str(&format!(" {}\n", transposed_rank + 1));
-        if rank < 7 {
-            board_str.push_str("  ├───┼───┼───┼───┼───┼───┼───┼───┤\n");
-        } else {
-            board_str.push_str("  └───┴───┴───┴───┴───┴───┴───┴───┘\n");
-        }
+fn depth_stats_table_lines(depth_stats: &[DepthStats]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{:>7} {:>9} {:>12} {:>12} {:>20} {:>12} {:>7} {:>8}",
+        "depth", "seldepth", "nodes", "time", "score", "pv/cut/all%", "ebf", "cut1st%"
+    )];
+    for stats in depth_stats {
+        lines.push(format!(
+            "{:>7} {:>9} {:>12} {:>12} {:>20} {:>12} {:>7} {:>8}",
+            stats.depth,
+            stats.selective_depth,
+            stats.nodes,
+            format!("{:.1?}", stats.duration),
+            format_score(stats.score),
+            stats.node_types.to_string(),
+            stats
+                .branching_factor
+                .map_or("-".to_string(), |factor| format!("{:.1}", factor)),
+            format!("{:.0}", stats.node_types.first_move_cutoff_percentage())
+        ));
     }
-    board_str.push_str("    a   b   c   d   e   f   g   h\n");
-    println!("{}", board_str);
+    lines
+}
+
+pub fn print_enter_move_prompt() {
+    println!("Enter your move:");
 }
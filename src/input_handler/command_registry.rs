@@ -0,0 +1,187 @@
+use crate::game::game::Game;
+
+use super::{parse_move, read_line, InputError, PlayerInput, MOVE_FORMATS};
+
+/// Runs a named command against the game, returning the text to show the player.
+pub type CommandHandler = fn(&mut Game, &[String]) -> Result<String, InputError>;
+
+/// A command typed by name at the move prompt, such as `fen` or `undo`.
+pub struct NamedCommand {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// How the command's arguments are written, e.g. "[depth]", for its help text.
+    pub arguments: &'static str,
+    pub description: &'static str,
+    pub handler: CommandHandler,
+}
+
+impl NamedCommand {
+    pub fn run(&self, game: &mut Game, arguments: &[String]) -> Result<String, InputError> {
+        (self.handler)(game, arguments)
+    }
+
+    /// The error to return when the command is given arguments it can't use.
+    pub fn usage_error(&self) -> InputError {
+        InputError::InvalidArguments {
+            usage: self.usage(),
+        }
+    }
+
+    fn usage(&self) -> String {
+        match self.arguments {
+            "" => self.name.to_string(),
+            arguments => format!("{} {}", self.name, arguments),
+        }
+    }
+
+    fn is_called(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+}
+
+/// The commands a mode accepts at its move prompt. Moves (in any of the
+/// `MOVE_FORMATS`), `help`, and `quit` are always accepted; each mode registers the
+/// named commands that make sense for it, and `help` lists whatever was
+/// registered.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<NamedCommand>,
+}
+
+const HELP_NAMES: [&str; 2] = ["help", "?"];
+const QUIT_NAMES: [&str; 2] = ["quit", "exit"];
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, command: NamedCommand) -> Self {
+        self.commands.push(command);
+        self
+    }
+
+    pub fn commands(&self) -> &[NamedCommand] {
+        &self.commands
+    }
+
+    /// Reads a line from stdin and parses it. The end of input quits.
+    pub fn read_input(&self) -> Result<PlayerInput<'_>, InputError> {
+        match read_line() {
+            Ok(Some(input)) => self.parse(&input),
+            Ok(None) => Ok(PlayerInput::Quit),
+            Err(error) => Err(InputError::IOError {
+                error: error.to_string(),
+            }),
+        }
+    }
+
+    /// Parses a line typed at the move prompt. A move takes precedence over a
+    /// named command; otherwise the first word names the command, and the rest
+    /// are its arguments.
+    pub fn parse(&self, input: &str) -> Result<PlayerInput<'_>, InputError> {
+        let raw = input.trim();
+        if let Some(command) = parse_move(raw) {
+            return Ok(PlayerInput::Move(Box::new(command)));
+        }
+
+        let mut words = raw.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        if HELP_NAMES.contains(&name) {
+            return Ok(PlayerInput::Help);
+        }
+        if QUIT_NAMES.contains(&name) {
+            return Ok(PlayerInput::Quit);
+        }
+        match self.commands.iter().find(|command| command.is_called(name)) {
+            Some(command) => Ok(PlayerInput::Named {
+                command,
+                arguments: words.map(str::to_string).collect(),
+            }),
+            None => Err(InputError::InvalidInput {
+                input: raw.to_string(),
+            }),
+        }
+    }
+
+    /// Lists the move formats and every command, generated from the registry so
+    /// that it can't fall out of date.
+    pub fn help_text(&self) -> String {
+        let mut entries: Vec<(String, &str)> = MOVE_FORMATS
+            .iter()
+            .map(|format| (format.usage.to_string(), format.description))
+            .collect();
+        for command in &self.commands {
+            let mut usage = command.usage();
+            for alias in command.aliases {
+                usage.push_str(&format!(", {}", alias));
+            }
+            entries.push((usage, command.description));
+        }
+        entries.push((HELP_NAMES.join(", "), "show this list of commands"));
+        entries.push((QUIT_NAMES.join(", "), "stop playing"));
+
+        let width = entries
+            .iter()
+            .map(|(usage, _)| usage.len())
+            .max()
+            .unwrap_or(0);
+        let mut text = String::from("Commands:\n");
+        for (usage, description) in entries {
+            text.push_str(&format!("  {:width$}  {}\n", usage, description));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo() -> NamedCommand {
+        NamedCommand {
+            name: "echo",
+            aliases: &["e"],
+            arguments: "<word>...",
+            description: "repeat the arguments back",
+            handler: |_game, arguments| Ok(arguments.join(" ")),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let registry = CommandRegistry::new().register(echo());
+
+        assert!(matches!(registry.parse("e2e4\n"), Ok(PlayerInput::Move(_))));
+        assert!(matches!(registry.parse(" Nf3 "), Ok(PlayerInput::Move(_))));
+        assert!(matches!(registry.parse("?"), Ok(PlayerInput::Help)));
+        assert!(matches!(registry.parse("exit"), Ok(PlayerInput::Quit)));
+        assert!(matches!(
+            registry.parse("e9"),
+            Err(InputError::InvalidInput { .. })
+        ));
+
+        let mut game = Game::new(0);
+        match registry.parse("e hello  world") {
+            Ok(PlayerInput::Named { command, arguments }) => {
+                assert_eq!(command.name, "echo");
+                assert_eq!(command.run(&mut game, &arguments).unwrap(), "hello world");
+            }
+            _ => panic!("expected the echo command"),
+        }
+    }
+
+    #[test]
+    fn test_help_text_lists_every_command() {
+        let registry = CommandRegistry::new().register(echo());
+        let help = registry.help_text();
+        for format in &MOVE_FORMATS {
+            assert!(help.contains(format.description));
+        }
+        assert!(help.contains("echo <word>..., e"));
+        assert!(help.contains("repeat the arguments back"));
+        assert!(help.contains("help, ?"));
+        assert!(help.contains("quit, exit"));
+        assert_eq!(echo().usage_error().to_string(), "usage: echo <word>...");
+    }
+}
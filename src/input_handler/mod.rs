@@ -1,49 +1,209 @@
-use std::io;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
 
+use self::command_registry::NamedCommand;
+use crate::board::piece::Piece;
+use crate::game::analyze::AnalyzeError;
+#[cfg(feature = "clipboard")]
+use crate::game::clipboard::ClipboardError;
 use crate::game::command::{Command, MakeMove};
-use regex::Regex;
+use crate::game::game::GameError;
+use regex::{Captures, Regex};
 use thiserror::Error;
 
+pub mod command_registry;
+
 #[derive(Error, Debug)]
 pub enum InputError {
     #[error("io error: {error:?}")]
     IOError { error: String },
-    #[error("invalid input: {input:?}")]
+    #[error("invalid input: {input:?} (type \"help\" to list commands)")]
     InvalidInput { input: String },
+    #[error("usage: {usage}")]
+    InvalidArguments { usage: String },
+    #[error("{error}")]
+    GameError { error: GameError },
+    #[error("{error}")]
+    AnalyzeError { error: AnalyzeError },
+    #[cfg(feature = "clipboard")]
+    #[error("{error}")]
+    ClipboardError { error: ClipboardError },
 }
 
-pub fn parse_player_move_input() -> Result<Box<dyn Command>, InputError> {
-    let mut input = String::new();
-    let raw = match io::stdin().read_line(&mut input) {
-        Ok(_n) => input.trim_start().trim_end(),
-        Err(error) => {
-            return Err(InputError::IOError {
-                error: error.to_string(),
-            })
-        }
-    };
+impl From<GameError> for InputError {
+    fn from(error: GameError) -> Self {
+        InputError::GameError { error }
+    }
+}
+
+/// What the player asked for at the move prompt.
+pub enum PlayerInput<'a> {
+    Move(Box<dyn Command>),
+    Help,
+    Quit,
+    Named {
+        command: &'a NamedCommand,
+        arguments: Vec<String>,
+    },
+}
 
-    let coordinate_re = Regex::new("^([a-h][1-8])([a-h][1-8])$").unwrap();
-    let algebraic_re =
-        Regex::new("^([NBRQK]?[a-h]?[1-8]?x?[a-h][1-8](=[NBRQ])?[+#]?|O-O(-O)?)$").unwrap();
+/// A way of writing a move at the prompt. Every mode accepts all of them.
+pub struct MoveFormat {
+    pub usage: &'static str,
+    pub description: &'static str,
+    pattern: &'static str,
+    parse: fn(&Captures) -> MakeMove,
+}
+
+pub const MOVE_FORMATS: [MoveFormat; 2] = [
+    MoveFormat {
+        usage: "<from><to>[piece]",
+        description: "move by coordinates, e.g. e2e4, or e7e8n to promote to a knight",
+        pattern: "^([a-h][1-8])([a-h][1-8])([nbrq])?$",
+        parse: |caps| MakeMove::Coordinate {
+            from_square: caps[1].to_string(),
+            to_square: caps[2].to_string(),
+            promote_to: caps
+                .get(3)
+                .and_then(|piece| Piece::from_char(piece.as_str().chars().next()?))
+                .map(|(piece, _)| piece),
+        },
+    },
+    MoveFormat {
+        usage: "<move>",
+        description: "move in algebraic notation, e.g. e4, Nf3, exd5, O-O, e8=Q",
+        pattern: "^([NBRQK]?[a-h]?[1-8]?x?[a-h][1-8](=[NBRQ])?[+#]?|O-O(-O)?)$",
+        parse: |caps| MakeMove::Algebraic {
+            algebraic: caps[1].to_string(),
+        },
+    },
+];
+
+/// The lines typed at the terminal, read on a background thread so that the
+/// player can type ahead of the prompt, e.g. a premove while the engine is
+/// thinking. Every interactive read goes through it, since the thread holds
+/// stdin for as long as the program runs.
+fn typed_lines() -> &'static Mutex<Receiver<io::Result<String>>> {
+    static LINES: OnceLock<Mutex<Receiver<io::Result<String>>>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
+
+/// Waits for the next line typed at the terminal, without its line ending.
+/// `None` once the input ends.
+pub fn read_line() -> io::Result<Option<String>> {
+    match typed_lines().lock().unwrap().recv() {
+        Ok(line) => line.map(Some),
+        Err(_) => Ok(None),
+    }
+}
 
-    if let Some(caps) = coordinate_re.captures(raw) {
-        let from_square = caps.get(1).unwrap().as_str();
-        let to_square = caps.get(2).unwrap().as_str();
-        let command = MakeMove::Coordinate {
-            from_square: from_square.to_string(),
-            to_square: to_square.to_string(),
+/// The next line the player typed before they were asked for it, if any,
+/// without waiting for one.
+pub fn typed_ahead() -> Option<String> {
+    typed_lines().lock().unwrap().try_recv().ok()?.ok()
+}
+
+/// Parses a move written in any of the `MOVE_FORMATS`.
+pub fn parse_move(input: &str) -> Option<MakeMove> {
+    MOVE_FORMATS.iter().find_map(|format| {
+        Regex::new(format.pattern)
+            .unwrap()
+            .captures(input)
+            .map(|caps| (format.parse)(&caps))
+    })
+}
+
+/// Asks the player which piece to promote to, given the moves that promote to
+/// each, e.g. "e8=Q", and returns the chosen move. Asks again until the answer
+/// names one of them; `None` if the player gives up with an empty answer or
+/// the input ends.
+pub fn prompt_promotion(choices: &[String]) -> Option<String> {
+    let pieces: Vec<&str> = choices
+        .iter()
+        .filter_map(|san| san.split_once('=').map(|(_, piece)| &piece[..1]))
+        .collect();
+    loop {
+        print!("Promote to ({})? ", pieces.join(", "));
+        io::stdout().flush().ok()?;
+        let answer = match read_line() {
+            Ok(Some(answer)) if !answer.trim().is_empty() => answer,
+            _ => return None,
         };
+        if let Some(choice) = choose_promotion(&answer, choices) {
+            return Some(choice.clone());
+        }
+    }
+}
 
-        Ok(Box::new(command))
-    } else if let Some(caps) = algebraic_re.captures(raw) {
-        let algebraic = caps.get(1).unwrap().as_str().to_string();
-        let command = MakeMove::Algebraic { algebraic };
+/// The promotion among `choices` to the piece named by `answer`, e.g. "n" or
+/// "Knight" for "e8=N".
+fn choose_promotion<'a>(answer: &str, choices: &'a [String]) -> Option<&'a String> {
+    let letter = match answer.trim().to_lowercase().as_str() {
+        "knight" => 'N',
+        answer => answer.chars().next()?.to_ascii_uppercase(),
+    };
+    choices.iter().find(|san| {
+        san.split_once('=')
+            .is_some_and(|(_, piece)| piece.starts_with(letter))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_move() {
+        assert!(matches!(
+            parse_move("e2e4"),
+            Some(MakeMove::Coordinate { .. })
+        ));
+        assert!(matches!(
+            parse_move("O-O-O"),
+            Some(MakeMove::Algebraic { .. })
+        ));
+        assert!(parse_move("e9").is_none());
+        assert!(matches!(
+            parse_move("e7e8n"),
+            Some(MakeMove::Coordinate {
+                promote_to: Some(Piece::Knight),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_move("e7e8"),
+            Some(MakeMove::Coordinate {
+                promote_to: None,
+                ..
+            })
+        ));
+        assert!(parse_move("e7e8k").is_none());
+    }
 
-        Ok(Box::new(command))
-    } else {
-        Err(InputError::InvalidInput {
-            input: raw.to_string(),
-        })
+    #[test]
+    fn test_choose_promotion() {
+        let choices: Vec<String> = ["exd8=Q+", "exd8=R+", "exd8=B", "exd8=N"]
+            .iter()
+            .map(|san| san.to_string())
+            .collect();
+        assert_eq!(choose_promotion("q\n", &choices).unwrap(), "exd8=Q+");
+        assert_eq!(choose_promotion("N", &choices).unwrap(), "exd8=N");
+        assert_eq!(choose_promotion("knight", &choices).unwrap(), "exd8=N");
+        assert_eq!(choose_promotion("bishop", &choices).unwrap(), "exd8=B");
+        assert_eq!(choose_promotion("k", &choices), None);
+        assert_eq!(choose_promotion("", &choices), None);
     }
 }
@@ -1,8 +1,12 @@
-pub mod alpha_beta_searcher;
-pub mod board;
-pub mod book;
-pub mod chess_move;
-pub mod evaluate;
+pub use chess_engine::{
+    alpha_beta_searcher, board, book, chess_move, engine_info, evaluate, move_generator, pgn,
+};
+pub use chess_engine::{
+    castle_kingside, castle_queenside, check_move, checkmate_move, chess_position, en_passant_move,
+    promotion, std_move,
+};
+
 pub mod game;
 pub mod input_handler;
-pub mod move_generator;
+pub mod search_trace;
+pub mod uci;
@@ -1,9 +1,33 @@
-use chess::board::color::Color;
-use chess::game::computer_vs_computer::computer_vs_computer;
-use chess::game::human_vs_computer::play_computer;
-use chess::game::player_vs_player::player_vs_player;
-use chess::game::position_counter::{run_count_positions, CountPositionsStrategy};
-use chess::game::stockfish_elo::determine_stockfish_elo;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chess_cli::alpha_beta_searcher::root_jitter::RootJitter;
+use chess_cli::alpha_beta_searcher::watchdog::Watchdog;
+use chess_cli::board::color::Color;
+use chess_cli::board::Board;
+use chess_cli::engine_info::EngineInfo;
+use chess_cli::evaluate::corpus::normalize_corpus;
+use chess_cli::evaluate::sparring::SparringProfile;
+use chess_cli::evaluate::symmetry::{verify_symmetry, SYMMETRY_POSITIONS};
+use chess_cli::evaluate::tune::{load_positions, tune};
+use chess_cli::evaluate::weights::EvaluationWeights;
+use chess_cli::evaluate::EvaluationTerms;
+use chess_cli::game::analyze::analyze;
+use chess_cli::game::annotate::annotate_pgn_file;
+use chess_cli::game::computer_vs_computer::computer_vs_computer;
+use chess_cli::game::engine_config::{DrawAgreement, EngineConfig, MoveNoise};
+use chess_cli::game::event::OutputFormat;
+use chess_cli::game::explain_move::explain_move;
+use chess_cli::game::game_options::GameOptions;
+use chess_cli::game::human_vs_computer::play_computer;
+use chess_cli::game::player_vs_player::player_vs_player;
+use chess_cli::game::position_counter::{run_count_positions, CountPositionsStrategy};
+use chess_cli::game::renderer::DisplayConfig;
+use chess_cli::game::stockfish_elo::determine_stockfish_elo;
+use chess_cli::game::tournament::{run_tournament, TournamentEngine, TournamentFormat};
+use chess_cli::game::trace_search::{trace_search, TraceFormat};
+use chess_cli::uci::run_uci;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -29,14 +53,99 @@ enum Chess {
     Play {
         #[structopt(short, long, default_value = "4")]
         depth: u8,
+        #[structopt(
+            long = "hash",
+            default_value = "16",
+            help = "The most memory, in megabytes, for the engine's transposition table"
+        )]
+        hash_mb: usize,
         #[structopt(short = "c", long = "color", default_value = "random")]
         color: Color,
+        #[structopt(
+            long,
+            help = "Choose randomly among engine moves scoring within this many centipawns of the best move"
+        )]
+        jitter: Option<i16>,
+        #[structopt(long, help = "Seed for `--jitter`, to make games reproducible")]
+        seed: Option<u64>,
+        #[structopt(
+            long,
+            help = "Give the engine a deliberate weakness to practice against: weak-endgame, ignores-king-safety, materialist, or careless"
+        )]
+        sparring: Option<SparringProfile>,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Evaluate positions with the weights in this JSON file, as written by `weights` or `tune`, rather than the built-in ones"
+        )]
+        weights: Option<PathBuf>,
+        #[structopt(
+            long,
+            help = "Let the engine think about its next move while you think about yours"
+        )]
+        ponder: bool,
+        #[structopt(
+            long,
+            help = "Play out each checkmate the engine finds before announcing it, to catch search bugs"
+        )]
+        verify_mates: bool,
+        #[structopt(
+            long,
+            help = "Abort, and log why, any engine search that takes over 10 times this many seconds, rather than wait on it"
+        )]
+        watchdog: Option<u64>,
+        #[cfg(feature = "opening-explorer")]
+        #[structopt(
+            long,
+            help = "Once the engine's book runs out, follow master games from the Lichess opening explorer (requires curl and an internet connection)"
+        )]
+        opening_explorer: bool,
+        #[structopt(
+            long,
+            help = "Promote pawns to queens without asking, unless the move names another piece (e.g. `e8=N` or `e7e8n`)"
+        )]
+        auto_queen: bool,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Draw the board as set in this JSON file: `coordinates` (true or false), `light_square` and `dark_square` (a character each), and `pieces` (`unicode` or `letters`)"
+        )]
+        display_config: Option<PathBuf>,
+        #[structopt(
+            long,
+            default_value = "text",
+            help = "`text` for the interactive board, or `json` for one JSON object per game event"
+        )]
+        output: OutputFormat,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Add the session's results to this JSON file, and print the all-time statistics it records"
+        )]
+        stats_file: Option<PathBuf>,
     },
     #[structopt(
         name = "pvp",
         about = "Play a game against another human on this local machine."
     )]
-    Pvp,
+    Pvp {
+        #[structopt(
+            long,
+            help = "Grade each move in the background, searching this many plies deep, and show the evaluation graph and each player's accuracy when the game ends"
+        )]
+        grade: Option<u8>,
+        #[structopt(
+            long,
+            help = "Promote pawns to queens without asking, unless the move names another piece (e.g. `e8=N` or `e7e8n`)"
+        )]
+        auto_queen: bool,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Draw the board as set in this JSON file: `coordinates` (true or false), `light_square` and `dark_square` (a character each), and `pieces` (`unicode` or `letters`)"
+        )]
+        display_config: Option<PathBuf>,
+    },
     #[structopt(
         name = "watch",
         about = "Watch the computer play against itself at the given `--depth` (default: 4)."
@@ -44,6 +153,53 @@ enum Chess {
     Watch {
         #[structopt(short, long, default_value = "4")]
         depth: u8,
+        #[structopt(
+            long = "hash",
+            default_value = "16",
+            help = "The most memory, in megabytes, for the engine's transposition table"
+        )]
+        hash_mb: usize,
+        #[structopt(
+            long,
+            help = "Choose randomly among engine moves scoring within this many centipawns of the best move"
+        )]
+        jitter: Option<i16>,
+        #[structopt(long, help = "Seed for `--jitter`, to make games reproducible")]
+        seed: Option<u64>,
+        #[structopt(
+            long,
+            help = "Play out each checkmate the engine finds before announcing it, to catch search bugs"
+        )]
+        verify_mates: bool,
+        #[structopt(
+            long,
+            help = "Abort, and log why, any engine search that takes over 10 times this many seconds, rather than wait on it"
+        )]
+        watchdog: Option<u64>,
+        #[cfg(feature = "opening-explorer")]
+        #[structopt(
+            long,
+            help = "Once the engine's book runs out, follow master games from the Lichess opening explorer (requires curl and an internet connection)"
+        )]
+        opening_explorer: bool,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Evaluate positions with the weights in this JSON file, as written by `weights` or `tune`, rather than the built-in ones"
+        )]
+        weights: Option<PathBuf>,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Draw the board as set in this JSON file: `coordinates` (true or false), `light_square` and `dark_square` (a character each), and `pieces` (`unicode` or `letters`)"
+        )]
+        display_config: Option<PathBuf>,
+        #[structopt(
+            long,
+            default_value = "text",
+            help = "`text` for the interactive board, or `json` for one JSON object per game event"
+        )]
+        output: OutputFormat,
     },
     #[structopt(
         name = "determine-stockfish-elo",
@@ -55,21 +211,572 @@ enum Chess {
         #[structopt(short, long, default_value = "1000")]
         starting_elo: u32,
     },
+    #[structopt(
+        name = "tournament",
+        about = "Play a tournament among engine configurations, each given with `--engine <depth>[:<sparring profile>]`, and print the crosstable and final standings. A `round-robin` (default) plays every pairing once per round, reversing colors every other round; a `swiss` tournament pairs engines with similar scores each round. The standings estimate each engine's Elo rating and its likelihood of superiority (LOS) over the engine placed below it."
+    )]
+    Tournament {
+        #[structopt(
+            short,
+            long = "engine",
+            required = true,
+            min_values = 2,
+            help = "An engine to enter, e.g. `4` or `3:materialist`; give at least two"
+        )]
+        engines: Vec<TournamentEngine>,
+        #[structopt(short, long, default_value = "round-robin")]
+        format: TournamentFormat,
+        #[structopt(short, long, default_value = "2")]
+        rounds: usize,
+        #[structopt(
+            short,
+            long,
+            default_value = "100",
+            help = "Adjudicate a game as a draw after this many moves"
+        )]
+        move_limit: u16,
+        #[structopt(
+            long,
+            help = "Resign for an engine once it has scored this many centipawns behind for several moves in a row"
+        )]
+        resign_threshold: Option<i16>,
+        #[structopt(
+            long,
+            help = "Have the engines agree to a draw once both have scored the game within 10 centipawns of level for this many of their moves in a row, past move 40"
+        )]
+        draw_agreement: Option<usize>,
+        #[structopt(
+            long,
+            help = "Play a random legal move at each of the first this many plies of every game, so that the games reach more varied positions, e.g. for training data"
+        )]
+        random_plies: Option<usize>,
+        #[structopt(
+            long,
+            help = "Choose randomly among engine moves scoring within this many centipawns of the best move"
+        )]
+        jitter: Option<i16>,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Write every game to this file as PGN, tagged for rating tools like Ordo and BayesElo"
+        )]
+        pgn: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "annotate",
+        about = "Analyze every position of every game in a PGN file at the given `--depth` (default: 3), and write the games back out as PGN annotated with evaluations and move assessments (`?!`, `?`, `??`). Games are analyzed in parallel."
+    )]
+    Annotate {
+        #[structopt(help = "Path to the PGN file to annotate")]
+        pgn: String,
+        #[structopt(short, long, default_value = "3")]
+        depth: u8,
+        #[structopt(
+            short,
+            long,
+            help = "Write the annotated PGN to this file instead of stdout"
+        )]
+        output: Option<String>,
+    },
+    #[structopt(
+        name = "uci",
+        about = "Speak the Universal Chess Interface over stdin and stdout, so the engine can be used from chess GUIs and bots."
+    )]
+    Uci,
+    #[structopt(
+        name = "explain-move",
+        about = "Explain why the engine prefers its own move over a given move. Searches both moves in the position at the given `--depth` (default: 4), and prints their principal variations, scores, and the point where the lines diverge."
+    )]
+    ExplainMove {
+        #[structopt(help = "The position, in FEN")]
+        fen: String,
+        #[structopt(
+            name = "move",
+            help = "The move to explain, in algebraic notation (e.g. `Nf3`) or UCI (e.g. `g1f3`)"
+        )]
+        chess_move: String,
+        #[structopt(short, long, default_value = "4")]
+        depth: u8,
+        #[structopt(
+            long,
+            help = "Drop castling rights the position can't have, rather than rejecting the FEN"
+        )]
+        lenient: bool,
+    },
+    #[structopt(
+        name = "analyze",
+        about = "Analyze a position by searching it at every depth up to `--depth` (default: 5). Prints the best move and score at each depth, and a sparkline of each of the `--top` (default: 3) root moves' scores by depth, to show whether the evaluation is converging or still swinging."
+    )]
+    Analyze {
+        #[structopt(help = "The position, in FEN")]
+        fen: String,
+        #[structopt(short, long, default_value = "5")]
+        depth: u8,
+        #[structopt(short, long, default_value = "3")]
+        top: usize,
+        #[structopt(
+            long,
+            help = "Drop castling rights the position can't have, rather than rejecting the FEN"
+        )]
+        lenient: bool,
+    },
+    #[structopt(
+        name = "trace-search",
+        about = "Search a position once at the given `--depth` (default: 4) while timing move generation, evaluation, and cache probes, and write the timings to a file as a Chrome trace (`--format chrome`, the default, for chrome://tracing, Perfetto, or speedscope) or as folded stacks (`--format folded`, for flamegraph.pl or inferno-flamegraph)."
+    )]
+    TraceSearch {
+        #[structopt(help = "The position, in FEN")]
+        fen: String,
+        #[structopt(short, long, default_value = "4")]
+        depth: u8,
+        #[structopt(short, long, help = "The file to write the trace to")]
+        output: String,
+        #[structopt(short, long, default_value = "chrome")]
+        format: TraceFormat,
+        #[structopt(
+            long,
+            help = "Drop castling rights the position can't have, rather than rejecting the FEN"
+        )]
+        lenient: bool,
+    },
+    #[structopt(
+        name = "tune",
+        about = "Tune the evaluation's material values, piece placement bonuses, and pawn structure, minor piece, and tempo weights to a file of labeled positions, one per line: a FEN and its game's result (`1-0`, `1/2-1/2`, `0-1`, or white's score). The weights are fit by logistic regression, minimizing the error between each result and the expected score of the position's evaluation, and written to `--output` as JSON."
+    )]
+    Tune {
+        #[structopt(parse(from_os_str), help = "Path to the labeled positions")]
+        positions: PathBuf,
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "The file to write the tuned weights to"
+        )]
+        output: PathBuf,
+        #[structopt(short, long, default_value = "500")]
+        iterations: usize,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Start from the weights in this JSON file, rather than the built-in ones"
+        )]
+        weights: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "weights",
+        about = "Write the evaluation's built-in weights (material values, piece placement bonus tables, and pawn structure, minor piece, king safety, and tempo weights) to `--output` as JSON, to edit and load with `--weights` or the UCI `WeightsFile` option."
+    )]
+    Weights {
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "The file to write the weights to"
+        )]
+        output: PathBuf,
+    },
+    #[structopt(
+        name = "verify-eval",
+        about = "Check that the evaluation is symmetric: that each position, and every position one move from it, scores the same as its mirror image with the colors swapped, once the mirror's score is negated. Checks a built-in set of positions unless given some. Lists any position that fails, and exits with an error if one does."
+    )]
+    VerifyEval {
+        #[structopt(help = "Positions to check, in FEN")]
+        fens: Vec<String>,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Evaluate positions with the weights in this JSON file, as written by `weights` or `tune`, rather than the built-in ones"
+        )]
+        weights: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "normalize-positions",
+        about = "Normalize a file of positions, one FEN or EPD per line and optionally labeled with a result as `tune` reads them, to prepare it for tuning or benchmarking. Each position is written as a full FEN without the castling rights it can't have or an en passant target that can't be captured on, and positions that repeat an earlier one are dropped. With `--white-to-move`, positions with black to move are mirrored, with their results swapped to match."
+    )]
+    NormalizePositions {
+        #[structopt(parse(from_os_str), help = "Path to the positions")]
+        positions: PathBuf,
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "The file to write the normalized positions to, rather than stdout"
+        )]
+        output: Option<PathBuf>,
+        #[structopt(long, help = "Mirror positions with black to move")]
+        white_to_move: bool,
+    },
 }
 
+/// The optional cargo features only the CLI can be built with, and whether each
+/// was enabled in this build.
+const CLI_FEATURES: [(&str, bool); 3] = [
+    ("clipboard", cfg!(feature = "clipboard")),
+    ("opening-explorer", cfg!(feature = "opening-explorer")),
+    ("terminal", cfg!(feature = "terminal")),
+];
+
 fn main() {
     env_logger::init();
 
-    let args = Chess::from_args();
+    // `--version` also lists the optional features the binary was built with,
+    // the engine's and the CLI's own, and the version of its zobrist tables.
+    let mut info = EngineInfo::current();
+    info.features.extend(
+        CLI_FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| *feature),
+    );
+    info.features.sort_unstable();
+    let long_version = format!(
+        "{} (features: {}; zobrist tables: {:016x})",
+        info.version,
+        info.feature_list(),
+        info.zobrist_version
+    );
+    let args = Chess::from_clap(
+        &Chess::clap()
+            .long_version(long_version.as_str())
+            .get_matches(),
+    );
 
     match args {
         Chess::CountPositions { depth, strategy } => run_count_positions(depth, strategy),
-        Chess::Play { depth, color } => play_computer(depth, color),
-        Chess::Watch { depth } => computer_vs_computer(0, 1000, depth),
-        Chess::Pvp => player_vs_player(),
+        Chess::Play {
+            depth,
+            hash_mb,
+            color,
+            jitter,
+            seed,
+            sparring,
+            weights,
+            ponder,
+            verify_mates,
+            watchdog,
+            #[cfg(feature = "opening-explorer")]
+            opening_explorer,
+            auto_queen,
+            display_config,
+            output,
+            stats_file,
+        } => play_computer(
+            &GameOptions {
+                depth,
+                hash_mb,
+                root_jitter: root_jitter(jitter, seed),
+                weights: load_weights(weights).map(Arc::new),
+                verify_mates,
+                watchdog: watchdog_after(watchdog),
+                #[cfg(feature = "opening-explorer")]
+                opening_explorer,
+                display: load_display_config(display_config),
+                output,
+            },
+            color,
+            sparring,
+            ponder,
+            auto_queen,
+            stats_file,
+        ),
+        Chess::Watch {
+            depth,
+            hash_mb,
+            jitter,
+            seed,
+            verify_mates,
+            watchdog,
+            #[cfg(feature = "opening-explorer")]
+            opening_explorer,
+            weights,
+            display_config,
+            output,
+        } => computer_vs_computer(
+            0,
+            1000,
+            &GameOptions {
+                depth,
+                hash_mb,
+                root_jitter: root_jitter(jitter, seed),
+                weights: load_weights(weights).map(Arc::new),
+                verify_mates,
+                watchdog: watchdog_after(watchdog),
+                #[cfg(feature = "opening-explorer")]
+                opening_explorer,
+                display: load_display_config(display_config),
+                output,
+            },
+        ),
+        Chess::Pvp {
+            grade,
+            auto_queen,
+            display_config,
+        } => player_vs_player(grade, auto_queen, load_display_config(display_config)),
         Chess::DetermineStockfishElo {
             depth,
             starting_elo,
         } => determine_stockfish_elo(depth, starting_elo),
+        Chess::Tournament {
+            engines,
+            format,
+            rounds,
+            move_limit,
+            resign_threshold,
+            draw_agreement,
+            random_plies,
+            jitter,
+            pgn,
+        } => run_tournament(
+            engines,
+            format,
+            rounds,
+            move_limit,
+            EngineConfig {
+                resign_threshold,
+                draw_agreement: draw_agreement.map(|moves| DrawAgreement {
+                    min_ply: 80,
+                    max_score: 10,
+                    moves,
+                }),
+                move_noise: (random_plies.is_some() || jitter.is_some()).then(|| MoveNoise {
+                    random_plies: random_plies.unwrap_or(0),
+                    jitter: jitter.unwrap_or(0),
+                }),
+                ..Default::default()
+            },
+            pgn,
+        ),
+        Chess::Annotate { pgn, depth, output } => annotate(&pgn, depth, output),
+        Chess::Uci => run_uci(),
+        Chess::ExplainMove {
+            fen,
+            chess_move,
+            depth,
+            lenient,
+        } => {
+            let fen = position_fen(fen, lenient);
+            if let Err(error) = explain_move(&fen, &chess_move, depth) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Chess::Analyze {
+            fen,
+            depth,
+            top,
+            lenient,
+        } => {
+            let fen = position_fen(fen, lenient);
+            if let Err(error) = analyze(&fen, depth, top) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Chess::TraceSearch {
+            fen,
+            depth,
+            output,
+            format,
+            lenient,
+        } => {
+            let fen = position_fen(fen, lenient);
+            if let Err(error) = trace_search(&fen, depth, &output, format) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Chess::Tune {
+            positions,
+            output,
+            iterations,
+            weights,
+        } => run_tune(&positions, &output, iterations, weights),
+        Chess::Weights { output } => {
+            if let Err(error) = EvaluationWeights::default().save(&output) {
+                eprintln!("error: could not write {}: {}", output.display(), error);
+                std::process::exit(1);
+            }
+        }
+        Chess::VerifyEval { fens, weights } => run_verify_eval(fens, weights),
+        Chess::NormalizePositions {
+            positions,
+            output,
+            white_to_move,
+        } => run_normalize_positions(&positions, output, white_to_move),
+    }
+}
+
+/// Loads the weights at `path`, if given, exiting with an error if they can't
+/// be read.
+fn load_weights(path: Option<PathBuf>) -> Option<EvaluationWeights> {
+    let path = path?;
+    match EvaluationWeights::load(&path) {
+        Ok(weights) => Some(weights),
+        Err(error) => {
+            eprintln!("error: could not load {}: {}", path.display(), error);
+            std::process::exit(1);
+        }
     }
 }
+
+/// Loads the display configuration at `path`, if given, exiting with an error
+/// if it can't be read.
+fn load_display_config(path: Option<PathBuf>) -> DisplayConfig {
+    let path = match path {
+        Some(path) => path,
+        None => return DisplayConfig::default(),
+    };
+    match DisplayConfig::load(&path) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("error: could not load {}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_tune(
+    positions: &std::path::Path,
+    output: &std::path::Path,
+    iterations: usize,
+    weights: Option<PathBuf>,
+) {
+    let initial = load_weights(weights).unwrap_or_default();
+    let report = match load_positions(positions)
+        .and_then(|positions| tune(&positions, &initial, iterations))
+    {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "mean squared error: {:.6} -> {:.6}",
+        report.initial_error, report.final_error
+    );
+    if let Err(error) = report.weights.save(output) {
+        eprintln!("error: could not write {}: {}", output.display(), error);
+        std::process::exit(1);
+    }
+}
+
+fn run_verify_eval(fens: Vec<String>, weights: Option<PathBuf>) {
+    let fens = if fens.is_empty() {
+        SYMMETRY_POSITIONS
+            .iter()
+            .map(|fen| fen.to_string())
+            .collect()
+    } else {
+        fens
+    };
+    let boards: Vec<Board> = fens
+        .iter()
+        .map(|fen| {
+            Board::from_fen(fen).unwrap_or_else(|error| {
+                eprintln!("error: invalid fen {:?}: {}", fen, error);
+                std::process::exit(1);
+            })
+        })
+        .collect();
+    let terms = EvaluationTerms {
+        weights: Arc::new(load_weights(weights).unwrap_or_default()),
+        ..Default::default()
+    };
+
+    let (checked, asymmetries) = verify_symmetry(&boards, &terms);
+    for asymmetry in &asymmetries {
+        println!(
+            "{}: scores {}, but its mirror scores {}",
+            asymmetry.fen, asymmetry.score, asymmetry.mirrored_score
+        );
+    }
+    println!(
+        "{} of {} positions evaluated asymmetrically",
+        asymmetries.len(),
+        checked
+    );
+    if !asymmetries.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn run_normalize_positions(
+    positions: &std::path::Path,
+    output: Option<PathBuf>,
+    white_to_move: bool,
+) {
+    let contents = match std::fs::read_to_string(positions) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("error: could not read {}: {}", positions.display(), error);
+            std::process::exit(1);
+        }
+    };
+    let corpus = normalize_corpus(&contents, white_to_move);
+    for (line, error) in &corpus.invalid {
+        eprintln!("warning: skipping line {}: {}", line, error);
+    }
+    eprintln!(
+        "{} positions read, {} duplicates and {} invalid dropped, {} written",
+        corpus.read,
+        corpus.duplicates,
+        corpus.invalid.len(),
+        corpus.lines.len()
+    );
+    let normalized: String = corpus
+        .lines
+        .iter()
+        .map(|line| line.clone() + "\n")
+        .collect();
+    match output {
+        Some(path) => {
+            if let Err(error) = std::fs::write(&path, normalized) {
+                eprintln!("error: could not write {}: {}", path.display(), error);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", normalized),
+    }
+}
+
+/// With `--lenient`, rewrites `fen` without the castling rights its position
+/// can't have. Any other problem with it is left for the command to report.
+fn position_fen(fen: String, lenient: bool) -> String {
+    match Board::from_fen_lenient(&fen) {
+        Ok(board) if lenient => board.to_fen(),
+        _ => fen,
+    }
+}
+
+fn annotate(pgn: &str, depth: u8, output: Option<String>) {
+    let annotated = match annotate_pgn_file(pgn, depth) {
+        Ok(annotated) => annotated,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(error) = std::fs::write(&path, annotated) {
+                eprintln!("error: could not write {}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", annotated),
+    }
+}
+
+fn root_jitter(jitter: Option<i16>, seed: Option<u64>) -> Option<RootJitter> {
+    jitter.map(|window| RootJitter::new(window, seed))
+}
+
+/// A watchdog for searches to a fixed depth, which have no budget of their own,
+/// that treats `seconds` as their budget.
+fn watchdog_after(seconds: Option<u64>) -> Option<Watchdog> {
+    seconds.map(|seconds| Watchdog {
+        default_budget: Some(Duration::from_secs(seconds)),
+        ..Default::default()
+    })
+}
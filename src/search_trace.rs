@@ -0,0 +1,260 @@
+//! Records how long the search spends in each of its phases, so that a single
+//! search can be profiled without an external profiler. The engine marks each
+//! phase with a `tracing` span, which costs next to nothing when no subscriber
+//! is listening. While a search is being recorded, a subscriber notes every
+//! span when it ends, and the recording can be written out as a Chrome trace
+//! (for `chrome://tracing`, Perfetto, or speedscope) or as folded stacks (for
+//! `flamegraph.pl` or `inferno-flamegraph`).
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::span::Id;
+use tracing::{Dispatch, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+/// A span that has ended.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanRecord {
+    pub name: &'static str,
+    /// Numbers the threads in the order they first recorded a span.
+    pub thread: u64,
+    /// When the span started, relative to the start of the recording.
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+#[derive(Default)]
+struct Recording {
+    threads: Vec<ThreadId>,
+    spans: Vec<SpanRecord>,
+}
+
+/// A subscriber layer that records every span as it ends.
+#[derive(Clone)]
+struct SpanRecorder {
+    started_at: Instant,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl<S> Layer<S> for SpanRecorder
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let started_at = match span.extensions_mut().remove::<Instant>() {
+            Some(started_at) => started_at,
+            None => return,
+        };
+        let duration = started_at.elapsed();
+        let thread_id = thread::current().id();
+
+        let mut recording = self.recording.lock().unwrap();
+        let thread = match recording.threads.iter().position(|id| *id == thread_id) {
+            Some(index) => index,
+            None => {
+                recording.threads.push(thread_id);
+                recording.threads.len() - 1
+            }
+        };
+        recording.spans.push(SpanRecord {
+            name: span.name(),
+            thread: thread as u64 + 1,
+            start: started_at.saturating_duration_since(self.started_at),
+            duration,
+        });
+    }
+}
+
+/// Runs `f` with a subscriber that records the spans it enters, on its own
+/// thread and on any threads it searches with, and returns its result along
+/// with the spans, ordered by thread and start time, with enclosing spans
+/// before the spans they enclose.
+pub fn record<T: Send>(f: impl FnOnce() -> T + Send) -> (T, Vec<SpanRecord>) {
+    let recorder = SpanRecorder {
+        started_at: Instant::now(),
+        recording: Arc::default(),
+    };
+    let dispatch = Dispatch::new(Registry::default().with(recorder.clone()));
+    let result = with_dispatch(&dispatch, f);
+
+    let mut spans = std::mem::take(&mut recorder.recording.lock().unwrap().spans);
+    spans.sort_by_key(|span| (span.thread, span.start, std::cmp::Reverse(span.duration)));
+    (result, spans)
+}
+
+/// Runs `f` with `dispatch` as the subscriber. The search scores root moves on
+/// rayon's threads, so it runs in a thread pool of its own whose threads all
+/// use the subscriber too.
+#[cfg(feature = "parallel")]
+fn with_dispatch<T: Send>(dispatch: &Dispatch, f: impl FnOnce() -> T + Send) -> T {
+    let worker_dispatch = dispatch.clone();
+    let pool = rayon::ThreadPoolBuilder::new()
+        .start_handler(move |_| {
+            // The pool's threads only end with the pool, so the subscriber
+            // stays set for as long as they run.
+            std::mem::forget(tracing::dispatcher::set_default(&worker_dispatch));
+        })
+        .build()
+        .expect("a thread pool for the recorded search");
+    pool.install(f)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn with_dispatch<T: Send>(dispatch: &Dispatch, f: impl FnOnce() -> T + Send) -> T {
+    tracing::dispatcher::with_default(dispatch, f)
+}
+
+#[derive(Serialize)]
+struct ChromeTrace<'a> {
+    #[serde(rename = "traceEvents")]
+    trace_events: Vec<ChromeTraceEvent<'a>>,
+}
+
+#[derive(Serialize)]
+struct ChromeTraceEvent<'a> {
+    name: &'a str,
+    ph: &'a str,
+    ts: f64,
+    dur: f64,
+    pid: u32,
+    tid: u64,
+}
+
+/// Formats the spans in the Chrome trace event format, as complete events.
+pub fn chrome_trace(spans: &[SpanRecord]) -> String {
+    let trace = ChromeTrace {
+        trace_events: spans
+            .iter()
+            .map(|span| ChromeTraceEvent {
+                name: span.name,
+                ph: "X",
+                ts: span.start.as_secs_f64() * 1e6,
+                dur: span.duration.as_secs_f64() * 1e6,
+                pid: 1,
+                tid: span.thread,
+            })
+            .collect(),
+    };
+    serde_json::to_string(&trace).unwrap()
+}
+
+/// Formats the spans as folded stacks: one line per distinct stack of nested
+/// spans, with the time (in microseconds) spent in the innermost span itself,
+/// e.g. `search;eval;movegen 1200`. Spans are nested by the time they cover on
+/// the same thread, so `spans` must be ordered as `record` orders them.
+pub fn folded_stacks(spans: &[SpanRecord]) -> String {
+    let mut self_times: BTreeMap<String, Duration> = BTreeMap::new();
+    // The enclosing spans of the current one, as (stack, end, self time).
+    let mut open: Vec<(String, Duration, Duration)> = Vec::new();
+    let mut thread = None;
+
+    let close = |open: &mut Vec<(String, Duration, Duration)>,
+                 self_times: &mut BTreeMap<String, Duration>| {
+        let (stack, _, self_time) = open.pop().unwrap();
+        *self_times.entry(stack).or_default() += self_time;
+    };
+
+    for span in spans {
+        if thread != Some(span.thread) {
+            while !open.is_empty() {
+                close(&mut open, &mut self_times);
+            }
+            thread = Some(span.thread);
+        }
+        while open.last().is_some_and(|(_, end, _)| *end <= span.start) {
+            close(&mut open, &mut self_times);
+        }
+        let stack = match open.last_mut() {
+            Some((parent, _, parent_self_time)) => {
+                *parent_self_time = parent_self_time.saturating_sub(span.duration);
+                format!("{};{}", parent, span.name)
+            }
+            None => span.name.to_string(),
+        };
+        open.push((stack, span.start + span.duration, span.duration));
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut self_times);
+    }
+
+    self_times
+        .iter()
+        .map(|(stack, self_time)| format!("{} {}\n", stack, self_time.as_micros()))
+        .collect()
+}
+
+/// The total time spent in each kind of span, excluding the spans nested in it,
+/// from most to least.
+pub fn self_time_by_name(spans: &[SpanRecord]) -> Vec<(String, Duration)> {
+    let mut totals: BTreeMap<String, u128> = BTreeMap::new();
+    for line in folded_stacks(spans).lines() {
+        let (stack, micros) = line.rsplit_once(' ').unwrap();
+        let name = stack.rsplit(';').next().unwrap();
+        *totals.entry(name.to_string()).or_default() += micros.parse::<u128>().unwrap();
+    }
+    let mut totals: Vec<(String, Duration)> = totals
+        .into_iter()
+        .map(|(name, micros)| (name, Duration::from_micros(micros as u64)))
+        .collect();
+    totals.sort_by(|(_, a), (_, b)| b.cmp(a));
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &'static str, start: u64, duration: u64) -> SpanRecord {
+        SpanRecord {
+            name,
+            thread: 1,
+            start: Duration::from_micros(start),
+            duration: Duration::from_micros(duration),
+        }
+    }
+
+    #[test]
+    fn test_folded_stacks() {
+        let spans = vec![
+            record("search", 0, 100),
+            record("movegen", 10, 20),
+            record("eval", 40, 50),
+            record("movegen", 50, 30),
+            record("search", 200, 10),
+        ];
+        assert_eq!(
+            folded_stacks(&spans),
+            "search 40\nsearch;eval 20\nsearch;eval;movegen 30\nsearch;movegen 20\n"
+        );
+        assert_eq!(
+            self_time_by_name(&spans),
+            vec![
+                ("movegen".to_string(), Duration::from_micros(50)),
+                ("search".to_string(), Duration::from_micros(40)),
+                ("eval".to_string(), Duration::from_micros(20)),
+            ]
+        );
+
+        let trace = chrome_trace(&spans[..1]);
+        assert_eq!(
+            trace,
+            r#"{"traceEvents":[{"name":"search","ph":"X","ts":0.0,"dur":100.0,"pid":1,"tid":1}]}"#
+        );
+    }
+}
@@ -0,0 +1,840 @@
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::contempt::contempt_for_ratings;
+use crate::alpha_beta_searcher::depth_boost::DepthBoost;
+use crate::alpha_beta_searcher::search_control::SearchControl;
+use crate::alpha_beta_searcher::transposition_table::{DEFAULT_HASH_MB, MAX_HASH_MB};
+use crate::alpha_beta_searcher::watchdog::Watchdog;
+use crate::alpha_beta_searcher::SearchError;
+use crate::board::color::Color;
+use crate::board::fen::{FenError, STARTING_POSITION_FEN};
+use crate::board::Board;
+use crate::engine_info::EngineInfo;
+#[cfg(feature = "nnue")]
+use crate::evaluate::nnue::{load_network, Network};
+use crate::evaluate::weights::EvaluationWeights;
+use crate::evaluate::{mate_in, EvaluationTerms};
+use crate::game::engine_config::EngineConfig;
+use crate::game::game::{Game, GameError};
+
+const DEFAULT_DEPTH: u8 = 4;
+const MAX_DEPTH: u8 = 20;
+/// The engine's rating, unless it's set with the `Rating` option, which
+/// contempt is worked out against once the opponent's rating is known.
+const DEFAULT_RATING: u32 = 2000;
+const MAX_RATING: u32 = 4000;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum UciError {
+    #[error("unknown command: {command:?}")]
+    UnknownCommand { command: String },
+    #[error("invalid {name} value: {value:?}")]
+    InvalidValue { name: &'static str, value: String },
+    #[error("invalid fen: {error}")]
+    FenError { error: FenError },
+    #[error("illegal move: {chess_move:?}")]
+    IllegalMove { chess_move: String },
+    #[error("could not load weights: {error}")]
+    WeightsError { error: String },
+    #[cfg(feature = "nnue")]
+    #[error("{error}")]
+    NnueError { error: String },
+}
+
+/// A command sent from the GUI to the engine.
+#[derive(Debug, PartialEq)]
+pub enum UciCommand {
+    Uci,
+    IsReady,
+    UciNewGame,
+    SetOption { name: String, value: Option<String> },
+    Position { fen: String, moves: Vec<String> },
+    Go(GoOptions),
+    PonderHit,
+    Stop,
+    Quit,
+}
+
+/// The search limits sent with `go`. When the GUI sends a clock, the engine
+/// deepens its search until the time it allots for the move runs out.
+#[derive(Debug, Default, PartialEq)]
+pub struct GoOptions {
+    pub depth: Option<u8>,
+    pub movetime: Option<u64>,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    /// Search until `stop`, however long that takes, and only then report the
+    /// best move.
+    pub infinite: bool,
+    /// Search on the opponent's time, after the move the engine predicted for
+    /// them, until `ponderhit` or `stop`.
+    pub ponder: bool,
+}
+
+impl GoOptions {
+    /// How long the engine may spend on this move, if the GUI imposed a limit.
+    /// A share of the clock is allocated according to the game's phase.
+    fn budget(&self, game: &Game) -> Option<Duration> {
+        let (time, increment) = match game.board().turn() {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        };
+        let from_clock = time.map(|time| {
+            game.engine_config().time_allocation.budget(
+                game.phase(),
+                Duration::from_millis(time),
+                Duration::from_millis(increment.unwrap_or(0)),
+            )
+        });
+        self.movetime.map(Duration::from_millis).or(from_clock)
+    }
+}
+
+pub fn parse_command(line: &str) -> Result<UciCommand, UciError> {
+    let mut tokens = line.split_whitespace();
+    let command = tokens.next().unwrap_or_default();
+    let rest: Vec<&str> = tokens.collect();
+
+    match command {
+        "uci" => Ok(UciCommand::Uci),
+        "isready" => Ok(UciCommand::IsReady),
+        "ucinewgame" => Ok(UciCommand::UciNewGame),
+        "ponderhit" => Ok(UciCommand::PonderHit),
+        "stop" => Ok(UciCommand::Stop),
+        "quit" => Ok(UciCommand::Quit),
+        "setoption" => parse_setoption(&rest),
+        "position" => parse_position(&rest),
+        "go" => parse_go(&rest).map(UciCommand::Go),
+        _ => Err(UciError::UnknownCommand {
+            command: line.to_string(),
+        }),
+    }
+}
+
+fn parse_setoption(tokens: &[&str]) -> Result<UciCommand, UciError> {
+    // setoption name <id> [value <x>], where both may contain spaces.
+    let value_index = tokens.iter().position(|&token| token == "value");
+    let name_end = value_index.unwrap_or(tokens.len());
+    let name = tokens
+        .get(1..name_end)
+        .filter(|_| tokens.first() == Some(&"name"))
+        .ok_or(UciError::InvalidValue {
+            name: "setoption",
+            value: tokens.join(" "),
+        })?
+        .join(" ");
+    let value = value_index.map(|i| tokens[i + 1..].join(" "));
+    Ok(UciCommand::SetOption { name, value })
+}
+
+fn parse_position(tokens: &[&str]) -> Result<UciCommand, UciError> {
+    let moves_index = tokens
+        .iter()
+        .position(|&token| token == "moves")
+        .unwrap_or(tokens.len());
+    let fen = match tokens.first() {
+        Some(&"startpos") => STARTING_POSITION_FEN.to_string(),
+        Some(&"fen") => tokens[1..moves_index].join(" "),
+        _ => {
+            return Err(UciError::InvalidValue {
+                name: "position",
+                value: tokens.join(" "),
+            })
+        }
+    };
+    let moves = tokens
+        .iter()
+        .skip(moves_index + 1)
+        .map(|chess_move| chess_move.to_string())
+        .collect();
+    Ok(UciCommand::Position { fen, moves })
+}
+
+fn parse_go(tokens: &[&str]) -> Result<GoOptions, UciError> {
+    let mut options = GoOptions::default();
+    let mut tokens = tokens.iter();
+    while let Some(&token) = tokens.next() {
+        if token == "infinite" {
+            options.infinite = true;
+            continue;
+        }
+        if token == "ponder" {
+            options.ponder = true;
+            continue;
+        }
+        let (name, field) = match token {
+            "movetime" => ("movetime", &mut options.movetime),
+            "wtime" => ("wtime", &mut options.wtime),
+            "btime" => ("btime", &mut options.btime),
+            "winc" => ("winc", &mut options.winc),
+            "binc" => ("binc", &mut options.binc),
+            "depth" => {
+                options.depth = Some(parse_value("depth", tokens.next())?);
+                continue;
+            }
+            // Ignore limits the engine doesn't support (e.g. `nodes`, `movestogo`).
+            _ => continue,
+        };
+        *field = Some(parse_value(name, tokens.next())?);
+    }
+    Ok(options)
+}
+
+fn parse_value<T: std::str::FromStr>(
+    name: &'static str,
+    value: Option<&&str>,
+) -> Result<T, UciError> {
+    let value = value.copied().unwrap_or_default();
+    value.parse().map_err(|_| UciError::InvalidValue {
+        name,
+        value: value.to_string(),
+    })
+}
+
+/// The opponent's rating from a `UCI_Opponent` value, which GUIs and bot
+/// clients such as lichess-bot send as `<title> <rating> <computer|human>
+/// <name>`, with `none` for a missing title or rating.
+fn parse_opponent_rating(value: &str) -> Option<u32> {
+    value.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Builds the game for a `position` command, replaying the moves (in UCI
+/// notation) from the given position.
+pub fn game_from_position(fen: &str, moves: &[String], depth: u8) -> Result<Game, UciError> {
+    let board = Board::from_fen(fen).map_err(|error| UciError::FenError { error })?;
+    let mut game = Game::from_board(board, depth);
+    replay_moves(&mut game, moves)?;
+    Ok(game)
+}
+
+/// Plays the moves (in UCI notation) in the game.
+fn replay_moves(game: &mut Game, moves: &[String]) -> Result<(), UciError> {
+    for uci in moves {
+        let enumerated_candidate_moves = game.enumerated_candidate_moves();
+        let chess_move = enumerated_candidate_moves
+            .into_iter()
+            .map(|(chess_move, _)| chess_move)
+            .find(|chess_move| chess_move.to_uci() == *uci)
+            .ok_or(UciError::IllegalMove {
+                chess_move: uci.clone(),
+            })?;
+        game.apply_chess_move(chess_move)
+            .map_err(|_| UciError::IllegalMove {
+                chess_move: uci.clone(),
+            })?;
+        game.board_mut().toggle_turn();
+    }
+    Ok(())
+}
+
+/// The engine side of a UCI session. Searches run on a background thread so
+/// that the engine can keep answering `isready` while it thinks, and be told to
+/// stop.
+pub struct UciEngine {
+    depth: u8,
+    /// The most memory, in megabytes, for each game's transposition table.
+    hash_mb: usize,
+    /// The game, which is kept from one `position` to the next so that the
+    /// engine's search state carries over between moves, and is reset by
+    /// `ucinewgame`.
+    game: Option<Game>,
+    /// Whether an option the game is built with has changed since it was
+    /// built, so that the next `position` builds a new one.
+    options_changed: bool,
+    search: Option<JoinHandle<Game>>,
+    control: SearchControl,
+    /// Whether the search is pondering, waiting for `ponderhit` or `stop`.
+    pondering: bool,
+    /// Whether the search is infinite, waiting for `stop` before it reports
+    /// its move.
+    infinite: bool,
+    /// The weights set with the `WeightsFile` option, which the hand-crafted
+    /// evaluation uses in place of the built-in ones.
+    weights: Option<Arc<EvaluationWeights>>,
+    /// The network set with the `EvalFile` option, which evaluates positions in
+    /// place of the hand-crafted evaluation.
+    #[cfg(feature = "nnue")]
+    network: Option<Arc<Network>>,
+    /// The engine's rating, set with the `Rating` option.
+    rating: u32,
+    /// The opponent's rating, if `UCI_Opponent` gave one. The engine presses
+    /// for a win against a weaker opponent and plays solidly against a stronger
+    /// one (see `contempt_for_ratings`).
+    opponent_rating: Option<u32>,
+}
+
+impl Default for UciEngine {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_DEPTH,
+            hash_mb: DEFAULT_HASH_MB,
+            game: None,
+            options_changed: false,
+            search: None,
+            control: SearchControl::new(),
+            pondering: false,
+            infinite: false,
+            weights: None,
+            #[cfg(feature = "nnue")]
+            network: None,
+            rating: DEFAULT_RATING,
+            opponent_rating: None,
+        }
+    }
+}
+
+impl UciEngine {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Handles a command. Returns false once the session should end.
+    pub fn handle(&mut self, command: UciCommand) -> Result<bool, UciError> {
+        match command {
+            UciCommand::Uci => {
+                let info = EngineInfo::current();
+                println!("id name {}", info.full_name());
+                println!("id author {}", info.author());
+                println!(
+                    "option name Depth type spin default {} min 1 max {}",
+                    DEFAULT_DEPTH, MAX_DEPTH
+                );
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MB, MAX_HASH_MB
+                );
+                println!("option name Ponder type check default false");
+                println!(
+                    "option name Rating type spin default {} min 0 max {}",
+                    DEFAULT_RATING, MAX_RATING
+                );
+                println!("option name UCI_Opponent type string default <empty>");
+                println!("option name WeightsFile type string default <empty>");
+                #[cfg(feature = "nnue")]
+                println!("option name EvalFile type string default <empty>");
+                println!("uciok");
+            }
+            UciCommand::IsReady => println!("readyok"),
+            UciCommand::UciNewGame => {
+                self.wait_for_search();
+                if let Some(game) = &mut self.game {
+                    game.new_game(Board::starting_position());
+                    #[cfg(feature = "nnue")]
+                    game.board_mut().set_nnue(self.network.clone());
+                }
+            }
+            UciCommand::SetOption { name, value } => {
+                if name.eq_ignore_ascii_case("depth") {
+                    let value = value.unwrap_or_default();
+                    self.depth = match value.parse() {
+                        Ok(depth @ 1..=MAX_DEPTH) => depth,
+                        _ => {
+                            return Err(UciError::InvalidValue {
+                                name: "Depth",
+                                value,
+                            })
+                        }
+                    };
+                } else if name.eq_ignore_ascii_case("hash") {
+                    let value = value.unwrap_or_default();
+                    self.hash_mb = match value.parse() {
+                        Ok(hash_mb @ 1..=MAX_HASH_MB) => hash_mb,
+                        _ => {
+                            return Err(UciError::InvalidValue {
+                                name: "Hash",
+                                value,
+                            })
+                        }
+                    };
+                    self.options_changed = true;
+                } else if name.eq_ignore_ascii_case("rating") {
+                    let value = value.unwrap_or_default();
+                    self.rating = match value.parse() {
+                        Ok(rating @ 0..=MAX_RATING) => rating,
+                        _ => {
+                            return Err(UciError::InvalidValue {
+                                name: "Rating",
+                                value,
+                            })
+                        }
+                    };
+                } else if name.eq_ignore_ascii_case("uci_opponent") {
+                    self.opponent_rating = parse_opponent_rating(&value.unwrap_or_default());
+                } else if name.eq_ignore_ascii_case("weightsfile") {
+                    self.set_weights_file(value.unwrap_or_default())?;
+                    self.options_changed = true;
+                } else if name.eq_ignore_ascii_case("evalfile") {
+                    #[cfg(feature = "nnue")]
+                    self.set_eval_file(value.unwrap_or_default())?;
+                }
+            }
+            UciCommand::Position { fen, moves } => {
+                self.wait_for_search();
+                self.set_position(&fen, &moves)?;
+            }
+            UciCommand::Go(options) => {
+                self.wait_for_search();
+                let mut game = match self.game.take() {
+                    Some(game) => game,
+                    None => self.new_game(STARTING_POSITION_FEN, &[])?,
+                };
+                self.control = game.reset_search_control();
+                game.set_contempt(self.opponent_rating.map_or(0, |opponent_rating| {
+                    contempt_for_ratings(self.rating, opponent_rating)
+                }));
+                self.pondering = options.ponder;
+                self.infinite = options.infinite;
+                self.search = Some(spawn_search(game, options, self.depth));
+            }
+            UciCommand::PonderHit if self.pondering => {
+                self.pondering = false;
+                self.control.ponderhit();
+            }
+            UciCommand::PonderHit => (),
+            // The best move found so far is still reported, as the protocol
+            // requires.
+            UciCommand::Stop => {
+                self.control.stop();
+                self.wait_for_search();
+            }
+            UciCommand::Quit => {
+                self.control.stop();
+                self.wait_for_search();
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Loads the evaluation weights at `path`, or goes back to the built-in
+    /// weights given `<empty>` or nothing.
+    fn set_weights_file(&mut self, path: String) -> Result<(), UciError> {
+        self.weights = match path.trim() {
+            "" | "<empty>" => None,
+            path => {
+                let weights = EvaluationWeights::load(Path::new(path)).map_err(|error| {
+                    UciError::WeightsError {
+                        error: error.to_string(),
+                    }
+                })?;
+                Some(Arc::new(weights))
+            }
+        };
+        Ok(())
+    }
+
+    /// Loads the network at `path` to evaluate positions with, or goes back to
+    /// the hand-crafted evaluation given `<empty>` or nothing.
+    #[cfg(feature = "nnue")]
+    fn set_eval_file(&mut self, path: String) -> Result<(), UciError> {
+        self.network = match path.trim() {
+            "" | "<empty>" => None,
+            path => {
+                let network =
+                    load_network(Path::new(path)).map_err(|error| UciError::NnueError {
+                        error: error.to_string(),
+                    })?;
+                Some(Arc::new(network))
+            }
+        };
+        Ok(())
+    }
+
+    /// Sets up the position in the current game, or in a new one if there is
+    /// none yet or its options have changed.
+    fn set_position(&mut self, fen: &str, moves: &[String]) -> Result<(), UciError> {
+        let mut game = match self.game.take() {
+            Some(game) if !self.options_changed => game,
+            _ => {
+                self.options_changed = false;
+                self.game = Some(self.new_game(fen, moves)?);
+                return Ok(());
+            }
+        };
+        game.set_position(Board::from_fen(fen).map_err(|error| UciError::FenError { error })?);
+        #[cfg(feature = "nnue")]
+        game.board_mut().set_nnue(self.network.clone());
+        replay_moves(&mut game, moves)?;
+        self.game = Some(game);
+        Ok(())
+    }
+
+    fn new_game(&self, fen: &str, moves: &[String]) -> Result<Game, UciError> {
+        let mut game = game_from_position(fen, moves, self.depth)?;
+        game.set_hash_size(self.hash_mb);
+        if let Some(weights) = &self.weights {
+            game.set_evaluation_terms(EvaluationTerms {
+                weights: weights.clone(),
+                ..Default::default()
+            });
+        }
+        #[cfg(feature = "nnue")]
+        game.board_mut().set_nnue(self.network.clone());
+        // A search that blows up shouldn't leave the GUI waiting on it forever.
+        game.set_watchdog(Some(Watchdog::default()));
+        Ok(game)
+    }
+
+    fn wait_for_search(&mut self) {
+        // Ponder and infinite searches wait for `ponderhit` or `stop` before
+        // they report their move, so they're stopped rather than waited on
+        // forever.
+        if self.pondering || self.infinite {
+            self.pondering = false;
+            self.infinite = false;
+            self.control.stop();
+        }
+        if let Some(search) = self.search.take() {
+            self.game = search.join().ok();
+        }
+    }
+}
+
+fn spawn_search(mut game: Game, options: GoOptions, default_depth: u8) -> JoinHandle<Game> {
+    thread::spawn(move || {
+        // An infinite search ignores the clock, and deepens until it's stopped.
+        let budget = match options.infinite {
+            true => None,
+            false => options.budget(&game),
+        };
+        let depth = match (options.depth, budget) {
+            (Some(depth), _) => depth,
+            (None, _) if options.infinite => MAX_DEPTH,
+            (None, Some(_)) => MAX_DEPTH,
+            (None, None) => default_depth,
+        };
+        game.set_search_depth(depth);
+        // A depth the GUI asks for is searched to exactly.
+        game.set_engine_config(EngineConfig {
+            depth_boost: options.depth.is_none().then(DepthBoost::default),
+            ..*game.engine_config()
+        });
+        game.time_manager_mut().set_budget(budget);
+        game.time_manager_mut()
+            .set_fixed_budget(options.movetime.is_some());
+        if options.ponder {
+            game.time_manager_mut().start_pondering();
+        }
+        game.time_manager_mut().start();
+
+        let result = game.select_alpha_beta_best_move();
+        if options.ponder {
+            game.search_control().wait_for_ponderhit_or_stop();
+        }
+        if options.infinite {
+            game.search_control().wait_for_stop();
+        }
+        // A search stopped before it finished its first iteration has no best
+        // move, but the protocol still requires one.
+        let result = match result {
+            Err(GameError::SearchError {
+                error: SearchError::Stopped,
+            }) => game
+                .enumerated_candidate_moves()
+                .into_iter()
+                .map(|(chess_move, _)| chess_move)
+                .next()
+                .ok_or(GameError::InvalidMove),
+            result => result,
+        };
+        match result {
+            Ok(best_move) => {
+                if !game.depth_stats().is_empty() {
+                    println!("{}", info_line(&game, &best_move.to_uci()));
+                }
+                match game.predict_reply_to(&best_move) {
+                    Some(reply) => {
+                        println!("bestmove {} ponder {}", best_move.to_uci(), reply.to_uci())
+                    }
+                    None => println!("bestmove {}", best_move.to_uci()),
+                }
+            }
+            Err(error) => {
+                println!("info string {}", error);
+                game.report_error(&error);
+                println!("bestmove 0000");
+            }
+        }
+        game
+    })
+}
+
+/// Summarizes a completed search. Scores are reported from the side to move's
+/// perspective, as the protocol requires.
+fn info_line(game: &Game, pv: &str) -> String {
+    let depth = game
+        .depth_stats()
+        .last()
+        .map_or(game.search_depth(), |stats| stats.depth);
+    let score = game.alpha_beta_score().unwrap_or_default();
+    let score = match game.board().turn() {
+        Color::White => score,
+        Color::Black => -score,
+    };
+    let score = match mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    };
+    let nodes = game.searched_position_count();
+    let elapsed = game.time_manager().elapsed();
+    let nps = (nodes as f64 / elapsed.as_secs_f64().max(0.001)) as u64;
+    let seldepth = game
+        .depth_stats()
+        .last()
+        .map_or(depth, |stats| stats.selective_depth);
+    // The protocol reports how full the hash is in permille.
+    let hashfull = (game.hash_fill_percentage() * 10.0) as u32;
+    format!(
+        "info depth {} seldepth {} score {} nodes {} time {} nps {} hashfull {} pv {}",
+        depth,
+        seldepth,
+        score,
+        nodes,
+        elapsed.as_millis(),
+        nps,
+        hashfull,
+        pv
+    )
+}
+
+/// Speaks UCI over stdin and stdout until the GUI sends `quit`.
+pub fn run_uci() {
+    let mut engine = UciEngine::new();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_command(&line).and_then(|command| engine.handle(command)) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(error) => println!("info string {}", error),
+        }
+    }
+    engine.wait_for_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(
+            parse_command("position startpos moves e2e4 e7e5"),
+            Ok(UciCommand::Position {
+                fen: STARTING_POSITION_FEN.to_string(),
+                moves: vec!["e2e4".to_string(), "e7e5".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_command("position fen 8/8/8/8/8/8/8/K6k w - - 0 1"),
+            Ok(UciCommand::Position {
+                fen: "8/8/8/8/8/8/8/K6k w - - 0 1".to_string(),
+                moves: vec![],
+            })
+        );
+        assert!(parse_command("position").is_err());
+    }
+
+    #[test]
+    fn test_parse_opponent_rating() {
+        assert_eq!(parse_opponent_rating("GM 2800 human Magnus"), Some(2800));
+        assert_eq!(
+            parse_opponent_rating("none 1850 computer some-bot"),
+            Some(1850)
+        );
+        assert_eq!(parse_opponent_rating("none none computer Stockfish"), None);
+        assert_eq!(parse_opponent_rating(""), None);
+    }
+
+    #[test]
+    fn test_parse_go() {
+        assert_eq!(
+            parse_command("go wtime 60000 btime 50000 winc 1000 binc 1000 movestogo 20"),
+            Ok(UciCommand::Go(GoOptions {
+                wtime: Some(60000),
+                btime: Some(50000),
+                winc: Some(1000),
+                binc: Some(1000),
+                ..Default::default()
+            }))
+        );
+        let options = match parse_command("go depth 6 movetime 2000").unwrap() {
+            UciCommand::Go(options) => options,
+            command => panic!("unexpected command: {:?}", command),
+        };
+        assert_eq!(options.depth, Some(6));
+        let game = Game::new(1);
+        assert_eq!(options.budget(&game), Some(Duration::from_secs(2)));
+        let options = match parse_command("go wtime 60000 winc 1000").unwrap() {
+            UciCommand::Go(options) => options,
+            command => panic!("unexpected command: {:?}", command),
+        };
+        // 1/40th of the clock in the opening, plus the increment.
+        assert_eq!(options.budget(&game), Some(Duration::from_millis(2500)));
+        assert!(parse_command("go depth x").is_err());
+
+        assert_eq!(
+            parse_command("go ponder movetime 500"),
+            Ok(UciCommand::Go(GoOptions {
+                movetime: Some(500),
+                ponder: true,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(parse_command("ponderhit"), Ok(UciCommand::PonderHit));
+    }
+
+    #[test]
+    fn test_go_infinite_waits_for_stop() {
+        let mut engine = UciEngine::new();
+        assert_eq!(
+            engine.handle(parse_command("position startpos").unwrap()),
+            Ok(true)
+        );
+        assert_eq!(
+            engine.handle(parse_command("go infinite").unwrap()),
+            Ok(true)
+        );
+        thread::sleep(Duration::from_millis(100));
+        assert!(!engine.search.as_ref().unwrap().is_finished());
+
+        assert_eq!(engine.handle(UciCommand::Stop), Ok(true));
+        assert!(engine.search.is_none());
+        let game = engine.game.as_ref().unwrap();
+        assert_eq!(game.search_depth(), MAX_DEPTH);
+        assert!(game.time_manager().budget().is_none());
+    }
+
+    #[test]
+    fn test_parse_setoption() {
+        assert_eq!(
+            parse_command("setoption name Depth value 6"),
+            Ok(UciCommand::SetOption {
+                name: "Depth".to_string(),
+                value: Some("6".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_command("setoption name Clear Hash"),
+            Ok(UciCommand::SetOption {
+                name: "Clear Hash".to_string(),
+                value: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_hash_option() {
+        let mut engine = UciEngine::new();
+        let set_hash = |value: &str| UciCommand::SetOption {
+            name: "Hash".to_string(),
+            value: Some(value.to_string()),
+        };
+        assert_eq!(engine.handle(set_hash("1")), Ok(true));
+        assert_eq!(engine.hash_mb, 1);
+        assert_eq!(
+            engine.handle(set_hash("0")),
+            Err(UciError::InvalidValue {
+                name: "Hash",
+                value: "0".to_string()
+            })
+        );
+        assert_eq!(engine.hash_mb, 1);
+    }
+
+    #[test]
+    fn test_weights_file_option() {
+        let path = std::env::temp_dir().join(format!("chess-uci-{}.json", std::process::id()));
+        let weights = EvaluationWeights {
+            bishop_pair_bonus: 45,
+            ..Default::default()
+        };
+        weights.save(&path).unwrap();
+
+        let mut engine = UciEngine::new();
+        let set_weights_file = |value: &str| UciCommand::SetOption {
+            name: "WeightsFile".to_string(),
+            value: Some(value.to_string()),
+        };
+        assert_eq!(
+            engine.handle(set_weights_file(path.to_str().unwrap())),
+            Ok(true)
+        );
+        let game = engine.new_game(STARTING_POSITION_FEN, &[]).unwrap();
+        assert_eq!(*game.evaluation_terms().weights, weights);
+
+        assert_eq!(engine.handle(set_weights_file("<empty>")), Ok(true));
+        let game = engine.new_game(STARTING_POSITION_FEN, &[]).unwrap();
+        assert_eq!(
+            *game.evaluation_terms().weights,
+            EvaluationWeights::default()
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(engine
+            .handle(set_weights_file(path.to_str().unwrap()))
+            .is_err());
+    }
+
+    #[cfg(feature = "nnue")]
+    #[test]
+    fn test_eval_file_option() {
+        // A network with one hidden neuron and every weight 0.
+        let mut network = b"CNUE".to_vec();
+        network.extend(1u32.to_le_bytes());
+        network.extend(1u32.to_le_bytes());
+        network.resize(network.len() + 2 * (64 * 10 * 64 + 3) + 4, 0);
+        let path = std::env::temp_dir().join(format!("chess-uci-{}.nnue", std::process::id()));
+        std::fs::write(&path, network).unwrap();
+
+        let mut engine = UciEngine::new();
+        let set_eval_file = |value: &str| UciCommand::SetOption {
+            name: "EvalFile".to_string(),
+            value: Some(value.to_string()),
+        };
+        assert_eq!(
+            engine.handle(set_eval_file(path.to_str().unwrap())),
+            Ok(true)
+        );
+        let game = engine.new_game(STARTING_POSITION_FEN, &[]).unwrap();
+        assert_eq!(game.board().nnue_score(), Some(0));
+
+        assert_eq!(engine.handle(set_eval_file("<empty>")), Ok(true));
+        let game = engine.new_game(STARTING_POSITION_FEN, &[]).unwrap();
+        assert_eq!(game.board().nnue_score(), None);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(engine
+            .handle(set_eval_file(path.to_str().unwrap()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_game_from_position() {
+        let moves = vec!["e2e4".to_string(), "e7e5".to_string(), "g1f3".to_string()];
+        let game = game_from_position(STARTING_POSITION_FEN, &moves, 1).unwrap();
+        assert_eq!(game.board().turn(), Color::Black);
+        assert_eq!(game.last_move().unwrap().to_uci(), "g1f3");
+
+        let moves = vec!["e2e5".to_string()];
+        assert_eq!(
+            game_from_position(STARTING_POSITION_FEN, &moves, 1).err(),
+            Some(UciError::IllegalMove {
+                chess_move: "e2e5".to_string()
+            })
+        );
+    }
+}